@@ -1,4 +1,6 @@
-// build.rs - Windows resource compilation for icon embedding
+// build.rs - Windows resource compilation for icon embedding, plus build info for --version
+use std::process::Command;
+
 fn main() {
     // Only compile Windows resources when targeting Windows
     // IMPORTANT: Use CARGO_CFG_TARGET_OS, not #[cfg(target_os)]
@@ -16,4 +18,27 @@ fn main() {
 
         res.compile().unwrap();
     }
+
+    // Target triple, e.g. "x86_64-pc-windows-gnu" (TARGET is only set for build scripts)
+    println!("cargo:rustc-env=KEYBLAST_TARGET={}", std::env::var("TARGET").unwrap());
+
+    // Commit hash, when building from a git checkout with git installed
+    let git_hash = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|hash| !hash.is_empty());
+    if let Some(hash) = git_hash {
+        println!("cargo:rustc-env=KEYBLAST_GIT_HASH={}", hash);
+    }
+
+    // Declaring any rerun-if-changed replaces Cargo's "any file changed" default,
+    // so list everything this script reads
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=assets/icon.ico");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+    println!("cargo:rerun-if-changed=.git/packed-refs");
 }
@@ -1,18 +1,244 @@
-/// Application state management for KeyBlast.
-///
-/// Tracks whether macro playback is enabled or disabled.
+//! Application state management for KeyBlast.
+//!
+//! Tracks whether macro playback is enabled or disabled, and the
+//! most-recently-run macros shown at the top of the Run Macro menu.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::config::{MacroDefinition, OnConfigDelete, StopIdleAction, TrayClickAction};
+use crate::execution::ExecutionEvent;
 
 pub struct AppState {
     pub enabled: bool,
 }
 
+/// What pressing the stop hotkey should do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopHotkeyAction {
+    /// Stop the running macro.
+    StopMacro,
+    Nothing,
+    ReleaseModifiers,
+    ClearQueue,
+    Disable,
+}
+
 impl AppState {
     pub fn new() -> Self {
         Self { enabled: true }
     }
 
-    pub fn toggle(&mut self) {
-        self.enabled = !self.enabled;
+    /// Whether a macro trigger should show the "KeyBlast is disabled" reminder.
+    ///
+    /// Only applies while disabled and when `settings.notify_when_disabled` is on.
+    pub fn should_notify_disabled(&self, notify_when_disabled: bool) -> bool {
+        !self.enabled && notify_when_disabled
+    }
+
+    /// Decide what the stop hotkey does.
+    ///
+    /// A running macro is always stopped; otherwise `idle_action`
+    /// (`settings.stop_hotkey_idle_action`) applies. Disabling while already
+    /// disabled does nothing.
+    pub fn stop_hotkey_action(&self, executing: bool, idle_action: StopIdleAction) -> StopHotkeyAction {
+        if executing {
+            return StopHotkeyAction::StopMacro;
+        }
+        match idle_action {
+            StopIdleAction::None => StopHotkeyAction::Nothing,
+            StopIdleAction::Release => StopHotkeyAction::ReleaseModifiers,
+            StopIdleAction::ClearQueue => StopHotkeyAction::ClearQueue,
+            StopIdleAction::Disable if self.enabled => StopHotkeyAction::Disable,
+            StopIdleAction::Disable => StopHotkeyAction::Nothing,
+        }
+    }
+}
+
+/// Whether Quit should ask before stopping a running macro.
+///
+/// Only when a macro is running and `settings.confirm_quit_if_running` is on.
+pub fn should_confirm_quit(executing: bool, confirm_quit_if_running: bool) -> bool {
+    executing && confirm_quit_if_running
+}
+
+/// Whether `event` should show the "Macro stopped" notification.
+///
+/// Only a cancel (stop hotkey or Stop Macro), and only with
+/// `settings.notify_on_cancel`. Completion flashes the icon instead (see
+/// `completion_notice`), and failures already notify.
+pub fn should_notify_cancel(event: &ExecutionEvent, notify_on_cancel: bool) -> bool {
+    notify_on_cancel && *event == ExecutionEvent::Cancelled
+}
+
+/// The "finished" notification text for `event`, if one should be shown.
+///
+/// Only on completion, and only with `settings.notify_on_complete`;
+/// `macro_name` is the running macro's (see `Execution::macro_name`).
+pub fn completion_notice(event: &ExecutionEvent, notify_on_complete: bool, macro_name: Option<&str>) -> Option<String> {
+    if !notify_on_complete || *event != ExecutionEvent::Completed {
+        return None;
+    }
+    Some(match macro_name {
+        Some(name) => format!("'{}' finished", name),
+        None => "Macro finished".to_string(),
+    })
+}
+
+/// When KeyBlast should auto-quit after `idle_minutes` without macro activity
+/// (`settings.auto_quit_idle_minutes`), or None if it never does (0).
+pub fn auto_quit_deadline(last_activity: Instant, idle_minutes: u64) -> Option<Instant> {
+    if idle_minutes == 0 {
+        return None;
+    }
+    last_activity.checked_add(Duration::from_secs(idle_minutes.saturating_mul(60)))
+}
+
+/// Whether KeyBlast has been idle long enough to auto-quit.
+pub fn should_auto_quit(last_activity: Instant, now: Instant, idle_minutes: u64) -> bool {
+    auto_quit_deadline(last_activity, idle_minutes).is_some_and(|deadline| now >= deadline)
+}
+
+/// When a config change marked dirty at or after `last_save` should be written,
+/// or None if nothing is waiting. Saves are at least `interval` apart.
+pub fn config_flush_deadline(dirty: bool, last_save: Instant, interval: Duration) -> Option<Instant> {
+    dirty.then(|| last_save + interval)
+}
+
+/// Whether a pending config change should be written now.
+pub fn should_flush_config(dirty: bool, last_save: Instant, now: Instant, interval: Duration) -> bool {
+    config_flush_deadline(dirty, last_save, interval).is_some_and(|deadline| now >= deadline)
+}
+
+/// How to handle a change to the config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigChangeAction {
+    /// Load the file (or defaults, if it's gone).
+    Reload,
+    /// The file is gone; keep the in-memory config.
+    KeepInMemory,
+    /// The file is gone; write the in-memory config back out.
+    Recreate,
+}
+
+/// Decide how to handle a config file change per `settings.on_config_delete`.
+///
+/// Only a file that is still missing counts as deleted (editors that save by
+/// replacing the file briefly remove it). A locked config is never written,
+/// so `RecreateFromMemory` keeps the in-memory config instead.
+pub fn config_change_action(file_exists: bool, on_delete: OnConfigDelete, locked: bool) -> ConfigChangeAction {
+    if file_exists {
+        return ConfigChangeAction::Reload;
+    }
+    match on_delete {
+        OnConfigDelete::Reset => ConfigChangeAction::Reload,
+        OnConfigDelete::Keep => ConfigChangeAction::KeepInMemory,
+        OnConfigDelete::RecreateFromMemory if locked => ConfigChangeAction::KeepInMemory,
+        OnConfigDelete::RecreateFromMemory => ConfigChangeAction::Recreate,
+    }
+}
+
+/// What a left click on the tray icon should do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayClickResponse {
+    Nothing,
+    ToggleEnabled,
+    /// Run the macro with this ID.
+    RunMacro(Uuid),
+    /// Let the tray open its menu (it does so itself; see `tray::create_tray`).
+    ShowMenu,
+}
+
+/// Decide what a tray icon left click does per `settings.tray_click_action`.
+///
+/// `RunPrimary` runs the first macro with `primary = true` (enabled and
+/// meant for this machine), and does nothing if there is none.
+pub fn tray_click_response(action: TrayClickAction, macros: &[MacroDefinition]) -> TrayClickResponse {
+    match action {
+        TrayClickAction::None => TrayClickResponse::Nothing,
+        TrayClickAction::Toggle => TrayClickResponse::ToggleEnabled,
+        TrayClickAction::RunPrimary => macros
+            .iter()
+            .find(|m| m.primary && m.enabled && crate::config::macro_applies(m))
+            .map_or(TrayClickResponse::Nothing, |m| TrayClickResponse::RunMacro(m.id)),
+        TrayClickAction::Menu => TrayClickResponse::ShowMenu,
+    }
+}
+
+/// Macros run this session, for the Run Macro menu.
+#[derive(Debug, Default)]
+pub struct RunHistory {
+    /// Most-recently-run macro IDs, most recent first (Run Macro > Recent).
+    pub recent: VecDeque<Uuid>,
+    /// How many times each macro has run (for `run_menu_sort = "usage"`).
+    pub counts: HashMap<Uuid, u32>,
+}
+
+impl RunHistory {
+    /// Record a run, keeping at most `recent_cap` recent entries.
+    pub fn record(&mut self, id: Uuid, recent_cap: usize) {
+        push_recent(&mut self.recent, id, recent_cap);
+        *self.counts.entry(id).or_insert(0) += 1;
+    }
+}
+
+/// Record a macro run in the most-recently-used list.
+///
+/// The list is ordered most-recent-first, holds each macro at most once,
+/// and is capped at `cap` entries (0 keeps the list empty).
+pub fn push_recent(recent: &mut VecDeque<Uuid>, id: Uuid, cap: usize) {
+    recent.retain(|existing| *existing != id);
+    recent.push_front(id);
+    recent.truncate(cap);
+}
+
+/// What a `Countdown` has due when polled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountdownStep {
+    /// Nothing yet; poll again at `Countdown::next_due`.
+    Waiting,
+    /// A one-second tick, with this many seconds left before the macro runs.
+    Tick(u8),
+    /// The countdown is over: run the macro.
+    Finished,
+}
+
+/// A macro's `countdown_secs` before it runs, giving time to focus the target.
+///
+/// Ticks once a second starting right away (3, 2, 1 for three seconds) and
+/// finishes `countdown_secs` after it started.
+#[derive(Debug, Clone)]
+pub struct Countdown {
+    pub macro_def: MacroDefinition,
+    /// Typed characters to backspace over when the macro runs (an abbreviation).
+    pub erase_len: usize,
+    started: Instant,
+    /// Ticks reported so far.
+    ticks: u8,
+}
+
+impl Countdown {
+    pub fn new(macro_def: MacroDefinition, now: Instant) -> Self {
+        Self { macro_def, erase_len: 0, started: now, ticks: 0 }
+    }
+
+    /// When the next tick, or the end of the countdown, is due.
+    pub fn next_due(&self) -> Instant {
+        self.started + Duration::from_secs(u64::from(self.ticks))
+    }
+
+    /// The step due at `now`. Poll until `Waiting`; a late poll catches up
+    /// one missed tick per call.
+    pub fn poll(&mut self, now: Instant) -> CountdownStep {
+        if now < self.next_due() {
+            return CountdownStep::Waiting;
+        }
+        let Some(left) = self.macro_def.countdown_secs.checked_sub(self.ticks).filter(|&left| left > 0) else {
+            return CountdownStep::Finished;
+        };
+        self.ticks += 1;
+        CountdownStep::Tick(left)
     }
 }
 
@@ -21,3 +247,257 @@ impl Default for AppState {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notify_when_disabled_and_flag_set() {
+        let state = AppState { enabled: false };
+        assert!(state.should_notify_disabled(true));
+    }
+
+    #[test]
+    fn test_no_notify_when_flag_off() {
+        let state = AppState { enabled: false };
+        assert!(!state.should_notify_disabled(false));
+    }
+
+    #[test]
+    fn test_no_notify_when_enabled() {
+        let state = AppState { enabled: true };
+        assert!(!state.should_notify_disabled(true));
+    }
+
+    #[test]
+    fn test_push_recent_most_recent_first() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut recent = VecDeque::new();
+        push_recent(&mut recent, a, 5);
+        push_recent(&mut recent, b, 5);
+        assert_eq!(recent, VecDeque::from(vec![b, a]));
+    }
+
+    #[test]
+    fn test_push_recent_dedups() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut recent = VecDeque::new();
+        push_recent(&mut recent, a, 5);
+        push_recent(&mut recent, b, 5);
+        push_recent(&mut recent, a, 5);
+        // Re-running moves to the front instead of duplicating
+        assert_eq!(recent, VecDeque::from(vec![a, b]));
+    }
+
+    #[test]
+    fn test_push_recent_caps_length() {
+        let ids: Vec<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+        let mut recent = VecDeque::new();
+        for id in &ids {
+            push_recent(&mut recent, *id, 3);
+        }
+        // Oldest entry dropped
+        assert_eq!(recent, VecDeque::from(vec![ids[3], ids[2], ids[1]]));
+
+        push_recent(&mut recent, ids[0], 0);
+        assert!(recent.is_empty());
+    }
+
+    #[test]
+    fn test_stop_hotkey_stops_running_macro_regardless_of_setting() {
+        let state = AppState { enabled: true };
+        assert_eq!(state.stop_hotkey_action(true, StopIdleAction::Disable), StopHotkeyAction::StopMacro);
+        assert_eq!(state.stop_hotkey_action(true, StopIdleAction::None), StopHotkeyAction::StopMacro);
+    }
+
+    #[test]
+    fn test_stop_hotkey_idle_action_dispatch() {
+        let enabled = AppState { enabled: true };
+        let cases = [
+            (StopIdleAction::None, StopHotkeyAction::Nothing),
+            (StopIdleAction::Release, StopHotkeyAction::ReleaseModifiers),
+            (StopIdleAction::ClearQueue, StopHotkeyAction::ClearQueue),
+            (StopIdleAction::Disable, StopHotkeyAction::Disable),
+        ];
+        for (idle_action, expected) in cases {
+            assert_eq!(enabled.stop_hotkey_action(false, idle_action), expected, "{:?}", idle_action);
+        }
+
+        let disabled = AppState { enabled: false };
+        assert_eq!(disabled.stop_hotkey_action(false, StopIdleAction::Disable), StopHotkeyAction::Nothing);
+    }
+
+    #[test]
+    fn test_run_history_counts_runs() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut history = RunHistory::default();
+        history.record(a, 1);
+        history.record(b, 1);
+        history.record(a, 1);
+
+        assert_eq!(history.recent, VecDeque::from(vec![a]));
+        assert_eq!(history.counts[&a], 2);
+        assert_eq!(history.counts[&b], 1);
+    }
+
+    #[test]
+    fn test_should_confirm_quit() {
+        assert!(should_confirm_quit(true, true));
+        assert!(!should_confirm_quit(true, false));
+        assert!(!should_confirm_quit(false, true));
+        assert!(!should_confirm_quit(false, false));
+    }
+
+    #[test]
+    fn test_should_notify_cancel() {
+        assert!(should_notify_cancel(&ExecutionEvent::Cancelled, true));
+        assert!(!should_notify_cancel(&ExecutionEvent::Cancelled, false));
+        assert!(!should_notify_cancel(&ExecutionEvent::Completed, true));
+        assert!(!should_notify_cancel(&ExecutionEvent::Failed("boom".to_string()), true));
+    }
+
+    #[test]
+    fn test_completion_notice() {
+        assert_eq!(
+            completion_notice(&ExecutionEvent::Completed, true, Some("Standup Notes")),
+            Some("'Standup Notes' finished".to_string())
+        );
+        assert_eq!(completion_notice(&ExecutionEvent::Completed, true, None), Some("Macro finished".to_string()));
+        assert_eq!(completion_notice(&ExecutionEvent::Completed, false, Some("Standup Notes")), None);
+        assert_eq!(completion_notice(&ExecutionEvent::Cancelled, true, Some("Standup Notes")), None);
+        assert_eq!(completion_notice(&ExecutionEvent::Failed("boom".to_string()), true, None), None);
+    }
+
+    #[test]
+    fn test_should_auto_quit() {
+        let last = Instant::now();
+        let minute = Duration::from_secs(60);
+
+        assert!(!should_auto_quit(last, last + 9 * minute, 10));
+        assert!(should_auto_quit(last, last + 10 * minute, 10));
+        assert!(should_auto_quit(last, last + 60 * minute, 10));
+        // 0 never quits
+        assert!(!should_auto_quit(last, last + 10_000 * minute, 0));
+        assert_eq!(auto_quit_deadline(last, 0), None);
+        assert_eq!(auto_quit_deadline(last, 2), Some(last + 2 * minute));
+        // Overflowing settings don't panic
+        assert!(!should_auto_quit(last, last + minute, u64::MAX));
+    }
+
+    #[test]
+    fn test_config_change_action() {
+        use ConfigChangeAction::*;
+
+        // A file that's still there is always reloaded
+        for policy in [OnConfigDelete::Reset, OnConfigDelete::Keep, OnConfigDelete::RecreateFromMemory] {
+            assert_eq!(config_change_action(true, policy, false), Reload);
+        }
+        // Deleted
+        assert_eq!(config_change_action(false, OnConfigDelete::Reset, false), Reload);
+        assert_eq!(config_change_action(false, OnConfigDelete::Keep, false), KeepInMemory);
+        assert_eq!(config_change_action(false, OnConfigDelete::RecreateFromMemory, false), Recreate);
+        assert_eq!(config_change_action(false, OnConfigDelete::RecreateFromMemory, true), KeepInMemory);
+    }
+
+    #[test]
+    fn test_tray_click_response() {
+        let plain = MacroDefinition { name: "Plain".to_string(), ..MacroDefinition::default() };
+        let primary = MacroDefinition { name: "Main".to_string(), primary: true, ..MacroDefinition::default() };
+        let second = MacroDefinition { name: "Other".to_string(), primary: true, ..MacroDefinition::default() };
+        let macros = vec![plain.clone(), primary.clone(), second];
+
+        assert_eq!(tray_click_response(TrayClickAction::None, &macros), TrayClickResponse::Nothing);
+        assert_eq!(tray_click_response(TrayClickAction::Toggle, &macros), TrayClickResponse::ToggleEnabled);
+        assert_eq!(tray_click_response(TrayClickAction::Menu, &macros), TrayClickResponse::ShowMenu);
+        // The first primary macro wins
+        assert_eq!(
+            tray_click_response(TrayClickAction::RunPrimary, &macros),
+            TrayClickResponse::RunMacro(primary.id)
+        );
+        assert_eq!(
+            tray_click_response(TrayClickAction::RunPrimary, &[plain]),
+            TrayClickResponse::Nothing
+        );
+    }
+
+    #[test]
+    fn test_tray_click_action_default_and_names() {
+        assert_eq!(crate::config::AppSettings::default().tray_click_action, TrayClickAction::Menu);
+        let settings: crate::config::AppSettings = toml::from_str(r#"tray_click_action = "run_primary""#).unwrap();
+        assert_eq!(settings.tray_click_action, TrayClickAction::RunPrimary);
+    }
+
+    #[test]
+    fn test_should_flush_config() {
+        let interval = Duration::from_millis(2000);
+        let last_save = Instant::now();
+
+        // Nothing to write
+        assert!(!should_flush_config(false, last_save, last_save + 10 * interval, interval));
+        assert_eq!(config_flush_deadline(false, last_save, interval), None);
+
+        // Rapid changes wait for the interval since the last save
+        assert!(!should_flush_config(true, last_save, last_save, interval));
+        assert!(!should_flush_config(true, last_save, last_save + Duration::from_millis(1999), interval));
+        assert!(should_flush_config(true, last_save, last_save + interval, interval));
+        assert_eq!(config_flush_deadline(true, last_save, interval), Some(last_save + interval));
+
+        // A change long after the last save goes out right away
+        assert!(should_flush_config(true, last_save, last_save + 100 * interval, interval));
+    }
+
+    /// Poll a countdown every 100 ms and note when each step came.
+    fn countdown_schedule(secs: u8) -> Vec<(u64, CountdownStep)> {
+        let start = Instant::now();
+        let macro_def = MacroDefinition { countdown_secs: secs, ..MacroDefinition::default() };
+        let mut countdown = Countdown::new(macro_def, start);
+        let mut steps = Vec::new();
+        for ms in (0..=10_000).step_by(100) {
+            loop {
+                match countdown.poll(start + Duration::from_millis(ms)) {
+                    CountdownStep::Waiting => break,
+                    CountdownStep::Finished => {
+                        steps.push((ms, CountdownStep::Finished));
+                        return steps;
+                    }
+                    step => steps.push((ms, step)),
+                }
+            }
+        }
+        steps
+    }
+
+    #[test]
+    fn test_countdown_ticks_once_a_second() {
+        assert_eq!(
+            countdown_schedule(3),
+            vec![
+                (0, CountdownStep::Tick(3)),
+                (1000, CountdownStep::Tick(2)),
+                (2000, CountdownStep::Tick(1)),
+                (3000, CountdownStep::Finished),
+            ]
+        );
+        assert_eq!(countdown_schedule(1), vec![(0, CountdownStep::Tick(1)), (1000, CountdownStep::Finished)]);
+        // No countdown: runs right away
+        assert_eq!(countdown_schedule(0), vec![(0, CountdownStep::Finished)]);
+    }
+
+    #[test]
+    fn test_countdown_next_due_and_late_poll() {
+        let start = Instant::now();
+        let macro_def = MacroDefinition { countdown_secs: 2, ..MacroDefinition::default() };
+        let mut countdown = Countdown::new(macro_def, start);
+        assert_eq!(countdown.next_due(), start);
+        assert_eq!(countdown.poll(start), CountdownStep::Tick(2));
+        assert_eq!(countdown.next_due(), start + Duration::from_secs(1));
+        assert_eq!(countdown.poll(start + Duration::from_millis(999)), CountdownStep::Waiting);
+
+        // Woken late: the missed tick comes first, then the end
+        let late = start + Duration::from_secs(5);
+        assert_eq!(countdown.poll(late), CountdownStep::Tick(1));
+        assert_eq!(countdown.poll(late), CountdownStep::Finished);
+        assert_eq!(countdown.poll(late), CountdownStep::Finished);
+    }
+}
@@ -0,0 +1,320 @@
+//! Safety review of what macros type (`keyblast --audit`).
+//!
+//! Flags content a reviewer should look at before a macro is shared or run on
+//! someone else's machine. The heuristics are deliberately simple and only
+//! advisory:
+//! - Shell metacharacters: typed text contains `$(`, a backtick, `&&`, `||`,
+//!   `|`, `>` or `<`, which chain or redirect commands if typed into a terminal
+//! - Long digit runs: 13 or more digits in a row, optionally grouped by
+//!   single spaces or dashes, the length of a payment card number
+//! - Run dialog: `r` is typed while `{KeyDown Meta}` is held, which opens
+//!   the Windows Run dialog
+//! - Typed files: every `{Type path}`, which types whatever that file holds,
+//!   noting paths that lead outside the config directory
+//!
+//! Text put on the clipboard by `{SetClip}` is checked like typed text, since
+//! `{Paste}` types it.
+
+use std::path::{Component, Path};
+
+use enigo::Key;
+
+use crate::config::{self, MacroDefinition};
+use crate::injection::{self, MacroSegment};
+
+/// Shell metacharacters, longest first so `&&` is reported rather than `&`.
+const SHELL_METACHARACTERS: &[&str] = &["$(", "&&", "||", "`", "|", ">", "<"];
+
+/// Digits in a row from which a number looks like a card number.
+const LONG_DIGIT_RUN: usize = 13;
+
+/// Something in a macro worth a second look.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RiskFlag {
+    /// Typed text contains these shell metacharacters.
+    ShellMetacharacters(Vec<String>),
+    /// Typed text contains a number this many digits long.
+    LongDigitSequence(usize),
+    /// Types `r` while Meta is held.
+    RunDialog,
+    /// Types the contents of this file (`{Type path}`, as written).
+    TypesFile { path: String, outside_config_dir: bool },
+}
+
+impl std::fmt::Display for RiskFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RiskFlag::ShellMetacharacters(found) => {
+                write!(f, "types shell metacharacters ({})", found.join(" "))
+            }
+            RiskFlag::LongDigitSequence(digits) => {
+                write!(f, "types a {}-digit number (card number?)", digits)
+            }
+            RiskFlag::RunDialog => write!(f, "opens the Run dialog (Meta+R)"),
+            RiskFlag::TypesFile { path, outside_config_dir } => {
+                write!(f, "types the file {}", path)?;
+                if *outside_config_dir {
+                    write!(f, " (outside the config directory)")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Risk flags for a macro's text (or `text_file`), parsed leniently.
+///
+/// A text file that can't be read yields no flags; validation reports it.
+pub fn macro_risk_report(macro_def: &MacroDefinition) -> Vec<RiskFlag> {
+    match config::load_macro_text(macro_def, &config::config_dir()) {
+        Ok(text) => risk_flags(&injection::parse_macro_sequence(&text), &config::config_dir()),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Risk flags for parsed segments, with `{Repeat}` blocks unrolled.
+///
+/// `config_dir` is what relative `{Type}` paths resolve against.
+pub fn risk_flags(segments: &[MacroSegment], config_dir: &Path) -> Vec<RiskFlag> {
+    let mut flags = Vec::new();
+    let mut typed_files: Vec<RiskFlag> = Vec::new();
+
+    // Text typed in one go; keys and commands in between split it
+    let mut texts = vec![String::new()];
+    let mut run_dialog = false;
+    let mut meta_held = false;
+    for segment in &injection::flatten_repeats(segments.to_vec()) {
+        match segment {
+            MacroSegment::Text(text) => {
+                run_dialog |= meta_held && text.contains(['r', 'R']);
+                texts.last_mut().expect("never empty").push_str(text);
+                continue;
+            }
+            // Each option is typed on its own
+            MacroSegment::OneOf(options) => texts.extend(options.iter().cloned()),
+            // Typed whole by a later {Paste}
+            MacroSegment::SetClip(text) => texts.push(text.clone()),
+            MacroSegment::TypeFile(path) => {
+                let flag = RiskFlag::TypesFile {
+                    path: path.clone(),
+                    outside_config_dir: is_outside(path, config_dir),
+                };
+                if !typed_files.contains(&flag) {
+                    typed_files.push(flag);
+                }
+            }
+            MacroSegment::KeyDown(Key::Meta) => meta_held = true,
+            MacroSegment::KeyUp(Key::Meta) => meta_held = false,
+            _ => {}
+        }
+        texts.push(String::new());
+    }
+
+    let mut found: Vec<String> = Vec::new();
+    for text in &texts {
+        let mut rest = text.to_string();
+        for meta in SHELL_METACHARACTERS {
+            if rest.contains(meta) {
+                if !found.iter().any(|f| f == meta) {
+                    found.push(meta.to_string());
+                }
+                // Don't report `|` again for an `||`
+                rest = rest.replace(meta, " ");
+            }
+        }
+    }
+    if !found.is_empty() {
+        flags.push(RiskFlag::ShellMetacharacters(found));
+    }
+
+    let longest = texts.iter().map(|text| longest_digit_run(text)).max().unwrap_or(0);
+    if longest >= LONG_DIGIT_RUN {
+        flags.push(RiskFlag::LongDigitSequence(longest));
+    }
+
+    if run_dialog {
+        flags.push(RiskFlag::RunDialog);
+    }
+    flags.extend(typed_files);
+    flags
+}
+
+/// Whether `path` resolves outside `config_dir`, going by the path alone.
+///
+/// Any `..` counts as outside rather than resolving symlinks on disk.
+fn is_outside(path: &str, config_dir: &Path) -> bool {
+    let resolved = config::resolve_config_relative(path, config_dir);
+    !resolved.starts_with(config_dir) || resolved.components().any(|c| c == Component::ParentDir)
+}
+
+/// Most digits in one run, allowing a single space or dash between digits.
+fn longest_digit_run(text: &str) -> usize {
+    let chars: Vec<char> = text.chars().collect();
+    let mut longest = 0;
+    let mut current = 0;
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii_digit() {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            let joins_digits = matches!(c, ' ' | '-')
+                && i > 0
+                && chars[i - 1].is_ascii_digit()
+                && chars.get(i + 1).is_some_and(char::is_ascii_digit);
+            if !joins_digits {
+                current = 0;
+            }
+        }
+    }
+    longest
+}
+
+/// Render the `--audit` report for `macros`.
+///
+/// Returns the report and whether anything was flagged.
+pub fn format_audit(macros: &[MacroDefinition]) -> (String, bool) {
+    let mut report = String::new();
+    let mut flagged = 0;
+    for macro_def in macros {
+        let flags = macro_risk_report(macro_def);
+        if flags.is_empty() {
+            continue;
+        }
+        flagged += 1;
+        report.push_str(&format!("{}:\n", macro_def.name));
+        for flag in flags {
+            report.push_str(&format!("  - {}\n", flag));
+        }
+    }
+    if flagged == 0 {
+        report.push_str(&format!("No risks found in {} macros\n", macros.len()));
+    } else {
+        report.push_str(&format!("{} of {} macros flagged\n", flagged, macros.len()));
+    }
+    (report, flagged > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::injection::parse_macro_sequence;
+
+    fn flags_for(text: &str) -> Vec<RiskFlag> {
+        risk_flags(&parse_macro_sequence(text), Path::new("/home/me/.config/keyblast"))
+    }
+
+    #[test]
+    fn test_shell_metacharacters() {
+        assert_eq!(
+            flags_for("ls | grep x && rm -rf $(pwd){Enter}"),
+            vec![RiskFlag::ShellMetacharacters(vec![
+                "$(".to_string(),
+                "&&".to_string(),
+                "|".to_string(),
+            ])]
+        );
+        assert_eq!(
+            flags_for("a || b"),
+            vec![RiskFlag::ShellMetacharacters(vec!["||".to_string()])]
+        );
+        assert_eq!(flags_for("Dear team, thanks; see you at 5.{Enter}"), vec![]);
+        assert_eq!(flags_for("Price: $5 (incl. tax) & shipping"), vec![]);
+    }
+
+    #[test]
+    fn test_long_digit_sequence() {
+        assert_eq!(flags_for("4111 1111 1111 1111"), vec![RiskFlag::LongDigitSequence(16)]);
+        assert_eq!(flags_for("card 4111-1111-1111-1"), vec![RiskFlag::LongDigitSequence(13)]);
+        assert_eq!(longest_digit_run("555-0100, 2026-10-16"), 8);
+        assert_eq!(flags_for("Order 123456789012"), vec![]);
+        // Keys in between split the number
+        assert_eq!(flags_for("1234567{Tab}1234567"), vec![]);
+    }
+
+    #[test]
+    fn test_run_dialog() {
+        assert_eq!(flags_for("{KeyDown Meta}r{KeyUp Meta}cmd{Enter}"), vec![RiskFlag::RunDialog]);
+        assert_eq!(flags_for("{KeyDown Win}R{KeyUp Win}"), vec![RiskFlag::RunDialog]);
+        assert_eq!(flags_for("{KeyDown Meta}{KeyUp Meta}run"), vec![]);
+        assert_eq!(flags_for("{KeyDown Ctrl}r{KeyUp Ctrl}"), vec![]);
+    }
+
+    #[test]
+    fn test_repeat_unrolled() {
+        assert_eq!(flags_for("{Repeat 20}1{EndRepeat}"), vec![RiskFlag::LongDigitSequence(20)]);
+        assert_eq!(
+            flags_for("{Repeat 2}a|b{EndRepeat}"),
+            vec![RiskFlag::ShellMetacharacters(vec!["|".to_string()])]
+        );
+    }
+
+    #[test]
+    fn test_one_of_options_checked_separately() {
+        assert_eq!(
+            flags_for("{OneOf ok|rm -rf $(pwd)}"),
+            vec![RiskFlag::ShellMetacharacters(vec!["$(".to_string()])]
+        );
+        // The separator itself isn't typed, and options aren't joined
+        assert_eq!(flags_for("{OneOf yes|no}"), vec![]);
+        assert_eq!(flags_for("{OneOf 1234567|1234567}"), vec![]);
+    }
+
+    #[test]
+    fn test_set_clip_text_checked() {
+        assert_eq!(
+            flags_for("{SetClip rm -rf ~ && x}{Paste}"),
+            vec![RiskFlag::ShellMetacharacters(vec!["&&".to_string()])]
+        );
+        assert_eq!(flags_for("{SetClip see you at 5}{Paste}"), vec![]);
+    }
+
+    #[test]
+    fn test_types_file() {
+        assert_eq!(
+            flags_for("{Type /home/me/.ssh/id_rsa}"),
+            vec![RiskFlag::TypesFile {
+                path: "/home/me/.ssh/id_rsa".to_string(),
+                outside_config_dir: true,
+            }]
+        );
+        assert_eq!(
+            flags_for("{Type ../../.ssh/id_rsa}"),
+            vec![RiskFlag::TypesFile {
+                path: "../../.ssh/id_rsa".to_string(),
+                outside_config_dir: true,
+            }]
+        );
+        // Flagged once even when typed twice, but not as outside
+        assert_eq!(
+            flags_for("{Type snippets/sig.txt}{Type snippets/sig.txt}"),
+            vec![RiskFlag::TypesFile {
+                path: "snippets/sig.txt".to_string(),
+                outside_config_dir: false,
+            }]
+        );
+        assert_eq!(flags_for("Type snippets/sig.txt"), vec![]);
+    }
+
+    #[test]
+    fn test_format_audit() {
+        let macros = vec![
+            MacroDefinition {
+                name: "Deploy".to_string(),
+                text: "make && make install".to_string(),
+                ..MacroDefinition::default()
+            },
+            MacroDefinition {
+                name: "Hello".to_string(),
+                text: "Hello".to_string(),
+                ..MacroDefinition::default()
+            },
+        ];
+        let (report, flagged) = format_audit(&macros);
+        assert!(flagged);
+        assert_eq!(report, "Deploy:\n  - types shell metacharacters (&&)\n1 of 2 macros flagged\n");
+
+        let (report, flagged) = format_audit(&macros[1..]);
+        assert!(!flagged);
+        assert_eq!(report, "No risks found in 1 macros\n");
+    }
+}
@@ -1,8 +1,8 @@
-/// Auto-start at login management for KeyBlast.
-///
-/// Uses the auto-launch crate for cross-platform login item management.
-/// - macOS: LaunchAgent plist in ~/Library/LaunchAgents/
-/// - Windows: Registry key in HKCU\Software\Microsoft\Windows\CurrentVersion\Run
+//! Auto-start at login management for KeyBlast.
+//!
+//! Uses the auto-launch crate for cross-platform login item management.
+//! - macOS: LaunchAgent plist in ~/Library/LaunchAgents/
+//! - Windows: Registry key in HKCU\Software\Microsoft\Windows\CurrentVersion\Run
 
 use auto_launch::{AutoLaunch, AutoLaunchBuilder};
 
@@ -0,0 +1,311 @@
+//! Zip bundles of macros plus the text files they reference.
+//!
+//! `export_macros` only writes the TOML, so macros using `text_file` lose
+//! their text when shared. A bundle is a zip holding the macros as
+//! `macros.toml` and each referenced file under `macro-files/`. Importing
+//! extracts those files into the config directory and points the macros at
+//! the extracted copies.
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::config::{self, AppSettings, Config, ConfigError, MacroDefinition};
+
+/// Name of the macro list inside a bundle.
+const BUNDLE_MACROS: &str = "macros.toml";
+
+/// Directory (inside the bundle and the config dir) holding macro text files.
+pub const BUNDLE_FILES_DIR: &str = "macro-files";
+
+/// True if `path` looks like a bundle (`.zip`) rather than a plain TOML export.
+pub fn is_bundle_path(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+/// Read macros from a bundle, a tab-separated list or a TOML export, by extension.
+///
+/// Also returns the text files a bundle extracted (none for other files);
+/// call `keep` on them once the macros are saved.
+pub fn import_file(path: &Path, config_dir: &Path) -> Result<(Vec<MacroDefinition>, ExtractedFiles), ConfigError> {
+    if is_bundle_path(path) {
+        import_bundle(path, config_dir)
+    } else if config::is_tsv_path(path) {
+        Ok((config::import_tsv(path)?, ExtractedFiles::default()))
+    } else {
+        Ok((config::import_macros(path)?, ExtractedFiles::default()))
+    }
+}
+
+/// Text files an import wrote to the config directory.
+///
+/// They are deleted when this is dropped unless `keep` was called, so an
+/// import whose merge or save fails leaves no files behind.
+#[must_use]
+#[derive(Debug, Default)]
+pub struct ExtractedFiles {
+    paths: Vec<PathBuf>,
+}
+
+impl ExtractedFiles {
+    /// Leave the files in place (the macros using them were saved).
+    pub fn keep(mut self) {
+        self.paths.clear();
+    }
+}
+
+impl Drop for ExtractedFiles {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            if let Err(e) = fs::remove_file(path) {
+                tracing::warn!("Could not remove unused imported file {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// Write the macros and every file they reference via `text_file` to a zip bundle.
+///
+/// Relative `text_file` paths are resolved against `config_dir`. Each file is
+/// stored under `macro-files/` (renamed if two files share a name) and the
+/// bundled macro is pointed at that copy.
+pub fn export_bundle(macros: &[MacroDefinition], config_dir: &Path, path: &Path) -> Result<(), ConfigError> {
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let options = SimpleFileOptions::default();
+
+    let mut bundled_macros = macros.to_vec();
+    let mut used_names = HashSet::new();
+    for macro_def in &mut bundled_macros {
+        let Some(ref text_file) = macro_def.text_file else {
+            continue;
+        };
+        let source = config::resolve_config_relative(text_file, config_dir);
+        let content = fs::read(&source)?;
+
+        let file_name = source
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "macro.txt".to_string());
+        let entry = unique_name(&file_name, |name| !used_names.contains(name));
+        used_names.insert(entry.clone());
+
+        let entry_path = format!("{}/{}", BUNDLE_FILES_DIR, entry);
+        zip.start_file(entry_path.as_str(), options)?;
+        zip.write_all(&content)?;
+        macro_def.text_file = Some(entry_path);
+    }
+
+    let bundle_config = Config {
+        version: 1,
+        macros: bundled_macros,
+        settings: AppSettings::default(),
+    };
+    zip.start_file(BUNDLE_MACROS, options)?;
+    zip.write_all(toml::to_string_pretty(&bundle_config)?.as_bytes())?;
+    zip.finish()?;
+    Ok(())
+}
+
+/// Read macros from a bundle made by `export_bundle`, extracting their text files.
+///
+/// Files go to `config_dir/macro-files/`. An existing file with different
+/// content is never overwritten; the imported copy gets a new name instead.
+/// Returns the macros (de-duplicated by name) for the caller to merge, like
+/// `config::import_macros`, and the files created, which are removed again
+/// unless the caller keeps them (a failed extraction removes them too).
+pub fn import_bundle(path: &Path, config_dir: &Path) -> Result<(Vec<MacroDefinition>, ExtractedFiles), ConfigError> {
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+
+    let mut content = String::new();
+    archive.by_name(BUNDLE_MACROS)?.read_to_string(&mut content)?;
+    let bundle_config: Config = toml::from_str(&content)?;
+    let mut macros = config::dedupe_macros(bundle_config.macros);
+
+    let files_dir = config_dir.join(BUNDLE_FILES_DIR);
+    let mut extracted = ExtractedFiles::default();
+    for macro_def in &mut macros {
+        let Some(ref text_file) = macro_def.text_file else {
+            continue;
+        };
+        // Only plain file names under macro-files/ are extracted, so a crafted
+        // bundle can't write outside the config directory
+        let Some(file_name) = bundled_file_name(text_file) else {
+            tracing::warn!("Skipping text file outside {}/: {}", BUNDLE_FILES_DIR, text_file);
+            macro_def.text_file = None;
+            continue;
+        };
+
+        let mut data = Vec::new();
+        archive.by_name(text_file)?.read_to_end(&mut data)?;
+
+        fs::create_dir_all(&files_dir)?;
+        let target_name = unique_name(file_name, |name| {
+            let existing = files_dir.join(name);
+            !existing.exists() || fs::read(&existing).is_ok_and(|current| current == data)
+        });
+        // An identical file already there is reused, and not ours to remove
+        let target = files_dir.join(&target_name);
+        if !target.exists() {
+            fs::write(&target, &data)?;
+            extracted.paths.push(target);
+        }
+        macro_def.text_file = Some(format!("{}/{}", BUNDLE_FILES_DIR, target_name));
+    }
+
+    Ok((macros, extracted))
+}
+
+/// The file name in a `macro-files/<name>` path, if it is exactly that shape.
+fn bundled_file_name(text_file: &str) -> Option<&str> {
+    let name = text_file.strip_prefix(BUNDLE_FILES_DIR)?.strip_prefix('/')?;
+    let is_plain = !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\']);
+    is_plain.then_some(name)
+}
+
+/// First of `name`, `stem-2.ext`, `stem-3.ext`, ... that `available` accepts.
+fn unique_name(name: &str, available: impl Fn(&str) -> bool) -> String {
+    if available(name) {
+        return name.to_string();
+    }
+    let path = PathBuf::from(name);
+    let stem = path.file_stem().map_or(name.into(), |s| s.to_string_lossy());
+    let ext = path.extension().map(|e| e.to_string_lossy());
+    (2..)
+        .map(|n| match ext {
+            Some(ref ext) => format!("{}-{}.{}", stem, n, ext),
+            None => format!("{}-{}", stem, n),
+        })
+        .find(|candidate| available(candidate))
+        .expect("unbounded candidate names")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_macro;
+    use tempfile::tempdir;
+
+    fn file_backed(name: &str, text_file: &str) -> MacroDefinition {
+        MacroDefinition {
+            text: String::new(),
+            text_file: Some(text_file.to_string()),
+            ..test_macro(name, "ctrl+shift+s")
+        }
+    }
+
+    #[test]
+    fn test_bundle_round_trip_with_text_file() {
+        let source_dir = tempdir().unwrap();
+        fs::write(source_dir.path().join("sig.txt"), "Best regards,{Enter}Jane").unwrap();
+        let inline = MacroDefinition {
+            name: "Inline".to_string(),
+            hotkey: "ctrl+shift+i".to_string(),
+            text: "hello".to_string(),
+            ..MacroDefinition::default()
+        };
+        let macros = vec![file_backed("Signature", "sig.txt"), inline.clone()];
+
+        let bundle = source_dir.path().join("bundle.zip");
+        export_bundle(&macros, source_dir.path(), &bundle).unwrap();
+
+        let target_dir = tempdir().unwrap();
+        let (imported, _files) = import_bundle(&bundle, target_dir.path()).unwrap();
+
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported[0].name, "Signature");
+        assert_eq!(
+            config::load_macro_text(&imported[0], target_dir.path()).unwrap(),
+            "Best regards,{Enter}Jane"
+        );
+        assert_eq!(imported[1], inline);
+    }
+
+    #[test]
+    fn test_import_does_not_overwrite_different_file() {
+        let source_dir = tempdir().unwrap();
+        fs::write(source_dir.path().join("sig.txt"), "bundled").unwrap();
+        let bundle = source_dir.path().join("bundle.zip");
+        export_bundle(&[file_backed("Signature", "sig.txt")], source_dir.path(), &bundle).unwrap();
+
+        let target_dir = tempdir().unwrap();
+        let files_dir = target_dir.path().join(BUNDLE_FILES_DIR);
+        fs::create_dir_all(&files_dir).unwrap();
+        fs::write(files_dir.join("sig.txt"), "already here").unwrap();
+
+        let (imported, _files) = import_bundle(&bundle, target_dir.path()).unwrap();
+        assert_eq!(imported[0].text_file.as_deref(), Some("macro-files/sig-2.txt"));
+        assert_eq!(fs::read_to_string(files_dir.join("sig.txt")).unwrap(), "already here");
+        assert_eq!(fs::read_to_string(files_dir.join("sig-2.txt")).unwrap(), "bundled");
+    }
+
+    #[test]
+    fn test_export_renames_clashing_file_names() {
+        let source_dir = tempdir().unwrap();
+        fs::create_dir_all(source_dir.path().join("a")).unwrap();
+        fs::create_dir_all(source_dir.path().join("b")).unwrap();
+        fs::write(source_dir.path().join("a/note.txt"), "A").unwrap();
+        fs::write(source_dir.path().join("b/note.txt"), "B").unwrap();
+        let macros = vec![file_backed("A", "a/note.txt"), file_backed("B", "b/note.txt")];
+
+        let bundle = source_dir.path().join("bundle.zip");
+        export_bundle(&macros, source_dir.path(), &bundle).unwrap();
+
+        let target_dir = tempdir().unwrap();
+        let (imported, _files) = import_bundle(&bundle, target_dir.path()).unwrap();
+        let texts: Vec<String> = imported
+            .iter()
+            .map(|m| config::load_macro_text(m, target_dir.path()).unwrap())
+            .collect();
+        assert_eq!(texts, vec!["A", "B"]);
+    }
+
+    #[test]
+    fn test_unkept_files_are_removed() {
+        let source_dir = tempdir().unwrap();
+        fs::write(source_dir.path().join("sig.txt"), "bundled").unwrap();
+        fs::write(source_dir.path().join("note.txt"), "note").unwrap();
+        let bundle = source_dir.path().join("bundle.zip");
+        let macros = [file_backed("Signature", "sig.txt"), file_backed("Note", "note.txt")];
+        export_bundle(&macros, source_dir.path(), &bundle).unwrap();
+
+        // One file is already there with the same content, so it is reused
+        let target_dir = tempdir().unwrap();
+        let files_dir = target_dir.path().join(BUNDLE_FILES_DIR);
+        fs::create_dir_all(&files_dir).unwrap();
+        fs::write(files_dir.join("sig.txt"), "bundled").unwrap();
+
+        let (_, files) = import_bundle(&bundle, target_dir.path()).unwrap();
+        assert!(files_dir.join("note.txt").exists());
+        // Like a failed save: only the file the import created goes away
+        drop(files);
+        assert!(!files_dir.join("note.txt").exists());
+        assert_eq!(fs::read_to_string(files_dir.join("sig.txt")).unwrap(), "bundled");
+
+        let (_, files) = import_bundle(&bundle, target_dir.path()).unwrap();
+        files.keep();
+        assert!(files_dir.join("note.txt").exists());
+    }
+
+    #[test]
+    fn test_is_bundle_path() {
+        assert!(is_bundle_path(Path::new("macros.zip")));
+        assert!(is_bundle_path(Path::new("MACROS.ZIP")));
+        assert!(!is_bundle_path(Path::new("macros.toml")));
+    }
+
+    #[test]
+    fn test_bundled_file_name_rejects_traversal() {
+        assert_eq!(bundled_file_name("macro-files/sig.txt"), Some("sig.txt"));
+        assert_eq!(bundled_file_name("macro-files/../config.toml"), None);
+        assert_eq!(bundled_file_name("macro-files/.."), None);
+        assert_eq!(bundled_file_name("/etc/passwd"), None);
+        assert_eq!(bundled_file_name("macro-files/"), None);
+    }
+}
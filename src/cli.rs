@@ -0,0 +1,822 @@
+//! Command-line handling for KeyBlast.
+//!
+//! With no arguments KeyBlast starts the tray application. One-shot commands
+//! (like `--inject`) run, print their result, and exit without showing the tray.
+
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::audit;
+use crate::bundle;
+use crate::config;
+use crate::diff;
+use crate::hotkey::{self, RegisterResult};
+use crate::injection;
+use crate::logging;
+use crate::permission;
+
+/// What the process should do, as determined from the command line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliCommand {
+    /// Start the tray application (default).
+    RunTray {
+        /// Show the setup wizard if there is no config yet.
+        wizard: bool,
+    },
+    /// Start the tray with macros disabled and no macro hotkeys or file watcher.
+    SafeMode,
+    /// Parse and inject DSL text once, then exit.
+    Inject {
+        /// Macro text in KeyBlast DSL syntax.
+        text: String,
+        /// Delay between keystrokes in milliseconds.
+        delay_ms: u64,
+        /// Pause before injecting, to give time to focus the target.
+        wait_ms: u64,
+    },
+    /// Compare what two macro texts would type, then exit.
+    Diff {
+        old: String,
+        new: String,
+        /// Keystroke delay to expand with (affects per-character splitting).
+        delay_ms: u64,
+    },
+    /// Report whether a hotkey string can be registered, then exit.
+    CheckHotkey(String),
+    /// Run a configured macro once by name, then exit.
+    Run {
+        name: String,
+        /// Start after this `{Label}` instead of at the beginning.
+        from: Option<String>,
+        /// Pause before injecting, to give time to focus the target.
+        wait_ms: u64,
+    },
+    /// Print a diagnostics report for support, then exit.
+    Doctor,
+    /// Flag risky content in the configured macros, then exit.
+    Audit,
+    /// Add the macros from a TOML export, bundle or tab-separated list to the config, then exit.
+    Import {
+        path: String,
+        /// What to do with an imported macro whose name already exists.
+        strategy: config::MergeStrategy,
+    },
+    /// Print the version and build info, then exit.
+    Version,
+    /// Type a generated string with no delay and report throughput, then exit.
+    Bench {
+        /// Number of characters to type.
+        chars: usize,
+        /// Pause before injecting, to give time to focus the target.
+        wait_ms: u64,
+        /// Only parse and expand the text; type nothing.
+        dry_run: bool,
+    },
+}
+
+/// Usage text printed on argument errors.
+pub const USAGE: &str = "\
+Usage: keyblast [OPTIONS]
+
+Options:
+  --inject <TEXT>           Type TEXT (KeyBlast DSL) once and exit
+  --diff <OLD> <NEW>        Show how NEW's keystrokes differ from OLD's and exit
+  --run <NAME>              Run the macro named NAME once and exit
+  --from <LABEL>            With --run, start after the macro's {Label LABEL}
+  --check-hotkey <HOTKEY>   Report whether HOTKEY is free and exit
+                            (exit 0 free, 1 used by a macro, 3 taken by the OS, 4 invalid)
+  --safe-mode               Start disabled, without macro hotkeys or config auto-reload
+  --no-wizard               On first run, write the example config without asking
+  --doctor                  Print diagnostics (OS, permissions, config, logs) and exit
+  --audit                   Flag risky content in your macros and exit (exit 1 if any)
+  --import <FILE>           Add the macros in FILE (.toml, .zip, or .tsv/.txt list) and exit
+  --merge <STRATEGY>        With --import, what to do with names that already exist:
+                            skip (default), overwrite or rename
+  --bench <CHARS>           Type CHARS characters with no delay, report chars/sec and exit
+  --dry-run                 With --bench, time parsing and expansion without typing
+  --delay <MS>              Delay between keystrokes for --inject/--diff (default: 0)
+  --wait <MS>               Pause before --inject/--run/--bench starts typing (default: 0)
+  -v, --version             Print version, target and git commit and exit";
+
+/// Parse command-line arguments (excluding the program name).
+///
+/// Returns an error message suitable for printing alongside `USAGE`.
+pub fn parse_args<I, S>(args: I) -> Result<CliCommand, String>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut inject_text: Option<String> = None;
+    let mut diff_texts: Option<(String, String)> = None;
+    let mut check_hotkey: Option<String> = None;
+    let mut run_name: Option<String> = None;
+    let mut from_label: Option<String> = None;
+    let mut doctor = false;
+    let mut audit = false;
+    let mut import_path: Option<String> = None;
+    let mut merge_strategy: Option<config::MergeStrategy> = None;
+    let mut safe_mode = false;
+    let mut no_wizard = false;
+    let mut bench_chars: Option<usize> = None;
+    let mut dry_run = false;
+    let mut delay_ms: Option<u64> = None;
+    let mut wait_ms: Option<u64> = None;
+
+    let mut args = args.into_iter().map(Into::into);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--inject" => {
+                let text = args.next().ok_or("--inject requires a text argument")?;
+                inject_text = Some(text);
+            }
+            "--diff" => {
+                let old = args.next().ok_or("--diff requires OLD and NEW text arguments")?;
+                let new = args.next().ok_or("--diff requires OLD and NEW text arguments")?;
+                diff_texts = Some((old, new));
+            }
+            "--check-hotkey" => {
+                let hotkey = args.next().ok_or("--check-hotkey requires a hotkey argument")?;
+                check_hotkey = Some(hotkey);
+            }
+            "--run" => {
+                let name = args.next().ok_or("--run requires a macro name")?;
+                run_name = Some(name);
+            }
+            "--from" => {
+                let label = args.next().ok_or("--from requires a label name")?;
+                from_label = Some(label);
+            }
+            "--doctor" => doctor = true,
+            "--audit" => audit = true,
+            "--import" => {
+                let path = args.next().ok_or("--import requires a file argument")?;
+                import_path = Some(path);
+            }
+            "--merge" => merge_strategy = Some(parse_merge_strategy(args.next())?),
+            "--safe-mode" => safe_mode = true,
+            "--no-wizard" => no_wizard = true,
+            "--bench" => {
+                let value = args.next().ok_or("--bench requires a character count")?;
+                let chars = value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| format!("Invalid value for --bench: '{}'", value))?;
+                bench_chars = Some(chars);
+            }
+            "--dry-run" => dry_run = true,
+            // Like most CLIs, --version wins over anything else on the line
+            "--version" | "-v" => return Ok(CliCommand::Version),
+            "--delay" => delay_ms = Some(parse_ms(&arg, args.next())?),
+            "--wait" => wait_ms = Some(parse_ms(&arg, args.next())?),
+            other => return Err(format!("Unknown argument: {}", other)),
+        }
+    }
+
+    if dry_run && bench_chars.is_none() {
+        return Err("--dry-run can only be used with --bench".to_string());
+    }
+
+    if from_label.is_some() && run_name.is_none() {
+        return Err("--from can only be used with --run".to_string());
+    }
+
+    if merge_strategy.is_some() && import_path.is_none() {
+        return Err("--merge can only be used with --import".to_string());
+    }
+
+    if no_wizard {
+        if safe_mode || doctor || audit || run_name.is_some() || check_hotkey.is_some() || inject_text.is_some()
+            || diff_texts.is_some() || bench_chars.is_some() || delay_ms.is_some() || wait_ms.is_some()
+        {
+            return Err("--no-wizard cannot be combined with other options".to_string());
+        }
+        return Ok(CliCommand::RunTray { wizard: false });
+    }
+
+    if let Some(path) = import_path {
+        if audit || safe_mode || doctor || run_name.is_some() || check_hotkey.is_some() || inject_text.is_some()
+            || diff_texts.is_some() || bench_chars.is_some() || delay_ms.is_some() || wait_ms.is_some()
+        {
+            return Err("--import cannot be combined with other options".to_string());
+        }
+        return Ok(CliCommand::Import {
+            path,
+            strategy: merge_strategy.unwrap_or_default(),
+        });
+    }
+
+    if audit {
+        if safe_mode || doctor || run_name.is_some() || check_hotkey.is_some() || inject_text.is_some()
+            || diff_texts.is_some() || bench_chars.is_some() || delay_ms.is_some() || wait_ms.is_some()
+        {
+            return Err("--audit cannot be combined with other options".to_string());
+        }
+        return Ok(CliCommand::Audit);
+    }
+
+    if let Some(name) = run_name {
+        // The macro's own delay (or default_delay_ms) applies, so --delay doesn't
+        if safe_mode || doctor || check_hotkey.is_some() || inject_text.is_some()
+            || diff_texts.is_some() || bench_chars.is_some() || delay_ms.is_some()
+        {
+            return Err("--run can only be combined with --from and --wait".to_string());
+        }
+        return Ok(CliCommand::Run {
+            name,
+            from: from_label,
+            wait_ms: wait_ms.unwrap_or(0),
+        });
+    }
+
+    if safe_mode {
+        if doctor || check_hotkey.is_some() || inject_text.is_some() || diff_texts.is_some()
+            || bench_chars.is_some() || delay_ms.is_some() || wait_ms.is_some()
+        {
+            return Err("--safe-mode cannot be combined with other options".to_string());
+        }
+        return Ok(CliCommand::SafeMode);
+    }
+
+    if doctor {
+        if check_hotkey.is_some() || inject_text.is_some() || diff_texts.is_some()
+            || bench_chars.is_some() || delay_ms.is_some() || wait_ms.is_some()
+        {
+            return Err("--doctor cannot be combined with other options".to_string());
+        }
+        return Ok(CliCommand::Doctor);
+    }
+
+    if let Some(hotkey) = check_hotkey {
+        if inject_text.is_some() || diff_texts.is_some() || bench_chars.is_some()
+            || delay_ms.is_some() || wait_ms.is_some()
+        {
+            return Err("--check-hotkey cannot be combined with other options".to_string());
+        }
+        return Ok(CliCommand::CheckHotkey(hotkey));
+    }
+
+    if let Some(chars) = bench_chars {
+        // Always typed with no delay: the point is to measure raw injection speed
+        if inject_text.is_some() || diff_texts.is_some() || delay_ms.is_some() {
+            return Err("--bench can only be combined with --wait and --dry-run".to_string());
+        }
+        return Ok(CliCommand::Bench {
+            chars,
+            wait_ms: wait_ms.unwrap_or(0),
+            dry_run,
+        });
+    }
+
+    match (inject_text, diff_texts) {
+        (Some(_), Some(_)) => Err("--inject and --diff cannot be combined".to_string()),
+        (Some(text), None) => Ok(CliCommand::Inject {
+            text,
+            delay_ms: delay_ms.unwrap_or(0),
+            wait_ms: wait_ms.unwrap_or(0),
+        }),
+        (None, Some(_)) if wait_ms.is_some() => {
+            Err("--wait can only be used with --inject".to_string())
+        }
+        (None, Some((old, new))) => Ok(CliCommand::Diff {
+            old,
+            new,
+            delay_ms: delay_ms.unwrap_or(0),
+        }),
+        (None, None) if delay_ms.is_some() || wait_ms.is_some() => {
+            Err("--delay and --wait can only be used with --inject or --diff".to_string())
+        }
+        (None, None) => Ok(CliCommand::RunTray { wizard: true }),
+    }
+}
+
+/// Parse a millisecond value for the given flag.
+fn parse_ms(flag: &str, value: Option<String>) -> Result<u64, String> {
+    let value = value.ok_or_else(|| format!("{} requires a value in milliseconds", flag))?;
+    value
+        .parse::<u64>()
+        .map_err(|_| format!("Invalid value for {}: '{}'", flag, value))
+}
+
+/// Parse the value of `--merge`.
+fn parse_merge_strategy(value: Option<String>) -> Result<config::MergeStrategy, String> {
+    let value = value.ok_or("--merge requires skip, overwrite or rename")?;
+    match value.as_str() {
+        "skip" => Ok(config::MergeStrategy::Skip),
+        "overwrite" => Ok(config::MergeStrategy::Overwrite),
+        "rename" => Ok(config::MergeStrategy::RenameOnConflict),
+        _ => Err(format!("Invalid value for --merge: '{}'", value)),
+    }
+}
+
+/// Inject DSL text once.
+///
+/// Checks accessibility permission first so a missing grant produces a clear
+/// error instead of silently typing nothing.
+pub fn run_inject(text: &str, delay_ms: u64, wait_ms: u64) -> Result<(), String> {
+    if !permission::check_accessibility_permission() {
+        return Err("Accessibility permission is required to inject keystrokes".to_string());
+    }
+
+    let mut injector = injection::KeystrokeInjector::new()
+        .map_err(|e| format!("Failed to initialize keystroke injector: {}", e))?;
+
+    if wait_ms > 0 {
+        thread::sleep(Duration::from_millis(wait_ms));
+    }
+
+    let segments = injection::parse_macro_sequence(text);
+    injector
+        .execute_sequence(&segments, delay_ms, 0)
+        .map_err(|e| format!("Injection failed: {}", e))
+}
+
+/// Run the configured macro called `name` once, optionally starting after
+/// its `{Label from}`.
+///
+/// Uses the macro's own delay and strictness. Runs like a short macro does
+/// from a hotkey: `{Prompt}` is skipped and Escape can't cancel.
+pub fn run_macro(name: &str, from: Option<&str>, wait_ms: u64) -> Result<(), String> {
+    let cfg = config::load_config().map_err(|e| format!("Failed to load config: {}", e))?;
+    let macro_def = cfg.macros.iter()
+        .find(|m| m.name == name)
+        .ok_or_else(|| format!("No macro named '{}'", name))?;
+    if !config::macro_applies(macro_def) {
+        return Err(format!(
+            "Macro '{}' is only for {}",
+            name,
+            macro_def.only_on.as_deref().unwrap_or_default()
+        ));
+    }
+
+    let text = config::load_macro_text(macro_def, &config::config_dir())
+        .map_err(|e| format!("Failed to read text for macro '{}': {}", name, e))?;
+    let segments = if macro_def.is_strict(&cfg.settings) {
+        injection::parse_macro_sequence_strict(&text).map_err(|issues| {
+            let issues: Vec<_> = issues.iter().map(ToString::to_string).collect();
+            format!("Macro '{}' has DSL errors: {}", name, issues.join(", "))
+        })?
+    } else {
+        injection::parse_macro_sequence(&text)
+    };
+    // Resolve the label before typing anything (one inside a {Repeat} is
+    // its first time round)
+    let segments = injection::flatten_repeats(segments);
+    let segments = match from {
+        Some(label) => injection::segments_after_label(&segments, label)
+            .map_err(|e| format!("Macro '{}': {}", name, e))?,
+        None => &segments[..],
+    };
+
+    if !permission::check_accessibility_permission() {
+        return Err("Accessibility permission is required to inject keystrokes".to_string());
+    }
+
+    let mut injector = injection::KeystrokeInjector::new()
+        .map_err(|e| format!("Failed to initialize keystroke injector: {}", e))?;
+    injector.configure(&cfg.settings, macro_def);
+
+    if wait_ms > 0 {
+        thread::sleep(Duration::from_millis(wait_ms));
+    }
+
+    logging::log_trigger(macro_def, "cli");
+    injector
+        .execute_sequence(segments, macro_def.effective_delay_ms(&cfg.settings), macro_def.jitter_ms)
+        .map_err(|e| format!("Injection failed: {}", e))
+}
+
+/// Audit the configured macros (see `audit`).
+///
+/// Returns the report and whether anything was flagged.
+pub fn run_audit() -> Result<(String, bool), String> {
+    let cfg = config::load_config().map_err(|e| format!("Failed to load config: {}", e))?;
+    Ok(audit::format_audit(&cfg.macros))
+}
+
+/// Add the macros in `path` to the config and save it.
+///
+/// Macros named like an existing one are handled per `strategy` (see
+/// `config::merge_imported`). Unlike Import Macros in the tray, no hotkeys
+/// are registered here: hotkey clashes are only reported as config warnings.
+/// A running KeyBlast picks the change up on auto-reload. Returns the summary
+/// to print.
+pub fn run_import(path: &Path, strategy: config::MergeStrategy) -> Result<String, String> {
+    let mut cfg = config::load_config().map_err(|e| format!("Failed to load config: {}", e))?;
+    if cfg.settings.locked {
+        return Err(format!("Failed to import macros: {}", config::ConfigError::Locked));
+    }
+    let (imported, files) = bundle::import_file(path, &config::config_dir())
+        .map_err(|e| format!("Failed to import {}: {}", path.display(), e))?;
+
+    let mut added = 0;
+    let mut overwritten = Vec::new();
+    let mut renamed = Vec::new();
+    let mut duplicates = Vec::new();
+    for action in config::merge_imported(&cfg.macros, imported, strategy) {
+        let mut macro_def = match action {
+            config::MergeAction::Add(macro_def) => macro_def,
+            config::MergeAction::Skip(name) => {
+                duplicates.push(name);
+                continue;
+            }
+            config::MergeAction::Overwrite(mut macro_def) => {
+                macro_def.modified = Some(config::timestamp_now());
+                overwritten.push(macro_def.name.clone());
+                if let Some(existing) = cfg.macros.iter_mut().find(|m| m.id == macro_def.id) {
+                    *existing = macro_def;
+                }
+                continue;
+            }
+            config::MergeAction::Rename { original, macro_def } => {
+                renamed.push(format!("{} -> {}", original, macro_def.name));
+                macro_def
+            }
+        };
+        macro_def.modified = Some(config::timestamp_now());
+        cfg.macros.push(macro_def);
+        added += 1;
+    }
+    cfg.settings.last_import_path = Some(path.display().to_string());
+    cfg.settings.last_import_strategy = Some(strategy);
+    config::save_config(&cfg).map_err(|e| format!("Failed to save config: {}", e))?;
+    files.keep();
+
+    let mut summary = format!("Added {} macros from {}\n", added, path.display());
+    if !overwritten.is_empty() {
+        summary.push_str(&format!("Overwritten: {}\n", overwritten.join(", ")));
+    }
+    if !renamed.is_empty() {
+        summary.push_str(&format!("Renamed: {}\n", renamed.join(", ")));
+    }
+    if !duplicates.is_empty() {
+        summary.push_str(&format!("Already present: {}\n", duplicates.join(", ")));
+    }
+    for warning in config::validate_config(&cfg) {
+        summary.push_str(&format!("Warning: {}\n", warning));
+    }
+    Ok(summary)
+}
+
+/// Print the differences between two macro texts.
+///
+/// Returns true if they are identical.
+pub fn run_diff(old: &str, new: &str, delay_ms: u64) -> bool {
+    let summary = diff::plan_diff(old, new, delay_ms);
+    print!("{}", summary);
+    if summary.is_identical() {
+        println!();
+    }
+    summary.is_identical()
+}
+
+/// Version line for `--version`, e.g.
+/// "KeyBlast 0.1.0 (x86_64-pc-windows-gnu, git 1a2b3c4)".
+///
+/// The git hash is left out when the build had no git checkout.
+pub fn version_string(version: &str, target: &str, git_hash: Option<&str>) -> String {
+    match git_hash {
+        Some(hash) => format!("KeyBlast {} ({}, git {})", version, target, hash),
+        None => format!("KeyBlast {} ({})", version, target),
+    }
+}
+
+/// `version_string` for this build (values embedded by build.rs).
+pub fn build_version() -> String {
+    version_string(
+        env!("CARGO_PKG_VERSION"),
+        env!("KEYBLAST_TARGET"),
+        option_env!("KEYBLAST_GIT_HASH"),
+    )
+}
+
+/// Characters cycled through to build the benchmark text.
+const BENCH_ALPHABET: &str = "abcdefghijklmnopqrstuvwxyz ";
+
+/// Throughput of typing `chars` characters in `elapsed`.
+///
+/// A zero duration (possible for tiny dry runs) reports 0 rather than infinity.
+pub fn chars_per_second(chars: usize, elapsed: Duration) -> f64 {
+    let secs = elapsed.as_secs_f64();
+    if secs > 0.0 {
+        chars as f64 / secs
+    } else {
+        0.0
+    }
+}
+
+/// Type `chars` generated characters with `delay_ms = 0` and report the elapsed
+/// time and throughput.
+///
+/// With `dry_run` the text is only parsed and expanded, which measures
+/// KeyBlast's own overhead without touching the focused app.
+/// Returns the summary line (also logged).
+pub fn run_bench(chars: usize, wait_ms: u64, dry_run: bool) -> Result<String, String> {
+    let text: String = BENCH_ALPHABET.chars().cycle().take(chars).collect();
+
+    let elapsed = if dry_run {
+        let start = Instant::now();
+        let segments = injection::parse_macro_sequence(&text);
+        let expanded = injection::expand_segments(segments, 0);
+        let elapsed = start.elapsed();
+        tracing::debug!("Dry run expanded {} segments", expanded.len());
+        elapsed
+    } else {
+        if !permission::check_accessibility_permission() {
+            return Err("Accessibility permission is required to inject keystrokes".to_string());
+        }
+        let mut injector = injection::KeystrokeInjector::new()
+            .map_err(|e| format!("Failed to initialize keystroke injector: {}", e))?;
+
+        if wait_ms > 0 {
+            thread::sleep(Duration::from_millis(wait_ms));
+        }
+
+        // Timing includes parsing, so it matches what a hotkey-triggered macro costs
+        let start = Instant::now();
+        let segments = injection::parse_macro_sequence(&text);
+        injector
+            .execute_sequence(&segments, 0, 0)
+            .map_err(|e| format!("Injection failed: {}", e))?;
+        start.elapsed()
+    };
+
+    let summary = format!(
+        "{} {} chars in {:.3}s ({:.0} chars/sec)",
+        if dry_run { "Expanded" } else { "Typed" },
+        chars,
+        elapsed.as_secs_f64(),
+        chars_per_second(chars, elapsed)
+    );
+    tracing::info!("Bench: {}", summary);
+    Ok(summary)
+}
+
+/// Exit code for a hotkey that can't be checked (unparseable or registration error).
+const CHECK_HOTKEY_INVALID: i32 = 4;
+
+/// Check whether a hotkey is free: not used by a configured macro and not
+/// taken by the OS or another app.
+///
+/// Briefly creates a hotkey manager, so must run on the main thread.
+/// Returns the message to print and the exit code.
+pub fn run_check_hotkey(hotkey_str: &str) -> (String, i32) {
+    let Some(hotkey) = config::parse_hotkey_string(hotkey_str) else {
+        return (format!("Invalid hotkey: '{}'", hotkey_str), CHECK_HOTKEY_INVALID);
+    };
+
+    // A macro in the config already uses it (even if the tray isn't running)
+    let canonical = config::hotkey_to_string(&hotkey);
+    if let Ok(cfg) = config::load_config() {
+        let owner = cfg.macros.iter()
+            .find(|m| config::normalize_hotkey_string(&m.hotkey).as_deref() == Some(canonical.as_str()));
+        if let Some(owner) = owner {
+            let result = RegisterResult::ConflictInternal(format!(
+                "Hotkey {} is already used by macro '{}'",
+                canonical, owner.name
+            ));
+            return check_hotkey_outcome(&canonical, &result);
+        }
+    }
+
+    let result = match hotkey::HotkeyManager::new() {
+        Ok(mut manager) => manager.check_available(hotkey),
+        Err(e) => RegisterResult::Error(format!("Failed to create hotkey manager: {}", e)),
+    };
+    check_hotkey_outcome(&canonical, &result)
+}
+
+/// Map a hotkey availability result to the printed message and exit code.
+fn check_hotkey_outcome(hotkey: &str, result: &RegisterResult) -> (String, i32) {
+    match result {
+        RegisterResult::Success => (format!("Hotkey {} is free", hotkey), 0),
+        RegisterResult::ConflictInternal(msg) => (msg.clone(), 1),
+        RegisterResult::ConflictExternal(msg) => (msg.clone(), 3),
+        RegisterResult::Error(msg) => (msg.clone(), CHECK_HOTKEY_INVALID),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_no_args_runs_tray() {
+        let args: Vec<String> = vec![];
+        assert_eq!(parse_args(args), Ok(CliCommand::RunTray { wizard: true }));
+    }
+
+    #[test]
+    fn test_parse_no_wizard() {
+        assert_eq!(parse_args(["--no-wizard"]), Ok(CliCommand::RunTray { wizard: false }));
+        assert!(parse_args(["--no-wizard", "--safe-mode"]).is_err());
+        assert!(parse_args(["--no-wizard", "--inject", "hi"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_inject_defaults() {
+        assert_eq!(
+            parse_args(["--inject", "Hello{Enter}World"]),
+            Ok(CliCommand::Inject {
+                text: "Hello{Enter}World".to_string(),
+                delay_ms: 0,
+                wait_ms: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_inject_with_flags() {
+        // Flags are accepted in any order
+        assert_eq!(
+            parse_args(["--wait", "500", "--inject", "hi", "--delay", "20"]),
+            Ok(CliCommand::Inject {
+                text: "hi".to_string(),
+                delay_ms: 20,
+                wait_ms: 500,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_inject_missing_text() {
+        assert!(parse_args(["--inject"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_invalid_delay() {
+        assert!(parse_args(["--inject", "hi", "--delay", "fast"]).is_err());
+        assert!(parse_args(["--inject", "hi", "--wait"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_delay_without_inject() {
+        assert!(parse_args(["--delay", "10"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_unknown_argument() {
+        assert!(parse_args(["--bogus"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_diff() {
+        assert_eq!(
+            parse_args(["--diff", "a{Enter}", "a{Tab}", "--delay", "5"]),
+            Ok(CliCommand::Diff {
+                old: "a{Enter}".to_string(),
+                new: "a{Tab}".to_string(),
+                delay_ms: 5,
+            })
+        );
+        assert!(parse_args(["--diff", "only-one"]).is_err());
+        assert!(parse_args(["--diff", "a", "b", "--wait", "10"]).is_err());
+        assert!(parse_args(["--diff", "a", "b", "--inject", "c"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_check_hotkey() {
+        assert_eq!(
+            parse_args(["--check-hotkey", "ctrl+shift+k"]),
+            Ok(CliCommand::CheckHotkey("ctrl+shift+k".to_string()))
+        );
+        assert!(parse_args(["--check-hotkey"]).is_err());
+        assert!(parse_args(["--check-hotkey", "ctrl+k", "--delay", "5"]).is_err());
+    }
+
+    #[test]
+    fn test_check_hotkey_outcome() {
+        assert_eq!(
+            check_hotkey_outcome("ctrl+shift+k", &RegisterResult::Success),
+            ("Hotkey ctrl+shift+k is free".to_string(), 0)
+        );
+        assert_eq!(
+            check_hotkey_outcome("ctrl+k", &RegisterResult::ConflictInternal("used".to_string())),
+            ("used".to_string(), 1)
+        );
+        assert_eq!(
+            check_hotkey_outcome("ctrl+k", &RegisterResult::ConflictExternal("taken".to_string())),
+            ("taken".to_string(), 3)
+        );
+        assert_eq!(
+            check_hotkey_outcome("ctrl+k", &RegisterResult::Error("boom".to_string())).1,
+            CHECK_HOTKEY_INVALID
+        );
+    }
+
+    #[test]
+    fn test_check_invalid_hotkey() {
+        let (message, code) = run_check_hotkey("ctrl+shift");
+        assert!(message.contains("Invalid hotkey"));
+        assert_eq!(code, CHECK_HOTKEY_INVALID);
+    }
+
+    #[test]
+    fn test_parse_doctor() {
+        assert_eq!(parse_args(["--doctor"]), Ok(CliCommand::Doctor));
+        assert!(parse_args(["--doctor", "--inject", "hi"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_safe_mode() {
+        assert_eq!(parse_args(["--safe-mode"]), Ok(CliCommand::SafeMode));
+        assert!(parse_args(["--safe-mode", "--doctor"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_bench() {
+        assert_eq!(
+            parse_args(["--bench", "500", "--wait", "1000"]),
+            Ok(CliCommand::Bench { chars: 500, wait_ms: 1000, dry_run: false })
+        );
+        assert_eq!(
+            parse_args(["--dry-run", "--bench", "10"]),
+            Ok(CliCommand::Bench { chars: 10, wait_ms: 0, dry_run: true })
+        );
+        assert!(parse_args(["--bench", "0"]).is_err());
+        assert!(parse_args(["--bench", "ten"]).is_err());
+        assert!(parse_args(["--bench", "10", "--delay", "5"]).is_err());
+        assert!(parse_args(["--dry-run"]).is_err());
+        assert!(parse_args(["--doctor", "--bench", "10"]).is_err());
+    }
+
+    #[test]
+    fn test_chars_per_second() {
+        assert_eq!(chars_per_second(1000, Duration::from_millis(500)), 2000.0);
+        assert_eq!(chars_per_second(30, Duration::from_secs(2)), 15.0);
+        assert_eq!(chars_per_second(10, Duration::ZERO), 0.0);
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_args(["--version"]), Ok(CliCommand::Version));
+        assert_eq!(parse_args(["-v"]), Ok(CliCommand::Version));
+        assert_eq!(parse_args(["--doctor", "-v"]), Ok(CliCommand::Version));
+    }
+
+    #[test]
+    fn test_version_string() {
+        assert_eq!(
+            version_string("0.1.0", "x86_64-pc-windows-gnu", Some("1a2b3c4")),
+            "KeyBlast 0.1.0 (x86_64-pc-windows-gnu, git 1a2b3c4)"
+        );
+        assert_eq!(
+            version_string("0.1.0", "aarch64-apple-darwin", None),
+            "KeyBlast 0.1.0 (aarch64-apple-darwin)"
+        );
+        assert!(build_version().starts_with(&format!("KeyBlast {} (", env!("CARGO_PKG_VERSION"))));
+    }
+
+    #[test]
+    fn test_parse_run() {
+        assert_eq!(
+            parse_args(["--run", "Onboarding"]),
+            Ok(CliCommand::Run { name: "Onboarding".to_string(), from: None, wait_ms: 0 })
+        );
+        assert_eq!(
+            parse_args(["--from", "step 3", "--run", "Onboarding", "--wait", "2000"]),
+            Ok(CliCommand::Run {
+                name: "Onboarding".to_string(),
+                from: Some("step 3".to_string()),
+                wait_ms: 2000,
+            })
+        );
+        assert!(parse_args(["--run"]).is_err());
+        assert!(parse_args(["--run", "A", "--from"]).is_err());
+        assert!(parse_args(["--from", "x"]).is_err());
+        assert!(parse_args(["--run", "A", "--delay", "5"]).is_err());
+        assert!(parse_args(["--run", "A", "--inject", "hi"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_audit() {
+        assert_eq!(parse_args(["--audit"]), Ok(CliCommand::Audit));
+        assert!(parse_args(["--audit", "--doctor"]).is_err());
+        assert!(parse_args(["--audit", "--run", "A"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_import() {
+        assert_eq!(
+            parse_args(["--import", "list.tsv"]),
+            Ok(CliCommand::Import {
+                path: "list.tsv".to_string(),
+                strategy: config::MergeStrategy::Skip,
+            })
+        );
+        assert_eq!(
+            parse_args(["--import", "list.tsv", "--merge", "rename"]),
+            Ok(CliCommand::Import {
+                path: "list.tsv".to_string(),
+                strategy: config::MergeStrategy::RenameOnConflict,
+            })
+        );
+        assert!(parse_args(["--import", "list.tsv", "--merge", "replace"]).is_err());
+        assert!(parse_args(["--import", "list.tsv", "--merge"]).is_err());
+        assert!(parse_args(["--merge", "skip"]).is_err());
+        assert!(parse_args(["--import"]).is_err());
+        assert!(parse_args(["--import", "list.tsv", "--audit"]).is_err());
+        assert!(parse_args(["--import", "list.tsv", "--run", "A"]).is_err());
+    }
+}
@@ -0,0 +1,87 @@
+//! Internal clipboard history for KeyBlast.
+//!
+//! `{Copy text}` / `{SetClip text}` put text on the system clipboard and push
+//! it onto this small bounded ring; `{Paste N}` types the Nth most recent
+//! entry (0 = the latest). This is KeyBlast's own history, not the OS
+//! clipboard manager's.
+
+use std::collections::VecDeque;
+
+/// Number of entries kept by default.
+pub const DEFAULT_RING_CAPACITY: usize = 10;
+
+/// Bounded most-recent-first history of copied text.
+#[derive(Debug, Clone)]
+pub struct ClipboardRing {
+    entries: VecDeque<String>,
+    capacity: usize,
+}
+
+impl ClipboardRing {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Add text as the most recent entry, dropping the oldest if full.
+    pub fn push(&mut self, text: String) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.entries.push_front(text);
+        self.entries.truncate(self.capacity);
+    }
+
+    /// Get the Nth most recent entry (0 = latest), if there is one.
+    pub fn get(&self, slot: usize) -> Option<&str> {
+        self.entries.get(slot).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+impl Default for ClipboardRing {
+    fn default() -> Self {
+        Self::new(DEFAULT_RING_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_and_get_most_recent_first() {
+        let mut ring = ClipboardRing::new(3);
+        ring.push("first".to_string());
+        ring.push("second".to_string());
+
+        assert_eq!(ring.get(0), Some("second"));
+        assert_eq!(ring.get(1), Some("first"));
+        assert_eq!(ring.get(2), None);
+    }
+
+    #[test]
+    fn test_push_drops_oldest_at_capacity() {
+        let mut ring = ClipboardRing::new(2);
+        for text in ["a", "b", "c"] {
+            ring.push(text.to_string());
+        }
+
+        assert_eq!(ring.len(), 2);
+        assert_eq!(ring.get(0), Some("c"));
+        assert_eq!(ring.get(1), Some("b"));
+        assert_eq!(ring.get(2), None);
+    }
+
+    #[test]
+    fn test_zero_capacity_keeps_nothing() {
+        let mut ring = ClipboardRing::new(0);
+        ring.push("a".to_string());
+        assert_eq!(ring.get(0), None);
+    }
+}
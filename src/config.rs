@@ -1,12 +1,15 @@
-/// Configuration management for KeyBlast.
-///
-/// Provides persistent storage of macro definitions in a TOML configuration file.
-/// Handles cross-platform config paths and serialization/deserialization.
+//! Configuration management for KeyBlast.
+//!
+//! Provides persistent storage of macro definitions in a TOML configuration file
+//! (or JSON, for generated configs: see `is_json_path`).
+//! Handles cross-platform config paths and serialization/deserialization.
 
 use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
 use serde::{Deserialize, Serialize};
 use global_hotkey::hotkey::{Code, HotKey, Modifiers};
 use uuid::Uuid;
@@ -20,6 +23,14 @@ pub enum ConfigError {
     Parse(toml::de::Error),
     /// Failed to serialize to TOML.
     Serialize(toml::ser::Error),
+    /// Failed to parse or serialize JSON.
+    Json(serde_json::Error),
+    /// Failed to read or write a zip bundle.
+    Zip(zip::result::ZipError),
+    /// The config has `settings.locked` set and must not be written.
+    Locked,
+    /// A line of an imported tab-separated list is malformed (1-based line number).
+    InvalidLine { line: usize, message: String },
 }
 
 impl std::fmt::Display for ConfigError {
@@ -28,6 +39,10 @@ impl std::fmt::Display for ConfigError {
             ConfigError::Io(e) => write!(f, "IO error: {}", e),
             ConfigError::Parse(e) => write!(f, "Parse error: {}", e),
             ConfigError::Serialize(e) => write!(f, "Serialize error: {}", e),
+            ConfigError::Json(e) => write!(f, "JSON error: {}", e),
+            ConfigError::Zip(e) => write!(f, "Zip error: {}", e),
+            ConfigError::Locked => write!(f, "Config is locked (settings.locked = true)"),
+            ConfigError::InvalidLine { line, message } => write!(f, "Line {}: {}", line, message),
         }
     }
 }
@@ -40,6 +55,12 @@ impl From<io::Error> for ConfigError {
     }
 }
 
+impl From<zip::result::ZipError> for ConfigError {
+    fn from(e: zip::result::ZipError) -> Self {
+        ConfigError::Zip(e)
+    }
+}
+
 impl From<toml::de::Error> for ConfigError {
     fn from(e: toml::de::Error) -> Self {
         ConfigError::Parse(e)
@@ -52,12 +73,89 @@ impl From<toml::ser::Error> for ConfigError {
     }
 }
 
+impl From<serde_json::Error> for ConfigError {
+    fn from(e: serde_json::Error) -> Self {
+        ConfigError::Json(e)
+    }
+}
+
+/// Problem with a single macro, found by `MacroDefinition::validate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MacroIssue {
+    /// No `text` and no `text_file`, so the macro types nothing.
+    EmptyText,
+    /// Hotkey has modifiers but no key (e.g. "ctrl+shift"), so it can never register.
+    NoKeyInHotkey { hotkey: String },
+    /// Hotkey can't be parsed (unknown key or modifier), so it can never register.
+    InvalidHotkey { hotkey: String },
+    /// Hotkey without Ctrl, Alt or Meta on a letter or digit, which steals
+    /// that key from normal typing everywhere.
+    RiskyHotkey { hotkey: String },
+    /// `{KeyDown}` keys never released by a matching `{KeyUp}`.
+    UnbalancedKeyDown { keys: Vec<String> },
+    /// Macro's `text_file` doesn't exist.
+    MissingTextFile { path: PathBuf },
+    /// Strictly parsed macro has DSL errors (unknown commands, unescaped braces).
+    InvalidDsl { issues: Vec<String> },
+    /// Advisory: content worth reviewing before sharing (see `audit`).
+    Risk(crate::audit::RiskFlag),
+    /// `mouse_button` isn't one of "middle", "x1" or "x2".
+    InvalidMouseButton { button: String },
+}
+
+impl MacroIssue {
+    /// Whether the macro can't work as written (as opposed to advisory
+    /// issues like a risky hotkey), so an edit with it isn't saved.
+    pub fn is_blocking(&self) -> bool {
+        !matches!(
+            self,
+            MacroIssue::RiskyHotkey { .. }
+                | MacroIssue::UnbalancedKeyDown { .. }
+                | MacroIssue::Risk(_)
+        )
+    }
+}
+
+impl std::fmt::Display for MacroIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MacroIssue::EmptyText => write!(f, "text is empty"),
+            MacroIssue::NoKeyInHotkey { hotkey } => {
+                write!(
+                    f,
+                    "hotkey '{}' has no key, only modifiers (add a key like '{}+k')",
+                    hotkey, hotkey.trim_end_matches(|c: char| c == '+' || c.is_whitespace())
+                )
+            }
+            MacroIssue::InvalidHotkey { hotkey } => write!(f, "hotkey '{}' is not valid", hotkey),
+            MacroIssue::RiskyHotkey { hotkey } => {
+                write!(f, "hotkey '{}' has no Ctrl, Alt or Meta, so it blocks normal typing of that key", hotkey)
+            }
+            MacroIssue::UnbalancedKeyDown { keys } => {
+                write!(f, "{{KeyDown}} without a matching {{KeyUp}}: {}", keys.join(", "))
+            }
+            MacroIssue::MissingTextFile { path } => {
+                write!(f, "text file does not exist: {}", path.display())
+            }
+            MacroIssue::InvalidDsl { issues } => write!(f, "DSL errors: {}", issues.join(", ")),
+            MacroIssue::Risk(flag) => write!(f, "review: {}", flag),
+            MacroIssue::InvalidMouseButton { button } => {
+                write!(f, "mouse button '{}' is not valid (use middle, x1 or x2)", button)
+            }
+        }
+    }
+}
+
 /// Warnings found during config validation.
 #[derive(Debug, Clone)]
 pub enum ValidationWarning {
     DuplicateName(String),
     DuplicateHotkey { hotkey: String, names: Vec<String> },
     DuplicateId { id: Uuid, names: Vec<String> },
+    /// A single-macro problem (see `MacroDefinition::validate`).
+    Macro { name: String, issue: MacroIssue },
+    /// A `/pattern/` entry in `blocklist_apps` that isn't a valid regex, so it never matches.
+    InvalidBlocklistEntry { pattern: String, error: String },
 }
 
 impl std::fmt::Display for ValidationWarning {
@@ -72,10 +170,64 @@ impl std::fmt::Display for ValidationWarning {
             ValidationWarning::DuplicateId { id, names } => {
                 write!(f, "Duplicate macro ID '{}' used by: {}", id, names.join(", "))
             }
+            ValidationWarning::Macro { name, issue } => {
+                write!(f, "Macro '{}': {}", name, issue)
+            }
+            ValidationWarning::InvalidBlocklistEntry { pattern, error } => {
+                write!(f, "blocklist_apps entry '{}' is not a valid regex: {}", pattern, error)
+            }
         }
     }
 }
 
+/// A macro's new name, hotkey and text, as entered with Edit... in the tray.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacroEdit {
+    pub name: String,
+    pub hotkey: String,
+    /// None keeps the current text (e.g. it comes from `text_file`).
+    pub text: Option<String>,
+}
+
+/// The macro with ID `id` in `macros`, changed as described by `edit`.
+///
+/// Fails (with a message for the user) if the macro is gone, the name is
+/// empty or taken by another macro, the hotkey doesn't parse, or the text
+/// is empty. `hotkey_enabled` is kept, so a menu-only macro stays one (and
+/// may leave its hotkey empty).
+pub fn apply_macro_edit(macros: &[MacroDefinition], id: Uuid, edit: MacroEdit) -> Result<MacroDefinition, String> {
+    let mut macro_def = macros
+        .iter()
+        .find(|m| m.id == id)
+        .cloned()
+        .ok_or("The macro no longer exists")?;
+
+    let name = edit.name.trim();
+    if name.is_empty() {
+        return Err("The name can't be empty".to_string());
+    }
+    if macros.iter().any(|m| m.id != id && m.name == name) {
+        return Err(format!("Another macro is already named '{}'", name));
+    }
+    let hotkey = edit.hotkey.trim();
+    // A menu-only macro may have no hotkey at all
+    let optional = !macro_def.hotkey_enabled && hotkey.is_empty();
+    if !optional && !is_valid_hotkey(hotkey) {
+        return Err(format!("'{}' is not a valid hotkey", hotkey));
+    }
+    if edit.text.as_ref().is_some_and(|text| text.is_empty()) {
+        return Err("The text can't be empty".to_string());
+    }
+
+    macro_def.name = name.to_string();
+    macro_def.hotkey = hotkey.to_string();
+    if let Some(text) = edit.text {
+        macro_def.text = text;
+    }
+    macro_def.modified = Some(timestamp_now());
+    Ok(macro_def)
+}
+
 /// Validate config and return any warnings.
 /// Does NOT modify the config - caller decides what to do with warnings.
 pub fn validate_config(config: &Config) -> Vec<ValidationWarning> {
@@ -94,10 +246,21 @@ pub fn validate_config(config: &Config) -> Vec<ValidationWarning> {
 
     // Check for duplicate hotkeys
     let mut hotkey_to_names: HashMap<String, Vec<String>> = HashMap::new();
-    for macro_def in &config.macros {
-        let normalized = macro_def.hotkey.to_lowercase();
+    // Menu-only and disabled macros never register their hotkey, so can't conflict
+    for macro_def in config.macros.iter().filter(|m| m.registers_hotkey()) {
+        // Compare canonical forms so "Cmd+Shift+K" and "shift+super+k" collide
+        let normalized = normalize_hotkey_string(&macro_def.hotkey)
+            .unwrap_or_else(|| macro_def.hotkey.to_lowercase());
         hotkey_to_names.entry(normalized).or_default().push(macro_def.name.clone());
     }
+    // A chord can't start with another macro's plain hotkey
+    for macro_def in config.macros.iter().filter(|m| m.registers_hotkey()) {
+        if let Some([first, _]) = parse_hotkey_chord(&macro_def.hotkey) {
+            if let Some(names) = hotkey_to_names.get_mut(&hotkey_to_string(&first)) {
+                names.push(macro_def.name.clone());
+            }
+        }
+    }
     for (hotkey, names) in hotkey_to_names {
         if names.len() > 1 {
             warnings.push(ValidationWarning::DuplicateHotkey { hotkey, names });
@@ -115,6 +278,26 @@ pub fn validate_config(config: &Config) -> Vec<ValidationWarning> {
         }
     }
 
+    // Then everything that can be checked one macro at a time
+    let config_dir = config_dir();
+    for macro_def in &config.macros {
+        for issue in macro_def.validate_with(&config.settings, &config_dir) {
+            warnings.push(ValidationWarning::Macro {
+                name: macro_def.name.clone(),
+                issue,
+            });
+        }
+    }
+
+    for pattern in &config.settings.blocklist_apps {
+        if let Some(Err(e)) = crate::focus::blocklist_regex(pattern) {
+            warnings.push(ValidationWarning::InvalidBlocklistEntry {
+                pattern: pattern.clone(),
+                error: e.to_string(),
+            });
+        }
+    }
+
     warnings
 }
 
@@ -124,15 +307,371 @@ pub struct AppSettings {
     /// Whether macros are enabled (default: true for new installs)
     #[serde(default = "default_enabled")]
     pub enabled: bool,
+    /// Stop a running macro and release modifiers when focus moves to another window
+    #[serde(default)]
+    pub stop_on_focus_loss: bool,
+    /// Show a reminder notification when a macro is triggered while disabled
+    #[serde(default)]
+    pub notify_when_disabled: bool,
+    /// Number of recently run macros listed at the top of Run Macro (0 hides the section)
+    #[serde(default = "default_recent_macros_count")]
+    pub recent_macros_count: usize,
+    /// Listen for typed macro abbreviations (requires global keyboard monitoring)
+    #[serde(default)]
+    pub hotstrings_enabled: bool,
+    /// Auto-release a `{KeyDown}` key not released within this many milliseconds (0 disables)
+    #[serde(default = "default_keydown_safety_ms")]
+    pub keydown_safety_ms: u64,
+    /// List all macros alphabetically in Run Macro (false: one submenu per group)
+    #[serde(default = "default_flat_run_menu")]
+    pub flat_run_menu: bool,
+    /// Tap a harmless key every N seconds while idle to keep the machine awake (0 = off)
+    #[serde(default)]
+    pub keep_awake_interval_s: u64,
+    /// Show hotkeys right-aligned as menu accelerators (false: in the label text)
+    #[serde(default = "default_show_hotkey_accelerators")]
+    pub show_hotkey_accelerators: bool,
+    /// Pause after special keys like {Enter} instead of the macro's `delay_ms` (unset: no override)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay_after_special_ms: Option<u64>,
+    /// Also write log output to stdout (useful with `cargo run`; ignored without a console)
+    #[serde(default)]
+    pub log_to_stdout: bool,
+    /// Number of log lines "Copy Logs to Clipboard" copies
+    #[serde(default = "default_log_copy_lines")]
+    pub log_copy_lines: usize,
+    /// Reject macros with unknown {Commands} or unescaped braces instead of typing them literally
+    #[serde(default)]
+    pub strict_dsl: bool,
+    /// What the stop hotkey does when no macro is running
+    #[serde(default)]
+    pub stop_hotkey_idle_action: StopIdleAction,
+    /// How text is typed ("unicode" works around layout issues like dead keys and AltGr)
+    #[serde(default)]
+    pub injection_method: InjectionMethod,
+    /// Order of macros in Run Macro
+    #[serde(default)]
+    pub run_menu_sort: RunMenuSort,
+    /// Type this many characters, then pause `burst_pause_ms` (both must be set)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burst_size: Option<usize>,
+    /// Pause after each burst of `burst_size` characters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burst_pause_ms: Option<u64>,
+    /// Button used by `{Click}` and `{DoubleClick}` without a button name
+    #[serde(default)]
+    pub default_click_button: ClickButton,
+    /// Gap between the two clicks of `{DoubleClick}`
+    #[serde(default = "default_double_click_gap_ms")]
+    pub double_click_gap_ms: u64,
+    /// When a macro is stopped mid-way through per-character text or paste, backspace over that part
+    #[serde(default)]
+    pub cancel_backspaces_partial: bool,
+    /// Read-only config: never write it and disable the menu actions that would
+    #[serde(default)]
+    pub locked: bool,
+    /// Serve /health and /run/{name} on 127.0.0.1 at this port (needs `http_token`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_port: Option<u16>,
+    /// Bearer token every HTTP request must send
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_token: Option<String>,
+    /// Web page origin allowed to call the HTTP endpoint from a browser (CORS)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_allowed_origin: Option<String>,
+    /// Ask before quitting while a macro is running
+    #[serde(default)]
+    pub confirm_quit_if_running: bool,
+    /// Shorten macro names in the tray menu to this many characters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub menu_label_max_chars: Option<usize>,
+    /// Imported macros whose hotkey is taken: "skip", "reassign" or "keep_new"
+    #[serde(default)]
+    pub import_conflict_policy: ImportConflictPolicy,
+    /// Show a "Macro stopped" notification when a running macro is cancelled
+    #[serde(default)]
+    pub notify_on_cancel: bool,
+    /// Don't run macros while one of these apps is focused (substrings, or /regex/)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocklist_apps: Vec<String>,
+    /// Write exported macros sorted by group and name (stable diffs) instead of config order
+    #[serde(default = "default_export_sorted")]
+    pub export_sorted: bool,
+    /// File last used by Import Macros, for Re-import Last File (set by KeyBlast)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_import_path: Option<String>,
+    /// How that import handled existing names, reused by Re-import Last File (set by KeyBlast)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_import_strategy: Option<MergeStrategy>,
+    /// Quit after this many minutes without a macro running (0 = never)
+    #[serde(default)]
+    pub auto_quit_idle_minutes: u64,
+    /// "physical" (US QWERTY key positions) or "logical" (active layout); read at startup
+    #[serde(default)]
+    pub hotkey_layout: HotkeyLayout,
+    /// Config file deleted while running: "reset", "keep" or "recreate_from_memory"
+    #[serde(default)]
+    pub on_config_delete: OnConfigDelete,
+    /// Put the clipboard back as it was once a macro that writes it (`{Copy}`) ends
+    #[serde(default)]
+    pub restore_clipboard: bool,
+    /// Left-clicking the tray icon: "none", "toggle", "run_primary" or "menu"
+    #[serde(default)]
+    pub tray_click_action: TrayClickAction,
+    /// Cancel a macro whose `{Prompt}` isn't answered within this many milliseconds (0 = wait forever)
+    #[serde(default = "default_interactive_timeout_ms")]
+    pub interactive_timeout_ms: u64,
+    /// Run macros bound to extra mouse buttons (`mouse_button`; requires global input monitoring)
+    #[serde(default)]
+    pub mouse_triggers_enabled: bool,
+    /// Key (e.g. "right_alt") that suspends all macros while held; unset disables pass-through
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suspend_while_held: Option<String>,
+    /// Wait this many milliseconds for a chord hotkey's second key (0 = wait until the next hotkey)
+    #[serde(default = "default_chord_timeout_ms")]
+    pub chord_timeout_ms: u64,
+    /// Delay between keystrokes for macros whose own `delay_ms` is 0 (0 = instant)
+    #[serde(default)]
+    pub default_delay_ms: u64,
+    /// Stop a running macro when the user presses a key, clicks, scrolls or moves the mouse
+    #[serde(default)]
+    pub abort_on_user_input: bool,
+    /// Show a notification when a macro running in the background finishes
+    #[serde(default)]
+    pub notify_on_complete: bool,
+}
+
+/// Order of the macros listed in Run Macro (within each group when grouped).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunMenuSort {
+    /// Alphabetical, ignoring case.
+    #[default]
+    Name,
+    /// Most recently edited (`modified`) first; never-edited macros last.
+    Recent,
+    /// Most often run this session first.
+    Usage,
+    /// As listed in the config file.
+    Order,
+}
+
+/// What importing does with a macro whose hotkey an existing macro already uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportConflictPolicy {
+    /// Leave the imported macro out.
+    #[default]
+    Skip,
+    /// Import it under a free hotkey (see `hotkey::suggest_available`).
+    Reassign,
+    /// Import it with its hotkey; the existing macro becomes menu-only.
+    KeepNew,
+}
+
+/// What importing does with a macro whose name an existing macro already has.
+///
+/// Picked per import from the **Import Macros** submenu (or `--merge`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStrategy {
+    /// Leave the imported macro out.
+    #[default]
+    Skip,
+    /// Replace the existing macro (keeping its ID) with the imported one.
+    Overwrite,
+    /// Import it under a free name: "Name (2)", "Name (3)", ...
+    #[serde(rename = "rename")]
+    RenameOnConflict,
+}
+
+/// What `merge_imported` decided for one imported macro.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeAction {
+    /// Add the macro; its name is new.
+    Add(MacroDefinition),
+    /// Leave out the macro with this name (`MergeStrategy::Skip`).
+    Skip(String),
+    /// Put the macro in place of the existing one with the same ID.
+    Overwrite(MacroDefinition),
+    /// Add the macro under a new name; `original` is its name in the file.
+    Rename { original: String, macro_def: MacroDefinition },
+}
+
+/// How macro text is sent to the focused app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InjectionMethod {
+    /// Let the platform type the whole string (fastest).
+    #[default]
+    Text,
+    /// One explicit Unicode key event per character, independent of the
+    /// keyboard layout, for layouts where dead keys or AltGr misbehave.
+    Unicode,
+}
+
+/// Mouse button `{Click}` and `{DoubleClick}` use when none is named.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClickButton {
+    #[default]
+    Left,
+    Right,
+    Middle,
+}
+
+/// How letter and digit keys in hotkey strings map to physical keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyLayout {
+    /// The key at that position on a US QWERTY keyboard, whatever it types.
+    #[default]
+    Physical,
+    /// The key that types that character in the active layout (Windows only;
+    /// elsewhere falls back to `Physical`).
+    Logical,
+}
+
+/// What left-clicking the tray icon does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayClickAction {
+    /// Nothing; the menu opens on right-click only.
+    None,
+    /// Enable or disable macros.
+    Toggle,
+    /// Run the macro marked `primary = true`.
+    RunPrimary,
+    /// Open the tray menu.
+    #[default]
+    Menu,
+}
+
+/// What to do when the config file is deleted while KeyBlast runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnConfigDelete {
+    /// Reload, which falls back to defaults (no macros).
+    #[default]
+    Reset,
+    /// Ignore the deletion and keep running with the in-memory config.
+    Keep,
+    /// Write the in-memory config back to disk.
+    RecreateFromMemory,
+}
+
+/// What the stop hotkey (Ctrl+Escape) does when no macro is running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopIdleAction {
+    /// Do nothing.
+    #[default]
+    None,
+    /// Release Ctrl/Shift/Alt/Meta, in case one is stuck down.
+    Release,
+    /// Discard macros waiting to run.
+    ClearQueue,
+    /// Disable KeyBlast, like unchecking Enable in the tray.
+    Disable,
 }
 
 fn default_enabled() -> bool {
     true
 }
 
+fn default_recent_macros_count() -> usize {
+    5
+}
+
+fn default_keydown_safety_ms() -> u64 {
+    10_000
+}
+
+fn default_flat_run_menu() -> bool {
+    true
+}
+
+fn default_export_sorted() -> bool {
+    true
+}
+
+fn default_interactive_timeout_ms() -> u64 {
+    300_000
+}
+
+fn default_chord_timeout_ms() -> u64 {
+    1_500
+}
+
+fn default_show_hotkey_accelerators() -> bool {
+    true
+}
+
+fn default_log_copy_lines() -> usize {
+    200
+}
+
+fn default_double_click_gap_ms() -> u64 {
+    50
+}
+
+fn default_hotkey_enabled() -> bool {
+    true
+}
+
+fn default_macro_enabled() -> bool {
+    true
+}
+
 impl Default for AppSettings {
     fn default() -> Self {
-        Self { enabled: true }
+        Self {
+            enabled: true,
+            stop_on_focus_loss: false,
+            notify_when_disabled: false,
+            recent_macros_count: default_recent_macros_count(),
+            hotstrings_enabled: false,
+            keydown_safety_ms: default_keydown_safety_ms(),
+            flat_run_menu: default_flat_run_menu(),
+            keep_awake_interval_s: 0,
+            show_hotkey_accelerators: default_show_hotkey_accelerators(),
+            delay_after_special_ms: None,
+            log_to_stdout: false,
+            log_copy_lines: default_log_copy_lines(),
+            strict_dsl: false,
+            stop_hotkey_idle_action: StopIdleAction::None,
+            injection_method: InjectionMethod::Text,
+            run_menu_sort: RunMenuSort::Name,
+            burst_size: None,
+            burst_pause_ms: None,
+            default_click_button: ClickButton::Left,
+            double_click_gap_ms: default_double_click_gap_ms(),
+            cancel_backspaces_partial: false,
+            locked: false,
+            http_port: None,
+            http_token: None,
+            http_allowed_origin: None,
+            confirm_quit_if_running: false,
+            menu_label_max_chars: None,
+            import_conflict_policy: ImportConflictPolicy::Skip,
+            notify_on_cancel: false,
+            blocklist_apps: Vec::new(),
+            export_sorted: default_export_sorted(),
+            last_import_path: None,
+            last_import_strategy: None,
+            auto_quit_idle_minutes: 0,
+            hotkey_layout: HotkeyLayout::Physical,
+            on_config_delete: OnConfigDelete::Reset,
+            restore_clipboard: false,
+            tray_click_action: TrayClickAction::Menu,
+            interactive_timeout_ms: default_interactive_timeout_ms(),
+            mouse_triggers_enabled: false,
+            suspend_while_held: None,
+            chord_timeout_ms: default_chord_timeout_ms(),
+            default_delay_ms: 0,
+            abort_on_user_input: false,
+            notify_on_complete: false,
+        }
     }
 }
 
@@ -147,13 +686,231 @@ pub struct MacroDefinition {
     /// Hotkey string like "ctrl+shift+k".
     pub hotkey: String,
     /// The text to inject, with {Enter}, {Tab}, etc.
+    /// May be omitted when `text_file` is set.
+    #[serde(default)]
     pub text: String,
-    /// Delay between keystrokes in milliseconds. 0 for instant (bulk) typing.
+    /// Delay between keystrokes in milliseconds. 0 uses `settings.default_delay_ms`
+    /// (instant, bulk typing unless that is set; see `effective_delay_ms`).
     #[serde(default)]
     pub delay_ms: u64,
+    /// Vary each keystroke delay randomly by up to this much either way
+    /// (never below 0). No effect when the effective delay is 0.
+    #[serde(default)]
+    pub jitter_ms: u64,
     /// Optional group/category for organization. None means "Ungrouped".
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub group: Option<String>,
+    /// Optional text abbreviation (e.g. ";sig") that expands to this macro when typed.
+    /// Only active when `settings.hotstrings_enabled` is on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abbreviation: Option<String>,
+    /// Read the macro text from this file instead of `text`.
+    /// Relative paths are resolved against the config directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_file: Option<String>,
+    /// Override `settings.strict_dsl` for this macro (e.g. false for JSON templates).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
+    /// Register the hotkey. When false the macro is menu-only: it is still
+    /// listed in Run Macro but never bound globally.
+    #[serde(default = "default_hotkey_enabled")]
+    pub hotkey_enabled: bool,
+    /// When KeyBlast last added or changed this macro (RFC 3339, UTC).
+    /// Set by import; see `timestamp_now`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<String>,
+    /// Types a secret: its text is never logged (see `loggable_text`).
+    #[serde(default)]
+    pub sensitive: bool,
+    /// Run by a tray icon click when `settings.tray_click_action = "run_primary"`.
+    #[serde(default)]
+    pub primary: bool,
+    /// Paste text through the clipboard (Cmd+V / Ctrl+V) instead of typing it;
+    /// the clipboard is put back afterwards.
+    #[serde(default)]
+    pub inject_via_clipboard: bool,
+    /// Extra mouse button that runs this macro: "middle", "x1" or "x2".
+    /// Only active when `settings.mouse_triggers_enabled` is on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mouse_button: Option<String>,
+    /// When false the macro is switched off from the tray: no hotkey,
+    /// abbreviation or mouse button is active and it can't be run.
+    #[serde(default = "default_macro_enabled")]
+    pub enabled: bool,
+    /// Only use this macro on one OS ("macos", "windows", "linux") or host
+    /// (its hostname); elsewhere it isn't registered or listed (see `macro_applies`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub only_on: Option<String>,
+    /// Wait this many seconds before typing, flashing the tray icon each
+    /// second, so the target window can be focused first. The stop hotkey
+    /// cancels the countdown.
+    #[serde(default)]
+    pub countdown_secs: u8,
+}
+
+impl MacroDefinition {
+    /// Whether the hotkey gets registered: the macro is enabled, not
+    /// menu-only, and meant for this machine.
+    pub fn registers_hotkey(&self) -> bool {
+        self.enabled && self.hotkey_enabled && macro_applies(self)
+    }
+
+    /// Whether this macro's text is parsed strictly: the macro's own `strict`
+    /// wins, then `settings.strict_dsl` (lenient by default).
+    pub fn is_strict(&self, settings: &AppSettings) -> bool {
+        self.strict.unwrap_or(settings.strict_dsl)
+    }
+
+    /// Delay between keystrokes when this macro runs: its own `delay_ms`
+    /// if set (non-zero), otherwise `settings.default_delay_ms`.
+    pub fn effective_delay_ms(&self, settings: &AppSettings) -> u64 {
+        if self.delay_ms > 0 {
+            self.delay_ms
+        } else {
+            settings.default_delay_ms
+        }
+    }
+
+    /// Run all single-macro checks against the real config directory, e.g.
+    /// before saving an edited macro.
+    pub fn validate(&self, settings: &AppSettings) -> Vec<MacroIssue> {
+        self.validate_with(settings, &config_dir())
+    }
+
+    /// Run all single-macro checks. `settings` decides strict parsing and
+    /// relative `text_file` paths resolve against `config_dir`.
+    pub fn validate_with(&self, settings: &AppSettings, config_dir: &Path) -> Vec<MacroIssue> {
+        let mut issues = Vec::new();
+
+        if self.text.is_empty() && self.text_file.is_none() {
+            issues.push(MacroIssue::EmptyText);
+        }
+
+        // Blank hotkeys are left to registration to report
+        if is_modifier_only_hotkey(&self.hotkey) {
+            issues.push(MacroIssue::NoKeyInHotkey { hotkey: self.hotkey.clone() });
+        } else if !self.hotkey.trim().is_empty() {
+            // A chord takes over its first step like a plain hotkey
+            let first_step = parse_hotkey_string(&self.hotkey)
+                .or_else(|| parse_hotkey_chord(&self.hotkey).map(|[first, _]| first));
+            match first_step {
+                None => issues.push(MacroIssue::InvalidHotkey { hotkey: self.hotkey.clone() }),
+                Some(hotkey) if is_risky_hotkey(&hotkey) => {
+                    issues.push(MacroIssue::RiskyHotkey { hotkey: self.hotkey.clone() })
+                }
+                Some(_) => {}
+            }
+        }
+
+        if let Some(ref button) = self.mouse_button {
+            if crate::mouse_trigger::parse_mouse_button(button).is_none() {
+                issues.push(MacroIssue::InvalidMouseButton { button: button.clone() });
+            }
+        }
+
+        if let Some(ref text_file) = self.text_file {
+            let path = resolve_config_relative(text_file, config_dir);
+            if !path.is_file() {
+                issues.push(MacroIssue::MissingTextFile { path });
+            }
+        }
+
+        // A missing text file was reported above
+        let Ok(text) = load_macro_text(self, config_dir) else {
+            return issues;
+        };
+        let segments = if self.is_strict(settings) {
+            match crate::injection::parse_macro_sequence_strict(&text) {
+                Ok(segments) => segments,
+                Err(dsl_issues) => {
+                    issues.push(MacroIssue::InvalidDsl {
+                        issues: dsl_issues.iter().map(ToString::to_string).collect(),
+                    });
+                    return issues;
+                }
+            }
+        } else {
+            crate::injection::parse_macro_sequence(&text)
+        };
+
+        let risks = crate::audit::risk_flags(&segments, config_dir);
+
+        let mut held = Vec::new();
+        for segment in &crate::injection::flatten_repeats(segments) {
+            match segment {
+                crate::injection::MacroSegment::KeyDown(key) if !held.contains(key) => held.push(*key),
+                crate::injection::MacroSegment::KeyUp(key) => held.retain(|k| k != key),
+                _ => {}
+            }
+        }
+        if !held.is_empty() {
+            issues.push(MacroIssue::UnbalancedKeyDown {
+                keys: held.iter().map(|key| format!("{:?}", key)).collect(),
+            });
+        }
+        issues.extend(risks.into_iter().map(MacroIssue::Risk));
+
+        issues
+    }
+}
+
+impl Default for MacroDefinition {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: String::new(),
+            hotkey: String::new(),
+            text: String::new(),
+            delay_ms: 0,
+            jitter_ms: 0,
+            group: None,
+            abbreviation: None,
+            text_file: None,
+            strict: None,
+            hotkey_enabled: true,
+            modified: None,
+            sensitive: false,
+            primary: false,
+            inject_via_clipboard: false,
+            mouse_button: None,
+            enabled: true,
+            only_on: None,
+            countdown_secs: 0,
+        }
+    }
+}
+
+/// Whether `macro_def` is meant for this machine: it has no `only_on`, or
+/// `only_on` names the current OS or hostname.
+pub fn macro_applies(macro_def: &MacroDefinition) -> bool {
+    static HOSTNAME: OnceLock<String> = OnceLock::new();
+    macro_def.only_on.as_deref().is_none_or(|condition| {
+        let hostname = HOSTNAME.get_or_init(|| gethostname::gethostname().to_string_lossy().into_owned());
+        only_on_matches(condition, std::env::consts::OS, hostname)
+    })
+}
+
+/// Whether an `only_on` value names `os` (as in `std::env::consts::OS`) or
+/// `hostname`, ignoring case and surrounding whitespace. A hostname also
+/// matches its short form ("laptop" for "laptop.example.com"). A blank
+/// value is treated like no `only_on`.
+fn only_on_matches(condition: &str, os: &str, hostname: &str) -> bool {
+    let condition = condition.trim();
+    let short_host = hostname.split('.').next().unwrap_or(hostname);
+    condition.is_empty()
+        || condition.eq_ignore_ascii_case(os)
+        || condition.eq_ignore_ascii_case(hostname)
+        || condition.eq_ignore_ascii_case(short_host)
+}
+
+/// Values of `std::env::consts::OS` an `only_on` can name.
+const OS_NAMES: &[&str] = &[
+    "linux", "macos", "windows", "ios", "android", "freebsd", "dragonfly", "netbsd", "openbsd", "solaris",
+];
+
+/// Whether an `only_on` value names an OS rather than a hostname.
+fn is_os_name(condition: &str) -> bool {
+    OS_NAMES.iter().any(|os| condition.trim().eq_ignore_ascii_case(os))
 }
 
 /// Application configuration.
@@ -189,6 +946,10 @@ impl Default for Config {
 /// - macOS: ~/Library/Application Support/keyblast/config.toml
 /// - Windows: %APPDATA%/keyblast/config.toml
 /// - Linux: ~/.config/keyblast/config.toml
+///
+/// `config.json` in the same directory is used instead if there is no
+/// `config.toml` (see `config_file_in`). Once `load_config` has run, the file
+/// it loaded stays the config path, even if it is deleted.
 pub fn config_path() -> PathBuf {
     let config_dir = if cfg!(target_os = "macos") {
         dirs::data_dir()
@@ -198,7 +959,116 @@ pub fn config_path() -> PathBuf {
             .unwrap_or_else(|| PathBuf::from("."))
     };
 
-    config_dir.join("keyblast").join("config.toml")
+    let loaded = LOADED_CONFIG_PATH.lock().ok().and_then(|loaded| loaded.clone());
+    config_file_in(&config_dir.join("keyblast"), loaded.as_deref())
+}
+
+/// Config file read by the last `load_config`.
+///
+/// Without it, deleting `config.json` would make `config_path` switch to
+/// `config.toml`: the watcher would miss the deletion and a save would
+/// write the other file.
+static LOADED_CONFIG_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// The config file in `dir`: `loaded` if that's a file in `dir` (whether or
+/// not it still exists), else `config.toml`, unless only `config.json` exists.
+///
+/// TOML stays the default, so a new config is always `config.toml`.
+fn config_file_in(dir: &Path, loaded: Option<&Path>) -> PathBuf {
+    if let Some(loaded) = loaded.filter(|loaded| loaded.parent() == Some(dir)) {
+        return loaded.to_path_buf();
+    }
+    let toml_path = dir.join("config.toml");
+    let json_path = dir.join("config.json");
+    if !toml_path.exists() && json_path.exists() {
+        json_path
+    } else {
+        toml_path
+    }
+}
+
+/// Whether `path` is read and written as JSON (`.json`) rather than TOML.
+pub fn is_json_path(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+/// Parse config file contents, as JSON or TOML depending on `path`.
+fn parse_config_file(content: &str, path: &Path) -> Result<Config, ConfigError> {
+    if is_json_path(path) {
+        Ok(serde_json::from_str(content)?)
+    } else {
+        Ok(toml::from_str(content)?)
+    }
+}
+
+/// Serialize a config for writing to `path`, as pretty JSON or TOML.
+fn serialize_config(config: &Config, path: &Path) -> Result<String, ConfigError> {
+    if is_json_path(path) {
+        Ok(serde_json::to_string_pretty(config)?)
+    } else {
+        Ok(toml::to_string_pretty(config)?)
+    }
+}
+
+/// `path` with `suffix` appended to the file name (`config.json` -> `config.json.bak`).
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Directory containing the config file.
+pub fn config_dir() -> PathBuf {
+    config_path()
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// The current time as an RFC 3339 UTC timestamp, e.g. "2026-10-16T09:30:05Z".
+///
+/// Used for `MacroDefinition::modified`; the fixed format sorts chronologically
+/// as a plain string.
+pub fn timestamp_now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+/// Resolve a path from the config (`text_file`, `{Type}` and bundled files).
+///
+/// Absolute paths are used as-is; relative paths are relative to `config_dir`,
+/// so a config directory can be moved along with the files it refers to.
+pub fn resolve_config_relative(path: &str, config_dir: &Path) -> PathBuf {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        config_dir.join(path)
+    }
+}
+
+/// The text a macro types: the contents of its `text_file` if set, else `text`.
+pub fn load_macro_text(macro_def: &MacroDefinition, config_dir: &Path) -> Result<String, ConfigError> {
+    match macro_def.text_file {
+        Some(ref text_file) => Ok(fs::read_to_string(resolve_config_relative(text_file, config_dir))?),
+        None => Ok(macro_def.text.clone()),
+    }
+}
+
+/// Placeholder logged instead of a sensitive macro's text.
+pub const REDACTED: &str = "[redacted]";
+
+/// A macro's text as it may appear in logs.
+///
+/// Sensitive macros give `REDACTED`; macros using `text_file` give the file
+/// path rather than its contents.
+pub fn loggable_text(macro_def: &MacroDefinition) -> String {
+    if macro_def.sensitive {
+        return REDACTED.to_string();
+    }
+    match macro_def.text_file {
+        Some(ref text_file) => format!("<{}>", text_file),
+        None => macro_def.text.clone(),
+    }
 }
 
 /// Load configuration from disk.
@@ -207,49 +1077,87 @@ pub fn config_path() -> PathBuf {
 /// Returns an error only if the file exists but cannot be parsed.
 pub fn load_config() -> Result<Config, ConfigError> {
     let path = config_path();
+    if let Ok(mut loaded) = LOADED_CONFIG_PATH.lock() {
+        *loaded = Some(path.clone());
+    }
+    load_config_from(&path)
+}
 
+/// `load_config` for the config file at `path` (JSON if it ends in `.json`).
+fn load_config_from(path: &Path) -> Result<Config, ConfigError> {
     if !path.exists() {
         return Ok(Config::default());
     }
 
-    let content = fs::read_to_string(&path)?;
-    let config: Config = toml::from_str(&content)?;
-    Ok(config)
+    let content = fs::read_to_string(path)?;
+    parse_config_file(&content, path)
 }
 
 /// Save configuration to disk.
 ///
 /// Creates parent directories if needed.
+/// Hotkeys are stored in canonical form (see `normalize_hotkey_string`);
+/// unparseable hotkeys are kept as written.
 /// Writes atomically by writing to a temp file first, then renaming.
 pub fn save_config(config: &Config) -> Result<(), ConfigError> {
-    let path = config_path();
+    save_config_to(config, &config_path())
+}
+
+/// `save_config` to the config file at `path`, in the format its extension says.
+fn save_config_to(config: &Config, path: &Path) -> Result<(), ConfigError> {
+    let mut config = config.clone();
+    for macro_def in &mut config.macros {
+        if let Some(canonical) = normalize_hotkey_string(&macro_def.hotkey) {
+            macro_def.hotkey = canonical;
+        }
+    }
 
     // Create parent directories if needed
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
 
-    // Serialize to pretty TOML
-    let content = toml::to_string_pretty(config)?;
+    // Serialize to pretty TOML (or JSON)
+    let content = serialize_config(&config, path)?;
 
     // Write atomically: temp file then rename
-    let temp_path = path.with_extension("toml.tmp");
+    let temp_path = with_suffix(path, ".tmp");
     fs::write(&temp_path, &content)?;
 
     // On Windows, fs::rename fails if destination exists - remove it first
     #[cfg(target_os = "windows")]
     {
         if path.exists() {
-            fs::remove_file(&path)?;
+            fs::remove_file(path)?;
         }
     }
 
-    fs::rename(&temp_path, &path)?;
+    fs::rename(&temp_path, path)?;
 
     Ok(())
 }
 
-/// Export all macros to a TOML file at the specified path.
+/// The config's macros in export order.
+///
+/// With `settings.export_sorted`, sorted by group (ungrouped last), then
+/// name, ignoring case, so the same macros always export identically.
+/// Otherwise config order. The config itself is not reordered.
+pub fn macros_for_export(config: &Config) -> Vec<MacroDefinition> {
+    let mut macros = config.macros.clone();
+    if config.settings.export_sorted {
+        macros.sort_by_cached_key(|m| {
+            (
+                m.group.is_none(),
+                m.group.as_deref().map(str::to_lowercase),
+                m.name.to_lowercase(),
+                m.name.clone(),
+            )
+        });
+    }
+    macros
+}
+
+/// Export all macros to a TOML file at the specified path (JSON for `.json`).
 ///
 /// Creates a standalone config file containing only the macros array.
 /// Useful for backup or sharing macro collections.
@@ -259,18 +1167,91 @@ pub fn export_macros(macros: &[MacroDefinition], path: &std::path::Path) -> Resu
         macros: macros.to_vec(),
         settings: AppSettings::default(),
     };
-    let content = toml::to_string_pretty(&export_config)?;
+    let content = serialize_config(&export_config, path)?;
     fs::write(path, content)?;
     Ok(())
 }
 
-/// De-duplicate macros by name, keeping the first occurrence.
-pub fn dedupe_macros(macros: Vec<MacroDefinition>) -> Vec<MacroDefinition> {
-    let mut seen: HashSet<String> = HashSet::new();
-    macros.into_iter().filter(|m| seen.insert(m.name.clone())).collect()
+/// A copy of `macro_def` with nothing tied to this machine, for sharing.
+///
+/// Drops `modified`, and `only_on` unless it names an OS (a
+/// hostname is this machine's), and inlines `text_file` (resolved against
+/// `config_dir`) into `text`. Fails if the text file can't be read, rather
+/// than sharing a macro that types nothing.
+pub fn shareable_macro(macro_def: &MacroDefinition, config_dir: &Path) -> Result<MacroDefinition, ConfigError> {
+    Ok(MacroDefinition {
+        text: load_macro_text(macro_def, config_dir)?,
+        text_file: None,
+        modified: None,
+        only_on: macro_def.only_on.clone().filter(|condition| is_os_name(condition)),
+        ..macro_def.clone()
+    })
 }
 
-/// Create default example macros for new users.
+/// Export macros like `export_macros`, but stripped for sharing (see
+/// `shareable_macro`) so the file is portable and leaks no local paths.
+pub fn export_macros_shareable(
+    macros: &[MacroDefinition],
+    config_dir: &Path,
+    path: &Path,
+) -> Result<(), ConfigError> {
+    let shared = macros
+        .iter()
+        .map(|m| shareable_macro(m, config_dir))
+        .collect::<Result<Vec<_>, _>>()?;
+    export_macros(&shared, path)
+}
+
+/// Export the entire configuration (settings and macros) to a TOML file (JSON for `.json`).
+///
+/// Unlike `export_macros`, the result restores a full setup via `import_config`.
+/// Macros are written in `macros_for_export` order.
+pub fn export_config(config: &Config, path: &std::path::Path) -> Result<(), ConfigError> {
+    let exported = Config {
+        macros: macros_for_export(config),
+        ..config.clone()
+    };
+    let content = serialize_config(&exported, path)?;
+    fs::write(path, content)?;
+    Ok(())
+}
+
+/// Import a full configuration exported with `export_config`.
+///
+/// De-duplicates macros by name like `import_macros`.
+/// Does NOT modify the current config - see `replace_config`.
+pub fn import_config(path: &std::path::Path) -> Result<Config, ConfigError> {
+    let content = fs::read_to_string(path)?;
+    let mut config = parse_config_file(&content, path)?;
+    config.macros = dedupe_macros(config.macros);
+    Ok(config)
+}
+
+/// Replace the config on disk, first copying the current file to `config.toml.bak`
+/// (`config.json.bak` for a JSON config).
+///
+/// Returns the backup path, or None if there was no existing config to back up.
+pub fn replace_config(config: &Config) -> Result<Option<PathBuf>, ConfigError> {
+    let path = config_path();
+    let backup = if path.exists() {
+        let backup_path = with_suffix(&path, ".bak");
+        fs::copy(&path, &backup_path)?;
+        Some(backup_path)
+    } else {
+        None
+    };
+
+    save_config(config)?;
+    Ok(backup)
+}
+
+/// De-duplicate macros by name, keeping the first occurrence.
+pub fn dedupe_macros(macros: Vec<MacroDefinition>) -> Vec<MacroDefinition> {
+    let mut seen: HashSet<String> = HashSet::new();
+    macros.into_iter().filter(|m| seen.insert(m.name.clone())).collect()
+}
+
+/// Create default example macros for new users.
 ///
 /// Returns 3 example macros demonstrating KeyBlast's DSL features:
 /// 1. Hello World - Basic text with Enter key
@@ -288,6 +1269,7 @@ pub fn default_example_macros() -> Vec<MacroDefinition> {
             text: "Hello from KeyBlast!{Enter}".to_string(),
             delay_ms: 0,
             group: Some("Examples".to_string()),
+            ..MacroDefinition::default()
         },
         // Special keys: Tab for field navigation
         MacroDefinition {
@@ -297,6 +1279,7 @@ pub fn default_example_macros() -> Vec<MacroDefinition> {
             text: "John Doe{Tab}john@example.com{Tab}{Tab}{Enter}".to_string(),
             delay_ms: 0,
             group: Some("Examples".to_string()),
+            ..MacroDefinition::default()
         },
         // DSL features: Delay for pacing, multi-line
         MacroDefinition {
@@ -306,21 +1289,225 @@ pub fn default_example_macros() -> Vec<MacroDefinition> {
             text: "Best regards,{Enter}{Delay 100}-- {Enter}Your Name{Enter}your@email.com".to_string(),
             delay_ms: 0,
             group: Some("Examples".to_string()),
+            ..MacroDefinition::default()
         },
     ]
 }
 
-/// Import macros from a TOML file.
+/// True if the error means the config location can't be written to
+/// (permission denied or a read-only filesystem).
+pub fn is_not_writable_error(error: &ConfigError) -> bool {
+    matches!(
+        error,
+        ConfigError::Io(e) if matches!(
+            e.kind(),
+            io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem
+        )
+    )
+}
+
+/// Check whether files can be created in `dir` (creating it if needed).
+///
+/// Writes and removes a small probe file, since permission bits alone don't
+/// account for read-only mounts or ACLs.
+pub fn is_dir_writable(dir: &Path) -> bool {
+    if fs::create_dir_all(dir).is_err() {
+        return false;
+    }
+    let probe = dir.join(".keyblast-write-test");
+    match fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// File name of the optional first-run macro template in the config directory.
+pub const DEFAULT_MACRO_TEMPLATE: &str = "default-macro.toml";
+
+/// Environment variable pointing at a first-run macro template (overrides the config dir file).
+pub const DEFAULT_MACRO_ENV: &str = "KEYBLAST_DEFAULT_MACRO";
+
+/// Locate the first-run macro template, if any.
+///
+/// `KEYBLAST_DEFAULT_MACRO` wins; otherwise `default-macro.toml` in the config
+/// directory is used when it exists.
+pub fn default_macro_template_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(DEFAULT_MACRO_ENV) {
+        return Some(PathBuf::from(path));
+    }
+    let path = config_dir().join(DEFAULT_MACRO_TEMPLATE);
+    path.exists().then_some(path)
+}
+
+/// Macros to seed a new config with.
+///
+/// Uses the template (a TOML file holding a single macro's fields) when given
+/// and valid; falls back to `default_example_macros` otherwise.
+pub fn first_run_macros(template: Option<&Path>) -> Vec<MacroDefinition> {
+    let Some(path) = template else {
+        return default_example_macros();
+    };
+
+    let parsed = fs::read_to_string(path)
+        .map_err(ConfigError::from)
+        .and_then(|content| toml::from_str::<MacroDefinition>(&content).map_err(ConfigError::from));
+    match parsed {
+        Ok(macro_def) => vec![macro_def],
+        Err(e) => {
+            tracing::warn!(
+                "Ignoring default macro template {}: {}",
+                path.display(),
+                e
+            );
+            default_example_macros()
+        }
+    }
+}
+
+/// Whether `path` looks like a tab-separated macro list (`.tsv` or `.txt`).
+pub fn is_tsv_path(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("tsv") || ext.eq_ignore_ascii_case("txt"))
+}
+
+/// Import macros from a tab-separated list, e.g. saved from a spreadsheet.
+///
+/// See `parse_tsv` for the format. Does NOT modify the current config.
+pub fn import_tsv(path: &Path) -> Result<Vec<MacroDefinition>, ConfigError> {
+    parse_tsv(&fs::read_to_string(path)?)
+}
+
+/// Parse a tab-separated macro list: one `name<TAB>hotkey<TAB>text` per line.
+///
+/// Blank lines and lines starting with `#` are skipped. In the text, `\t`,
+/// `\n` and `\\` stand for a tab, a line break and a backslash. An empty
+/// hotkey makes the macro menu-only. The first malformed line fails the
+/// whole list; names are de-duplicated like `import_macros`.
+pub fn parse_tsv(content: &str) -> Result<Vec<MacroDefinition>, ConfigError> {
+    let mut macros = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let invalid = |message: String| ConfigError::InvalidLine { line: index + 1, message };
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [name, hotkey, text] = fields[..] else {
+            return Err(invalid(format!(
+                "expected 3 tab-separated fields (name, hotkey, text), found {}",
+                fields.len()
+            )));
+        };
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(invalid("macro name is empty".to_string()));
+        }
+        let hotkey = hotkey.trim();
+        if !hotkey.is_empty() && !is_valid_hotkey(hotkey) {
+            return Err(invalid(format!("invalid hotkey '{}'", hotkey)));
+        }
+
+        macros.push(MacroDefinition {
+            name: name.to_string(),
+            hotkey: hotkey.to_string(),
+            text: unescape_tsv_text(text),
+            hotkey_enabled: !hotkey.is_empty(),
+            ..MacroDefinition::default()
+        });
+    }
+    Ok(dedupe_macros(macros))
+}
+
+/// Turn `\t`, `\n` and `\\` into a tab, a line break and a backslash.
+/// Any other backslash is kept as is.
+fn unescape_tsv_text(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.peek() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('\\') => unescaped.push('\\'),
+            _ => {
+                unescaped.push('\\');
+                continue;
+            }
+        }
+        chars.next();
+    }
+    unescaped
+}
+
+/// Import macros from a TOML file (JSON for `.json`).
 ///
 /// Parses a config file and returns the macros array.
 /// De-duplicates by name within the imported file.
 /// Does NOT modify the current config - caller decides how to merge.
 pub fn import_macros(path: &std::path::Path) -> Result<Vec<MacroDefinition>, ConfigError> {
     let content = fs::read_to_string(path)?;
-    let config: Config = toml::from_str(&content)?;
+    let config = parse_config_file(&content, path)?;
     Ok(dedupe_macros(config.macros))
 }
 
+/// Decide how each imported macro joins `existing`, by name, per `strategy`.
+///
+/// Imported macros are expected to have unique names (see `import_macros`).
+/// A macro being added gets a new ID if its ID is already in use; one
+/// overwriting an existing macro takes that macro's ID. Hotkeys aren't looked
+/// at here (see `transaction::import_macros`).
+pub fn merge_imported(
+    existing: &[MacroDefinition],
+    imported: Vec<MacroDefinition>,
+    strategy: MergeStrategy,
+) -> Vec<MergeAction> {
+    let existing_ids: HashMap<&str, Uuid> = existing.iter().map(|m| (m.name.as_str(), m.id)).collect();
+    let mut names: HashSet<String> = existing.iter().map(|m| m.name.clone()).collect();
+    let mut ids: HashSet<Uuid> = existing.iter().map(|m| m.id).collect();
+
+    imported
+        .into_iter()
+        .map(|mut macro_def| {
+            if let Some(&existing_id) = existing_ids.get(macro_def.name.as_str()) {
+                match strategy {
+                    MergeStrategy::Skip => return MergeAction::Skip(macro_def.name),
+                    MergeStrategy::Overwrite => {
+                        macro_def.id = existing_id;
+                        return MergeAction::Overwrite(macro_def);
+                    }
+                    MergeStrategy::RenameOnConflict => {}
+                }
+            }
+            if !ids.insert(macro_def.id) {
+                macro_def.id = Uuid::new_v4();
+                ids.insert(macro_def.id);
+            }
+            if names.insert(macro_def.name.clone()) {
+                return MergeAction::Add(macro_def);
+            }
+            let renamed = unique_name(&macro_def.name, &names);
+            let original = std::mem::replace(&mut macro_def.name, renamed);
+            names.insert(macro_def.name.clone());
+            MergeAction::Rename { original, macro_def }
+        })
+        .collect()
+}
+
+/// `name` with the first " (2)", " (3)", ... suffix that isn't in `taken`.
+pub fn unique_name(name: &str, taken: &HashSet<String>) -> String {
+    (2..)
+        .map(|n| format!("{} ({})", name, n))
+        .find(|candidate| !taken.contains(candidate))
+        .expect("unbounded counter")
+}
+
 /// Parse a hotkey string like "ctrl+shift+k" into a HotKey.
 ///
 /// # Supported modifiers (case-insensitive)
@@ -334,7 +1521,7 @@ pub fn import_macros(path: &std::path::Path) -> Result<Vec<MacroDefinition>, Con
 ///
 /// - a-z (letter keys)
 /// - 0-9 (digit keys)
-/// - f1-f12 (function keys)
+/// - f1-f24 (function keys)
 ///
 /// # Examples
 ///
@@ -346,7 +1533,8 @@ pub fn import_macros(path: &std::path::Path) -> Result<Vec<MacroDefinition>, Con
 pub fn parse_hotkey_string(s: &str) -> Option<HotKey> {
     let parts: Vec<&str> = s.split('+').map(|p| p.trim()).collect();
 
-    if parts.is_empty() {
+    // "ctrl+k ctrl+s" is a chord (see `parse_hotkey_chord`), not one hotkey
+    if parts.is_empty() || parts.iter().any(|p| p.contains(char::is_whitespace)) {
         return None;
     }
 
@@ -357,12 +1545,9 @@ pub fn parse_hotkey_string(s: &str) -> Option<HotKey> {
         let lower = part.to_lowercase();
 
         // Check if it's a modifier
-        match lower.as_str() {
-            "ctrl" | "control" => modifiers |= Modifiers::CONTROL,
-            "shift" => modifiers |= Modifiers::SHIFT,
-            "alt" | "option" => modifiers |= Modifiers::ALT,
-            "meta" | "cmd" | "command" | "super" | "win" => modifiers |= Modifiers::META,
-            _ => {
+        match parse_modifier(&lower) {
+            Some(modifier) => modifiers |= modifier,
+            None => {
                 // Not a modifier, should be the key
                 key_code = parse_key_code(&lower);
             }
@@ -382,8 +1567,217 @@ pub fn parse_hotkey_string(s: &str) -> Option<HotKey> {
     Some(HotKey::new(mods, code))
 }
 
-/// Parse a key name into a Code.
+/// Parse a two-step chord hotkey like "ctrl+k ctrl+s": two hotkeys separated
+/// by whitespace, pressed one after the other.
+///
+/// Spaces around `+` are allowed as in `parse_hotkey_string`. Returns None for
+/// a single hotkey, more than two steps, or a step that doesn't parse.
+pub fn parse_hotkey_chord(s: &str) -> Option<[HotKey; 2]> {
+    let joined = s.split('+').map(str::trim).collect::<Vec<_>>().join("+");
+    let steps: Vec<&str> = joined.split_whitespace().collect();
+    let [first, second] = steps[..] else {
+        return None;
+    };
+    Some([parse_hotkey_string(first)?, parse_hotkey_string(second)?])
+}
+
+/// Whether a hotkey string is a single hotkey or a chord.
+fn is_valid_hotkey(s: &str) -> bool {
+    parse_hotkey_string(s).is_some() || parse_hotkey_chord(s).is_some()
+}
+
+/// Format a HotKey in canonical string form, e.g. "ctrl+alt+shift+meta+k".
+///
+/// Modifiers always appear in that order, lowercase, and the result parses
+/// back to the same HotKey with `parse_hotkey_string`.
+pub fn hotkey_to_string(hotkey: &HotKey) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    for (modifier, name) in [
+        (Modifiers::CONTROL, "ctrl"),
+        (Modifiers::ALT, "alt"),
+        (Modifiers::SHIFT, "shift"),
+        (Modifiers::META, "meta"),
+    ] {
+        // HotKey::new stores META as SUPER
+        let present = hotkey.mods.contains(modifier)
+            || (modifier == Modifiers::META && hotkey.mods.contains(Modifiers::SUPER));
+        if present {
+            parts.push(name.to_string());
+        }
+    }
+    parts.push(key_code_name(hotkey.key));
+    parts.join("+")
+}
+
+/// Human-friendly hotkey text for menus and notifications.
+///
+/// macOS uses the standard symbols in Apple's order (⌃⌥⇧⌘K); other
+/// platforms spell modifiers out ("Ctrl+Alt+Shift+Win+K"). Display only:
+/// config files keep the `hotkey_to_string` form.
+pub fn pretty_hotkey(hotkey: &HotKey) -> String {
+    pretty_hotkey_styled(hotkey, cfg!(target_os = "macos"))
+}
+
+/// `pretty_hotkey` with the macOS symbol style chosen explicitly.
+fn pretty_hotkey_styled(hotkey: &HotKey, mac_symbols: bool) -> String {
+    let meta_word = if cfg!(target_os = "windows") { "Win" } else { "Super" };
+    let modifiers = [
+        (Modifiers::CONTROL, "\u{2303}", "Ctrl"),
+        (Modifiers::ALT, "\u{2325}", "Alt"),
+        (Modifiers::SHIFT, "\u{21E7}", "Shift"),
+        (Modifiers::SUPER, "\u{2318}", meta_word),
+    ];
+
+    let name = hotkey.key.to_string();
+    let key = name
+        .strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Digit"))
+        .unwrap_or(&name);
+
+    let held = modifiers.into_iter().filter(|(modifier, _, _)| {
+        // HotKey::new stores META as SUPER
+        hotkey.mods.contains(*modifier)
+            || (*modifier == Modifiers::SUPER && hotkey.mods.contains(Modifiers::META))
+    });
+    if mac_symbols {
+        let mut text: String = held.map(|(_, symbol, _)| symbol).collect();
+        text.push_str(key);
+        text
+    } else {
+        let mut parts: Vec<&str> = held.map(|(_, _, word)| word).collect();
+        parts.push(key);
+        parts.join("+")
+    }
+}
+
+/// `pretty_hotkey` for a hotkey string, falling back to the string as written
+/// if it doesn't parse.
+pub fn display_hotkey(s: &str) -> String {
+    if let Some(steps) = parse_hotkey_chord(s) {
+        return steps.iter().map(pretty_hotkey).collect::<Vec<_>>().join(" ");
+    }
+    parse_hotkey_string(s).map_or_else(|| s.to_string(), |hotkey| pretty_hotkey(&hotkey))
+}
+
+/// Canonical form of a hotkey string, or None if it doesn't parse.
+///
+/// Equivalent inputs ("Cmd+Shift+K", "shift+super+k") normalize to the same
+/// string. Chords normalize step by step ("ctrl+k ctrl+s").
+pub fn normalize_hotkey_string(s: &str) -> Option<String> {
+    if let Some(steps) = parse_hotkey_chord(s) {
+        return Some(steps.iter().map(hotkey_to_string).collect::<Vec<_>>().join(" "));
+    }
+    parse_hotkey_string(s).map(|hotkey| hotkey_to_string(&hotkey))
+}
+
+/// Key name as accepted by `parse_key_code` ("k", "5", "f12").
+fn key_code_name(code: Code) -> String {
+    key_code_name_for(code, hotkey_layout(), crate::keyboard_layout::char_for_code)
+}
+
+/// `key_code_name` under `layout`: Logical names the character `reverse`
+/// says the key types, so the name parses back to the same key.
+fn key_code_name_for<F>(code: Code, layout: HotkeyLayout, reverse: F) -> String
+where
+    F: FnOnce(Code) -> Option<char>,
+{
+    if layout == HotkeyLayout::Logical {
+        if let Some(c) = reverse(code) {
+            return c.to_string();
+        }
+    }
+    let name = code.to_string();
+    let short = name
+        .strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Digit"))
+        .unwrap_or(&name);
+    short.to_lowercase()
+}
+
+/// Parse a lowercase modifier name into its Modifiers flag.
+fn parse_modifier(s: &str) -> Option<Modifiers> {
+    match s {
+        "ctrl" | "control" => Some(Modifiers::CONTROL),
+        "shift" => Some(Modifiers::SHIFT),
+        "alt" | "option" => Some(Modifiers::ALT),
+        "meta" | "cmd" | "command" | "super" | "win" => Some(Modifiers::META),
+        _ => None,
+    }
+}
+
+/// Check whether a hotkey string names modifiers but no key (e.g. "ctrl+shift" or "ctrl+").
+///
+/// Empty hotkeys are not considered modifier-only.
+fn is_modifier_only_hotkey(s: &str) -> bool {
+    let parts: Vec<String> = s.split('+').map(|p| p.trim().to_lowercase()).collect();
+    let has_modifier = parts.iter().any(|p| parse_modifier(p).is_some());
+    let has_key = parts.iter().any(|p| !p.is_empty() && parse_modifier(p).is_none());
+    has_modifier && !has_key
+}
+
+/// Check whether a hotkey takes over a letter or digit without Ctrl, Alt or
+/// Meta (e.g. "k" or "shift+k"). Function keys are fine on their own.
+fn is_risky_hotkey(hotkey: &HotKey) -> bool {
+    let is_function_key = matches!(
+        hotkey.key,
+        Code::F1 | Code::F2 | Code::F3 | Code::F4 | Code::F5 | Code::F6
+            | Code::F7 | Code::F8 | Code::F9 | Code::F10 | Code::F11 | Code::F12
+            | Code::F13 | Code::F14 | Code::F15 | Code::F16 | Code::F17 | Code::F18
+            | Code::F19 | Code::F20 | Code::F21 | Code::F22 | Code::F23 | Code::F24
+    );
+    !is_function_key && !hotkey.mods.intersects(Modifiers::CONTROL | Modifiers::ALT | Modifiers::META)
+}
+
+/// Whether hotkeys are parsed with `HotkeyLayout::Logical`.
+///
+/// Process-wide so every hotkey string (registration, conflicts, menus)
+/// resolves to the same key; set once at startup.
+static HOTKEY_LAYOUT_LOGICAL: AtomicBool = AtomicBool::new(false);
+
+/// Choose how hotkey strings map to keys for the rest of the process.
+pub fn set_hotkey_layout(layout: HotkeyLayout) {
+    HOTKEY_LAYOUT_LOGICAL.store(layout == HotkeyLayout::Logical, Ordering::Relaxed);
+}
+
+fn hotkey_layout() -> HotkeyLayout {
+    if HOTKEY_LAYOUT_LOGICAL.load(Ordering::Relaxed) {
+        HotkeyLayout::Logical
+    } else {
+        HotkeyLayout::Physical
+    }
+}
+
+/// The Code for the single-character key `c`, whose US QWERTY position is
+/// `physical`. Logical asks `lookup` for the active layout and falls back
+/// to `physical` when it can't say.
+fn resolve_char_code<F>(c: char, physical: Code, layout: HotkeyLayout, lookup: F) -> Code
+where
+    F: FnOnce(char) -> Option<Code>,
+{
+    match layout {
+        HotkeyLayout::Physical => physical,
+        HotkeyLayout::Logical => lookup(c).unwrap_or(physical),
+    }
+}
+
+/// Parse a key name into a Code, honoring `settings.hotkey_layout` for
+/// letters and digits.
 fn parse_key_code(s: &str) -> Option<Code> {
+    let physical = parse_physical_key_code(s)?;
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(resolve_char_code(
+            c,
+            physical,
+            hotkey_layout(),
+            crate::keyboard_layout::code_for_char,
+        )),
+        _ => Some(physical),
+    }
+}
+
+/// Parse a key name into the Code at that position on a US QWERTY keyboard.
+fn parse_physical_key_code(s: &str) -> Option<Code> {
     // Single letter (a-z)
     if s.len() == 1 {
         let c = s.chars().next()?;
@@ -436,7 +1830,7 @@ fn parse_key_code(s: &str) -> Option<Code> {
         }
     }
 
-    // Function keys (f1-f12)
+    // Function keys (f1-f24)
     if s.starts_with('f') && s.len() <= 3 {
         if let Ok(num) = s[1..].parse::<u8>() {
             return match num {
@@ -452,6 +1846,18 @@ fn parse_key_code(s: &str) -> Option<Code> {
                 10 => Some(Code::F10),
                 11 => Some(Code::F11),
                 12 => Some(Code::F12),
+                13 => Some(Code::F13),
+                14 => Some(Code::F14),
+                15 => Some(Code::F15),
+                16 => Some(Code::F16),
+                17 => Some(Code::F17),
+                18 => Some(Code::F18),
+                19 => Some(Code::F19),
+                20 => Some(Code::F20),
+                21 => Some(Code::F21),
+                22 => Some(Code::F22),
+                23 => Some(Code::F23),
+                24 => Some(Code::F24),
                 _ => None,
             };
         }
@@ -460,6 +1866,17 @@ fn parse_key_code(s: &str) -> Option<Code> {
     None
 }
 
+/// A macro named `name` on `hotkey` that types "x", for tests.
+#[cfg(test)]
+pub(crate) fn test_macro(name: &str, hotkey: &str) -> MacroDefinition {
+    MacroDefinition {
+        name: name.to_string(),
+        hotkey: hotkey.to_string(),
+        text: "x".to_string(),
+        ..MacroDefinition::default()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -484,6 +1901,7 @@ mod tests {
                     text: "Hello{Enter}World".to_string(),
                     delay_ms: 0,
                     group: None,
+                    ..MacroDefinition::default()
                 },
                 MacroDefinition {
                     id: Uuid::new_v4(),
@@ -492,9 +1910,13 @@ mod tests {
                     text: "Typing slowly...".to_string(),
                     delay_ms: 20,
                     group: Some("Work".to_string()),
+                    ..MacroDefinition::default()
                 },
             ],
-            settings: AppSettings { enabled: false },
+            settings: AppSettings {
+                enabled: false,
+                ..AppSettings::default()
+            },
         };
 
         // Serialize to TOML
@@ -515,6 +1937,7 @@ mod tests {
             text: "Hello".to_string(),
             delay_ms: 0,
             group: None,
+            ..MacroDefinition::default()
         };
 
         let toml_str = toml::to_string(&macro_def).unwrap();
@@ -667,6 +2090,7 @@ mod tests {
             text: "Hello".to_string(),
             delay_ms: 0,
             group: Some("Work".to_string()),
+            ..MacroDefinition::default()
         };
         let toml_str = toml::to_string(&macro_def).unwrap();
         assert!(toml_str.contains("group = \"Work\""));
@@ -679,6 +2103,7 @@ mod tests {
             text: "Hello".to_string(),
             delay_ms: 0,
             group: None,
+            ..MacroDefinition::default()
         };
         let toml_str_no_group = toml::to_string(&macro_def_no_group).unwrap();
         assert!(!toml_str_no_group.contains("group"));
@@ -699,6 +2124,7 @@ mod tests {
                 text: "Text 1".to_string(),
                 delay_ms: 0,
                 group: Some("Group A".to_string()),
+                ..MacroDefinition::default()
             },
             MacroDefinition {
                 id: Uuid::new_v4(),
@@ -707,6 +2133,7 @@ mod tests {
                 text: "Text 2".to_string(),
                 delay_ms: 10,
                 group: None,
+                ..MacroDefinition::default()
             },
         ];
 
@@ -723,6 +2150,139 @@ mod tests {
         assert_eq!(imported[1].group, None);
     }
 
+    #[test]
+    fn test_json_export_import_roundtrip() {
+        use tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        let export_path = dir.path().join("export.JSON");
+
+        let macros = vec![
+            MacroDefinition {
+                name: "Macro 1".to_string(),
+                hotkey: "ctrl+1".to_string(),
+                text: "Text 1{Enter}".to_string(),
+                delay_ms: 25,
+                group: Some("Group A".to_string()),
+                ..MacroDefinition::default()
+            },
+            MacroDefinition {
+                name: "Macro 2".to_string(),
+                hotkey: "ctrl+2".to_string(),
+                text: "Text 2".to_string(),
+                ..MacroDefinition::default()
+            },
+        ];
+
+        export_macros(&macros, &export_path).unwrap();
+        let content = fs::read_to_string(&export_path).unwrap();
+        assert!(content.trim_start().starts_with('{'), "{}", content);
+        // Unset optional fields are left out, like in TOML
+        assert!(!content.contains("\"group\": null"), "{}", content);
+
+        assert_eq!(import_macros(&export_path).unwrap(), macros);
+    }
+
+    #[test]
+    fn test_json_import_optional_fields() {
+        use tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("generated.json");
+        fs::write(
+            &path,
+            r#"{
+                "macros": [
+                    { "name": "Bare", "hotkey": "ctrl+shift+b", "text": "hi" },
+                    { "name": "Full", "hotkey": "ctrl+shift+f", "text": "slow", "group": "Work", "delay_ms": 30 }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let imported = import_macros(&path).unwrap();
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported[0].group, None);
+        assert_eq!(imported[0].delay_ms, 0);
+        assert!(imported[0].enabled);
+        assert_eq!(imported[1].group.as_deref(), Some("Work"));
+        assert_eq!(imported[1].delay_ms, 30);
+
+        // TOML syntax in a .json file is a JSON error
+        fs::write(&path, "[[macros]]\nname = \"x\"\n").unwrap();
+        assert!(matches!(import_macros(&path), Err(ConfigError::Json(_))));
+    }
+
+    #[test]
+    fn test_json_config_save_load_roundtrip() {
+        use tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        let config = Config {
+            macros: vec![MacroDefinition {
+                name: "Sig".to_string(),
+                hotkey: "Ctrl+Shift+S".to_string(),
+                text: "Best".to_string(),
+                ..MacroDefinition::default()
+            }],
+            settings: AppSettings {
+                enabled: false,
+                ..AppSettings::default()
+            },
+            ..Config::default()
+        };
+
+        save_config_to(&config, &path).unwrap();
+        assert!(!with_suffix(&path, ".tmp").exists());
+        let loaded = load_config_from(&path).unwrap();
+        assert_eq!(loaded.settings, config.settings);
+        assert_eq!(loaded.macros.len(), 1);
+        // Hotkeys are normalized on save, as in TOML
+        assert_eq!(loaded.macros[0].hotkey, normalize_hotkey_string("Ctrl+Shift+S").unwrap());
+
+        // A JSON config without settings gets the defaults
+        fs::write(&path, r#"{ "macros": [] }"#).unwrap();
+        assert_eq!(load_config_from(&path).unwrap().settings, AppSettings::default());
+    }
+
+    #[test]
+    fn test_config_file_in_keeps_loaded_json_after_removal() {
+        use tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        let json_path = dir.path().join("config.json");
+        fs::write(&json_path, "{}").unwrap();
+        let loaded = config_file_in(dir.path(), None);
+        assert_eq!(loaded, json_path);
+
+        fs::remove_file(&json_path).unwrap();
+        assert_eq!(config_file_in(dir.path(), Some(&loaded)), json_path);
+        // Nothing loaded yet: a new config is TOML
+        assert_eq!(config_file_in(dir.path(), None), dir.path().join("config.toml"));
+        // A file loaded from another directory doesn't apply
+        let other = tempdir().unwrap();
+        assert_eq!(config_file_in(other.path(), Some(&loaded)), other.path().join("config.toml"));
+    }
+
+    #[test]
+    fn test_config_file_in_prefers_toml() {
+        use tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        assert_eq!(config_file_in(dir.path(), None), dir.path().join("config.toml"));
+
+        fs::write(dir.path().join("config.json"), "{}").unwrap();
+        assert_eq!(config_file_in(dir.path(), None), dir.path().join("config.json"));
+
+        fs::write(dir.path().join("config.toml"), "").unwrap();
+        assert_eq!(config_file_in(dir.path(), None), dir.path().join("config.toml"));
+
+        assert!(is_json_path(Path::new("macros.Json")));
+        assert!(!is_json_path(Path::new("macros.toml")));
+        assert!(!is_json_path(Path::new("json")));
+    }
+
     #[test]
     fn test_import_dedupes_within_file() {
         use tempfile::tempdir;
@@ -770,6 +2330,8 @@ text = "hello"
 "#;
         let config: Config = toml::from_str(toml_str).unwrap();
         assert!(config.settings.enabled, "Default should be enabled=true");
+        // Every other setting gets its default too
+        assert_eq!(config.settings, AppSettings::default());
     }
 
     #[test]
@@ -796,7 +2358,11 @@ text = "hello"
         let config = Config {
             version: 1,
             macros: vec![],
-            settings: AppSettings { enabled: false },
+            settings: AppSettings {
+                enabled: false,
+                chord_timeout_ms: 800,
+                ..AppSettings::default()
+            },
         };
 
         let toml_str = toml::to_string_pretty(&config).unwrap();
@@ -804,5 +2370,988 @@ text = "hello"
 
         assert_eq!(config.settings.enabled, parsed.settings.enabled);
         assert!(!parsed.settings.enabled, "Roundtrip should preserve enabled=false");
+        assert_eq!(parsed.settings, config.settings);
+    }
+
+    #[test]
+    fn test_validate_modifier_only_hotkey() {
+        let config = Config {
+            macros: vec![MacroDefinition {
+                name: "No Key".to_string(),
+                hotkey: "ctrl+shift".to_string(),
+                text: "hi".to_string(),
+                ..MacroDefinition::default()
+            }],
+            ..Config::default()
+        };
+
+        let warnings = validate_config(&config);
+        assert_eq!(warnings.len(), 1);
+        assert!(matches!(
+            &warnings[0],
+            ValidationWarning::Macro { name, issue: MacroIssue::NoKeyInHotkey { hotkey } }
+                if hotkey == "ctrl+shift" && name == "No Key"
+        ));
+    }
+
+    #[test]
+    fn test_is_modifier_only_hotkey() {
+        assert!(is_modifier_only_hotkey("ctrl"));
+        assert!(is_modifier_only_hotkey("Ctrl+Shift+"));
+        assert!(!is_modifier_only_hotkey("ctrl+shift+k"));
+        // Unknown keys are invalid, but not modifier-only
+        assert!(!is_modifier_only_hotkey("ctrl+bogus"));
+        assert!(!is_modifier_only_hotkey(""));
+    }
+
+    #[test]
+    fn test_full_config_export_import_roundtrip() {
+        use tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("full.toml");
+
+        let config = Config {
+            version: 1,
+            macros: vec![MacroDefinition {
+                name: "Macro 1".to_string(),
+                hotkey: "ctrl+1".to_string(),
+                text: "Text 1".to_string(),
+                group: Some("Group A".to_string()),
+                ..MacroDefinition::default()
+            }],
+            settings: AppSettings {
+                enabled: false,
+                stop_on_focus_loss: true,
+                recent_macros_count: 2,
+                ..AppSettings::default()
+            },
+        };
+
+        export_config(&config, &path).unwrap();
+        let imported = import_config(&path).unwrap();
+
+        // Settings survive, unlike export_macros
+        assert_eq!(imported, config);
+    }
+
+    #[test]
+    fn test_normalize_equivalent_hotkeys() {
+        let canonical = Some("ctrl+shift+meta+k".to_string());
+        assert_eq!(normalize_hotkey_string("Cmd+Shift+Ctrl+K"), canonical);
+        assert_eq!(normalize_hotkey_string("super+shift+control+k"), canonical);
+        assert_eq!(normalize_hotkey_string(" ctrl + shift + win + k "), canonical);
+
+        assert_eq!(normalize_hotkey_string("Option+5"), Some("alt+5".to_string()));
+        assert_eq!(normalize_hotkey_string("F12"), Some("f12".to_string()));
+        assert_eq!(normalize_hotkey_string("ctrl+shift"), None);
+    }
+
+    #[test]
+    fn test_hotkey_to_string_roundtrip() {
+        for s in ["ctrl+alt+shift+meta+z", "shift+0", "ctrl+f1"] {
+            let hotkey = parse_hotkey_string(s).unwrap();
+            assert_eq!(hotkey_to_string(&hotkey), s);
+            assert_eq!(parse_hotkey_string(&hotkey_to_string(&hotkey)), Some(hotkey));
+        }
+    }
+
+    #[test]
+    fn test_validate_duplicate_hotkey_canonical() {
+        let config = Config {
+            macros: vec![
+                MacroDefinition {
+                    name: "A".to_string(),
+                    hotkey: "Cmd+Shift+K".to_string(),
+                    ..MacroDefinition::default()
+                },
+                MacroDefinition {
+                    name: "B".to_string(),
+                    hotkey: "shift+super+k".to_string(),
+                    ..MacroDefinition::default()
+                },
+            ],
+            ..Config::default()
+        };
+
+        let warnings = validate_config(&config);
+        assert!(warnings.iter().any(|w| matches!(
+            w,
+            ValidationWarning::DuplicateHotkey { hotkey, .. } if hotkey == "shift+meta+k"
+        )));
+    }
+
+    #[test]
+    fn test_resolve_config_relative() {
+        let config_dir = std::env::temp_dir().join("keyblast-test");
+
+        assert_eq!(resolve_config_relative("projects/site", &config_dir), config_dir.join("projects/site"));
+        assert_eq!(resolve_config_relative(".", &config_dir), config_dir.join("."));
+
+        let absolute = std::env::temp_dir().join("elsewhere");
+        assert_eq!(resolve_config_relative(absolute.to_str().unwrap(), &config_dir), absolute);
+    }
+
+    #[test]
+    fn test_validate_invalid_blocklist_regex() {
+        let mut config = Config::default();
+        config.settings.blocklist_apps = vec!["/^steam/".to_string(), "/([unclosed/".to_string(), "(".to_string()];
+
+        let warnings = validate_config(&config);
+        assert_eq!(warnings.len(), 1);
+        assert!(matches!(
+            &warnings[0],
+            ValidationWarning::InvalidBlocklistEntry { pattern, .. } if pattern == "/([unclosed/"
+        ));
+    }
+
+    #[test]
+    fn test_first_run_macros_uses_template() {
+        use tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(DEFAULT_MACRO_TEMPLATE);
+        fs::write(
+            &path,
+            "name = \"Team Greeting\"\nhotkey = \"ctrl+shift+g\"\ntext = \"Hi from the team\"\n",
+        )
+        .unwrap();
+
+        let macros = first_run_macros(Some(&path));
+        assert_eq!(macros.len(), 1);
+        assert_eq!(macros[0].name, "Team Greeting");
+        assert_eq!(macros[0].text, "Hi from the team");
+    }
+
+    #[test]
+    fn test_first_run_macros_falls_back() {
+        use tempfile::tempdir;
+
+        let builtin: Vec<String> = default_example_macros().into_iter().map(|m| m.name).collect();
+        let names = |macros: Vec<MacroDefinition>| macros.into_iter().map(|m| m.name).collect::<Vec<_>>();
+
+        // No template
+        assert_eq!(names(first_run_macros(None)), builtin);
+
+        // Missing file
+        let dir = tempdir().unwrap();
+        let missing = dir.path().join("nope.toml");
+        assert_eq!(names(first_run_macros(Some(&missing))), builtin);
+
+        // Invalid template (no hotkey/text)
+        let invalid = dir.path().join(DEFAULT_MACRO_TEMPLATE);
+        fs::write(&invalid, "name = \"Half\"\n").unwrap();
+        assert_eq!(names(first_run_macros(Some(&invalid))), builtin);
+    }
+
+    #[test]
+    fn test_is_not_writable_error() {
+        let denied = ConfigError::Io(io::Error::from(io::ErrorKind::PermissionDenied));
+        assert!(is_not_writable_error(&denied));
+
+        let read_only = ConfigError::Io(io::Error::from(io::ErrorKind::ReadOnlyFilesystem));
+        assert!(is_not_writable_error(&read_only));
+
+        let not_found = ConfigError::Io(io::Error::from(io::ErrorKind::NotFound));
+        assert!(!is_not_writable_error(&not_found));
+    }
+
+    #[test]
+    fn test_is_dir_writable() {
+        use tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        assert!(is_dir_writable(dir.path()));
+        // Probe file is cleaned up
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_load_macro_text_from_file() {
+        use tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("sig.txt"), "Regards,{Enter}Me").unwrap();
+
+        let file_backed: MacroDefinition = toml::from_str(
+            "name = \"Sig\"\nhotkey = \"ctrl+shift+s\"\ntext_file = \"sig.txt\"\n",
+        )
+        .unwrap();
+        assert_eq!(load_macro_text(&file_backed, dir.path()).unwrap(), "Regards,{Enter}Me");
+
+        let inline = MacroDefinition {
+            text: "inline".to_string(),
+            ..MacroDefinition::default()
+        };
+        assert_eq!(load_macro_text(&inline, dir.path()).unwrap(), "inline");
+
+        let missing = MacroDefinition {
+            text_file: Some("nope.txt".to_string()),
+            ..MacroDefinition::default()
+        };
+        assert!(load_macro_text(&missing, dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_pretty_hotkey_mac_symbols() {
+        let hotkey = parse_hotkey_string("ctrl+alt+shift+cmd+k").unwrap();
+        assert_eq!(pretty_hotkey_styled(&hotkey, true), "\u{2303}\u{2325}\u{21E7}\u{2318}K");
+
+        let hotkey = parse_hotkey_string("cmd+f5").unwrap();
+        assert_eq!(pretty_hotkey_styled(&hotkey, true), "\u{2318}F5");
+    }
+
+    #[test]
+    fn test_pretty_hotkey_words() {
+        let hotkey = parse_hotkey_string("shift+ctrl+k").unwrap();
+        assert_eq!(pretty_hotkey_styled(&hotkey, false), "Ctrl+Shift+K");
+
+        let hotkey = parse_hotkey_string("ctrl+alt+shift+cmd+1").unwrap();
+        let meta_word = if cfg!(target_os = "windows") { "Win" } else { "Super" };
+        assert_eq!(
+            pretty_hotkey_styled(&hotkey, false),
+            format!("Ctrl+Alt+Shift+{}+1", meta_word)
+        );
+    }
+
+    #[test]
+    fn test_display_hotkey_falls_back_to_raw() {
+        assert_eq!(display_hotkey("ctrl+shift"), "ctrl+shift");
+        assert_eq!(display_hotkey("ctrl+k"), pretty_hotkey(&parse_hotkey_string("ctrl+k").unwrap()));
+    }
+
+    #[test]
+    fn test_effective_strictness() {
+        let lenient_settings = AppSettings::default();
+        let strict_settings = AppSettings {
+            strict_dsl: true,
+            ..AppSettings::default()
+        };
+        let inherit = MacroDefinition::default();
+        let force_strict = MacroDefinition {
+            strict: Some(true),
+            ..MacroDefinition::default()
+        };
+        let force_lenient = MacroDefinition {
+            strict: Some(false),
+            ..MacroDefinition::default()
+        };
+
+        // Default is lenient
+        assert!(!inherit.is_strict(&lenient_settings));
+        // Setting applies when the macro doesn't say
+        assert!(inherit.is_strict(&strict_settings));
+        // Macro override wins either way
+        assert!(force_strict.is_strict(&lenient_settings));
+        assert!(!force_lenient.is_strict(&strict_settings));
+    }
+
+    #[test]
+    fn test_effective_delay_ms() {
+        let instant_settings = AppSettings::default();
+        let slow_settings = AppSettings {
+            default_delay_ms: 40,
+            ..AppSettings::default()
+        };
+        let inherit = MacroDefinition::default();
+        let own_delay = MacroDefinition {
+            delay_ms: 15,
+            ..MacroDefinition::default()
+        };
+
+        // Instant unless something sets a delay
+        assert_eq!(inherit.effective_delay_ms(&instant_settings), 0);
+        // Setting applies when the macro's delay is 0
+        assert_eq!(inherit.effective_delay_ms(&slow_settings), 40);
+        // Macro's own delay wins, faster or slower
+        assert_eq!(own_delay.effective_delay_ms(&instant_settings), 15);
+        assert_eq!(own_delay.effective_delay_ms(&slow_settings), 15);
+    }
+
+    #[test]
+    fn test_strict_macros_validated() {
+        let config = Config {
+            settings: AppSettings {
+                strict_dsl: true,
+                ..AppSettings::default()
+            },
+            macros: vec![
+                MacroDefinition {
+                    name: "Typo".to_string(),
+                    hotkey: "ctrl+1".to_string(),
+                    text: "Hi{Entr}".to_string(),
+                    ..MacroDefinition::default()
+                },
+                MacroDefinition {
+                    name: "JSON".to_string(),
+                    hotkey: "ctrl+2".to_string(),
+                    text: "{\"a\": 1}".to_string(),
+                    strict: Some(false),
+                    ..MacroDefinition::default()
+                },
+            ],
+            ..Config::default()
+        };
+
+        let warnings = validate_config(&config);
+        assert_eq!(warnings.len(), 1);
+        assert!(matches!(
+            &warnings[0],
+            ValidationWarning::Macro { name, issue: MacroIssue::InvalidDsl { .. } } if name == "Typo"
+        ));
+    }
+
+    #[test]
+    fn test_injection_method_setting() {
+        let config: Config = toml::from_str("[settings]\ninjection_method = \"unicode\"\n").unwrap();
+        assert_eq!(config.settings.injection_method, InjectionMethod::Unicode);
+        assert_eq!(AppSettings::default().injection_method, InjectionMethod::Text);
+        assert!(toml::from_str::<Config>("[settings]\ninjection_method = \"morse\"\n").is_err());
+    }
+
+    #[test]
+    fn test_default_click_button_setting() {
+        let config: Config = toml::from_str("[settings]\ndefault_click_button = \"right\"\n").unwrap();
+        assert_eq!(config.settings.default_click_button, ClickButton::Right);
+        assert_eq!(AppSettings::default().default_click_button, ClickButton::Left);
+        assert!(toml::from_str::<Config>("[settings]\ndefault_click_button = \"thumb\"\n").is_err());
+    }
+
+    #[test]
+    fn test_timestamp_now_format() {
+        let now = timestamp_now();
+        assert_eq!(now.len(), "1970-01-01T00:00:00Z".len());
+        assert!(now.ends_with('Z'));
+        assert!(chrono::DateTime::parse_from_rfc3339(&now).is_ok());
+    }
+
+    #[test]
+    fn test_loggable_text_redacts_sensitive_macros() {
+        let normal = MacroDefinition {
+            text: "hello{Enter}".to_string(),
+            ..MacroDefinition::default()
+        };
+        let secret = MacroDefinition {
+            text: "hunter2".to_string(),
+            sensitive: true,
+            ..MacroDefinition::default()
+        };
+        let secret_file = MacroDefinition {
+            text_file: Some("secret.txt".to_string()),
+            sensitive: true,
+            ..MacroDefinition::default()
+        };
+        let file = MacroDefinition {
+            text_file: Some("notes.txt".to_string()),
+            ..MacroDefinition::default()
+        };
+
+        assert_eq!(loggable_text(&normal), "hello{Enter}");
+        assert_eq!(loggable_text(&secret), REDACTED);
+        assert_eq!(loggable_text(&secret_file), REDACTED);
+        assert_eq!(loggable_text(&file), "<notes.txt>");
+    }
+
+    #[test]
+    fn test_export_is_independent_of_macro_order() {
+        use tempfile::tempdir;
+
+        // Fixed IDs so both exports serialize identically
+        let grouped = |id: u128, name: &str, group: Option<&str>| MacroDefinition {
+            id: Uuid::from_u128(id),
+            name: name.to_string(),
+            hotkey: "ctrl+1".to_string(),
+            group: group.map(str::to_string),
+            ..MacroDefinition::default()
+        };
+        let macros = vec![
+            grouped(1, "zeta", None),
+            grouped(2, "Beta", Some("work")),
+            grouped(3, "alpha", None),
+            grouped(4, "Alpha2", Some("Home")),
+        ];
+        let mut reversed = macros.clone();
+        reversed.reverse();
+        let first = Config { macros, ..Config::default() };
+        let second = Config { macros: reversed, ..Config::default() };
+
+        let dir = tempdir().unwrap();
+        let (a, b) = (dir.path().join("a.toml"), dir.path().join("b.toml"));
+        export_config(&first, &a).unwrap();
+        export_config(&second, &b).unwrap();
+        assert_eq!(fs::read_to_string(&a).unwrap(), fs::read_to_string(&b).unwrap());
+
+        export_macros(&macros_for_export(&first), &a).unwrap();
+        export_macros(&macros_for_export(&second), &b).unwrap();
+        assert_eq!(fs::read_to_string(&a).unwrap(), fs::read_to_string(&b).unwrap());
+
+        let order: Vec<_> = macros_for_export(&first).into_iter().map(|m| m.name).collect();
+        assert_eq!(order, vec!["Alpha2", "Beta", "alpha", "zeta"]);
+        // In-memory order is untouched
+        assert_eq!(first.macros[0].name, "zeta");
+
+        let unsorted = Config {
+            settings: AppSettings { export_sorted: false, ..AppSettings::default() },
+            ..second
+        };
+        assert_eq!(macros_for_export(&unsorted)[0].name, "Alpha2");
+        assert_eq!(macros_for_export(&unsorted)[3].name, "zeta");
+    }
+
+    #[test]
+    fn test_last_import_path_roundtrip() {
+        // Not written until something was imported
+        let toml_str = toml::to_string_pretty(&Config::default()).unwrap();
+        assert!(!toml_str.contains("last_import_path"));
+        let parsed: Config = toml::from_str(&toml_str).unwrap();
+        assert_eq!(parsed.settings.last_import_path, None);
+
+        let mut config = Config::default();
+        config.settings.last_import_path = Some("/home/me/team-macros.toml".to_string());
+        config.settings.last_import_strategy = Some(MergeStrategy::RenameOnConflict);
+        let toml_str = toml::to_string_pretty(&config).unwrap();
+        assert!(toml_str.contains("last_import_strategy = \"rename\""));
+        let parsed: Config = toml::from_str(&toml_str).unwrap();
+        assert_eq!(
+            parsed.settings.last_import_path.as_deref(),
+            Some("/home/me/team-macros.toml")
+        );
+        assert_eq!(parsed.settings.last_import_strategy, Some(MergeStrategy::RenameOnConflict));
+    }
+
+    fn issues_of(macro_def: MacroDefinition) -> Vec<MacroIssue> {
+        macro_def.validate_with(&AppSettings::default(), &std::env::temp_dir())
+    }
+
+    #[test]
+    fn test_macro_issue_is_blocking() {
+        assert!(MacroIssue::EmptyText.is_blocking());
+        assert!(MacroIssue::InvalidDsl { issues: vec![] }.is_blocking());
+        assert!(MacroIssue::MissingTextFile { path: PathBuf::from("gone.txt") }.is_blocking());
+        assert!(!MacroIssue::RiskyHotkey { hotkey: "k".to_string() }.is_blocking());
+        assert!(!MacroIssue::UnbalancedKeyDown { keys: vec!["Shift".to_string()] }.is_blocking());
+    }
+
+    #[test]
+    fn test_macro_validate_clean() {
+        assert_eq!(test_macro("Sig", "ctrl+shift+k").validate(&AppSettings::default()), vec![]);
+        // Menu-only macros may have no hotkey; a bare function key is fine
+        assert_eq!(issues_of(MacroDefinition { hotkey: String::new(), ..test_macro("Sig", "ctrl+shift+k") }), vec![]);
+        assert_eq!(issues_of(MacroDefinition { hotkey: "f9".to_string(), ..test_macro("Sig", "ctrl+shift+k") }), vec![]);
+    }
+
+    #[test]
+    fn test_macro_validate_empty_text() {
+        assert_eq!(
+            issues_of(MacroDefinition { text: String::new(), ..test_macro("Sig", "ctrl+shift+k") }),
+            vec![MacroIssue::EmptyText]
+        );
+    }
+
+    #[test]
+    fn test_macro_validate_bad_hotkeys() {
+        assert_eq!(
+            issues_of(MacroDefinition { hotkey: "ctrl+alt".to_string(), ..test_macro("Sig", "ctrl+shift+k") }),
+            vec![MacroIssue::NoKeyInHotkey { hotkey: "ctrl+alt".to_string() }]
+        );
+        assert_eq!(
+            issues_of(MacroDefinition { hotkey: "ctrl+banana".to_string(), ..test_macro("Sig", "ctrl+shift+k") }),
+            vec![MacroIssue::InvalidHotkey { hotkey: "ctrl+banana".to_string() }]
+        );
+        for risky in ["k", "shift+5"] {
+            assert_eq!(
+                issues_of(MacroDefinition { hotkey: risky.to_string(), ..test_macro("Sig", "ctrl+shift+k") }),
+                vec![MacroIssue::RiskyHotkey { hotkey: risky.to_string() }]
+            );
+        }
+    }
+
+    #[test]
+    fn test_macro_validate_unbalanced_keydown() {
+        let held = MacroDefinition {
+            text: "{KeyDown Ctrl}{KeyDown Shift}a{KeyUp Ctrl}".to_string(),
+            ..test_macro("Sig", "ctrl+shift+k")
+        };
+        assert_eq!(
+            issues_of(held),
+            vec![MacroIssue::UnbalancedKeyDown { keys: vec!["Shift".to_string()] }]
+        );
+        let balanced = MacroDefinition {
+            text: "{KeyDown Ctrl}c{KeyUp Ctrl}".to_string(),
+            ..test_macro("Sig", "ctrl+shift+k")
+        };
+        assert_eq!(issues_of(balanced), vec![]);
+    }
+
+    #[test]
+    fn test_macro_validate_paths_and_dsl() {
+        use tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        let issues = MacroDefinition {
+            text: String::new(),
+            text_file: Some("missing.txt".to_string()),
+            ..test_macro("Sig", "ctrl+shift+k")
+        }
+        .validate_with(&AppSettings::default(), dir.path());
+        assert_eq!(
+            issues,
+            vec![MacroIssue::MissingTextFile { path: dir.path().join("missing.txt") }]
+        );
+
+        let strict = MacroDefinition { text: "Hi{Entr}".to_string(), strict: Some(true), ..test_macro("Sig", "ctrl+shift+k") };
+        assert!(matches!(issues_of(strict).as_slice(), [MacroIssue::InvalidDsl { .. }]));
+    }
+
+    #[test]
+    fn test_macro_issue_in_config_warning() {
+        let config = Config {
+            macros: vec![MacroDefinition { hotkey: "k".to_string(), ..test_macro("Sig", "ctrl+shift+k") }],
+            ..Config::default()
+        };
+        let warnings = validate_config(&config);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].to_string(),
+            "Macro 'Sig': hotkey 'k' has no Ctrl, Alt or Meta, so it blocks normal typing of that key"
+        );
+    }
+
+    #[test]
+    fn test_export_macros_shareable_strips_local_fields() {
+        use tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("snippets")).unwrap();
+        fs::write(dir.path().join("snippets/sig.txt"), "Best,{Enter}Me").unwrap();
+        let macros = vec![
+            MacroDefinition {
+                name: "Signature".to_string(),
+                hotkey: "ctrl+shift+s".to_string(),
+                text_file: Some("snippets/sig.txt".to_string()),
+                modified: Some("2026-01-02T03:04:05Z".to_string()),
+                group: Some("Email".to_string()),
+                only_on: Some("my-laptop".to_string()),
+                ..MacroDefinition::default()
+            },
+            MacroDefinition {
+                name: "Inline".to_string(),
+                hotkey: "ctrl+shift+i".to_string(),
+                text: "hello".to_string(),
+                only_on: Some("MacOS".to_string()),
+                ..MacroDefinition::default()
+            },
+        ];
+
+        let path = dir.path().join("shared.toml");
+        export_macros_shareable(&macros, dir.path(), &path).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        for field in ["text_file", "modified", "snippets/", "my-laptop"] {
+            assert!(!content.contains(field), "{} leaked into:\n{}", field, content);
+        }
+        let shared = import_macros(&path).unwrap();
+        assert_eq!(shared[0].text, "Best,{Enter}Me");
+        assert_eq!(shared[0].text_file, None);
+        assert_eq!(shared[0].modified, None);
+        assert_eq!(shared[0].only_on, None);
+        // Everything else is kept, including an only_on naming an OS
+        assert_eq!(shared[1].only_on.as_deref(), Some("MacOS"));
+        assert_eq!(shared[0].group.as_deref(), Some("Email"));
+        assert_eq!(shared[0].hotkey, "ctrl+shift+s");
+        assert_eq!(shared[1].text, "hello");
+
+        // An unreadable text file fails the export instead of sharing an empty macro
+        let broken = vec![MacroDefinition {
+            text_file: Some("missing.txt".to_string()),
+            ..macros[0].clone()
+        }];
+        assert!(export_macros_shareable(&broken, dir.path(), &dir.path().join("x.toml")).is_err());
+    }
+
+    /// Stub AZERTY lookups: A and Q swap places, M sits on the semicolon key.
+    fn azerty_code(c: char) -> Option<Code> {
+        match c {
+            'a' => Some(Code::KeyQ),
+            'q' => Some(Code::KeyA),
+            'm' => Some(Code::Semicolon),
+            _ => None,
+        }
+    }
+
+    fn azerty_char(code: Code) -> Option<char> {
+        match code {
+            Code::KeyQ => Some('a'),
+            Code::KeyA => Some('q'),
+            Code::Semicolon => Some('m'),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_resolve_char_code_by_layout() {
+        // Physical never asks the layout
+        let physical = resolve_char_code('a', Code::KeyA, HotkeyLayout::Physical, |_| {
+            panic!("physical layout must not look up the active layout")
+        });
+        assert_eq!(physical, Code::KeyA);
+
+        assert_eq!(resolve_char_code('a', Code::KeyA, HotkeyLayout::Logical, azerty_code), Code::KeyQ);
+        assert_eq!(resolve_char_code('m', Code::KeyM, HotkeyLayout::Logical, azerty_code), Code::Semicolon);
+        // Falls back to the physical key when the layout can't say (or isn't supported)
+        assert_eq!(resolve_char_code('k', Code::KeyK, HotkeyLayout::Logical, azerty_code), Code::KeyK);
+        assert_eq!(resolve_char_code('k', Code::KeyK, HotkeyLayout::Logical, |_| None), Code::KeyK);
+    }
+
+    #[test]
+    fn test_key_code_name_by_layout() {
+        assert_eq!(key_code_name_for(Code::KeyQ, HotkeyLayout::Physical, azerty_char), "q");
+        assert_eq!(key_code_name_for(Code::KeyQ, HotkeyLayout::Logical, azerty_char), "a");
+        assert_eq!(key_code_name_for(Code::Semicolon, HotkeyLayout::Logical, azerty_char), "m");
+        assert_eq!(key_code_name_for(Code::F5, HotkeyLayout::Logical, azerty_char), "f5");
+    }
+
+    #[test]
+    fn test_hotkey_layout_setting() {
+        assert_eq!(AppSettings::default().hotkey_layout, HotkeyLayout::Physical);
+        let config: Config = toml::from_str("[settings]\nhotkey_layout = \"logical\"\n").unwrap();
+        assert_eq!(config.settings.hotkey_layout, HotkeyLayout::Logical);
+    }
+
+    #[test]
+    fn test_macro_validate_risk_is_advisory() {
+        let risky = MacroDefinition { text: "curl x | sh{Enter}".to_string(), ..test_macro("Sig", "ctrl+shift+k") };
+        assert_eq!(
+            issues_of(risky),
+            vec![MacroIssue::Risk(crate::audit::RiskFlag::ShellMetacharacters(vec!["|".to_string()]))]
+        );
+    }
+
+    #[test]
+    fn test_parse_function_keys_f13_to_f24() {
+        assert_eq!(parse_hotkey_string("ctrl+f12").map(|hk| hk.key), Some(Code::F12));
+        assert_eq!(parse_hotkey_string("ctrl+f13").map(|hk| hk.key), Some(Code::F13));
+        assert_eq!(parse_hotkey_string("F24").map(|hk| hk.key), Some(Code::F24));
+        assert_eq!(normalize_hotkey_string("Shift+F18"), Some("shift+f18".to_string()));
+
+        for invalid in ["f0", "f25", "f99", "f100", "fx"] {
+            assert_eq!(parse_physical_key_code(invalid), None, "{}", invalid);
+            assert_eq!(parse_hotkey_string(&format!("ctrl+{}", invalid)), None, "{}", invalid);
+        }
+        // Bare F13-F24 keys don't count as risky
+        let macro_def = MacroDefinition {
+            hotkey: "f20".to_string(),
+            text: "x".to_string(),
+            ..MacroDefinition::default()
+        };
+        assert_eq!(macro_def.validate_with(&AppSettings::default(), &std::env::temp_dir()), vec![]);
+    }
+
+    #[test]
+    fn test_inject_via_clipboard_field() {
+        let plain: MacroDefinition = toml::from_str("name = \"A\"\nhotkey = \"ctrl+k\"\ntext = \"x\"").unwrap();
+        assert!(!plain.inject_via_clipboard);
+
+        let toml_str = r#"
+            name = "Emoji"
+            hotkey = "ctrl+e"
+            text = "😀 你好"
+            inject_via_clipboard = true
+        "#;
+        let macro_def: MacroDefinition = toml::from_str(toml_str).unwrap();
+        assert!(macro_def.inject_via_clipboard);
+        let back: MacroDefinition = toml::from_str(&toml::to_string(&macro_def).unwrap()).unwrap();
+        assert_eq!(back, macro_def);
+    }
+
+    #[test]
+    fn test_parse_tsv_valid_lines() {
+        let content = "Greeting\tctrl+shift+g\tHello!{Enter}\nAddress\tctrl+alt+a\t1 Main St\\nSpringfield\\tUSA\n";
+        let macros = parse_tsv(content).unwrap();
+        assert_eq!(macros.len(), 2);
+        assert_eq!(macros[0].name, "Greeting");
+        assert_eq!(macros[0].hotkey, "ctrl+shift+g");
+        assert_eq!(macros[0].text, "Hello!{Enter}");
+        assert!(macros[0].hotkey_enabled);
+        assert_eq!(macros[1].text, "1 Main St\nSpringfield\tUSA");
+    }
+
+    #[test]
+    fn test_parse_tsv_comments_and_blanks() {
+        let content = "# name\thotkey\ttext\n\n   \n  # indented comment\r\nMenu Only\t\tC:\\\\temp\\x\r\n";
+        let macros = parse_tsv(content).unwrap();
+        assert_eq!(macros.len(), 1);
+        // No hotkey: menu-only
+        assert_eq!(macros[0].hotkey, "");
+        assert!(!macros[0].hotkey_enabled);
+        // \\ is a backslash; an unknown escape stays as typed
+        assert_eq!(macros[0].text, "C:\\temp\\x");
+    }
+
+    #[test]
+    fn test_parse_tsv_malformed_rows() {
+        let message = |content: &str| parse_tsv(content).unwrap_err().to_string();
+        assert_eq!(
+            message("# header\nOnly a name\n"),
+            "Line 2: expected 3 tab-separated fields (name, hotkey, text), found 1"
+        );
+        assert_eq!(
+            message("A\tctrl+a\ttext\twith a raw tab"),
+            "Line 1: expected 3 tab-separated fields (name, hotkey, text), found 4"
+        );
+        assert_eq!(message(" \tctrl+a\ttext"), "Line 1: macro name is empty");
+        assert_eq!(message("A\tctrl+nope\ttext"), "Line 1: invalid hotkey 'ctrl+nope'");
+    }
+
+    #[test]
+    fn test_import_tsv_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("macros.tsv");
+        fs::write(&path, "Sig\tctrl+shift+s\tThanks\nSig\tctrl+shift+t\tDuplicate\n").unwrap();
+        assert!(is_tsv_path(&path));
+        assert!(!is_tsv_path(Path::new("macros.toml")));
+
+        let macros = import_tsv(&path).unwrap();
+        assert_eq!(macros.len(), 1);
+        assert_eq!(macros[0].text, "Thanks");
+    }
+
+    #[test]
+    fn test_interactive_timeout_ms() {
+        assert_eq!(AppSettings::default().interactive_timeout_ms, 300_000);
+        let config: Config = toml::from_str("[settings]\ninteractive_timeout_ms = 0\n").unwrap();
+        assert_eq!(config.settings.interactive_timeout_ms, 0);
+        let config: Config = toml::from_str("[settings]\n").unwrap();
+        assert_eq!(config.settings.interactive_timeout_ms, 300_000);
+    }
+
+    #[test]
+    fn test_apply_macro_edit() {
+        let sig = MacroDefinition {
+            name: "Sig".to_string(),
+            hotkey: "ctrl+shift+s".to_string(),
+            text: "Thanks".to_string(),
+            ..MacroDefinition::default()
+        };
+        let other = MacroDefinition { name: "Other".to_string(), ..MacroDefinition::default() };
+        let macros = vec![sig.clone(), other];
+        let edit = |name: &str, hotkey: &str, text: Option<&str>| MacroEdit {
+            name: name.to_string(),
+            hotkey: hotkey.to_string(),
+            text: text.map(str::to_string),
+        };
+
+        let edited = apply_macro_edit(&macros, sig.id, edit(" Signature ", "ctrl+alt+s", Some("Cheers{Enter}"))).unwrap();
+        assert_eq!(edited.id, sig.id);
+        assert_eq!(edited.name, "Signature");
+        assert_eq!(edited.hotkey, "ctrl+alt+s");
+        assert_eq!(edited.text, "Cheers{Enter}");
+        assert!(edited.modified.is_some());
+
+        // Text left alone
+        let edited = apply_macro_edit(&macros, sig.id, edit("Sig", "ctrl+shift+s", None)).unwrap();
+        assert_eq!(edited.text, "Thanks");
+
+        assert!(apply_macro_edit(&macros, sig.id, edit("  ", "ctrl+a", None)).is_err());
+        assert!(apply_macro_edit(&macros, sig.id, edit("Other", "ctrl+a", None)).is_err());
+        assert!(apply_macro_edit(&macros, sig.id, edit("Sig", "ctrl+nope", None)).is_err());
+        assert!(apply_macro_edit(&macros, sig.id, edit("Sig", "ctrl+a", Some(""))).is_err());
+        assert!(apply_macro_edit(&macros, Uuid::new_v4(), edit("Sig", "ctrl+a", None)).is_err());
+
+        // Only a menu-only macro can do without a hotkey
+        assert!(apply_macro_edit(&macros, sig.id, edit("Sig", "", None)).is_err());
+        let menu_only = MacroDefinition { hotkey_enabled: false, ..sig.clone() };
+        let edited = apply_macro_edit(&[menu_only], sig.id, edit("Sig", "", None)).unwrap();
+        assert_eq!(edited.hotkey, "");
+    }
+
+    #[test]
+    fn test_mouse_button_field() {
+        let toml_str = "name = \"Back\"\nhotkey = \"ctrl+alt+b\"\ntext = \"x\"\nmouse_button = \"x1\"\n";
+        let macro_def: MacroDefinition = toml::from_str(toml_str).unwrap();
+        assert_eq!(macro_def.mouse_button.as_deref(), Some("x1"));
+        assert!(!macro_def.validate(&AppSettings::default()).iter().any(|i| matches!(i, MacroIssue::InvalidMouseButton { .. })));
+
+        let bad = MacroDefinition { mouse_button: Some("left".to_string()), ..macro_def };
+        assert!(bad.validate(&AppSettings::default()).contains(&MacroIssue::InvalidMouseButton { button: "left".to_string() }));
+        assert!(!AppSettings::default().mouse_triggers_enabled);
+    }
+
+    #[test]
+    fn test_macro_enabled_field() {
+        let toml_str = "name = \"A\"\nhotkey = \"ctrl+alt+a\"\ntext = \"x\"\n";
+        let macro_def: MacroDefinition = toml::from_str(toml_str).unwrap();
+        assert!(macro_def.enabled);
+        assert!(macro_def.registers_hotkey());
+
+        let disabled: MacroDefinition = toml::from_str(&format!("{}enabled = false\n", toml_str)).unwrap();
+        assert!(!disabled.registers_hotkey());
+
+        // A disabled macro may share its hotkey (e.g. work and home sets)
+        let config = Config {
+            macros: vec![macro_def, MacroDefinition { name: "B".to_string(), ..disabled }],
+            ..Config::default()
+        };
+        assert!(!validate_config(&config)
+            .iter()
+            .any(|w| matches!(w, ValidationWarning::DuplicateHotkey { .. })));
+    }
+
+    #[test]
+    fn test_parse_hotkey_chord() {
+        let ctrl_k = HotKey::new(Some(Modifiers::CONTROL), Code::KeyK);
+        let ctrl_s = HotKey::new(Some(Modifiers::CONTROL), Code::KeyS);
+        assert_eq!(parse_hotkey_chord("ctrl+k ctrl+s"), Some([ctrl_k, ctrl_s]));
+        assert_eq!(parse_hotkey_chord(" Ctrl + K   ctrl+S "), Some([ctrl_k, ctrl_s]));
+        assert_eq!(parse_hotkey_chord("ctrl+k s").map(|[_, second]| second.mods), Some(Modifiers::empty()));
+
+        assert_eq!(parse_hotkey_chord("ctrl+k"), None);
+        assert_eq!(parse_hotkey_chord("ctrl+k ctrl+s ctrl+t"), None);
+        assert_eq!(parse_hotkey_chord("ctrl+k ctrl+nope"), None);
+        // Not silently read as its last step
+        assert_eq!(parse_hotkey_string("ctrl+k ctrl+s"), None);
+
+        assert_eq!(normalize_hotkey_string("Ctrl+K  cmd+S").as_deref(), Some("ctrl+k meta+s"));
+        assert_eq!(
+            display_hotkey("ctrl+k ctrl+s"),
+            format!("{} {}", pretty_hotkey(&ctrl_k), pretty_hotkey(&ctrl_s))
+        );
+    }
+
+    #[test]
+    fn test_validate_chord_hotkeys() {
+        assert!(test_macro("A", "ctrl+k ctrl+s").validate(&AppSettings::default()).is_empty());
+        assert!(matches!(
+            test_macro("A", "ctrl+k ctrl+s ctrl+t").validate(&AppSettings::default())[..],
+            [MacroIssue::InvalidHotkey { .. }]
+        ));
+        // A bare letter as the first step would eat typing
+        assert!(matches!(test_macro("A", "k ctrl+s").validate(&AppSettings::default())[..], [MacroIssue::RiskyHotkey { .. }]));
+
+        // Chords may share a first step, but not with a plain hotkey
+        let config = Config {
+            macros: vec![
+                test_macro("Save", "ctrl+k ctrl+s"),
+                test_macro("Tab", "ctrl+k ctrl+t"),
+                test_macro("Plain", "ctrl+k"),
+            ],
+            ..Config::default()
+        };
+        let duplicates: Vec<_> = validate_config(&config)
+            .into_iter()
+            .filter_map(|w| match w {
+                ValidationWarning::DuplicateHotkey { hotkey, names } => Some((hotkey, names)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].0, "ctrl+k");
+        assert_eq!(duplicates[0].1, vec!["Plain", "Save", "Tab"]);
+
+        assert_eq!(AppSettings::default().chord_timeout_ms, 1_500);
+    }
+
+    fn named(name: &str, text: &str) -> MacroDefinition {
+        MacroDefinition { text: text.to_string(), ..test_macro(name, "") }
+    }
+
+    #[test]
+    fn test_merge_imported_strategies() {
+        let existing = vec![named("Sig", "old"), named("Sig (2)", "older")];
+        let imported = || vec![named("Sig", "new"), named("Other", "x")];
+
+        let skip = merge_imported(&existing, imported(), MergeStrategy::Skip);
+        assert_eq!(skip[0], MergeAction::Skip("Sig".to_string()));
+        assert!(matches!(&skip[1], MergeAction::Add(m) if m.name == "Other"));
+
+        let overwrite = merge_imported(&existing, imported(), MergeStrategy::Overwrite);
+        let MergeAction::Overwrite(replacement) = &overwrite[0] else {
+            panic!("expected overwrite, got {:?}", overwrite[0]);
+        };
+        assert_eq!((replacement.id, replacement.text.as_str()), (existing[0].id, "new"));
+        assert!(matches!(&overwrite[1], MergeAction::Add(m) if m.name == "Other"));
+
+        // "Sig (2)" is taken too, so the copy becomes "Sig (3)"
+        let rename = merge_imported(&existing, imported(), MergeStrategy::RenameOnConflict);
+        let MergeAction::Rename { original, macro_def } = &rename[0] else {
+            panic!("expected rename, got {:?}", rename[0]);
+        };
+        assert_eq!((original.as_str(), macro_def.name.as_str()), ("Sig", "Sig (3)"));
+        assert_ne!(macro_def.id, existing[0].id);
+        assert!(matches!(&rename[1], MergeAction::Add(m) if m.name == "Other"));
+    }
+
+    #[test]
+    fn test_merge_imported_regenerates_taken_ids() {
+        let existing = vec![named("A", "a")];
+        // Re-importing a file exported under another name keeps the old IDs
+        let copy = MacroDefinition { name: "B".to_string(), ..existing[0].clone() };
+        let actions = merge_imported(&existing, vec![copy.clone(), copy], MergeStrategy::Skip);
+
+        let ids: Vec<Uuid> = actions
+            .iter()
+            .map(|action| match action {
+                MergeAction::Add(m) | MergeAction::Rename { macro_def: m, .. } => m.id,
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_ne!(ids[0], existing[0].id);
+        assert_ne!(ids[0], ids[1]);
+        assert!(merge_imported(&existing, vec![], MergeStrategy::Overwrite).is_empty());
+    }
+
+    #[test]
+    fn test_unique_name() {
+        let taken: HashSet<String> = ["Sig", "Sig (2)", "Sig (4)"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(unique_name("Sig", &taken), "Sig (3)");
+        assert_eq!(unique_name("Other", &taken), "Other (2)");
+    }
+
+    #[test]
+    fn test_only_on_matches_os_and_hostname() {
+        assert!(only_on_matches("macos", "macos", "laptop"));
+        assert!(only_on_matches(" Windows ", "windows", "desk"));
+        assert!(!only_on_matches("macos", "windows", "desk"));
+        // Hostnames, in full or short form
+        assert!(only_on_matches("Build-01", "linux", "build-01"));
+        assert!(only_on_matches("laptop", "macos", "laptop.example.com"));
+        assert!(only_on_matches("laptop.example.com", "macos", "laptop.example.com"));
+        assert!(!only_on_matches("laptop", "macos", "laptop2.example.com"));
+        assert!(!only_on_matches("example", "macos", "laptop.example.com"));
+        // Blank is like unset
+        assert!(only_on_matches("  ", "linux", "desk"));
+    }
+
+    #[test]
+    fn test_macro_applies() {
+        let everywhere = MacroDefinition { hotkey: "ctrl+1".to_string(), ..MacroDefinition::default() };
+        assert!(macro_applies(&everywhere));
+        assert!(everywhere.registers_hotkey());
+
+        let here = MacroDefinition { only_on: Some(std::env::consts::OS.to_uppercase()), ..everywhere.clone() };
+        assert!(macro_applies(&here));
+        let this_host = gethostname::gethostname().to_string_lossy().into_owned();
+        assert!(macro_applies(&MacroDefinition { only_on: Some(this_host), ..everywhere.clone() }));
+
+        let elsewhere = MacroDefinition { only_on: Some("no-such-os-or-host".to_string()), ..everywhere };
+        assert!(!macro_applies(&elsewhere));
+        assert!(!elsewhere.registers_hotkey());
+    }
+
+    #[test]
+    fn test_only_on_serialization() {
+        let toml_str = r#"
+            [[macros]]
+            name = "Mac only"
+            hotkey = "cmd+shift+m"
+            text = "x"
+            only_on = "macos"
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.macros[0].only_on.as_deref(), Some("macos"));
+
+        // Left out of saved configs when unset
+        let plain = Config { macros: vec![MacroDefinition::default()], ..Config::default() };
+        assert!(!toml::to_string(&plain).unwrap().contains("only_on"));
     }
 }
@@ -0,0 +1,249 @@
+//! Dry-run comparison of macro texts for KeyBlast.
+//!
+//! `plan_diff` parses and expands two macro texts and reports which segments
+//! were added, removed, or changed, without injecting anything. Useful for
+//! checking that a rewrite of a macro still types the same thing.
+
+use crate::injection::{self, MacroSegment};
+
+/// A single difference between two expanded macro sequences.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SegmentChange {
+    /// Segment only present in the new text, at this index of the new sequence.
+    Added { index: usize, segment: MacroSegment },
+    /// Segment only present in the old text, at this index of the old sequence.
+    Removed { index: usize, segment: MacroSegment },
+    /// Segment replaced in place, at this index of the new sequence.
+    Changed {
+        index: usize,
+        old: MacroSegment,
+        new: MacroSegment,
+    },
+}
+
+/// Result of comparing two macro texts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffSummary {
+    /// Number of segments the old text expands to.
+    pub old_len: usize,
+    /// Number of segments the new text expands to.
+    pub new_len: usize,
+    /// Differences in sequence order.
+    pub changes: Vec<SegmentChange>,
+}
+
+impl DiffSummary {
+    /// True when both texts produce the same segments.
+    pub fn is_identical(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl std::fmt::Display for DiffSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_identical() {
+            return write!(f, "Identical ({} segments)", self.old_len);
+        }
+
+        writeln!(
+            f,
+            "{} difference(s) ({} -> {} segments):",
+            self.changes.len(),
+            self.old_len,
+            self.new_len
+        )?;
+        for change in &self.changes {
+            match change {
+                SegmentChange::Added { index, segment } => {
+                    writeln!(f, "  + [{}] {:?}", index, segment)?;
+                }
+                SegmentChange::Removed { index, segment } => {
+                    writeln!(f, "  - [{}] {:?}", index, segment)?;
+                }
+                SegmentChange::Changed { index, old, new } => {
+                    writeln!(f, "  ~ [{}] {:?} -> {:?}", index, old, new)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compare what two macro texts would type with the given keystroke delay.
+///
+/// Both texts are parsed and expanded the way execution would (per-character
+/// Text when `delay_ms > 0`), so a difference here means a difference in output.
+/// `{Paste}` is compared as-is since clipboard contents aren't part of the macro.
+pub fn plan_diff(old_text: &str, new_text: &str, delay_ms: u64) -> DiffSummary {
+    let old = expand_for_diff(injection::parse_macro_sequence(old_text), delay_ms);
+    let new = expand_for_diff(injection::parse_macro_sequence(new_text), delay_ms);
+
+    DiffSummary {
+        old_len: old.len(),
+        new_len: new.len(),
+        changes: diff_segments(&old, &new),
+    }
+}
+
+/// Expand like `injection::expand_segments`, but keep Paste as a marker.
+fn expand_for_diff(segments: Vec<MacroSegment>, delay_ms: u64) -> Vec<MacroSegment> {
+    let segments = injection::flatten_repeats(segments);
+    if delay_ms == 0 {
+        return segments;
+    }
+
+    segments
+        .into_iter()
+        .flat_map(|seg| match seg {
+            MacroSegment::Text(text) => text
+                .chars()
+                .map(|c| MacroSegment::Text(c.to_string()))
+                .collect::<Vec<_>>(),
+            other => vec![other],
+        })
+        .collect()
+}
+
+/// One step of the edit script produced by the LCS walk.
+enum Op {
+    Keep,
+    Remove(usize),
+    Add(usize),
+}
+
+/// Diff two segment sequences using a longest-common-subsequence table.
+///
+/// Runs of removals directly followed by additions are reported pairwise as
+/// `Changed` (e.g. `{Delay 100}` -> `{Delay 200}`).
+pub fn diff_segments(old: &[MacroSegment], new: &[MacroSegment]) -> Vec<SegmentChange> {
+    let (n, m) = (old.len(), new.len());
+
+    // lcs[i][j] = LCS length of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && old[i] == new[j] {
+            ops.push(Op::Keep);
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            ops.push(Op::Add(j));
+            j += 1;
+        } else {
+            ops.push(Op::Remove(i));
+            i += 1;
+        }
+    }
+
+    // Collapse each run of removes/adds into Changed pairs plus leftovers
+    let mut changes = Vec::new();
+    let mut removed: Vec<usize> = Vec::new();
+    let mut added: Vec<usize> = Vec::new();
+    let flush = |removed: &mut Vec<usize>, added: &mut Vec<usize>, changes: &mut Vec<SegmentChange>| {
+        let paired = removed.len().min(added.len());
+        for k in 0..paired {
+            changes.push(SegmentChange::Changed {
+                index: added[k],
+                old: old[removed[k]].clone(),
+                new: new[added[k]].clone(),
+            });
+        }
+        for &index in &removed[paired..] {
+            changes.push(SegmentChange::Removed { index, segment: old[index].clone() });
+        }
+        for &index in &added[paired..] {
+            changes.push(SegmentChange::Added { index, segment: new[index].clone() });
+        }
+        removed.clear();
+        added.clear();
+    };
+
+    for op in ops {
+        match op {
+            Op::Keep => flush(&mut removed, &mut added, &mut changes),
+            Op::Remove(index) => removed.push(index),
+            Op::Add(index) => added.push(index),
+        }
+    }
+    flush(&mut removed, &mut added, &mut changes);
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enigo::Key;
+
+    #[test]
+    fn test_identical_texts() {
+        let summary = plan_diff("Hello{Enter}World", "Hello{enter}World", 0);
+        assert!(summary.is_identical());
+        assert_eq!(summary.old_len, 3);
+    }
+
+    #[test]
+    fn test_added_segment() {
+        let summary = plan_diff("Hello{Enter}", "Hello{Tab}{Enter}", 0);
+        assert_eq!(
+            summary.changes,
+            vec![SegmentChange::Added {
+                index: 1,
+                segment: MacroSegment::SpecialKey(Key::Tab),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_removed_segment() {
+        let summary = plan_diff("a{Delay 50}b", "ab", 10);
+        assert_eq!(
+            summary.changes,
+            vec![SegmentChange::Removed {
+                index: 1,
+                segment: MacroSegment::Delay(50),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_changed_delay() {
+        let summary = plan_diff("Hi{Delay 100}{Enter}", "Hi{Delay 200}{Enter}", 0);
+        assert_eq!(
+            summary.changes,
+            vec![SegmentChange::Changed {
+                index: 1,
+                old: MacroSegment::Delay(100),
+                new: MacroSegment::Delay(200),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_keystroke_delay_compares_per_character() {
+        // Without a delay the whole text is one segment
+        assert_eq!(plan_diff("abc", "abd", 0).changes.len(), 1);
+
+        // With a delay only the last character differs
+        let summary = plan_diff("abc", "abd", 10);
+        assert_eq!(
+            summary.changes,
+            vec![SegmentChange::Changed {
+                index: 2,
+                old: MacroSegment::Text("c".to_string()),
+                new: MacroSegment::Text("d".to_string()),
+            }]
+        );
+    }
+}
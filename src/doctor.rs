@@ -0,0 +1,188 @@
+//! Startup diagnostics for KeyBlast (`keyblast --doctor`).
+//!
+//! Collects the facts support usually has to ask for one at a time (OS,
+//! permissions, config health, log location, autostart) into one report.
+
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use crate::autostart;
+use crate::config::{self, Config};
+use crate::logging;
+use crate::permission;
+
+/// Everything the doctor report is built from.
+#[derive(Debug)]
+pub struct DoctorInputs {
+    /// Operating system and architecture, e.g. "macos (aarch64)".
+    pub os: String,
+    /// Accessibility permission (macOS only).
+    pub accessibility: Option<bool>,
+    /// Running as Administrator (Windows only).
+    pub elevated: Option<bool>,
+    /// Display server, e.g. "x11" or "wayland" (Linux only).
+    pub display_server: Option<String>,
+    pub config_path: PathBuf,
+    /// Whether the config file exists.
+    pub config_exists: bool,
+    /// The parsed config, or the parse error.
+    pub config: Result<Config, String>,
+    pub log_dir: PathBuf,
+    pub auto_start: bool,
+}
+
+impl DoctorInputs {
+    /// Gather inputs from the running system. Does not prompt for permissions.
+    pub fn gather() -> Self {
+        let config_path = config::config_path();
+        Self {
+            os: format!("{} ({})", std::env::consts::OS, std::env::consts::ARCH),
+            accessibility: permission::accessibility_status(),
+            elevated: permission::is_elevated(),
+            display_server: display_server(),
+            config_exists: config_path.exists(),
+            config_path,
+            config: config::load_config().map_err(|e| e.to_string()),
+            log_dir: logging::log_directory(),
+            auto_start: autostart::is_auto_start_enabled(),
+        }
+    }
+}
+
+/// Which display server the session uses (Linux only).
+#[cfg(target_os = "linux")]
+fn display_server() -> Option<String> {
+    if let Ok(session) = std::env::var("XDG_SESSION_TYPE") {
+        if !session.is_empty() {
+            return Some(session);
+        }
+    }
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        Some("wayland".to_string())
+    } else if std::env::var_os("DISPLAY").is_some() {
+        Some("x11".to_string())
+    } else {
+        Some("unknown (no DISPLAY or WAYLAND_DISPLAY)".to_string())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn display_server() -> Option<String> {
+    None
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+/// Render the report. Platform-specific lines are omitted where they don't apply.
+pub fn format_report(inputs: &DoctorInputs) -> String {
+    let mut report = String::new();
+    // Writing to a String can't fail
+    let _ = writeln!(report, "KeyBlast {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(report, "OS: {}", inputs.os);
+    if let Some(trusted) = inputs.accessibility {
+        let _ = writeln!(report, "Accessibility trusted: {}", yes_no(trusted));
+    }
+    if let Some(elevated) = inputs.elevated {
+        let _ = writeln!(report, "Running elevated: {}", yes_no(elevated));
+    }
+    if let Some(ref server) = inputs.display_server {
+        let _ = writeln!(report, "Display server: {}", server);
+    }
+
+    let _ = writeln!(report, "Config path: {}", inputs.config_path.display());
+    match (&inputs.config, inputs.config_exists) {
+        (_, false) => {
+            let _ = writeln!(report, "Config: not created yet (defaults in use)");
+        }
+        (Ok(cfg), true) => {
+            let _ = writeln!(report, "Config: parses OK");
+            let _ = writeln!(report, "Macros: {}", cfg.macros.len());
+            let warnings = config::validate_config(cfg);
+            if warnings.is_empty() {
+                let _ = writeln!(report, "Validation warnings: none");
+            } else {
+                let _ = writeln!(report, "Validation warnings: {}", warnings.len());
+                for warning in warnings {
+                    let _ = writeln!(report, "  - {}", warning);
+                }
+            }
+        }
+        (Err(e), true) => {
+            let _ = writeln!(report, "Config: FAILED TO PARSE: {}", e);
+        }
+    }
+
+    let _ = writeln!(report, "Log directory: {}", inputs.log_dir.display());
+    let _ = writeln!(report, "Start at login: {}", yes_no(inputs.auto_start));
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_macro;
+
+    fn stub_inputs(config: Result<Config, String>) -> DoctorInputs {
+        DoctorInputs {
+            os: "linux (x86_64)".to_string(),
+            accessibility: None,
+            elevated: None,
+            display_server: Some("x11".to_string()),
+            config_path: PathBuf::from("/home/me/.config/keyblast/config.toml"),
+            config_exists: true,
+            config,
+            log_dir: PathBuf::from("/home/me/.local/share/keyblast/logs"),
+            auto_start: false,
+        }
+    }
+
+    #[test]
+    fn test_report_with_valid_config() {
+        let cfg = Config {
+            macros: vec![test_macro("A", "ctrl+1"), test_macro("B", "ctrl+1")],
+            ..Config::default()
+        };
+        let report = format_report(&stub_inputs(Ok(cfg)));
+
+        assert!(report.contains("OS: linux (x86_64)\n"));
+        assert!(report.contains("Display server: x11\n"));
+        assert!(report.contains("Config path: /home/me/.config/keyblast/config.toml\n"));
+        assert!(report.contains("Config: parses OK\n"));
+        assert!(report.contains("Macros: 2\n"));
+        assert!(report.contains("Validation warnings: 1\n  - Hotkey"));
+        assert!(report.contains("Log directory: /home/me/.local/share/keyblast/logs\n"));
+        assert!(report.contains("Start at login: no\n"));
+        // Platform lines that don't apply are left out
+        assert!(!report.contains("Accessibility"));
+        assert!(!report.contains("elevated"));
+    }
+
+    #[test]
+    fn test_report_with_broken_config_and_platform_lines() {
+        let mut inputs = stub_inputs(Err("Parse error: expected `=`".to_string()));
+        inputs.accessibility = Some(false);
+        inputs.elevated = Some(true);
+        let report = format_report(&inputs);
+
+        assert!(report.contains("Config: FAILED TO PARSE: Parse error: expected `=`\n"));
+        assert!(!report.contains("Macros:"));
+        assert!(report.contains("Accessibility trusted: no\n"));
+        assert!(report.contains("Running elevated: yes\n"));
+    }
+
+    #[test]
+    fn test_report_without_config_file() {
+        let mut inputs = stub_inputs(Ok(Config::default()));
+        inputs.config_exists = false;
+        let report = format_report(&inputs);
+
+        assert!(report.contains("Config: not created yet"));
+        assert!(!report.contains("Validation warnings"));
+    }
+}
@@ -1,32 +1,42 @@
-/// Async macro execution infrastructure.
-///
-/// Provides non-blocking macro execution with cancellation support.
-/// Worker thread handles timing/delays; commands flow to main thread via channel.
-///
-/// # Architecture
-///
-/// On macOS, keystroke injection must occur on the main thread (TIS/TSM requirement).
-/// This module spawns a worker thread for timing coordination while sending individual
-/// keystroke commands back to the main thread for execution.
-///
-/// ```text
-/// Worker Thread                    Main Thread
-/// +--------------+                +--------------+
-/// | for segment  |  -- Inject --> | receive cmd  |
-/// | in segments  |                | execute key  |
-/// | sleep delay  |                | check stop   |
-/// +--------------+                +--------------+
-/// ```
+//! Async macro execution infrastructure.
+//!
+//! Provides non-blocking macro execution with cancellation support.
+//! Worker thread handles timing/delays; commands flow to main thread via channel.
+//!
+//! # Architecture
+//!
+//! On macOS, keystroke injection must occur on the main thread (TIS/TSM requirement).
+//! This module spawns a worker thread for timing coordination while sending individual
+//! keystroke commands back to the main thread for execution.
+//!
+//! ```text
+//! Worker Thread                    Main Thread
+//! +--------------+                +--------------+
+//! | for segment  |  -- Inject --> | receive cmd  |
+//! | in segments  |                | execute key  |
+//! | sleep delay  |                | check stop   |
+//! +--------------+                +--------------+
+//! ```
+//!
+//! `{Prompt label}` is the one segment that answers back: handling its Inject
+//! command starts the dialog on a helper thread, so the main thread keeps
+//! running the event loop, and `pump` types the answer once it arrives. The
+//! worker waits for that before moving on, so delays after it aren't eaten
+//! up while the dialog is open.
 
+use std::cell::Cell;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
 
-use arboard::Clipboard;
-use crossbeam_channel::{unbounded, Receiver, Sender};
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender, TryRecvError};
+
+use enigo::Key;
 
-use crate::injection::MacroSegment;
+use crate::injection::{
+    expand_double_clicks, expand_segments_with, flatten_repeats, jittered_delay, read_clipboard_text, MacroSegment,
+};
 
 /// Command sent from worker thread to main thread.
 #[derive(Debug)]
@@ -39,6 +49,39 @@ pub enum ExecutionCommand {
     Cancelled,
 }
 
+/// Lifecycle event of an execution, delivered to `Execution::on_event` callbacks.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExecutionEvent {
+    /// The first `pump` of this execution.
+    Started,
+    /// A segment was injected successfully.
+    SegmentDone(MacroSegment),
+    /// Every segment was injected.
+    Completed,
+    /// Stopped by the user (or focus loss) before finishing.
+    Cancelled,
+    /// Injecting a segment failed; the execution is stopped.
+    Failed(String),
+}
+
+/// "Type `size` characters, then pause `pause_ms`" rhythm, for apps that
+/// throttle input (`settings.burst_size` / `settings.burst_pause_ms`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Burst {
+    pub size: usize,
+    pub pause_ms: u64,
+}
+
+impl Burst {
+    /// The rhythm configured in settings, if both values are set and non-zero.
+    pub fn from_settings(size: Option<usize>, pause_ms: Option<u64>) -> Option<Self> {
+        match (size, pause_ms) {
+            (Some(size), Some(pause_ms)) if size > 0 && pause_ms > 0 => Some(Self { size, pause_ms }),
+            _ => None,
+        }
+    }
+}
+
 /// Handle for controlling a running macro execution.
 ///
 /// Provides methods to request cancellation and check execution status.
@@ -69,6 +112,218 @@ impl ExecutionHandle {
     }
 }
 
+/// Answers a `{Prompt label}`: the text to type, or None to cancel. Called
+/// on a helper thread with the execution's stop flag.
+type PromptFn = Arc<dyn Fn(&str, &AtomicBool) -> Option<String> + Send + Sync>;
+
+/// A `{Prompt}` whose answer is still being asked for on a helper thread.
+struct PendingPrompt {
+    label: String,
+    answer: Receiver<Option<String>>,
+}
+
+/// A running macro execution: the command receiver paired with its handle.
+///
+/// Callers (hotkey, menu, CLI) hold one of these instead of juggling the
+/// receiver and handle separately.
+pub struct Execution {
+    /// Commands from the worker thread, processed in the main event loop.
+    rx: Receiver<ExecutionCommand>,
+    /// Handle for cancellation and cleanup.
+    handle: ExecutionHandle,
+    /// Set once Complete or Cancelled has been received.
+    done: Cell<bool>,
+    /// Whether `pump` has reported `Started`.
+    started: bool,
+    /// Callbacks registered with `on_event`.
+    observers: Vec<Box<dyn FnMut(ExecutionEvent)>>,
+    /// Asks for the text of a `{Prompt label}`, set with `on_prompt`.
+    prompt: Option<PromptFn>,
+    /// The prompt being answered, if any; nothing after it runs until then.
+    pending_prompt: Option<PendingPrompt>,
+    /// Lets the worker continue after a `{Prompt}` was answered.
+    resume: Sender<()>,
+    /// Name of the macro being run, set with `set_macro_name`.
+    macro_name: Option<String>,
+}
+
+impl Execution {
+    /// Drain all commands currently available without blocking.
+    ///
+    /// Marks the execution as done when Complete or Cancelled is drained.
+    /// Only tests read raw commands; callers use `pump`, which also answers
+    /// prompts.
+    #[cfg(test)]
+    pub fn poll(&self) -> Vec<ExecutionCommand> {
+        let commands: Vec<ExecutionCommand> = self.rx.try_iter().collect();
+        if commands.iter().any(|cmd| {
+            matches!(cmd, ExecutionCommand::Complete | ExecutionCommand::Cancelled)
+        }) {
+            self.done.set(true);
+        }
+        commands
+    }
+
+    /// Call `callback` with every lifecycle event reported by `pump`.
+    ///
+    /// Lets observers follow an execution without reading the command channel.
+    pub fn on_event(&mut self, callback: impl FnMut(ExecutionEvent) + 'static) {
+        self.observers.push(Box::new(callback));
+    }
+
+    /// Ask `callback` for the text to type at each `{Prompt label}`.
+    ///
+    /// It is called on a helper thread with the label and the execution's
+    /// stop flag, and may block (e.g. show a dialog) without holding up the
+    /// caller of `pump`. Once `stop` sets the flag it should give up and
+    /// return None. Returning None cancels the execution. Without a callback
+    /// every prompt cancels.
+    pub fn on_prompt(&mut self, callback: impl Fn(&str, &AtomicBool) -> Option<String> + Send + Sync + 'static) {
+        self.prompt = Some(Arc::new(callback));
+    }
+
+    /// Whether a `{Prompt}` is waiting for its answer.
+    pub fn is_awaiting_prompt(&self) -> bool {
+        self.pending_prompt.is_some()
+    }
+
+    /// Drain available commands, injecting each segment with `inject`.
+    ///
+    /// Wraps `poll`: reports `Started` on the first call, then `SegmentDone`
+    /// per injected segment and `Completed` or `Cancelled` at the end. If
+    /// `inject` fails the execution is stopped, `Failed` is reported, the
+    /// rest of the drained commands are discarded and it counts as done.
+    /// A `{Prompt}` is answered with the `on_prompt` callback and its answer
+    /// injected as text by the first `pump` after it arrives; until then
+    /// nothing after the prompt is injected. An unanswered prompt stops the
+    /// execution as `Cancelled`. Events go to `on_event` callbacks and are
+    /// also returned.
+    pub fn pump<E, F>(&mut self, mut inject: F) -> Vec<ExecutionEvent>
+    where
+        E: std::fmt::Display,
+        F: FnMut(&MacroSegment) -> Result<(), E>,
+    {
+        let mut events = Vec::new();
+        if !self.started {
+            self.started = true;
+            events.push(ExecutionEvent::Started);
+        }
+
+        self.drain(&mut events, &mut inject);
+
+        for observer in &mut self.observers {
+            for event in &events {
+                observer(event.clone());
+            }
+        }
+        events
+    }
+
+    /// Inject available commands for `pump`, stopping at an unanswered prompt.
+    fn drain<E, F>(&mut self, events: &mut Vec<ExecutionEvent>, inject: &mut F)
+    where
+        E: std::fmt::Display,
+        F: FnMut(&MacroSegment) -> Result<(), E>,
+    {
+        loop {
+            let result = if let Some(pending) = self.pending_prompt.take() {
+                match pending.answer.try_recv() {
+                    Err(TryRecvError::Empty) => {
+                        self.pending_prompt = Some(pending);
+                        return;
+                    }
+                    Ok(Some(text)) => {
+                        let _ = self.resume.send(());
+                        inject(&MacroSegment::Text(text)).map(|()| MacroSegment::Prompt(pending.label))
+                    }
+                    Ok(None) | Err(TryRecvError::Disconnected) => {
+                        self.stop();
+                        self.done.set(true);
+                        events.push(ExecutionEvent::Cancelled);
+                        return;
+                    }
+                }
+            } else {
+                // One at a time, so commands after a prompt wait for its answer
+                let Ok(cmd) = self.rx.try_recv() else {
+                    return;
+                };
+                match cmd {
+                    ExecutionCommand::Inject(MacroSegment::Prompt(label)) => {
+                        self.pending_prompt = Some(self.ask(label));
+                        continue;
+                    }
+                    ExecutionCommand::Inject(segment) => inject(&segment).map(|()| segment),
+                    ExecutionCommand::Complete => {
+                        self.done.set(true);
+                        events.push(ExecutionEvent::Completed);
+                        continue;
+                    }
+                    ExecutionCommand::Cancelled => {
+                        self.done.set(true);
+                        events.push(ExecutionEvent::Cancelled);
+                        continue;
+                    }
+                }
+            };
+            match result {
+                Ok(segment) => events.push(ExecutionEvent::SegmentDone(segment)),
+                Err(e) => {
+                    self.stop();
+                    self.done.set(true);
+                    events.push(ExecutionEvent::Failed(e.to_string()));
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Start asking the `on_prompt` callback for `label` on a helper thread.
+    fn ask(&self, label: String) -> PendingPrompt {
+        let (tx, answer) = bounded(1);
+        match self.prompt {
+            Some(ref prompt) => {
+                let prompt = Arc::clone(prompt);
+                let stop_flag = Arc::clone(&self.handle.stop_flag);
+                let thread_label = label.clone();
+                std::thread::spawn(move || {
+                    let _ = tx.send(prompt(&thread_label, &stop_flag));
+                });
+            }
+            None => {
+                let _ = tx.send(None);
+            }
+        }
+        PendingPrompt { label, answer }
+    }
+
+    /// Record which macro this execution runs, for messages about it
+    /// (e.g. the `notify_on_complete` notification).
+    pub fn set_macro_name(&mut self, name: &str) {
+        self.macro_name = Some(name.to_string());
+    }
+
+    /// The name given with `set_macro_name`.
+    pub fn macro_name(&self) -> Option<&str> {
+        self.macro_name.as_deref()
+    }
+
+    /// Whether a Complete or Cancelled command has been drained by `poll`.
+    pub fn is_done(&self) -> bool {
+        self.done.get()
+    }
+
+    /// Request the execution to stop. See `ExecutionHandle::stop`.
+    pub fn stop(&self) {
+        self.handle.stop();
+    }
+
+    /// Wait for the worker thread to complete. See `ExecutionHandle::join`.
+    pub fn join(self) {
+        self.handle.join();
+    }
+}
+
 /// Start async execution of a macro.
 ///
 /// Spawns a worker thread that iterates through segments, sending each to the main
@@ -78,42 +333,49 @@ impl ExecutionHandle {
 /// # Arguments
 ///
 /// * `segments` - The macro segments to execute
-/// * `delay_ms` - Delay between segments in milliseconds
+/// * `options` - Delays and typing behavior (see `ExecutionOptions`)
 ///
 /// # Returns
 ///
-/// A tuple of:
-/// * `Receiver<ExecutionCommand>` - Process these commands in the main event loop
-/// * `ExecutionHandle` - Use to request cancellation or wait for completion
+/// An `Execution` - pump it in the main event loop, and use it
+/// to request cancellation or wait for completion.
 ///
 /// # Example
 ///
 /// ```ignore
 /// let segments = parse_macro_sequence("Hello{Enter}World");
-/// let (rx, handle) = start_execution(segments, 50);
+/// let options = ExecutionOptions { delay_ms: 50, ..Default::default() };
+/// let mut execution = start_execution(segments, options);
 ///
 /// // In event loop:
-/// while let Ok(cmd) = rx.try_recv() {
-///     match cmd {
-///         ExecutionCommand::Inject(segment) => injector.execute_single_segment(&segment),
-///         ExecutionCommand::Complete => println!("Done!"),
-///         ExecutionCommand::Cancelled => println!("Stopped"),
+/// for event in execution.pump(|segment| injector.execute_single_segment(segment)) {
+///     match event {
+///         ExecutionEvent::Completed => println!("Done!"),
+///         ExecutionEvent::Cancelled => println!("Stopped"),
+///         _ => {}
 ///     }
 /// }
 ///
 /// // To cancel:
-/// handle.stop();
+/// execution.stop();
 /// ```
-pub fn start_execution(
-    segments: Vec<MacroSegment>,
-    delay_ms: u64,
-) -> (Receiver<ExecutionCommand>, ExecutionHandle) {
+pub fn start_execution(segments: Vec<MacroSegment>, options: ExecutionOptions) -> Execution {
+    start_execution_with(segments, options, read_clipboard_text)
+}
+
+/// `start_execution` with a caller-supplied clipboard reader for `{Paste}`
+/// expansion (see `expand_segments_with`).
+fn start_execution_with<F>(segments: Vec<MacroSegment>, options: ExecutionOptions, read_clipboard: F) -> Execution
+where
+    F: FnMut() -> Option<String> + Send + 'static,
+{
     let (tx, rx) = unbounded();
+    let (resume_tx, resume_rx) = unbounded();
     let stop_flag = Arc::new(AtomicBool::new(false));
     let stop_flag_clone = Arc::clone(&stop_flag);
 
     let thread = std::thread::spawn(move || {
-        execution_worker(segments, delay_ms, stop_flag_clone, tx);
+        execution_worker(segments, options, read_clipboard, stop_flag_clone, tx, resume_rx);
     });
 
     let handle = ExecutionHandle {
@@ -121,7 +383,37 @@ pub fn start_execution(
         thread: Some(thread),
     };
 
-    (rx, handle)
+    Execution {
+        rx,
+        handle,
+        done: Cell::new(false),
+        started: false,
+        observers: Vec::new(),
+        prompt: None,
+        pending_prompt: None,
+        resume: resume_tx,
+        macro_name: None,
+    }
+}
+
+/// How `start_execution` paces and types a macro.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExecutionOptions {
+    /// Delay between segments in milliseconds.
+    pub delay_ms: u64,
+    /// Vary each of those delays by up to this much (see `jittered_delay`).
+    pub jitter_ms: u64,
+    /// If set, delay after special key segments instead of `delay_ms`.
+    pub delay_after_special_ms: Option<u64>,
+    /// If set, pause after every `burst.size` typed characters.
+    pub burst: Option<Burst>,
+    /// On cancel, backspace over a half-typed Text or Paste segment.
+    pub revert_partial: bool,
+    /// Text is pasted, so it isn't split per character even with a delay
+    /// (one paste per segment, not per character).
+    pub inject_via_clipboard: bool,
+    /// Gap between the clicks of `{DoubleClick}`, in milliseconds.
+    pub double_click_gap_ms: u64,
 }
 
 /// Worker thread function.
@@ -131,81 +423,219 @@ pub fn start_execution(
 ///
 /// Key timing behaviors:
 /// - {Delay N} segments: worker sleeps (doesn't send to main thread)
-/// - Text segments with delay_ms > 0: split into per-character injections
-/// - All other segments: sent to main thread, worker sleeps delay_ms after
-fn execution_worker(
+/// - Text segments with delay_ms > 0: split into per-character injections,
+///   unless they are pasted (`inject_via_clipboard`)
+/// - With a burst rhythm: text is cut into bursts with a pause after each
+///   (see `apply_burst_rhythm`)
+/// - {Prompt} segments: sent to main thread, worker waits on `resume`
+///   until the prompt is answered
+/// - All other segments: sent to main thread, worker sleeps after
+///   (see `delay_after_segment`), varied by `jitter_ms` (see `jittered_delay`)
+fn execution_worker<F: FnMut() -> Option<String>>(
     segments: Vec<MacroSegment>,
-    delay_ms: u64,
+    options: ExecutionOptions,
+    mut read_clipboard: F,
     stop_flag: Arc<AtomicBool>,
     tx: Sender<ExecutionCommand>,
+    resume: Receiver<()>,
 ) {
-    // Expand segments: Text and Paste with delay_ms > 0 become per-character
-    let expanded: Vec<MacroSegment> = if delay_ms > 0 {
-        segments.into_iter().flat_map(|seg| {
-            match seg {
-                MacroSegment::Text(text) => {
-                    // Split text into individual characters for per-char delay
-                    text.chars()
-                        .map(|c| MacroSegment::Text(c.to_string()))
-                        .collect::<Vec<_>>()
-                }
-                MacroSegment::Paste => {
-                    // Read clipboard and expand to per-char for consistent delay behavior
-                    match Clipboard::new().and_then(|mut cb| cb.get_text()) {
-                        Ok(text) => {
-                            text.chars()
-                                .map(|c| MacroSegment::Text(c.to_string()))
-                                .collect::<Vec<_>>()
-                        }
-                        Err(e) => {
-                            eprintln!("Warning: Could not read clipboard for delayed paste: {}", e);
-                            vec![] // Skip paste on error
-                        }
-                    }
-                }
-                other => vec![other],
+    let ExecutionOptions {
+        delay_ms,
+        jitter_ms,
+        delay_after_special_ms,
+        burst,
+        revert_partial,
+        inject_via_clipboard,
+        double_click_gap_ms,
+    } = options;
+    // Expand segments: {Repeat} blocks are unrolled, {DoubleClick} becomes
+    // click, gap, click, and Text and Paste with delay_ms > 0 become
+    // per-character (shared with execute_sequence so both paths type
+    // identically). Each piece is tagged with the index of the unrolled
+    // segment it came from.
+    let expand_delay_ms = if inject_via_clipboard { 0 } else { delay_ms };
+    let mut expanded: Vec<(usize, MacroSegment)> = Vec::new();
+    // Segments are expanded one at a time, so track {SetClip} here for a
+    // later {Paste} as expand_segments_with does within one call
+    let mut set_clip: Option<String> = None;
+    for (source, segment) in flatten_repeats(segments).into_iter().enumerate() {
+        if let MacroSegment::SetClip(text) = &segment {
+            set_clip = Some(text.clone());
+        }
+        let pieces = expand_segments_with(
+            expand_double_clicks(vec![segment], double_click_gap_ms),
+            expand_delay_ms,
+            || set_clip.clone().or_else(&mut read_clipboard),
+        );
+        expanded.extend(pieces.into_iter().map(|piece| (source, piece)));
+    }
+    if let Some(burst) = burst {
+        expanded = apply_burst_rhythm(expanded, burst);
+    }
+
+    let segment_count = expanded.len();
+    let next_sources: Vec<Option<usize>> = expanded
+        .iter()
+        .skip(1)
+        .map(|(source, _)| Some(*source))
+        .chain(std::iter::once(None))
+        .collect();
+    let mut partial = PartialText::default();
+
+    // On cancel, optionally erase what was typed of an unfinished segment
+    let cancel = |partial: &PartialText| {
+        if revert_partial {
+            for backspace in partial.backspaces() {
+                let _ = tx.send(ExecutionCommand::Inject(backspace));
             }
-        }).collect()
-    } else {
-        segments
+        }
+        let _ = tx.send(ExecutionCommand::Cancelled);
     };
 
-    let segment_count = expanded.len();
+    for (i, ((source, segment), next_source)) in expanded.into_iter().zip(next_sources).enumerate() {
+        let source_done = next_source != Some(source);
 
-    for (i, segment) in expanded.into_iter().enumerate() {
         // Check for cancellation before each segment
         if stop_flag.load(Ordering::Relaxed) {
-            let _ = tx.send(ExecutionCommand::Cancelled);
+            cancel(&partial);
             return;
         }
 
         // Handle Delay segments in worker thread (don't block main thread)
         if let MacroSegment::Delay(ms) = segment {
             if !cancellable_sleep(ms, &stop_flag) {
-                let _ = tx.send(ExecutionCommand::Cancelled);
+                cancel(&partial);
                 return;
             }
+            partial.record(source, &segment, source_done);
             continue; // Don't send Delay to main thread
         }
 
+        // Labels type nothing, so they don't cost a keystroke delay either
+        if matches!(segment, MacroSegment::Label(_)) {
+            partial.record(source, &segment, source_done);
+            continue;
+        }
+
+        let pause_ms = delay_after_segment(&segment, jittered_delay(delay_ms, jitter_ms), delay_after_special_ms);
+        let is_prompt = matches!(segment, MacroSegment::Prompt(_));
+        partial.record(source, &segment, source_done);
+
         // Send segment to main thread for execution
         if tx.send(ExecutionCommand::Inject(segment)).is_err() {
             // Receiver dropped, exit gracefully
             return;
         }
 
+        // Don't run ahead while the main thread shows the prompt
+        if is_prompt && !wait_for_resume(&resume, &stop_flag) {
+            cancel(&partial);
+            return;
+        }
+
         // Wait between segments if delay specified (not after last segment)
-        if delay_ms > 0 && i < segment_count.saturating_sub(1) {
-            if !cancellable_sleep(delay_ms, &stop_flag) {
-                let _ = tx.send(ExecutionCommand::Cancelled);
-                return;
-            }
+        if pause_ms > 0
+            && i < segment_count.saturating_sub(1)
+            && !cancellable_sleep(pause_ms, &stop_flag)
+        {
+            cancel(&partial);
+            return;
         }
     }
 
     let _ = tx.send(ExecutionCommand::Complete);
 }
 
+/// Characters sent so far from the source segment being typed, so a cancel
+/// can erase a half-typed Text or Paste (`settings.cancel_backspaces_partial`).
+///
+/// Only per-character (or burst) expansion splits a segment; a segment sent
+/// whole is either not started or finished, and a finished one is never erased.
+#[derive(Debug, Default)]
+struct PartialText {
+    /// Source segment index of the last piece recorded.
+    source: Option<usize>,
+    /// Characters sent from that segment, 0 once it is finished.
+    chars: usize,
+}
+
+impl PartialText {
+    /// Record that `piece` of segment `source` was sent; `source_done` if it
+    /// was that segment's last piece.
+    fn record(&mut self, source: usize, piece: &MacroSegment, source_done: bool) {
+        if self.source != Some(source) {
+            self.source = Some(source);
+            self.chars = 0;
+        }
+        if let MacroSegment::Text(text) = piece {
+            self.chars += text.chars().count();
+        }
+        if source_done {
+            self.chars = 0;
+        }
+    }
+
+    /// Backspaces that erase the unfinished segment's characters (best effort:
+    /// assumes each character took one backspace to delete).
+    fn backspaces(&self) -> Vec<MacroSegment> {
+        vec![MacroSegment::SpecialKey(Key::Backspace); self.chars]
+    }
+}
+
+/// Cut text into bursts of `burst.size` characters with a `{Delay}` after each.
+///
+/// The count carries across text segments (a burst can span `a{Enter}b`);
+/// other segments pass through uncounted. No pause is added after the last
+/// character. The inserted delays are cancellable like any `{Delay}`.
+///
+/// Segments are tagged (the worker tags each with its source segment); each
+/// chunk and the pause after it keep the tag of the text they came from.
+pub fn apply_burst_rhythm<T: Copy>(segments: Vec<(T, MacroSegment)>, burst: Burst) -> Vec<(T, MacroSegment)> {
+    let total_chars: usize = segments
+        .iter()
+        .map(|(_, s)| match s {
+            MacroSegment::Text(text) => text.chars().count(),
+            _ => 0,
+        })
+        .sum();
+
+    let mut result = Vec::new();
+    let mut typed = 0;
+    let mut in_burst = 0;
+    for (tag, segment) in segments {
+        let MacroSegment::Text(text) = segment else {
+            result.push((tag, segment));
+            continue;
+        };
+        let mut chars = text.chars().peekable();
+        while chars.peek().is_some() {
+            let chunk: String = chars.by_ref().take(burst.size - in_burst).collect();
+            let len = chunk.chars().count();
+            result.push((tag, MacroSegment::Text(chunk)));
+            typed += len;
+            in_burst += len;
+            if in_burst == burst.size {
+                in_burst = 0;
+                if typed < total_chars {
+                    result.push((tag, MacroSegment::Delay(burst.pause_ms)));
+                }
+            }
+        }
+    }
+    result
+}
+
+/// How long to pause after injecting `segment`.
+///
+/// Special keys use `delay_after_special_ms` when set (e.g. a longer pause
+/// after `{Enter}` for a form to submit); everything else uses `delay_ms`.
+pub fn delay_after_segment(segment: &MacroSegment, delay_ms: u64, delay_after_special_ms: Option<u64>) -> u64 {
+    match (segment, delay_after_special_ms) {
+        (MacroSegment::SpecialKey(_), Some(special_ms)) => special_ms,
+        _ => delay_ms,
+    }
+}
+
 /// Sleep for the specified duration, checking the stop flag periodically.
 /// Returns true if sleep completed, false if cancelled.
 fn cancellable_sleep(ms: u64, stop_flag: &Arc<AtomicBool>) -> bool {
@@ -222,10 +652,25 @@ fn cancellable_sleep(ms: u64, stop_flag: &Arc<AtomicBool>) -> bool {
     true
 }
 
+/// Wait until the main thread has answered a `{Prompt}`.
+///
+/// Returns false if stop was requested or the execution was dropped.
+fn wait_for_resume(resume: &Receiver<()>, stop_flag: &Arc<AtomicBool>) -> bool {
+    loop {
+        if stop_flag.load(Ordering::Relaxed) {
+            return false;
+        }
+        match resume.recv_timeout(Duration::from_millis(50)) {
+            Ok(()) => return true,
+            Err(crossbeam_channel::RecvTimeoutError::Timeout) => continue,
+            Err(crossbeam_channel::RecvTimeoutError::Disconnected) => return false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use enigo::Key;
 
     #[test]
     fn test_execution_command_debug() {
@@ -236,9 +681,9 @@ mod tests {
     }
 
     #[test]
-    fn test_start_execution_returns_receiver_and_handle() {
+    fn test_start_execution_returns_execution() {
         let segments = vec![MacroSegment::Text("test".to_string())];
-        let (rx, handle) = start_execution(segments, 0);
+        let execution = start_execution(segments, ExecutionOptions::default());
 
         // Should receive the segment and completion
         // Give thread time to run
@@ -247,7 +692,7 @@ mod tests {
         let mut received_inject = false;
         let mut received_complete = false;
 
-        while let Ok(cmd) = rx.try_recv() {
+        for cmd in execution.poll() {
             match cmd {
                 ExecutionCommand::Inject(_) => received_inject = true,
                 ExecutionCommand::Complete => received_complete = true,
@@ -259,7 +704,7 @@ mod tests {
         assert!(received_complete, "Should receive Complete command");
 
         // Clean up
-        handle.join();
+        execution.join();
     }
 
     #[test]
@@ -271,25 +716,25 @@ mod tests {
             MacroSegment::Text("c".to_string()),
         ];
 
-        let (rx, handle) = start_execution(segments, 200); // 200ms delay
+        let execution = start_execution(segments, ExecutionOptions { delay_ms: 200, ..Default::default() });
 
         // Wait a bit then request stop
         std::thread::sleep(Duration::from_millis(50));
-        handle.stop();
+        execution.stop();
 
         // Wait for worker to finish
         std::thread::sleep(Duration::from_millis(100));
 
         // Should have received at least one inject and a cancelled
         let mut received_cancelled = false;
-        while let Ok(cmd) = rx.try_recv() {
+        for cmd in execution.poll() {
             if matches!(cmd, ExecutionCommand::Cancelled) {
                 received_cancelled = true;
             }
         }
 
         assert!(received_cancelled, "Should receive Cancelled after stop");
-        handle.join();
+        execution.join();
     }
 
     #[test]
@@ -300,14 +745,14 @@ mod tests {
             MacroSegment::Text("World".to_string()),
         ];
 
-        let (rx, handle) = start_execution(segments, 0);
+        let execution = start_execution(segments, ExecutionOptions::default());
 
         std::thread::sleep(Duration::from_millis(50));
 
         let mut inject_count = 0;
         let mut completed = false;
 
-        while let Ok(cmd) = rx.try_recv() {
+        for cmd in execution.poll() {
             match cmd {
                 ExecutionCommand::Inject(_) => inject_count += 1,
                 ExecutionCommand::Complete => completed = true,
@@ -318,6 +763,454 @@ mod tests {
         assert_eq!(inject_count, 3, "Should receive 3 Inject commands");
         assert!(completed, "Should receive Complete command");
 
-        handle.join();
+        execution.join();
+    }
+
+    #[test]
+    fn test_poll_drains_available_commands() {
+        let segments = vec![
+            MacroSegment::Text("a".to_string()),
+            MacroSegment::Text("b".to_string()),
+        ];
+        let execution = start_execution(segments, ExecutionOptions::default());
+
+        std::thread::sleep(Duration::from_millis(50));
+
+        // First poll drains everything sent so far: 2 injects + Complete
+        let first = execution.poll();
+        assert_eq!(first.len(), 3);
+
+        // Nothing left on a second poll
+        assert!(execution.poll().is_empty());
+
+        execution.join();
+    }
+
+    #[test]
+    fn test_is_done_after_complete() {
+        let segments = vec![MacroSegment::Text("a".to_string())];
+        let execution = start_execution(segments, ExecutionOptions::default());
+
+        // Not done until Complete has been drained
+        assert!(!execution.is_done());
+
+        std::thread::sleep(Duration::from_millis(50));
+        let commands = execution.poll();
+
+        assert!(commands.iter().any(|c| matches!(c, ExecutionCommand::Complete)));
+        assert!(execution.is_done());
+
+        execution.join();
+    }
+
+    #[test]
+    fn test_inject_via_clipboard_sends_text_whole() {
+        // With a delay, pasted text still goes out one segment at a time
+        let execution = start_execution(
+            vec![MacroSegment::Text("你好 👋".to_string()), MacroSegment::SpecialKey(Key::Tab)],
+            ExecutionOptions { delay_ms: 5, inject_via_clipboard: true, ..Default::default() },
+        );
+        std::thread::sleep(Duration::from_millis(100));
+
+        let injected: Vec<MacroSegment> = execution
+            .poll()
+            .into_iter()
+            .filter_map(|cmd| match cmd {
+                ExecutionCommand::Inject(segment) => Some(segment),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            injected,
+            vec![MacroSegment::Text("你好 👋".to_string()), MacroSegment::SpecialKey(Key::Tab)]
+        );
+        execution.join();
+    }
+
+    #[test]
+    fn test_delayed_paste_matches_sync_expansion() {
+        use crate::injection::parse_macro_sequence;
+
+        for text in ["a{Paste}b", "{SetClip q}a{Paste}b"] {
+            // The sync path (execute_sequence) types exactly this expansion
+            let sync_expanded = expand_segments_with(parse_macro_sequence(text), 1, || Some("xy".to_string()));
+
+            // The async worker must inject the same per-character sequence
+            let execution = start_execution_with(
+                parse_macro_sequence(text),
+                ExecutionOptions { delay_ms: 1, ..Default::default() },
+                || Some("xy".to_string()),
+            );
+            let deadline = Instant::now() + Duration::from_secs(5);
+            let mut injected = Vec::new();
+            let mut complete = false;
+            while !complete && Instant::now() < deadline {
+                for cmd in execution.poll() {
+                    match cmd {
+                        ExecutionCommand::Inject(segment) => injected.push(segment),
+                        ExecutionCommand::Complete => complete = true,
+                        _ => {}
+                    }
+                }
+                std::thread::sleep(Duration::from_millis(5));
+            }
+
+            assert!(complete, "{}", text);
+            assert_eq!(injected, sync_expanded, "{}", text);
+            execution.join();
+        }
+    }
+
+    #[test]
+    fn test_delay_after_special_key_segments() {
+        let segments = [
+            MacroSegment::Text("a".to_string()),
+            MacroSegment::SpecialKey(Key::Return),
+            MacroSegment::Text("b".to_string()),
+            MacroSegment::SpecialKey(Key::Tab),
+        ];
+
+        let delays: Vec<u64> = segments
+            .iter()
+            .map(|s| delay_after_segment(s, 10, Some(300)))
+            .collect();
+        assert_eq!(delays, vec![10, 300, 10, 300]);
+
+        // Unset: every segment uses the per-keystroke delay
+        let delays: Vec<u64> = segments
+            .iter()
+            .map(|s| delay_after_segment(s, 10, None))
+            .collect();
+        assert_eq!(delays, vec![10, 10, 10, 10]);
+
+        // Works without a per-keystroke delay too
+        assert_eq!(delay_after_segment(&segments[1], 0, Some(250)), 250);
+        assert_eq!(delay_after_segment(&segments[0], 0, Some(250)), 0);
+    }
+
+    /// Events delivered to an `on_event` callback.
+    fn record_events(execution: &mut Execution) -> std::rc::Rc<std::cell::RefCell<Vec<ExecutionEvent>>> {
+        let seen = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let sink = std::rc::Rc::clone(&seen);
+        execution.on_event(move |event| sink.borrow_mut().push(event));
+        seen
+    }
+
+    /// Pump with a stub injector until `until` holds for the events seen so far.
+    fn pump_until(
+        execution: &mut Execution,
+        seen: &std::cell::RefCell<Vec<ExecutionEvent>>,
+        mut inject: impl FnMut(&MacroSegment) -> Result<(), String>,
+        until: impl Fn(&[ExecutionEvent]) -> bool,
+    ) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !until(&seen.borrow()) && Instant::now() < deadline {
+            execution.pump(&mut inject);
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    fn finished(events: &[ExecutionEvent]) -> bool {
+        matches!(
+            events.last(),
+            Some(ExecutionEvent::Completed | ExecutionEvent::Cancelled | ExecutionEvent::Failed(_))
+        )
+    }
+
+    #[test]
+    fn test_on_event_successful_run() {
+        let mut execution = start_execution(vec![
+            MacroSegment::Text("hi".to_string()),
+            MacroSegment::SpecialKey(Key::Return),
+        ], ExecutionOptions::default());
+        let seen = record_events(&mut execution);
+
+        let mut injected = Vec::new();
+        pump_until(&mut execution, &seen, |segment| {
+            injected.push(segment.clone());
+            Ok(())
+        }, finished);
+        assert!(execution.is_done());
+        execution.join();
+
+        assert_eq!(
+            *seen.borrow(),
+            vec![
+                ExecutionEvent::Started,
+                ExecutionEvent::SegmentDone(MacroSegment::Text("hi".to_string())),
+                ExecutionEvent::SegmentDone(MacroSegment::SpecialKey(Key::Return)),
+                ExecutionEvent::Completed,
+            ]
+        );
+        assert_eq!(injected.len(), 2);
+    }
+
+    #[test]
+    fn test_on_event_cancelled_run() {
+        let mut execution = start_execution(vec![
+            MacroSegment::Text("a".to_string()),
+            MacroSegment::Delay(5000),
+            MacroSegment::Text("b".to_string()),
+        ], ExecutionOptions::default());
+        let seen = record_events(&mut execution);
+
+        pump_until(&mut execution, &seen, |_| Ok(()), |events| events.len() >= 2);
+        // Stop during the delay, as the stop hotkey would
+        execution.stop();
+        pump_until(&mut execution, &seen, |_| Ok(()), finished);
+        assert!(execution.is_done());
+        execution.join();
+
+        assert_eq!(
+            *seen.borrow(),
+            vec![
+                ExecutionEvent::Started,
+                ExecutionEvent::SegmentDone(MacroSegment::Text("a".to_string())),
+                ExecutionEvent::Cancelled,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stop_during_double_click_gap() {
+        let mut execution = start_execution(
+            vec![MacroSegment::DoubleClick(None)],
+            ExecutionOptions { double_click_gap_ms: 5000, ..Default::default() },
+        );
+        let seen = record_events(&mut execution);
+
+        // The gap runs on the worker: pump returns, and stop cuts it short
+        let mut injected = Vec::new();
+        pump_until(&mut execution, &seen, |segment| {
+            injected.push(segment.clone());
+            Ok(())
+        }, |events| events.len() >= 2);
+        execution.stop();
+        pump_until(&mut execution, &seen, |_| Ok(()), finished);
+        execution.join();
+
+        assert_eq!(injected, vec![MacroSegment::Click(None)]);
+        assert_eq!(seen.borrow().last(), Some(&ExecutionEvent::Cancelled));
+    }
+
+    #[test]
+    fn test_on_event_failed_injection() {
+        let mut execution = start_execution(vec![
+            MacroSegment::Text("a".to_string()),
+            MacroSegment::Text("b".to_string()),
+        ], ExecutionOptions::default());
+        let seen = record_events(&mut execution);
+
+        pump_until(&mut execution, &seen, |_| Err("no permission".to_string()), finished);
+        assert!(execution.is_done());
+        execution.join();
+
+        assert_eq!(
+            *seen.borrow(),
+            vec![ExecutionEvent::Started, ExecutionEvent::Failed("no permission".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_burst_from_settings() {
+        assert_eq!(Burst::from_settings(Some(5), Some(100)), Some(Burst { size: 5, pause_ms: 100 }));
+        assert_eq!(Burst::from_settings(Some(5), None), None);
+        assert_eq!(Burst::from_settings(None, Some(100)), None);
+        assert_eq!(Burst::from_settings(Some(0), Some(100)), None);
+    }
+
+    #[test]
+    fn test_burst_rhythm_schedule() {
+        let burst = Burst { size: 3, pause_ms: 200 };
+        let text = |s: &str| MacroSegment::Text(s.to_string());
+        let rhythm = |segments: Vec<MacroSegment>| -> Vec<MacroSegment> {
+            let tagged = segments.into_iter().map(|segment| ((), segment)).collect();
+            apply_burst_rhythm(tagged, burst).into_iter().map(|(_, segment)| segment).collect()
+        };
+
+        // 10 chars: three full bursts then the remainder, no trailing pause
+        assert_eq!(
+            rhythm(vec![text("abcdefghij")]),
+            vec![
+                text("abc"), MacroSegment::Delay(200),
+                text("def"), MacroSegment::Delay(200),
+                text("ghi"), MacroSegment::Delay(200),
+                text("j"),
+            ]
+        );
+
+        // Exact multiple: no pause after the final burst
+        assert_eq!(
+            rhythm(vec![text("abcdef")]),
+            vec![text("abc"), MacroSegment::Delay(200), text("def")]
+        );
+
+        // Bursts span segments; special keys aren't counted
+        assert_eq!(
+            rhythm(vec![text("ab"), MacroSegment::SpecialKey(Key::Return), text("cd")]),
+            vec![
+                text("ab"), MacroSegment::SpecialKey(Key::Return), text("c"),
+                MacroSegment::Delay(200),
+                text("d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_partial_text_backspace_count() {
+        let text = |s: &str| MacroSegment::Text(s.to_string());
+        let mut partial = PartialText::default();
+
+        // Two of three per-character pieces of segment 0 sent
+        partial.record(0, &text("a"), false);
+        partial.record(0, &text("b"), false);
+        assert_eq!(partial.backspaces(), vec![MacroSegment::SpecialKey(Key::Backspace); 2]);
+
+        // A finished segment is never erased
+        partial.record(0, &text("c"), true);
+        assert!(partial.backspaces().is_empty());
+
+        // A new segment starts counting from zero; burst pauses add nothing
+        partial.record(1, &text("xy"), false);
+        partial.record(1, &MacroSegment::Delay(100), false);
+        assert_eq!(partial.backspaces().len(), 2);
+    }
+
+    #[test]
+    fn test_cancel_reverts_partial_segment() {
+        // Per-character typing with a long gap, so the stop lands mid-segment
+        let execution = start_execution(
+            vec![MacroSegment::Text("done".to_string()), MacroSegment::SpecialKey(Key::Tab), MacroSegment::Text("abc".to_string())],
+            ExecutionOptions {
+                delay_ms: 300,
+                delay_after_special_ms: Some(0),
+                revert_partial: true,
+                ..Default::default()
+            },
+        );
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut injected = Vec::new();
+        while injected.len() < 6 && Instant::now() < deadline {
+            injected.extend(execution.poll().into_iter().filter_map(|cmd| match cmd {
+                ExecutionCommand::Inject(segment) => Some(segment),
+                _ => None,
+            }));
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        execution.stop();
+
+        let mut rest = Vec::new();
+        while !execution.is_done() && Instant::now() < deadline {
+            rest.extend(execution.poll());
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        // "done" and Tab are finished; only the "a" of "abc" is erased
+        let backspaces = rest
+            .iter()
+            .filter(|cmd| matches!(cmd, ExecutionCommand::Inject(MacroSegment::SpecialKey(Key::Backspace))))
+            .count();
+        assert_eq!(backspaces, 1);
+        assert!(matches!(rest.last(), Some(ExecutionCommand::Cancelled)));
+        execution.join();
+    }
+
+    #[test]
+    fn test_prompt_answer_is_typed() {
+        let mut execution = start_execution(vec![
+            MacroSegment::Text("Hi ".to_string()),
+            MacroSegment::Prompt("Name".to_string()),
+            MacroSegment::SpecialKey(Key::Return),
+        ], ExecutionOptions::default());
+        let mut injected = Vec::new();
+        let labels = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&labels);
+        execution.on_prompt(move |label, _| {
+            sink.lock().unwrap().push(label.to_string());
+            Some("Ada".to_string())
+        });
+        let seen = record_events(&mut execution);
+
+        pump_until(&mut execution, &seen, |segment| {
+            injected.push(segment.clone());
+            Ok(())
+        }, finished);
+        execution.join();
+
+        assert_eq!(*labels.lock().unwrap(), vec!["Name".to_string()]);
+        assert_eq!(
+            injected,
+            vec![
+                MacroSegment::Text("Hi ".to_string()),
+                MacroSegment::Text("Ada".to_string()),
+                MacroSegment::SpecialKey(Key::Return),
+            ]
+        );
+        assert_eq!(
+            seen.borrow()[2],
+            ExecutionEvent::SegmentDone(MacroSegment::Prompt("Name".to_string()))
+        );
+        assert_eq!(seen.borrow().last(), Some(&ExecutionEvent::Completed));
+    }
+
+    #[test]
+    fn test_cancelled_prompt_cancels_execution() {
+        let mut execution = start_execution(vec![
+            MacroSegment::Prompt("Name".to_string()),
+            MacroSegment::Text("after".to_string()),
+        ], ExecutionOptions::default());
+        execution.on_prompt(|_, _| None);
+        let seen = record_events(&mut execution);
+
+        let mut injected = Vec::new();
+        pump_until(&mut execution, &seen, |segment| {
+            injected.push(segment.clone());
+            Ok(())
+        }, finished);
+        assert!(execution.is_done());
+        execution.join();
+
+        assert!(injected.is_empty());
+        assert_eq!(*seen.borrow(), vec![ExecutionEvent::Started, ExecutionEvent::Cancelled]);
+    }
+
+    #[test]
+    fn test_pump_does_not_wait_for_prompt_and_stop_cancels_it() {
+        let mut execution = start_execution(vec![
+            MacroSegment::Prompt("Name".to_string()),
+            MacroSegment::Text("after".to_string()),
+        ], ExecutionOptions::default());
+        // Like a dialog nobody answers: only gives up when stopped
+        execution.on_prompt(|_, stop| {
+            while !stop.load(Ordering::Relaxed) {
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            None
+        });
+        let seen = record_events(&mut execution);
+
+        // Each pump returns while the prompt is open
+        let mut injected = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !execution.is_awaiting_prompt() && Instant::now() < deadline {
+            execution.pump(|segment| {
+                injected.push(segment.clone());
+                Ok::<(), String>(())
+            });
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert!(execution.is_awaiting_prompt());
+        assert!(!execution.is_done());
+
+        execution.stop();
+        pump_until(&mut execution, &seen, |segment| {
+            injected.push(segment.clone());
+            Ok(())
+        }, finished);
+        assert!(execution.is_done());
+        execution.join();
+
+        assert!(injected.is_empty());
+        assert_eq!(*seen.borrow(), vec![ExecutionEvent::Started, ExecutionEvent::Cancelled]);
     }
 }
@@ -0,0 +1,321 @@
+//! Focus-change detection for KeyBlast.
+//!
+//! If the user switches applications while a macro is typing, continuing to
+//! inject into the newly focused app is dangerous. This module provides a
+//! `FocusWatcher` abstraction so the main loop can stop execution when focus moves.
+//!
+//! # Platform Support
+//!
+//! - Windows: polls `GetForegroundWindow` and reports when the window handle changes
+//! - macOS/Linux: no focus source yet; the watcher never reports a change
+//!
+//! `foreground_app` names the focused application for `settings.blocklist_apps`:
+//! the executable name on Windows and Linux (X11, via `xdotool`), the app name
+//! on macOS (via `lsappinfo`).
+
+#[cfg(not(target_os = "windows"))]
+use std::process::Command;
+
+use regex::{Regex, RegexBuilder};
+
+/// Source of focus-change notifications.
+///
+/// Implementations report whether the foreground window changed since the
+/// last call. The stop decision is kept separate (see `focus_action`) so it
+/// can be tested with a stub watcher.
+pub trait FocusWatcher {
+    /// Returns true if focus moved to a different window since the last poll.
+    fn focus_changed(&mut self) -> bool;
+
+    /// Forget any previously observed focus (call when a new execution starts).
+    fn reset(&mut self);
+}
+
+/// What the main loop should do after polling the focus watcher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusAction {
+    /// Keep going.
+    None,
+    /// Stop the running macro and release held modifiers.
+    StopAndRelease,
+}
+
+/// Decide whether a focus change should stop the running macro.
+///
+/// The watcher is only polled while a macro is executing and the
+/// `stop_on_focus_loss` setting is on.
+pub fn focus_action(watcher: &mut dyn FocusWatcher, stop_on_focus_loss: bool, executing: bool) -> FocusAction {
+    if !stop_on_focus_loss || !executing {
+        return FocusAction::None;
+    }
+
+    if watcher.focus_changed() {
+        FocusAction::StopAndRelease
+    } else {
+        FocusAction::None
+    }
+}
+
+/// Focus watcher that polls the platform's foreground window.
+pub struct PollingFocusWatcher {
+    /// Foreground window seen on the previous poll.
+    last_window: Option<usize>,
+}
+
+impl PollingFocusWatcher {
+    pub fn new() -> Self {
+        Self { last_window: None }
+    }
+}
+
+impl Default for PollingFocusWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FocusWatcher for PollingFocusWatcher {
+    fn focus_changed(&mut self) -> bool {
+        let current = match foreground_window() {
+            Some(window) => window,
+            // Platform doesn't expose focus - never report a change
+            None => return false,
+        };
+
+        let changed = matches!(self.last_window, Some(last) if last != current);
+        self.last_window = Some(current);
+        changed
+    }
+
+    fn reset(&mut self) {
+        self.last_window = foreground_window();
+    }
+}
+
+/// `blocklist_apps` compiled once when settings load, so each trigger only
+/// has to match.
+///
+/// Entries are case-insensitive substrings, or case-insensitive regexes when
+/// written as `/pattern/`. Empty entries and invalid regexes (reported by
+/// `validate_config`) never match.
+#[derive(Debug, Default)]
+pub struct Blocklist {
+    entries: Vec<(String, BlocklistPattern)>,
+}
+
+#[derive(Debug)]
+enum BlocklistPattern {
+    /// Lowercased substring.
+    Substring(String),
+    Regex(Regex),
+}
+
+impl Blocklist {
+    pub fn new(patterns: &[String]) -> Self {
+        let entries = patterns
+            .iter()
+            .filter(|pattern| !pattern.is_empty())
+            .filter_map(|pattern| {
+                let compiled = match blocklist_regex(pattern) {
+                    Some(regex) => BlocklistPattern::Regex(regex.ok()?),
+                    None => BlocklistPattern::Substring(pattern.to_lowercase()),
+                };
+                Some((pattern.clone(), compiled))
+            })
+            .collect();
+        Self { entries }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entry matching `app`, if any (the first one listed).
+    pub fn find(&self, app: &str) -> Option<&str> {
+        let app_lower = app.to_lowercase();
+        self.entries
+            .iter()
+            .find(|(_, compiled)| match compiled {
+                BlocklistPattern::Substring(substring) => app_lower.contains(substring.as_str()),
+                BlocklistPattern::Regex(regex) => regex.is_match(app),
+            })
+            .map(|(pattern, _)| pattern.as_str())
+    }
+}
+
+/// The regex a `/pattern/` entry compiles to, or None for a substring entry.
+pub fn blocklist_regex(pattern: &str) -> Option<Result<Regex, regex::Error>> {
+    let regex = pattern.strip_prefix('/')?.strip_suffix('/')?;
+    Some(RegexBuilder::new(regex).case_insensitive(true).build())
+}
+
+/// Name of the focused application, or None if it can't be determined.
+#[cfg(target_os = "windows")]
+pub fn foreground_app() -> Option<String> {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    // SAFETY: GetForegroundWindow has no preconditions; GetWindowThreadProcessId
+    // accepts any window handle and writes one u32 to `pid`.
+    let pid = unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_null() {
+            return None;
+        }
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(hwnd, &mut pid);
+        pid
+    };
+    if pid == 0 {
+        return None;
+    }
+
+    let mut buffer = [0u16; 1024];
+    let mut len = buffer.len() as u32;
+    // SAFETY: the process handle is checked before use and closed after;
+    // `buffer` holds `len` u16s, which QueryFullProcessImageNameW respects.
+    let ok = unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if process.is_null() {
+            return None;
+        }
+        let ok = QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, buffer.as_mut_ptr(), &mut len);
+        CloseHandle(process);
+        ok
+    };
+    if ok == 0 {
+        return None;
+    }
+    let path = String::from_utf16_lossy(&buffer[..len as usize]);
+    path.rsplit(['\\', '/']).next().map(str::to_string)
+}
+
+#[cfg(target_os = "macos")]
+pub fn foreground_app() -> Option<String> {
+    // `lsappinfo front` prints the front app's ASN; `info -only name` prints
+    // "LSDisplayName"="Safari"
+    let asn = command_output(Command::new("lsappinfo").arg("front"))?;
+    let info = command_output(Command::new("lsappinfo").args(["info", "-only", "name", &asn]))?;
+    let name = info.split_once('=')?.1.trim().trim_matches('"');
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn foreground_app() -> Option<String> {
+    // X11 only; xdotool isn't available (or can't see windows) on Wayland
+    let pid = command_output(Command::new("xdotool").args(["getactivewindow", "getwindowpid"]))?;
+    let comm = std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?;
+    let name = comm.trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Trimmed stdout of a command that succeeded.
+#[cfg(not(target_os = "windows"))]
+fn command_output(command: &mut Command) -> Option<String> {
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// Get an opaque identifier for the current foreground window.
+#[cfg(target_os = "windows")]
+fn foreground_window() -> Option<usize> {
+    use windows_sys::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+
+    // SAFETY: GetForegroundWindow takes no arguments and has no preconditions.
+    let hwnd = unsafe { GetForegroundWindow() };
+    if hwnd.is_null() {
+        None
+    } else {
+        Some(hwnd as usize)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn foreground_window() -> Option<usize> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stub watcher that reports a fixed sequence of focus changes.
+    struct StubWatcher {
+        changes: Vec<bool>,
+    }
+
+    impl FocusWatcher for StubWatcher {
+        fn focus_changed(&mut self) -> bool {
+            if self.changes.is_empty() {
+                false
+            } else {
+                self.changes.remove(0)
+            }
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    #[test]
+    fn test_focus_change_during_execution_stops() {
+        let mut watcher = StubWatcher { changes: vec![false, true] };
+
+        assert_eq!(focus_action(&mut watcher, true, true), FocusAction::None);
+        assert_eq!(focus_action(&mut watcher, true, true), FocusAction::StopAndRelease);
+    }
+
+    #[test]
+    fn test_focus_change_ignored_when_setting_off() {
+        let mut watcher = StubWatcher { changes: vec![true] };
+        assert_eq!(focus_action(&mut watcher, false, true), FocusAction::None);
+    }
+
+    #[test]
+    fn test_focus_change_ignored_when_idle() {
+        let mut watcher = StubWatcher { changes: vec![true] };
+        assert_eq!(focus_action(&mut watcher, true, false), FocusAction::None);
+        // Watcher was not polled, so the change is still pending
+        assert_eq!(watcher.changes, vec![true]);
+    }
+
+    #[test]
+    fn test_blocklist_match_substring_and_regex() {
+        let patterns = vec![
+            "keepass".to_string(),
+            "/^steam.*\\.exe$/".to_string(),
+            "1Password".to_string(),
+        ];
+        let blocklist = Blocklist::new(&patterns);
+
+        assert_eq!(blocklist.find("KeePassXC.exe"), Some("keepass"));
+        assert_eq!(blocklist.find("steamwebhelper.exe"), Some("/^steam.*\\.exe$/"));
+        assert_eq!(blocklist.find("1password"), Some("1Password"));
+        // Anchored regex doesn't match mid-name
+        assert_eq!(blocklist.find("notsteam.exe"), None);
+        assert_eq!(blocklist.find("notepad.exe"), None);
+    }
+
+    #[test]
+    fn test_blocklist_match_first_entry_wins_and_skips_bad_entries() {
+        let patterns = vec![
+            String::new(),
+            "/([unclosed/".to_string(),
+            "code".to_string(),
+            "/Code/".to_string(),
+        ];
+        let blocklist = Blocklist::new(&patterns);
+
+        assert_eq!(blocklist.find("Code.exe"), Some("code"));
+        assert_eq!(blocklist.find("([unclosed"), None);
+        assert!(Blocklist::new(&[]).is_empty());
+        assert!(blocklist_regex("/([unclosed/").is_some_and(|r| r.is_err()));
+        assert!(blocklist_regex("code").is_none());
+    }
+}
@@ -1,11 +1,15 @@
-/// Global hotkey management for KeyBlast.
-///
-/// Provides registration and lookup of global keyboard shortcuts that trigger macro playback.
+//! Global hotkey management for KeyBlast.
+//!
+//! Provides registration and lookup of global keyboard shortcuts that trigger macro playback.
 
 use std::collections::HashMap;
-use global_hotkey::hotkey::HotKey;
+use std::time::{Duration, Instant};
+use global_hotkey::hotkey::{Code, HotKey, Modifiers};
 use global_hotkey::GlobalHotKeyManager;
 
+use crate::config::{self, MacroDefinition};
+use crate::transaction::HotkeyRegistrar;
+
 /// Result of attempting to register a hotkey.
 #[derive(Debug)]
 pub enum RegisterResult {
@@ -68,10 +72,7 @@ impl HotkeyManager {
                 ))
             }
             Err(global_hotkey::Error::FailedToRegister(msg)) => {
-                RegisterResult::ConflictExternal(format!(
-                    "Hotkey unavailable (may be used by system or another app): {}",
-                    msg
-                ))
+                RegisterResult::ConflictExternal(external_conflict_message(&msg, external_owner(&hotkey).as_deref()))
             }
             Err(e) => RegisterResult::Error(format!("Registration error: {}", e)),
         }
@@ -89,6 +90,17 @@ impl HotkeyManager {
         }
     }
 
+    /// Check whether a hotkey can be registered, without keeping it registered.
+    ///
+    /// Returns `Success` if the hotkey is free.
+    pub fn check_available(&mut self, hotkey: HotKey) -> RegisterResult {
+        let result = self.try_register(hotkey, String::new());
+        if matches!(result, RegisterResult::Success) {
+            let _ = self.unregister(&hotkey);
+        }
+        result
+    }
+
     /// Register a hotkey without a macro name (for system hotkeys like stop).
     pub fn register_raw(&mut self, hotkey: HotKey) -> Result<(), global_hotkey::Error> {
         self.manager.register(hotkey)
@@ -105,3 +117,793 @@ impl HotkeyManager {
     }
 
 }
+
+/// The `ConflictExternal` message for a hotkey the OS or another app holds,
+/// naming the owner when `external_owner` found it.
+fn external_conflict_message(error: &str, owner: Option<&str>) -> String {
+    match owner {
+        Some(owner) => format!("Hotkey unavailable (used by {}): {}", owner, error),
+        None => format!("Hotkey unavailable (may be used by system or another app): {}", error),
+    }
+}
+
+/// Who holds `hotkey`, if it can be told.
+///
+/// Windows has no API to ask which process registered a hotkey, so only
+/// the shell's own shortcuts (Win+L, Win+E, ...) are recognised; see
+/// `windows_shortcut`.
+#[cfg(target_os = "windows")]
+fn external_owner(hotkey: &HotKey) -> Option<String> {
+    windows_shortcut(hotkey).map(|action| format!("Windows: {}", action))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn external_owner(_hotkey: &HotKey) -> Option<String> {
+    None
+}
+
+/// What a Windows shell shortcut does, if `hotkey` is one.
+#[cfg(target_os = "windows")]
+fn windows_shortcut(hotkey: &HotKey) -> Option<&'static str> {
+    const SHORTCUTS: [(&str, &str); 22] = [
+        ("win+a", "Quick Settings"),
+        ("win+b", "focus the taskbar"),
+        ("win+d", "show desktop"),
+        ("win+e", "File Explorer"),
+        ("win+g", "Game Bar"),
+        ("win+h", "voice typing"),
+        ("win+i", "Settings"),
+        ("win+k", "Cast"),
+        ("win+l", "lock the PC"),
+        ("win+m", "minimize all"),
+        ("win+n", "notifications"),
+        ("win+p", "Project"),
+        ("win+r", "Run dialog"),
+        ("win+s", "Search"),
+        ("win+u", "Accessibility settings"),
+        ("win+v", "clipboard history"),
+        ("win+w", "Widgets"),
+        ("win+x", "Quick Link menu"),
+        ("win+shift+s", "Snipping Tool"),
+        ("win+ctrl+d", "new virtual desktop"),
+        ("win+ctrl+f4", "close virtual desktop"),
+        ("win+ctrl+shift+b", "restart the graphics driver"),
+    ];
+    let is = |keys: &str| config::parse_hotkey_string(keys).is_some_and(|hk| hk.id() == hotkey.id());
+    if let Some(&(_, action)) = SHORTCUTS.iter().find(|&&(keys, _)| is(keys)) {
+        return Some(action);
+    }
+    // Win+1..9 open the apps pinned to the taskbar
+    (1..=9).any(|n| is(&format!("win+{}", n))).then_some("taskbar app")
+}
+
+/// The hotkey that stops a running macro (Ctrl+Escape on all platforms).
+pub fn stop_hotkey() -> HotKey {
+    HotKey::new(Some(Modifiers::CONTROL), Code::Escape)
+}
+
+/// The macros whose hotkeys should be registered: none in safe mode.
+///
+/// Safe mode exists to recover from a misbehaving macro, so only KeyBlast's
+/// own stop hotkey is registered.
+pub fn registrable_macros(macros: &[MacroDefinition], safe_mode: bool) -> &[MacroDefinition] {
+    if safe_mode {
+        &[]
+    } else {
+        macros
+    }
+}
+
+/// Register `hotkey` for `macro_def` and record it in the hotkey ID -> macro map.
+///
+/// Two config macros can parse to the same hotkey (validation only warns).
+/// Binding is first-wins: a hotkey already in `registered` is refused before
+/// touching the registrar, so the second macro can't replace the first in the
+/// map and leave its binding unreachable by delete-by-UUID.
+pub fn bind_macro(
+    registrar: &mut dyn HotkeyRegistrar,
+    registered: &mut HashMap<u32, MacroDefinition>,
+    hotkey: HotKey,
+    macro_def: &MacroDefinition,
+) -> Result<(), String> {
+    if let Some(owner) = registered.get(&hotkey.id()) {
+        return Err(format!(
+            "Hotkey {} is already bound to macro '{}'",
+            config::hotkey_to_string(&hotkey),
+            owner.name
+        ));
+    }
+    registrar.register(hotkey, macro_def.name.clone())?;
+    registered.insert(hotkey.id(), macro_def.clone());
+    Ok(())
+}
+
+/// Modifier sets tried, in order, by `suggest_available`.
+const SUGGESTED_EXTRA_MODIFIERS: [Modifiers; 4] = [
+    Modifiers::ALT,
+    Modifiers::SHIFT,
+    Modifiers::ALT.union(Modifiers::SHIFT),
+    Modifiers::META,
+];
+
+/// Whether a suggested hotkey can be used, and if not, who has it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandidateStatus {
+    Free,
+    /// A macro in the config (or KeyBlast itself) has it.
+    InternalConflict,
+    /// The OS or another app has it.
+    ExternalConflict,
+}
+
+impl CandidateStatus {
+    /// Classify a `HotkeyManager::check_available` result. Other
+    /// registration errors count as external: the hotkey can't be had.
+    pub fn from_register_result(result: &RegisterResult) -> Self {
+        match result {
+            RegisterResult::Success => Self::Free,
+            RegisterResult::ConflictInternal(_) => Self::InternalConflict,
+            RegisterResult::ConflictExternal(_) | RegisterResult::Error(_) => Self::ExternalConflict,
+        }
+    }
+}
+
+/// A hotkey `suggest_available_detailed` looked at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HotkeyCandidate {
+    pub hotkey: HotKey,
+    pub status: CandidateStatus,
+}
+
+/// A free alternative to `hotkey`: the same key with an extra modifier
+/// (Alt, Shift, Alt+Shift, then Meta), first one `is_free` accepts.
+///
+/// Modifier sets the hotkey already contains are skipped.
+pub fn suggest_available(hotkey: HotKey, is_free: impl FnMut(&HotKey) -> bool) -> Option<HotKey> {
+    suggest_available_many(hotkey, 1, is_free).into_iter().next()
+}
+
+/// Up to `limit` free alternatives to `hotkey`, in `suggest_available`'s order.
+pub fn suggest_available_many(
+    hotkey: HotKey,
+    limit: usize,
+    mut is_free: impl FnMut(&HotKey) -> bool,
+) -> Vec<HotKey> {
+    // Which conflict a taken hotkey has doesn't matter once it's filtered out
+    suggest_available_detailed(hotkey, limit, |candidate| {
+        if is_free(candidate) {
+            CandidateStatus::Free
+        } else {
+            CandidateStatus::ExternalConflict
+        }
+    })
+    .into_iter()
+    .filter(|candidate| candidate.status == CandidateStatus::Free)
+    .map(|candidate| candidate.hotkey)
+    .collect()
+}
+
+/// The alternatives to `hotkey` in `suggest_available`'s order, each with
+/// its `status`, up to and including the `count`th free one.
+///
+/// The taken ones explain why fewer than `count` are free.
+pub fn suggest_available_detailed(
+    hotkey: HotKey,
+    count: usize,
+    mut status: impl FnMut(&HotKey) -> CandidateStatus,
+) -> Vec<HotkeyCandidate> {
+    let mut candidates = Vec::new();
+    let mut free = 0;
+    for candidate in suggestion_candidates(hotkey) {
+        if free == count {
+            break;
+        }
+        let status = status(&candidate);
+        if status == CandidateStatus::Free {
+            free += 1;
+        }
+        candidates.push(HotkeyCandidate { hotkey: candidate, status });
+    }
+    candidates
+}
+
+fn suggestion_candidates(hotkey: HotKey) -> impl Iterator<Item = HotKey> {
+    SUGGESTED_EXTRA_MODIFIERS
+        .iter()
+        .filter(move |extra| !hotkey.mods.contains(**extra))
+        .map(move |extra| HotKey::new(Some(hotkey.mods | *extra), hotkey.key))
+}
+
+/// Macros whose hotkey couldn't be registered for a reason other than
+/// another macro in the config having it (i.e. the OS or another app has it).
+///
+/// Menu-only and disabled macros and invalid hotkeys are left out.
+pub fn unregistered_macros<'a>(
+    macros: &'a [MacroDefinition],
+    registered: &HashMap<u32, MacroDefinition>,
+) -> Vec<&'a MacroDefinition> {
+    macros
+        .iter()
+        .filter(|m| m.registers_hotkey())
+        .filter(|m| config::parse_hotkey_string(&m.hotkey).is_some_and(|hk| !registered.contains_key(&hk.id())))
+        .collect()
+}
+
+/// Unregister every hotkey in `registered`, then register `macros` in their place.
+///
+/// `registered` ends up as the new hotkey ID -> macro map. Menu-only macros
+/// (`hotkey_enabled = false`) and disabled macros are left out, and macros with an invalid or
+/// unavailable hotkey are skipped with a message.
+pub fn register_macros(
+    registrar: &mut dyn HotkeyRegistrar,
+    registered: &mut HashMap<u32, MacroDefinition>,
+    macros: &[MacroDefinition],
+) {
+    for (_, macro_def) in registered.drain() {
+        if let Some(hotkey) = config::parse_hotkey_string(&macro_def.hotkey) {
+            let _ = registrar.unregister(&hotkey);
+        }
+    }
+
+    for macro_def in macros.iter().filter(|m| m.registers_hotkey()) {
+        let Some(hotkey) = config::parse_hotkey_string(&macro_def.hotkey) else {
+            // Chords are registered by `ChordTracker::set_chords`
+            if config::parse_hotkey_chord(&macro_def.hotkey).is_some() {
+                continue;
+            }
+            tracing::warn!(
+                "Invalid hotkey '{}' for macro '{}'",
+                macro_def.hotkey, macro_def.name
+            );
+            continue;
+        };
+        match bind_macro(registrar, registered, hotkey, macro_def) {
+            Ok(()) => {
+                tracing::info!("Registered: {} -> {}", macro_def.hotkey, macro_def.name);
+            }
+            Err(e) => {
+                tracing::error!("Failed to register '{}': {}", macro_def.name, e);
+            }
+        }
+    }
+}
+
+/// Replace every macro hotkey registration, chords included, with `macros`
+/// from the config (none in safe mode).
+///
+/// Registering from the config rather than from `registered` gives a macro
+/// whose hotkey was taken earlier another try.
+pub fn reregister_macros(
+    registrar: &mut dyn HotkeyRegistrar,
+    registered: &mut HashMap<u32, MacroDefinition>,
+    chords: &mut ChordTracker,
+    macros: &[MacroDefinition],
+    safe_mode: bool,
+    chord_timeout: Option<Duration>,
+) {
+    // Release chord first steps so plain hotkeys can have those keys again
+    chords.clear(registrar);
+    let macros = registrable_macros(macros, safe_mode);
+    register_macros(registrar, registered, macros);
+    chords.set_chords(registrar, registered, macros, chord_timeout);
+}
+
+/// What a hotkey press meant for the chord hotkeys (see `ChordTracker::press`).
+#[derive(Debug, PartialEq)]
+pub enum ChordPress {
+    /// The first step of one or more chords; waiting for the second.
+    Started,
+    /// The second step of this macro's chord.
+    Completed(Box<MacroDefinition>),
+    /// Not part of a chord; handle it as a plain hotkey.
+    NotChord,
+}
+
+/// Two-step chord hotkeys like "ctrl+k ctrl+s", and the chord in progress.
+///
+/// Only first steps stay registered. Pressing one registers the second steps
+/// that can follow it (unless KeyBlast already has them) until the chord
+/// completes, another hotkey is pressed or the timeout passes, so the second
+/// key doesn't also reach the focused app.
+#[derive(Default)]
+pub struct ChordTracker {
+    chords: Vec<([HotKey; 2], MacroDefinition)>,
+    /// Registered first steps, one per chord group
+    prefixes: Vec<HotKey>,
+    /// First step pressed, and when
+    pending: Option<(HotKey, Instant)>,
+    /// Second steps registered while a chord is pending
+    armed: Vec<HotKey>,
+    /// How long to wait for the second step (None: until the next hotkey)
+    timeout: Option<Duration>,
+}
+
+impl ChordTracker {
+    /// Replace the chords with those among `macros`, registering their first steps.
+    ///
+    /// Call after the plain hotkeys are in `registered`: a first step a plain
+    /// hotkey already has is refused, as is a chord another macro already has.
+    pub fn set_chords(
+        &mut self,
+        registrar: &mut dyn HotkeyRegistrar,
+        registered: &HashMap<u32, MacroDefinition>,
+        macros: &[MacroDefinition],
+        timeout: Option<Duration>,
+    ) {
+        self.clear(registrar);
+        self.timeout = timeout;
+
+        for macro_def in macros.iter().filter(|m| m.registers_hotkey()) {
+            let Some(steps) = config::parse_hotkey_chord(&macro_def.hotkey) else {
+                continue;
+            };
+            let owner = registered
+                .get(&steps[0].id())
+                .or_else(|| self.chords.iter().find(|(s, _)| *s == steps).map(|(_, m)| m));
+            if let Some(owner) = owner {
+                tracing::warn!(
+                    "Failed to register '{}': {} is already bound to macro '{}'",
+                    macro_def.name, macro_def.hotkey, owner.name
+                );
+                continue;
+            }
+            if !self.prefixes.contains(&steps[0]) {
+                if let Err(e) = registrar.register(steps[0], macro_def.name.clone()) {
+                    tracing::error!("Failed to register '{}': {}", macro_def.name, e);
+                    continue;
+                }
+                self.prefixes.push(steps[0]);
+            }
+            tracing::info!("Registered: {} -> {}", macro_def.hotkey, macro_def.name);
+            self.chords.push((steps, macro_def.clone()));
+        }
+    }
+
+    /// Unregister every chord, ending any chord in progress.
+    pub fn clear(&mut self, registrar: &mut dyn HotkeyRegistrar) {
+        self.cancel(registrar);
+        for prefix in self.prefixes.drain(..) {
+            let _ = registrar.unregister(&prefix);
+        }
+        self.chords.clear();
+    }
+
+    /// Feed a pressed hotkey's ID.
+    ///
+    /// A pending chord ends with any press: it completes if this is one of its
+    /// second steps, and otherwise the press is handled as if nothing was pending.
+    pub fn press(
+        &mut self,
+        registrar: &mut dyn HotkeyRegistrar,
+        registered: &HashMap<u32, MacroDefinition>,
+        hotkey_id: u32,
+        now: Instant,
+    ) -> ChordPress {
+        let pending = self.pending.filter(|&(_, since)| !self.timed_out(since, now));
+        self.cancel(registrar);
+
+        if let Some((first, _)) = pending {
+            let completed = self
+                .chords
+                .iter()
+                .find(|(steps, _)| steps[0] == first && steps[1].id() == hotkey_id);
+            if let Some((_, macro_def)) = completed {
+                return ChordPress::Completed(Box::new(macro_def.clone()));
+            }
+        }
+
+        let Some(&first) = self.prefixes.iter().find(|p| p.id() == hotkey_id) else {
+            return ChordPress::NotChord;
+        };
+        self.pending = Some((first, now));
+        for (steps, macro_def) in self.chords.iter().filter(|(steps, _)| steps[0] == first) {
+            let second = steps[1];
+            let taken = registered.contains_key(&second.id())
+                || self.prefixes.contains(&second)
+                || self.armed.contains(&second);
+            if taken {
+                continue;
+            }
+            match registrar.register(second, macro_def.name.clone()) {
+                Ok(()) => self.armed.push(second),
+                Err(e) => tracing::error!("Failed to register second step of '{}': {}", macro_def.name, e),
+            }
+        }
+        ChordPress::Started
+    }
+
+    /// End the chord in progress if its second step didn't come in time.
+    pub fn expire(&mut self, registrar: &mut dyn HotkeyRegistrar, now: Instant) {
+        if self.pending.is_some_and(|(_, since)| self.timed_out(since, now)) {
+            self.cancel(registrar);
+        }
+    }
+
+    /// When the chord in progress times out, if one is and it can.
+    pub fn deadline(&self) -> Option<Instant> {
+        let (_, since) = self.pending?;
+        Some(since + self.timeout?)
+    }
+
+    fn timed_out(&self, since: Instant, now: Instant) -> bool {
+        self.timeout.is_some_and(|timeout| now.saturating_duration_since(since) >= timeout)
+    }
+
+    fn cancel(&mut self, registrar: &mut dyn HotkeyRegistrar) {
+        self.pending = None;
+        for second in self.armed.drain(..) {
+            let _ = registrar.unregister(&second);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_macro;
+    use crate::transaction::StubRegistrar;
+
+    #[test]
+    fn test_reregistering_keeps_same_map() {
+        let mut registrar = StubRegistrar::default();
+        let mut registered = HashMap::new();
+        let macros = vec![test_macro("One", "ctrl+1"), test_macro("Two", "ctrl+shift+2")];
+        register_macros(&mut registrar, &mut registered, &macros);
+        let before = registered.clone();
+
+        // Re-register from the current map
+        let current: Vec<_> = registered.values().cloned().collect();
+        register_macros(&mut registrar, &mut registered, &current);
+
+        assert_eq!(registered, before);
+        assert_eq!(registrar.registered.len(), 2);
+    }
+
+    #[test]
+    fn test_reregister_macros_retries_from_config() {
+        let mut registrar = StubRegistrar::default();
+        let mut registered = HashMap::new();
+        let mut chords = ChordTracker::default();
+        let macros = vec![
+            test_macro("One", "ctrl+1"),
+            test_macro("Two", "ctrl+2"),
+            test_macro("Save", "ctrl+k ctrl+s"),
+        ];
+        // Another app has ctrl+2 at first
+        let taken = config::parse_hotkey_string("ctrl+2").unwrap();
+        registrar.registered.insert(taken.id());
+        reregister_macros(&mut registrar, &mut registered, &mut chords, &macros, false, None);
+        let names: Vec<_> = registered.values().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["One"]);
+
+        registrar.registered.remove(&taken.id());
+        reregister_macros(&mut registrar, &mut registered, &mut chords, &macros, false, None);
+        let mut names: Vec<_> = registered.values().map(|m| m.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["One", "Two"]);
+        // Both plain hotkeys plus the chord's first step
+        assert_eq!(registrar.registered.len(), 3);
+
+        reregister_macros(&mut registrar, &mut registered, &mut chords, &macros, true, None);
+        assert!(registered.is_empty());
+        assert!(registrar.registered.is_empty());
+    }
+
+    #[test]
+    fn test_register_macros_replaces_and_skips_invalid() {
+        let mut registrar = StubRegistrar::default();
+        let mut registered = HashMap::new();
+        register_macros(&mut registrar, &mut registered, &[test_macro("Old", "ctrl+1")]);
+
+        let macros = vec![test_macro("New", "ctrl+2"), test_macro("Bad", "ctrl+shift")];
+        register_macros(&mut registrar, &mut registered, &macros);
+
+        let names: Vec<_> = registered.values().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["New"]);
+        assert_eq!(registrar.registered.len(), 1);
+    }
+
+    #[test]
+    fn test_safe_mode_registers_no_macros() {
+        let macros = vec![test_macro("One", "ctrl+1"), test_macro("Two", "ctrl+2")];
+        assert_eq!(registrable_macros(&macros, false).len(), 2);
+        assert!(registrable_macros(&macros, true).is_empty());
+
+        let mut registrar = StubRegistrar::default();
+        let mut registered = HashMap::new();
+        register_macros(&mut registrar, &mut registered, registrable_macros(&macros, true));
+        assert!(registered.is_empty());
+        assert!(registrar.registered.is_empty());
+    }
+
+    #[test]
+    fn test_menu_only_macro_not_registered() {
+        let menu_only = MacroDefinition {
+            hotkey_enabled: false,
+            ..test_macro("Menu Only", "ctrl+2")
+        };
+        let macros = vec![test_macro("Hotkey", "ctrl+1"), menu_only];
+
+        let mut registrar = StubRegistrar::default();
+        let mut registered = HashMap::new();
+        register_macros(&mut registrar, &mut registered, &macros);
+
+        let names: Vec<_> = registered.values().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["Hotkey"]);
+        assert_eq!(registrar.registered.len(), 1);
+    }
+
+    /// Registrar that accepts everything, even a hotkey that's already registered.
+    #[derive(Default)]
+    struct PermissiveRegistrar {
+        registrations: usize,
+    }
+
+    impl HotkeyRegistrar for PermissiveRegistrar {
+        fn register(&mut self, _hotkey: HotKey, _name: String) -> Result<(), String> {
+            self.registrations += 1;
+            Ok(())
+        }
+
+        fn unregister(&mut self, _hotkey: &HotKey) -> Result<(), String> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_duplicate_hotkey_first_wins() {
+        let first = test_macro("First", "ctrl+shift+k");
+        let second = test_macro("Second", "Shift+Ctrl+K");
+        let mut registrar = PermissiveRegistrar::default();
+        let mut registered = HashMap::new();
+
+        register_macros(&mut registrar, &mut registered, &[first.clone(), second.clone()]);
+
+        // The second is refused before reaching the registrar
+        assert_eq!(registrar.registrations, 1);
+        assert_eq!(registered.len(), 1);
+        // Delete-by-UUID finds the first macro's binding, and nothing for the second
+        assert!(registered.values().any(|m| m.id == first.id));
+        assert!(!registered.values().any(|m| m.id == second.id));
+
+        let hotkey = config::parse_hotkey_string(&second.hotkey).unwrap();
+        let err = bind_macro(&mut registrar, &mut registered, hotkey, &second).unwrap_err();
+        assert!(err.contains("'First'"), "{}", err);
+    }
+
+    #[test]
+    fn test_suggest_available_adds_modifiers() {
+        let ctrl_k = HotKey::new(Some(Modifiers::CONTROL), Code::KeyK);
+        let ctrl_alt_k = HotKey::new(Some(Modifiers::CONTROL | Modifiers::ALT), Code::KeyK);
+        let ctrl_shift_k = HotKey::new(Some(Modifiers::CONTROL | Modifiers::SHIFT), Code::KeyK);
+
+        assert_eq!(suggest_available(ctrl_k, |_| true), Some(ctrl_alt_k));
+        assert_eq!(suggest_available(ctrl_k, |hk| *hk != ctrl_alt_k), Some(ctrl_shift_k));
+        assert_eq!(suggest_available(ctrl_k, |_| false), None);
+
+        // Modifiers already present aren't suggested again
+        let suggested = suggest_available(ctrl_alt_k, |_| true).unwrap();
+        assert_eq!(suggested.mods, Modifiers::CONTROL | Modifiers::ALT | Modifiers::SHIFT);
+    }
+
+    #[test]
+    fn test_suggest_available_many() {
+        let ctrl_k = HotKey::new(Some(Modifiers::CONTROL), Code::KeyK);
+        let ctrl_shift_k = HotKey::new(Some(Modifiers::CONTROL | Modifiers::SHIFT), Code::KeyK);
+
+        let all = suggest_available_many(ctrl_k, 5, |_| true);
+        assert_eq!(all.len(), 4);
+        assert_eq!(all[0], suggest_available(ctrl_k, |_| true).unwrap());
+
+        let taken = suggest_available_many(ctrl_k, 2, |hk| *hk != ctrl_shift_k);
+        assert_eq!(taken.len(), 2);
+        assert!(!taken.contains(&ctrl_shift_k));
+        assert!(suggest_available_many(ctrl_k, 5, |_| false).is_empty());
+    }
+
+    #[test]
+    fn test_suggest_available_detailed() {
+        let ctrl_k = HotKey::new(Some(Modifiers::CONTROL), Code::KeyK);
+        let ctrl_alt_k = HotKey::new(Some(Modifiers::CONTROL | Modifiers::ALT), Code::KeyK);
+        let ctrl_shift_k = HotKey::new(Some(Modifiers::CONTROL | Modifiers::SHIFT), Code::KeyK);
+
+        // Mocked manager: another macro has Ctrl+Alt+K, the OS has Ctrl+Shift+K
+        // and anything else fails with some other error
+        let check = |candidate: &HotKey| {
+            let result = if *candidate == ctrl_alt_k {
+                RegisterResult::ConflictInternal("used by KeyBlast".to_string())
+            } else if *candidate == ctrl_shift_k {
+                RegisterResult::ConflictExternal("used by the OS".to_string())
+            } else if candidate.mods.intersects(Modifiers::META | Modifiers::SUPER) {
+                RegisterResult::Error("no".to_string())
+            } else {
+                RegisterResult::Success
+            };
+            CandidateStatus::from_register_result(&result)
+        };
+
+        let statuses: Vec<_> = suggest_available_detailed(ctrl_k, 5, check)
+            .into_iter()
+            .map(|candidate| candidate.status)
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                CandidateStatus::InternalConflict,
+                CandidateStatus::ExternalConflict,
+                CandidateStatus::Free,
+                CandidateStatus::ExternalConflict,
+            ]
+        );
+
+        // Stops at the countth free one
+        let first = suggest_available_detailed(ctrl_k, 1, check);
+        assert_eq!(first.len(), 3);
+        assert_eq!(first[0].hotkey, ctrl_alt_k);
+        assert!(suggest_available_detailed(ctrl_k, 0, check).is_empty());
+
+        // The simple forms are the free ones
+        let free = suggest_available_many(ctrl_k, 5, |hk| check(hk) == CandidateStatus::Free);
+        assert_eq!(free, vec![first[2].hotkey]);
+        assert_eq!(suggest_available(ctrl_k, |hk| check(hk) == CandidateStatus::Free), Some(first[2].hotkey));
+    }
+
+    #[test]
+    fn test_unregistered_macros() {
+        let bound = test_macro("Bound", "ctrl+1");
+        let duplicate = test_macro("Duplicate", "ctrl+1");
+        let taken = test_macro("Taken", "ctrl+2");
+        let menu_only = MacroDefinition {
+            hotkey_enabled: false,
+            ..test_macro("Menu", "ctrl+3")
+        };
+        let invalid = test_macro("Invalid", "ctrl+nope");
+        let macros = vec![bound.clone(), duplicate, taken, menu_only, invalid];
+        let hotkey = config::parse_hotkey_string("ctrl+1").unwrap();
+        let registered = HashMap::from([(hotkey.id(), bound)]);
+
+        let names: Vec<_> = unregistered_macros(&macros, &registered).iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["Taken"]);
+    }
+
+    #[test]
+    fn test_register_macros_leaves_chords_to_tracker() {
+        let mut registrar = StubRegistrar::default();
+        let mut registered = HashMap::new();
+        let macros = vec![test_macro("Plain", "ctrl+1"), test_macro("Chord", "ctrl+k ctrl+s")];
+        register_macros(&mut registrar, &mut registered, &macros);
+        assert_eq!(registered.len(), 1);
+
+        let mut chords = ChordTracker::default();
+        chords.set_chords(&mut registrar, &registered, &macros, None);
+        let ctrl_k = config::parse_hotkey_string("ctrl+k").unwrap();
+        assert!(registrar.registered.contains(&ctrl_k.id()));
+        assert_eq!(registrar.registered.len(), 2);
+
+        chords.clear(&mut registrar);
+        assert_eq!(registrar.registered.len(), 1);
+    }
+
+    #[test]
+    fn test_chord_press_sequence() {
+        let save = test_macro("Save", "ctrl+k ctrl+s");
+        let tab = test_macro("Tab", "ctrl+k ctrl+t");
+        let plain = test_macro("Plain", "ctrl+s");
+        let hk = |s: &str| config::parse_hotkey_string(s).unwrap();
+        let mut registrar = StubRegistrar::default();
+        let mut registered = HashMap::new();
+        let macros = vec![save.clone(), tab.clone(), plain.clone()];
+        register_macros(&mut registrar, &mut registered, &macros);
+        let mut chords = ChordTracker::default();
+        chords.set_chords(&mut registrar, &registered, &macros, Some(Duration::from_millis(1500)));
+        // ctrl+s (plain) and the shared first step
+        assert_eq!(registrar.registered.len(), 2);
+
+        let start = Instant::now();
+        assert_eq!(chords.press(&mut registrar, &registered, hk("ctrl+1").id(), start), ChordPress::NotChord);
+        assert_eq!(chords.press(&mut registrar, &registered, hk("ctrl+k").id(), start), ChordPress::Started);
+        // ctrl+t is registered only while the chord is pending
+        assert!(registrar.registered.contains(&hk("ctrl+t").id()));
+        assert_eq!(chords.deadline(), Some(start + Duration::from_millis(1500)));
+
+        // The pending chord wins over the plain ctrl+s macro
+        let now = start + Duration::from_millis(500);
+        assert_eq!(
+            chords.press(&mut registrar, &registered, hk("ctrl+s").id(), now),
+            ChordPress::Completed(Box::new(save))
+        );
+        assert!(!registrar.registered.contains(&hk("ctrl+t").id()));
+        assert_eq!(chords.deadline(), None);
+
+        // Without a pending chord ctrl+s is the plain macro's
+        assert_eq!(chords.press(&mut registrar, &registered, hk("ctrl+s").id(), now), ChordPress::NotChord);
+
+        // Any other hotkey ends the pending chord
+        chords.press(&mut registrar, &registered, hk("ctrl+k").id(), now);
+        assert_eq!(chords.press(&mut registrar, &registered, hk("ctrl+1").id(), now), ChordPress::NotChord);
+        assert_eq!(chords.press(&mut registrar, &registered, hk("ctrl+t").id(), now), ChordPress::NotChord);
+
+        chords.press(&mut registrar, &registered, hk("ctrl+k").id(), now);
+        assert_eq!(
+            chords.press(&mut registrar, &registered, hk("ctrl+t").id(), now),
+            ChordPress::Completed(Box::new(tab))
+        );
+    }
+
+    #[test]
+    fn test_chord_timeout() {
+        let chord = test_macro("Chord", "ctrl+k ctrl+s");
+        let hk = |s: &str| config::parse_hotkey_string(s).unwrap();
+        let mut registrar = StubRegistrar::default();
+        let registered = HashMap::new();
+        let mut chords = ChordTracker::default();
+        chords.set_chords(&mut registrar, &registered, std::slice::from_ref(&chord), Some(Duration::from_millis(1000)));
+
+        let start = Instant::now();
+        let late = start + Duration::from_millis(1000);
+        chords.press(&mut registrar, &registered, hk("ctrl+k").id(), start);
+        assert_eq!(chords.press(&mut registrar, &registered, hk("ctrl+s").id(), late), ChordPress::NotChord);
+
+        chords.press(&mut registrar, &registered, hk("ctrl+k").id(), start);
+        chords.expire(&mut registrar, start + Duration::from_millis(999));
+        assert!(registrar.registered.contains(&hk("ctrl+s").id()));
+        chords.expire(&mut registrar, late);
+        assert!(!registrar.registered.contains(&hk("ctrl+s").id()));
+        assert_eq!(chords.deadline(), None);
+
+        // No timeout: waits for the next hotkey
+        chords.set_chords(&mut registrar, &registered, std::slice::from_ref(&chord), None);
+        chords.press(&mut registrar, &registered, hk("ctrl+k").id(), start);
+        assert_eq!(chords.deadline(), None);
+        let much_later = start + Duration::from_secs(3600);
+        chords.expire(&mut registrar, much_later);
+        assert_eq!(
+            chords.press(&mut registrar, &registered, hk("ctrl+s").id(), much_later),
+            ChordPress::Completed(Box::new(chord))
+        );
+    }
+
+    #[test]
+    fn test_chord_conflicts_first_wins() {
+        let plain = test_macro("Plain", "ctrl+k");
+        let first = test_macro("First", "ctrl+j ctrl+s");
+        let second = test_macro("Second", "Ctrl+J Ctrl+S");
+        let shadowed = test_macro("Shadowed", "ctrl+k ctrl+s");
+        let macros = vec![plain, first.clone(), second, shadowed];
+        let mut registrar = StubRegistrar::default();
+        let mut registered = HashMap::new();
+        register_macros(&mut registrar, &mut registered, &macros);
+        let mut chords = ChordTracker::default();
+        chords.set_chords(&mut registrar, &registered, &macros, None);
+
+        let names: Vec<_> = chords.chords.iter().map(|(_, m)| m.name.as_str()).collect();
+        assert_eq!(names, vec!["First"]);
+        assert_eq!(registrar.registered.len(), 2);
+    }
+
+    #[test]
+    fn test_external_conflict_message() {
+        assert_eq!(
+            external_conflict_message("RegisterHotKey failed", Some("Windows: lock the PC")),
+            "Hotkey unavailable (used by Windows: lock the PC): RegisterHotKey failed"
+        );
+        // Generic fallback when the owner can't be told
+        assert_eq!(
+            external_conflict_message("RegisterHotKey failed", None),
+            "Hotkey unavailable (may be used by system or another app): RegisterHotKey failed"
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "windows")]
+    fn test_windows_shortcut() {
+        let hotkey = |s: &str| config::parse_hotkey_string(s).unwrap();
+        assert_eq!(windows_shortcut(&hotkey("win+l")), Some("lock the PC"));
+        assert_eq!(windows_shortcut(&hotkey("Shift+Win+S")), Some("Snipping Tool"));
+        assert_eq!(windows_shortcut(&hotkey("win+3")), Some("taskbar app"));
+        assert_eq!(windows_shortcut(&hotkey("win+0")), None);
+        assert_eq!(windows_shortcut(&hotkey("ctrl+l")), None);
+        assert_eq!(windows_shortcut(&hotkey("win+alt+l")), None);
+        assert_eq!(external_owner(&hotkey("win+e")).as_deref(), Some("Windows: File Explorer"));
+    }
+}
@@ -0,0 +1,364 @@
+//! Hotstring (text abbreviation) expansion for KeyBlast.
+//!
+//! Lets a macro fire when its `abbreviation` (e.g. ";sig") is typed anywhere.
+//! A global keyboard listener feeds typed characters into an
+//! `AbbreviationMatcher`; on a match the main loop backspaces the typed
+//! abbreviation and injects the macro.
+//!
+//! # Permission Caveats
+//!
+//! Listening to all keyboard input is sensitive:
+//! - macOS: requires Accessibility / Input Monitoring permission
+//! - Linux: X11 only (no Wayland support)
+//! - Windows: works without extra permissions, but not in elevated apps
+//!
+//! The listener is only started when `settings.hotstrings_enabled` is on.
+//! Injected expansion text is seen by the listener too. The main loop reports
+//! when it injects (see `HotstringListener::begin_injection`) and those
+//! keystrokes are ignored, so an expansion that contains its own abbreviation
+//! doesn't re-trigger.
+
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use rdev::{Event, EventType, Key};
+use uuid::Uuid;
+
+use crate::config::{self, MacroDefinition};
+use crate::input_abort::{is_injected, INJECTION_GRACE};
+use crate::input_hook;
+
+/// A completed abbreviation match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HotstringMatch {
+    /// ID of the macro whose abbreviation was typed.
+    pub macro_id: Uuid,
+    /// Number of characters to backspace to remove the typed abbreviation.
+    pub typed_len: usize,
+}
+
+/// State machine matching typed characters against macro abbreviations.
+///
+/// Keeps the last few typed characters (as many as the longest abbreviation)
+/// and fires when they end with an abbreviation. Backspace edits the buffer,
+/// so correcting a typo mid-abbreviation still matches.
+#[derive(Debug, Default)]
+pub struct AbbreviationMatcher {
+    abbreviations: Vec<(String, Uuid)>,
+    buffer: String,
+}
+
+impl AbbreviationMatcher {
+    pub fn new(abbreviations: Vec<(String, Uuid)>) -> Self {
+        Self {
+            abbreviations,
+            buffer: String::new(),
+        }
+    }
+
+    /// Replace the abbreviation list (e.g. after a config reload).
+    pub fn set_abbreviations(&mut self, abbreviations: Vec<(String, Uuid)>) {
+        self.abbreviations = abbreviations;
+        self.buffer.clear();
+    }
+
+    /// Feed one typed character. Returns a match when an abbreviation completes.
+    pub fn feed(&mut self, c: char) -> Option<HotstringMatch> {
+        self.buffer.push(c);
+
+        // Only the longest abbreviation's worth of input can ever match
+        let max_len = self
+            .abbreviations
+            .iter()
+            .map(|(abbr, _)| abbr.chars().count())
+            .max()
+            .unwrap_or(0);
+        let excess = self.buffer.chars().count().saturating_sub(max_len);
+        if excess > 0 {
+            let cut = self.buffer.char_indices().nth(excess).map_or(self.buffer.len(), |(i, _)| i);
+            self.buffer.drain(..cut);
+        }
+
+        let matched = self
+            .abbreviations
+            .iter()
+            .find(|(abbr, _)| self.buffer.ends_with(abbr.as_str()))
+            .map(|(abbr, id)| HotstringMatch {
+                macro_id: *id,
+                typed_len: abbr.chars().count(),
+            });
+
+        if matched.is_some() {
+            self.buffer.clear();
+        }
+        matched
+    }
+
+    /// Handle a Backspace press by removing the last buffered character.
+    pub fn backspace(&mut self) {
+        self.buffer.pop();
+    }
+
+    /// Forget partial input (cursor moved, Enter pressed, mouse clicked, ...).
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+    }
+}
+
+/// Collect the (abbreviation, macro id) pairs from a macro list.
+///
+/// Disabled macros, macros for other machines (`only_on`) and macros
+/// without an abbreviation, or with an empty one, are skipped.
+pub fn abbreviations_from(macros: &[MacroDefinition]) -> Vec<(String, Uuid)> {
+    macros
+        .iter()
+        .filter(|m| m.enabled && config::macro_applies(m))
+        .filter_map(|m| {
+            m.abbreviation
+                .as_ref()
+                .filter(|abbr| !abbr.is_empty())
+                .map(|abbr| (abbr.clone(), m.id))
+        })
+        .collect()
+}
+
+/// Feed one input event to `matcher`, returning the matches it completes.
+///
+/// KeyBlast's own keystrokes (`injected`) are ignored and forget partial
+/// input, so typed expansion text never completes an abbreviation.
+fn handle_event(matcher: &mut AbbreviationMatcher, event: &Event, injected: bool) -> Vec<HotstringMatch> {
+    if injected {
+        matcher.reset();
+        return Vec::new();
+    }
+
+    let mut matches = Vec::new();
+    match event.event_type {
+        EventType::KeyPress(Key::Backspace) => matcher.backspace(),
+        EventType::KeyPress(key) if is_modifier(key) => {}
+        EventType::KeyPress(_) => {
+            // Printable input carries its typed text in `name`
+            match event.name.as_deref().filter(|s| !s.chars().any(char::is_control)) {
+                Some(text) => matches.extend(text.chars().filter_map(|c| matcher.feed(c))),
+                None => matcher.reset(),
+            }
+        }
+        EventType::ButtonPress(_) => matcher.reset(),
+        _ => {}
+    }
+    matches
+}
+
+/// State shared between the hook thread and the main thread.
+#[derive(Debug, Default)]
+struct Shared {
+    matcher: AbbreviationMatcher,
+    /// KeyBlast is injecting keystrokes right now.
+    injecting: bool,
+    /// When KeyBlast last finished injecting.
+    last_injection: Option<Instant>,
+}
+
+/// Global keyboard listener feeding an `AbbreviationMatcher`.
+///
+/// The listener runs for the rest of the process lifetime (see `input_hook`);
+/// disable hotstrings by clearing the abbreviation list.
+pub struct HotstringListener {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl HotstringListener {
+    /// Start listening for typed abbreviations.
+    ///
+    /// `on_match` is called from the listener thread for every completed match;
+    /// forward it to the main thread (e.g. via the event loop proxy).
+    pub fn start<F>(abbreviations: Vec<(String, Uuid)>, on_match: F) -> Self
+    where
+        F: Fn(HotstringMatch) + Send + 'static,
+    {
+        let shared = Arc::new(Mutex::new(Shared {
+            matcher: AbbreviationMatcher::new(abbreviations),
+            ..Shared::default()
+        }));
+        let thread_shared = Arc::clone(&shared);
+
+        input_hook::subscribe(move |event| {
+            let Ok(mut shared) = thread_shared.lock() else {
+                return;
+            };
+            let injected = is_injected(Instant::now(), shared.injecting, shared.last_injection, INJECTION_GRACE);
+            for m in handle_event(&mut shared.matcher, event, injected) {
+                on_match(m);
+            }
+        });
+
+        Self { shared }
+    }
+
+    /// Replace the abbreviations being listened for.
+    pub fn set_abbreviations(&self, abbreviations: Vec<(String, Uuid)>) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.matcher.set_abbreviations(abbreviations);
+        }
+    }
+
+    /// KeyBlast is about to inject keystrokes; they aren't the user typing.
+    pub fn begin_injection(&self) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.injecting = true;
+        }
+    }
+
+    /// Injection finished; its events may still arrive for `INJECTION_GRACE`.
+    pub fn end_injection(&self) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.injecting = false;
+            shared.last_injection = Some(Instant::now());
+        }
+    }
+}
+
+/// Modifier keys don't type anything and shouldn't break a partial match.
+fn is_modifier(key: Key) -> bool {
+    matches!(
+        key,
+        Key::ShiftLeft
+            | Key::ShiftRight
+            | Key::ControlLeft
+            | Key::ControlRight
+            | Key::Alt
+            | Key::AltGr
+            | Key::MetaLeft
+            | Key::MetaRight
+            | Key::CapsLock
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_str(matcher: &mut AbbreviationMatcher, s: &str) -> Option<HotstringMatch> {
+        let mut last = None;
+        for c in s.chars() {
+            last = matcher.feed(c);
+        }
+        last
+    }
+
+    #[test]
+    fn test_full_match() {
+        let id = Uuid::new_v4();
+        let mut matcher = AbbreviationMatcher::new(vec![(";sig".to_string(), id)]);
+
+        assert_eq!(
+            feed_str(&mut matcher, ";sig"),
+            Some(HotstringMatch { macro_id: id, typed_len: 4 })
+        );
+    }
+
+    #[test]
+    fn test_partial_match_does_not_fire() {
+        let id = Uuid::new_v4();
+        let mut matcher = AbbreviationMatcher::new(vec![(";sig".to_string(), id)]);
+
+        assert_eq!(feed_str(&mut matcher, ";si"), None);
+        // Completing it later still fires
+        assert!(matcher.feed('g').is_some());
+    }
+
+    #[test]
+    fn test_mismatch_resets() {
+        let id = Uuid::new_v4();
+        let mut matcher = AbbreviationMatcher::new(vec![(";sig".to_string(), id)]);
+
+        assert_eq!(feed_str(&mut matcher, ";sx"), None);
+        assert_eq!(feed_str(&mut matcher, "ig"), None);
+    }
+
+    #[test]
+    fn test_match_after_other_text() {
+        let id = Uuid::new_v4();
+        let mut matcher = AbbreviationMatcher::new(vec![(";sig".to_string(), id)]);
+
+        // The second ';' starts a fresh partial match
+        assert!(feed_str(&mut matcher, ";;sig").is_some());
+        // Text before the abbreviation doesn't matter
+        assert!(feed_str(&mut matcher, "hello ;sig").is_some());
+    }
+
+    #[test]
+    fn test_backspace_and_reset() {
+        let id = Uuid::new_v4();
+        let mut matcher = AbbreviationMatcher::new(vec![(";sig".to_string(), id)]);
+
+        feed_str(&mut matcher, ";sx");
+        matcher.backspace();
+        assert!(feed_str(&mut matcher, "ig").is_some());
+
+        feed_str(&mut matcher, ";si");
+        matcher.reset();
+        assert_eq!(matcher.feed('g'), None);
+    }
+
+    #[test]
+    fn test_multibyte_abbreviation_length() {
+        let id = Uuid::new_v4();
+        let mut matcher = AbbreviationMatcher::new(vec![("éé".to_string(), id)]);
+
+        assert_eq!(
+            feed_str(&mut matcher, "xéé"),
+            Some(HotstringMatch { macro_id: id, typed_len: 2 })
+        );
+    }
+
+    fn key_event(text: &str) -> Event {
+        Event {
+            time: std::time::SystemTime::now(),
+            name: Some(text.to_string()),
+            event_type: EventType::KeyPress(Key::KeyA),
+        }
+    }
+
+    #[test]
+    fn test_injected_input_ignored() {
+        let id = Uuid::new_v4();
+        let mut matcher = AbbreviationMatcher::new(vec![(";sig".to_string(), id)]);
+
+        // An expansion that types its own abbreviation doesn't fire again
+        for c in ["B", "y", "e", " ", ";", "s", "i", "g"] {
+            assert!(handle_event(&mut matcher, &key_event(c), true).is_empty());
+        }
+
+        // Injected input also drops what the user had typed so far
+        for c in [";", "s", "i"] {
+            assert!(handle_event(&mut matcher, &key_event(c), false).is_empty());
+        }
+        handle_event(&mut matcher, &key_event("x"), true);
+        assert!(handle_event(&mut matcher, &key_event("g"), false).is_empty());
+
+        // The user typing it still fires
+        let typed: Vec<_> = [";", "s", "i", "g"]
+            .into_iter()
+            .flat_map(|c| handle_event(&mut matcher, &key_event(c), false))
+            .collect();
+        assert_eq!(typed, vec![HotstringMatch { macro_id: id, typed_len: 4 }]);
+    }
+
+    #[test]
+    fn test_abbreviations_from_skips_missing_and_empty() {
+        let with = MacroDefinition {
+            name: "Sig".to_string(),
+            abbreviation: Some(";sig".to_string()),
+            ..MacroDefinition::default()
+        };
+        let empty = MacroDefinition {
+            abbreviation: Some(String::new()),
+            ..MacroDefinition::default()
+        };
+        let without = MacroDefinition::default();
+
+        let abbreviations = abbreviations_from(&[with.clone(), empty, without]);
+        assert_eq!(abbreviations, vec![(";sig".to_string(), with.id)]);
+    }
+}
@@ -0,0 +1,244 @@
+//! Optional localhost HTTP endpoint for kiosk setups.
+//!
+//! When `settings.http_port` and `settings.http_token` are both set, a small
+//! server listens on 127.0.0.1 only:
+//! - `GET /health` returns `{"status":"ok","version":"..."}`
+//! - `POST /run/{name}` runs the macro with that name (URL-encoded)
+//!
+//! Every request must send `Authorization: Bearer <http_token>`. Runs are
+//! forwarded to the event loop and answered with 202 before the macro types,
+//! so an unknown name is only reported in the log.
+//!
+//! For a local web page, set `settings.http_allowed_origin` to the page's
+//! origin (e.g. `http://localhost:8080`). CORS preflight `OPTIONS` requests
+//! are answered without a token, since browsers never send one with them, and
+//! every response then allows that origin to read it.
+
+use std::io;
+use std::sync::Arc;
+use std::thread;
+
+use tiny_http::{Header, Response, Server};
+
+/// What a request asks for, after checking the token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Route {
+    /// CORS preflight (`OPTIONS`), answered before the token is checked.
+    Preflight,
+    Health,
+    /// Run the macro with this (decoded) name.
+    Run(String),
+    /// Missing or wrong token.
+    Unauthorized,
+    /// Known path, wrong method.
+    MethodNotAllowed,
+    NotFound,
+}
+
+/// Route a request by method, URL and `Authorization` header value.
+///
+/// The token is checked before the path, so an unauthenticated caller can't
+/// probe which paths exist. Only preflight requests skip it.
+pub fn route(method: &str, url: &str, authorization: Option<&str>, token: &str) -> Route {
+    if method == "OPTIONS" {
+        return Route::Preflight;
+    }
+
+    let authorized = authorization
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|given| constant_time_eq(given.trim().as_bytes(), token.trim().as_bytes()));
+    if !authorized {
+        return Route::Unauthorized;
+    }
+
+    let path = url.split(['?', '#']).next().unwrap_or("");
+    if path == "/health" {
+        return if method == "GET" { Route::Health } else { Route::MethodNotAllowed };
+    }
+    if let Some(name) = path.strip_prefix("/run/") {
+        let Some(name) = percent_decode(name).filter(|name| !name.is_empty()) else {
+            return Route::NotFound;
+        };
+        return if method == "POST" { Route::Run(name) } else { Route::MethodNotAllowed };
+    }
+    Route::NotFound
+}
+
+/// Compare without stopping at the first differing byte, so response timing
+/// doesn't reveal how much of a guessed token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// CORS headers for responses to a page served from `origin`.
+fn cors_headers(origin: &str) -> Vec<Header> {
+    [
+        ("Access-Control-Allow-Origin", origin),
+        ("Access-Control-Allow-Methods", "GET, POST, OPTIONS"),
+        ("Access-Control-Allow-Headers", "Authorization"),
+        ("Vary", "Origin"),
+    ]
+    .into_iter()
+    .filter_map(|(field, value)| Header::from_bytes(field, value).ok())
+    .collect()
+}
+
+/// Decode `%XX` escapes. Returns None for malformed escapes or invalid UTF-8.
+fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Running HTTP server. Dropping it stops the server thread.
+pub struct HttpServer {
+    server: Arc<Server>,
+}
+
+impl HttpServer {
+    /// Bind to 127.0.0.1:`port` and serve requests on a background thread.
+    ///
+    /// `on_run` is called (on that thread) with the name from `POST /run/{name}`.
+    /// With `allowed_origin` set, responses carry CORS headers for that origin.
+    pub fn start<F>(port: u16, token: String, allowed_origin: Option<String>, on_run: F) -> io::Result<Self>
+    where
+        F: Fn(String) + Send + 'static,
+    {
+        let server = Server::http(("127.0.0.1", port)).map_err(io::Error::other)?;
+        let server = Arc::new(server);
+        let thread_server = Arc::clone(&server);
+
+        thread::spawn(move || {
+            for request in thread_server.incoming_requests() {
+                let authorization = request
+                    .headers()
+                    .iter()
+                    .find(|h| h.field.equiv("Authorization"))
+                    .map(|h| h.value.as_str().to_string());
+                let route = route(
+                    request.method().as_str(),
+                    request.url(),
+                    authorization.as_deref(),
+                    &token,
+                );
+
+                let (status, body) = match route {
+                    Route::Preflight => (204, String::new()),
+                    Route::Health => (
+                        200,
+                        format!(r#"{{"status":"ok","version":"{}"}}"#, env!("CARGO_PKG_VERSION")),
+                    ),
+                    Route::Run(name) => {
+                        tracing::info!("HTTP run request: {}", name);
+                        on_run(name);
+                        (202, r#"{"status":"accepted"}"#.to_string())
+                    }
+                    Route::Unauthorized => (401, r#"{"error":"unauthorized"}"#.to_string()),
+                    Route::MethodNotAllowed => (405, r#"{"error":"method not allowed"}"#.to_string()),
+                    Route::NotFound => (404, r#"{"error":"not found"}"#.to_string()),
+                };
+
+                let content_type = Header::from_bytes("Content-Type", "application/json")
+                    .expect("static header is valid");
+                let mut response = Response::from_string(body)
+                    .with_status_code(status)
+                    .with_header(content_type);
+                if let Some(ref origin) = allowed_origin {
+                    for header in cors_headers(origin) {
+                        response.add_header(header);
+                    }
+                }
+                if let Err(e) = request.respond(response) {
+                    tracing::warn!("Failed to send HTTP response: {}", e);
+                }
+            }
+        });
+
+        Ok(Self { server })
+    }
+}
+
+impl Drop for HttpServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "s3cret";
+    const AUTH: Option<&str> = Some("Bearer s3cret");
+
+    #[test]
+    fn test_route_health_and_run() {
+        assert_eq!(route("GET", "/health", AUTH, TOKEN), Route::Health);
+        assert_eq!(route("GET", "/health?x=1", AUTH, TOKEN), Route::Health);
+        assert_eq!(
+            route("POST", "/run/My%20Macro", AUTH, TOKEN),
+            Route::Run("My Macro".to_string())
+        );
+        assert_eq!(
+            route("POST", "/run/Caf%C3%A9", AUTH, TOKEN),
+            Route::Run("Café".to_string())
+        );
+    }
+
+    #[test]
+    fn test_route_requires_token() {
+        assert_eq!(route("GET", "/health", None, TOKEN), Route::Unauthorized);
+        assert_eq!(route("GET", "/health", Some("Bearer wrong"), TOKEN), Route::Unauthorized);
+        assert_eq!(route("POST", "/run/A", Some("s3cret"), TOKEN), Route::Unauthorized);
+        assert_eq!(route("GET", "/health", Some("Bearer s3cre"), TOKEN), Route::Unauthorized);
+        // Surrounding whitespace is ignored on both sides
+        assert_eq!(route("GET", "/health", Some("Bearer  s3cret "), TOKEN), Route::Health);
+        assert_eq!(route("GET", "/health", AUTH, " s3cret\n"), Route::Health);
+        // Checked before the path
+        assert_eq!(route("GET", "/nope", None, TOKEN), Route::Unauthorized);
+    }
+
+    #[test]
+    fn test_route_preflight_skips_token() {
+        assert_eq!(route("OPTIONS", "/run/A", None, TOKEN), Route::Preflight);
+        assert_eq!(route("OPTIONS", "/health", Some("Bearer wrong"), TOKEN), Route::Preflight);
+        // Other methods still need the token
+        assert_eq!(route("POST", "/run/A", None, TOKEN), Route::Unauthorized);
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"s3cret", b"s3cret"));
+        assert!(!constant_time_eq(b"s3cret", b"s3creT"));
+        assert!(!constant_time_eq(b"s3cret", b"s3cret2"));
+        assert!(constant_time_eq(b"", b""));
+    }
+
+    #[test]
+    fn test_route_rejects_bad_method_and_path() {
+        assert_eq!(route("POST", "/health", AUTH, TOKEN), Route::MethodNotAllowed);
+        assert_eq!(route("GET", "/run/A", AUTH, TOKEN), Route::MethodNotAllowed);
+        assert_eq!(route("POST", "/run/", AUTH, TOKEN), Route::NotFound);
+        assert_eq!(route("POST", "/run/%zz", AUTH, TOKEN), Route::NotFound);
+        assert_eq!(route("GET", "/", AUTH, TOKEN), Route::NotFound);
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("a%2Fb").as_deref(), Some("a/b"));
+        assert_eq!(percent_decode("plain").as_deref(), Some("plain"));
+        assert_eq!(percent_decode("trailing%2"), None);
+        assert_eq!(percent_decode("%FF"), None);
+    }
+}
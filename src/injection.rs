@@ -1,34 +1,68 @@
-/// Keystroke injection for KeyBlast.
-///
-/// Provides safe keystroke injection that properly handles modifier keys
-/// held from hotkey activation and supports configurable typing delay.
+//! Keystroke injection for KeyBlast.
+//!
+//! Provides safe keystroke injection that properly handles modifier keys
+//! held from hotkey activation and supports configurable typing delay.
 
 use arboard::Clipboard;
-use enigo::{Direction, Enigo, InputError, Key, Keyboard, NewConError, Settings};
+use chrono::format::{Item, StrftimeItems};
+use enigo::{Axis, Button, Coordinate, Direction, Enigo, InputError, Key, Keyboard, Mouse, NewConError, Settings};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+use crate::clipboard_ring::ClipboardRing;
+use crate::config::{self, AppSettings, ClickButton, InjectionMethod, MacroDefinition};
 
 /// Error type for injection operations.
+///
+/// Variants let callers choose a notification (e.g. permission guidance
+/// vs. a transient failure) instead of parsing a message.
 #[derive(Debug)]
-pub struct InjectionError(pub String);
+pub enum InjectionError {
+    /// Failed to create the Enigo connection.
+    NewCon(NewConError),
+    /// Simulating input failed.
+    Input(InputError),
+    /// Failed to access the clipboard.
+    Clipboard(String),
+    /// The OS refused input simulation; accessibility permission is probably missing.
+    PermissionLikely,
+}
 
 impl std::fmt::Display for InjectionError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        match self {
+            InjectionError::NewCon(e) => write!(f, "Failed to create Enigo: {:?}", e),
+            InjectionError::Input(e) => write!(f, "Input error: {:?}", e),
+            InjectionError::Clipboard(e) => write!(f, "Clipboard error: {}", e),
+            InjectionError::PermissionLikely => {
+                write!(f, "Failed to create Enigo: {:?}", NewConError::NoPermission)
+            }
+        }
     }
 }
 
-impl std::error::Error for InjectionError {}
+impl std::error::Error for InjectionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            InjectionError::NewCon(e) => Some(e),
+            InjectionError::Input(e) => Some(e),
+            InjectionError::Clipboard(_) | InjectionError::PermissionLikely => None,
+        }
+    }
+}
 
 impl From<NewConError> for InjectionError {
     fn from(e: NewConError) -> Self {
-        InjectionError(format!("Failed to create Enigo: {:?}", e))
+        match e {
+            NewConError::NoPermission => InjectionError::PermissionLikely,
+            other => InjectionError::NewCon(other),
+        }
     }
 }
 
 impl From<InputError> for InjectionError {
     fn from(e: InputError) -> Self {
-        InjectionError(format!("Input error: {:?}", e))
+        InjectionError::Input(e)
     }
 }
 
@@ -42,8 +76,35 @@ impl From<InputError> for InjectionError {
 /// shortcuts).
 pub struct KeystrokeInjector {
     enigo: Enigo,
+    /// Keys pressed by `{KeyDown}` and not yet released, with press time.
+    /// Checked by the keydown watchdog (see `release_stale_keys`).
+    held_keys: Vec<(Key, Instant)>,
+    /// Text set by `{Copy}`/`{SetClip}`, for `{Paste N}`.
+    clipboard_ring: ClipboardRing,
+    /// How text segments are typed (`settings.injection_method`).
+    injection_method: InjectionMethod,
+    /// Button for `{Click}`/`{DoubleClick}` without a button name.
+    default_click_button: Button,
+    /// Gap between the clicks of `{DoubleClick}`, in milliseconds.
+    double_click_gap_ms: u64,
+    /// Save the clipboard before writing it (`settings.restore_clipboard`).
+    restore_clipboard: bool,
+    /// Clipboard contents to put back by `restore_saved_clipboard`.
+    clipboard_restore: ClipboardRestore,
+    /// Paste typed text through the clipboard (the macro's `inject_via_clipboard`).
+    inject_via_clipboard: bool,
 }
 
+/// How long the target app gets to read the clipboard after the paste
+/// shortcut, before the clipboard changes again.
+const PASTE_SETTLE_MS: u64 = 50;
+
+/// Modifier of the platform paste shortcut (Cmd+V / Ctrl+V).
+#[cfg(target_os = "macos")]
+const PASTE_MODIFIER: Key = Key::Meta;
+#[cfg(not(target_os = "macos"))]
+const PASTE_MODIFIER: Key = Key::Control;
+
 impl KeystrokeInjector {
     /// Create a new KeystrokeInjector.
     ///
@@ -71,7 +132,43 @@ impl KeystrokeInjector {
         };
 
         let enigo = Enigo::new(&settings)?;
-        Ok(Self { enigo })
+        Ok(Self {
+            enigo,
+            held_keys: Vec::new(),
+            clipboard_ring: ClipboardRing::default(),
+            injection_method: InjectionMethod::default(),
+            default_click_button: Button::Left,
+            double_click_gap_ms: 50,
+            restore_clipboard: false,
+            clipboard_restore: ClipboardRestore::default(),
+            inject_via_clipboard: false,
+        })
+    }
+
+    /// Apply the settings and per-macro options for running `macro_def`: how
+    /// text is typed (see `type_text` and `paste_text`), whether clipboard
+    /// contents are saved for `restore_saved_clipboard`, and the `{Click}`
+    /// default button and `{DoubleClick}` gap.
+    pub fn configure(&mut self, settings: &AppSettings, macro_def: &MacroDefinition) {
+        self.injection_method = settings.injection_method;
+        self.restore_clipboard = settings.restore_clipboard;
+        self.inject_via_clipboard = macro_def.inject_via_clipboard;
+        self.default_click_button = settings.default_click_button.into();
+        self.double_click_gap_ms = settings.double_click_gap_ms;
+    }
+
+    /// Put back the clipboard contents saved since the last call, if any.
+    ///
+    /// `execute_sequence` calls this itself; async executions call it when
+    /// they end, however they end.
+    pub fn restore_saved_clipboard(&mut self) {
+        if !self.clipboard_restore.is_saved() {
+            return;
+        }
+        match Clipboard::new() {
+            Ok(mut clipboard) => self.clipboard_restore.restore(&mut clipboard),
+            Err(e) => tracing::warn!("Could not restore clipboard: {}", e),
+        }
     }
 
     /// Release common modifier keys that might be held from hotkey activation.
@@ -94,18 +191,20 @@ impl KeystrokeInjector {
     ///
     /// * `segments` - The parsed macro segments to execute
     /// * `delay_ms` - Delay between keystrokes (0 for bulk typing)
+    /// * `jitter_ms` - Vary each delay by up to this much (see `jittered_delay`)
     ///
     /// # Example
     ///
     /// ```ignore
     /// let mut injector = KeystrokeInjector::new()?;
     /// let segments = parse_macro_sequence("Hello{Enter}World");
-    /// injector.execute_sequence(&segments, 0)?;
+    /// injector.execute_sequence(&segments, 0, 0)?;
     /// ```
     pub fn execute_sequence(
         &mut self,
         segments: &[MacroSegment],
         delay_ms: u64,
+        jitter_ms: u64,
     ) -> Result<(), InjectionError> {
         // Release any modifiers held from hotkey activation
         self.release_modifiers()?;
@@ -113,56 +212,28 @@ impl KeystrokeInjector {
         // Wait for modifiers to fully release (macOS needs longer)
         thread::sleep(Duration::from_millis(50));
 
-        for segment in segments {
-            match segment {
-                MacroSegment::Text(text) => {
-                    if delay_ms == 0 {
-                        self.enigo.text(text)?;
-                    } else {
-                        for c in text.chars() {
-                            self.enigo.text(&c.to_string())?;
-                            thread::sleep(Duration::from_millis(delay_ms));
-                        }
-                    }
-                }
-                MacroSegment::SpecialKey(key) => {
-                    self.enigo.key(*key, Direction::Click)?;
-                    if delay_ms > 0 {
-                        thread::sleep(Duration::from_millis(delay_ms));
-                    }
-                }
-                // New segment types - execution handled in Plan 08-02
-                MacroSegment::Delay(ms) => {
-                    thread::sleep(Duration::from_millis(*ms));
-                }
-                MacroSegment::KeyDown(key) => {
-                    self.enigo.key(*key, Direction::Press)?;
-                }
-                MacroSegment::KeyUp(key) => {
-                    self.enigo.key(*key, Direction::Release)?;
-                }
-                MacroSegment::Paste => {
-                    // Read clipboard and type contents
-                    let mut clipboard = Clipboard::new()
-                        .map_err(|e| InjectionError(format!("Clipboard error: {}", e)))?;
-
-                    match clipboard.get_text() {
-                        Ok(text) => {
-                            if delay_ms == 0 {
-                                self.enigo.text(&text)?;
-                            } else {
-                                for c in text.chars() {
-                                    self.enigo.text(&c.to_string())?;
-                                    thread::sleep(Duration::from_millis(delay_ms));
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            // Log but don't fail - clipboard might be empty or inaccessible
-                            eprintln!("Warning: Could not read clipboard: {}", e);
-                        }
-                    }
-                }
+        // Same per-character expansion as the async worker, so a macro types
+        // identically on either path (including Paste content). Pasted text
+        // goes in one piece per segment.
+        let expand_delay_ms = if self.inject_via_clipboard { 0 } else { delay_ms };
+        let segments = expand_double_clicks(segments.to_vec(), self.double_click_gap_ms);
+        let expanded = expand_segments(segments, expand_delay_ms);
+
+        // Put the clipboard back on failure too
+        let result = self.execute_expanded(&expanded, delay_ms, jitter_ms);
+        self.restore_saved_clipboard();
+        result
+    }
+
+    fn execute_expanded(&mut self, expanded: &[MacroSegment], delay_ms: u64, jitter_ms: u64) -> Result<(), InjectionError> {
+        for segment in expanded {
+            self.execute_single_segment(segment)?;
+
+            // Pace typed characters and special keys; modifier presses don't wait
+            if delay_ms > 0
+                && matches!(segment, MacroSegment::Text(_) | MacroSegment::SpecialKey(_))
+            {
+                thread::sleep(Duration::from_millis(jittered_delay(delay_ms, jitter_ms)));
             }
         }
 
@@ -195,7 +266,7 @@ impl KeystrokeInjector {
     pub fn execute_single_segment(&mut self, segment: &MacroSegment) -> Result<(), InjectionError> {
         match segment {
             MacroSegment::Text(text) => {
-                self.enigo.text(text)?;
+                self.type_str(text)?;
             }
             MacroSegment::SpecialKey(key) => {
                 self.enigo.key(*key, Direction::Click)?;
@@ -206,22 +277,104 @@ impl KeystrokeInjector {
             }
             MacroSegment::KeyDown(key) => {
                 self.enigo.key(*key, Direction::Press)?;
+                self.held_keys.retain(|(held, _)| held != key);
+                self.held_keys.push((*key, Instant::now()));
             }
             MacroSegment::KeyUp(key) => {
                 self.enigo.key(*key, Direction::Release)?;
+                self.held_keys.retain(|(held, _)| held != key);
             }
             MacroSegment::Paste => {
                 // Read clipboard and type contents
                 let mut clipboard = Clipboard::new()
-                    .map_err(|e| InjectionError(format!("Clipboard error: {}", e)))?;
+                    .map_err(|e| InjectionError::Clipboard(e.to_string()))?;
 
                 match clipboard.get_text() {
                     Ok(text) => {
-                        self.enigo.text(&text)?;
+                        self.type_str(&text)?;
                     }
                     Err(e) => {
                         // Log but don't fail - clipboard might be empty or inaccessible
-                        eprintln!("Warning: Could not read clipboard: {}", e);
+                        tracing::warn!("Could not read clipboard: {}", e);
+                    }
+                }
+            }
+            MacroSegment::SetClip(text) => {
+                // Remember it even if the system clipboard can't be written
+                self.clipboard_ring.push(text.clone());
+                let result = Clipboard::new().and_then(|mut cb| {
+                    if self.restore_clipboard {
+                        self.clipboard_restore.save(&mut cb);
+                    }
+                    cb.set_text(text.clone())
+                });
+                if let Err(e) = result {
+                    tracing::warn!("Could not set clipboard: {}", e);
+                }
+            }
+            MacroSegment::PasteSlot(slot) => match self.clipboard_ring.get(*slot) {
+                Some(text) => {
+                    let text = text.to_string();
+                    self.type_str(&text)?;
+                }
+                None => {
+                    // Missing slot types nothing rather than failing the macro
+                    tracing::warn!(
+                        "Clipboard history slot {} is empty ({} entries)",
+                        slot,
+                        self.clipboard_ring.len()
+                    );
+                }
+            },
+            MacroSegment::TypeFile(path) => {
+                if let Some(text) = read_type_file(path) {
+                    self.type_str(&text)?;
+                }
+            }
+            MacroSegment::Click(button) => {
+                let button = button.unwrap_or(self.default_click_button);
+                self.enigo.button(button, Direction::Click)?;
+            }
+            MacroSegment::ClickAt { button, x, y } => {
+                let button = button.unwrap_or(self.default_click_button);
+                self.enigo.move_mouse(*x, *y, Coordinate::Abs)?;
+                self.enigo.button(button, Direction::Click)?;
+            }
+            MacroSegment::MouseMove { x, y, relative } => {
+                let coordinate = if *relative { Coordinate::Rel } else { Coordinate::Abs };
+                self.enigo.move_mouse(*x, *y, coordinate)?;
+            }
+            MacroSegment::Scroll { dx, dy } => {
+                if *dx != 0 {
+                    self.enigo.scroll(*dx, Axis::Horizontal)?;
+                }
+                if *dy != 0 {
+                    self.enigo.scroll(*dy, Axis::Vertical)?;
+                }
+            }
+            MacroSegment::DoubleClick(button) => {
+                // Expanded with its gap before execution (see `expand_double_clicks`),
+                // so the gap never blocks the caller; unexpanded, click twice
+                let button = button.unwrap_or(self.default_click_button);
+                self.enigo.button(button, Direction::Click)?;
+                self.enigo.button(button, Direction::Click)?;
+            }
+            MacroSegment::Prompt(label) => {
+                // Answered by Execution::pump; nothing to ask with here
+                tracing::warn!("Skipping {{Prompt {}}} outside an async execution", label);
+            }
+            MacroSegment::Label(_) => {}
+            MacroSegment::DateTime(format) => {
+                self.type_str(&format_datetime(format))?;
+            }
+            MacroSegment::OneOf(options) => {
+                let text = choose_option(options, &mut fastrand::Rng::new()).to_string();
+                self.type_str(&text)?;
+            }
+            MacroSegment::Repeat { count, body } => {
+                for _ in 0..*count {
+                    for segment in body {
+                        self.execute_single_segment(segment)?;
                     }
                 }
             }
@@ -229,6 +382,43 @@ impl KeystrokeInjector {
         Ok(())
     }
 
+    /// Type `text`, or paste it if `inject_via_clipboard` is set.
+    fn type_str(&mut self, text: &str) -> Result<(), InjectionError> {
+        if self.inject_via_clipboard {
+            self.paste_text(text)
+        } else {
+            type_text(&mut self.enigo, text, self.injection_method).map_err(Into::into)
+        }
+    }
+
+    /// Put `text` on the clipboard and press the paste shortcut, then put the
+    /// clipboard back as it was (also if the shortcut fails).
+    ///
+    /// Much faster than typing for long, emoji or CJK text, and nothing is
+    /// dropped by apps that can't keep up with synthetic key events.
+    fn paste_text(&mut self, text: &str) -> Result<(), InjectionError> {
+        let mut clipboard = Clipboard::new().map_err(|e| InjectionError::Clipboard(e.to_string()))?;
+        let mut restore = ClipboardRestore::default();
+        restore.save(&mut clipboard);
+        if let Err(e) = clipboard.set_text(text) {
+            return Err(InjectionError::Clipboard(e.to_string()));
+        }
+
+        let result = self.press_paste_shortcut();
+        // Give the app time to read the clipboard before it changes back
+        thread::sleep(Duration::from_millis(PASTE_SETTLE_MS));
+        restore.restore(&mut clipboard);
+        result
+    }
+
+    fn press_paste_shortcut(&mut self) -> Result<(), InjectionError> {
+        self.enigo.key(PASTE_MODIFIER, Direction::Press)?;
+        let result = self.enigo.key(Key::Unicode('v'), Direction::Click);
+        // Never leave the modifier held
+        self.enigo.key(PASTE_MODIFIER, Direction::Release)?;
+        result.map_err(Into::into)
+    }
+
     /// Release modifiers and wait for them to take effect.
     ///
     /// Call once at the start of async execution before processing segments.
@@ -255,6 +445,32 @@ impl KeystrokeInjector {
         thread::sleep(Duration::from_millis(50));
         Ok(())
     }
+
+    /// Whether any `{KeyDown}` key is still waiting for its `{KeyUp}`.
+    pub fn has_held_keys(&self) -> bool {
+        !self.held_keys.is_empty()
+    }
+
+    /// Release keys that have been held by `{KeyDown}` for longer than `timeout`.
+    ///
+    /// Safety net for macros that stop (or forget) before their `{KeyUp}`,
+    /// which would otherwise leave e.g. Shift stuck down. Returns the released keys.
+    pub fn release_stale_keys(&mut self, timeout: Duration) -> Result<Vec<Key>, InjectionError> {
+        let stale = stale_keys(&self.held_keys, Instant::now(), timeout);
+        for key in &stale {
+            self.enigo.key(*key, Direction::Release)?;
+        }
+        self.held_keys.retain(|(held, _)| !stale.contains(held));
+        Ok(stale)
+    }
+}
+
+/// Keys from `held` that were pressed at least `timeout` before `now`.
+pub fn stale_keys(held: &[(Key, Instant)], now: Instant, timeout: Duration) -> Vec<Key> {
+    held.iter()
+        .filter(|(_, pressed_at)| now.saturating_duration_since(*pressed_at) >= timeout)
+        .map(|(key, _)| *key)
+        .collect()
 }
 
 /// A segment of a macro sequence.
@@ -272,6 +488,476 @@ pub enum MacroSegment {
     KeyUp(Key),
     /// Paste current clipboard contents as text.
     Paste,
+    /// Put text on the clipboard and push it onto the clipboard history.
+    SetClip(String),
+    /// Type the Nth most recent clipboard history entry (0 = latest).
+    PasteSlot(usize),
+    /// Type the current contents of a file, read when the macro runs.
+    TypeFile(String),
+    /// Click a mouse button (None: `settings.default_click_button`).
+    Click(Option<Button>),
+    /// Move the pointer to screen position (x, y), then click like `Click`.
+    ClickAt { button: Option<Button>, x: i32, y: i32 },
+    /// Move the pointer to screen position (x, y), or by (x, y) if `relative`.
+    MouseMove { x: i32, y: i32, relative: bool },
+    /// Scroll the wheel by notches: positive `dx` is right, positive `dy` down.
+    Scroll { dx: i32, dy: i32 },
+    /// Click a mouse button twice (None: `settings.default_click_button`).
+    DoubleClick(Option<Button>),
+    /// Ask for text with this label when the macro runs and type the answer.
+    /// Only the async execution path can ask (see `Execution::on_prompt`).
+    Prompt(String),
+    /// A named checkpoint; types nothing. `--run NAME --from LABEL` starts
+    /// after it (see `segments_after_label`).
+    Label(String),
+    /// `{Repeat N}...{EndRepeat}`: run `body` `count` times. Unrolled by
+    /// `flatten_repeats` before execution.
+    Repeat { count: u32, body: Vec<MacroSegment> },
+    /// Type the current local date/time in this strftime format
+    /// (`{Date %Y-%m-%d}`, `{Time %H:%M}`), formatted when it runs.
+    DateTime(String),
+    /// `{OneOf a|b|c}`: type one of these texts, picked at random each run
+    /// (see `choose_option`).
+    OneOf(Vec<String>),
+}
+
+/// DSL text that parses back to the segment (see `parse_macro_sequence`).
+///
+/// Braces in text are doubled. A key with no DSL name, or a command argument
+/// containing `}`, can't be written faithfully and comes back as literal text.
+impl std::fmt::Display for MacroSegment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MacroSegment::Text(text) => write!(f, "{}", text.replace('{', "{{").replace('}', "}}")),
+            MacroSegment::SpecialKey(Key::Unicode(c)) => write!(f, "{}", MacroSegment::Text(c.to_string())),
+            MacroSegment::SpecialKey(key) => match special_key_name(*key) {
+                Some(name) => write!(f, "{{{}}}", name),
+                None => write!(f, "{{{:?}}}", key),
+            },
+            MacroSegment::Delay(ms) => write!(f, "{{Delay {}}}", ms),
+            MacroSegment::KeyDown(key) => write!(f, "{{KeyDown {}}}", modifier_key_name(*key)),
+            MacroSegment::KeyUp(key) => write!(f, "{{KeyUp {}}}", modifier_key_name(*key)),
+            MacroSegment::Paste => write!(f, "{{Paste}}"),
+            MacroSegment::SetClip(text) => write!(f, "{{SetClip {}}}", text),
+            MacroSegment::PasteSlot(slot) => write!(f, "{{Paste {}}}", slot),
+            MacroSegment::TypeFile(path) => write!(f, "{{Type {}}}", path),
+            MacroSegment::Click(None) => write!(f, "{{Click}}"),
+            MacroSegment::Click(Some(button)) => write!(f, "{{Click {}}}", button_name(*button)),
+            MacroSegment::ClickAt { button: None, x, y } => write!(f, "{{Click {} {}}}", x, y),
+            MacroSegment::ClickAt { button: Some(button), x, y } => {
+                write!(f, "{{Click {} {} {}}}", button_name(*button), x, y)
+            }
+            MacroSegment::MouseMove { x, y, relative } => {
+                write!(f, "{{MouseMove {} {}{}}}", x, y, if *relative { " rel" } else { "" })
+            }
+            MacroSegment::Scroll { dx, dy } => write!(f, "{{Scroll {} {}}}", dx, dy),
+            MacroSegment::DoubleClick(None) => write!(f, "{{DoubleClick}}"),
+            MacroSegment::DoubleClick(Some(button)) => write!(f, "{{DoubleClick {}}}", button_name(*button)),
+            MacroSegment::Prompt(label) => write!(f, "{{Prompt {}}}", label),
+            MacroSegment::Label(name) => write!(f, "{{Label {}}}", name),
+            MacroSegment::Repeat { count, body } => {
+                write!(f, "{{Repeat {}}}{}{{EndRepeat}}", count, segments_to_dsl(body))
+            }
+            MacroSegment::DateTime(format) => write!(f, "{{Date {}}}", format),
+            MacroSegment::OneOf(options) => {
+                let options: Vec<String> = options.iter().map(|option| escape_option(option)).collect();
+                write!(f, "{{OneOf {}}}", options.join("|"))
+            }
+        }
+    }
+}
+
+/// DSL text for a whole segment list (see `MacroSegment`'s `Display`).
+pub fn segments_to_dsl(segments: &[MacroSegment]) -> String {
+    segments.iter().map(ToString::to_string).collect()
+}
+
+/// Pick the text a `{OneOf}` types this run.
+///
+/// Callers pass `fastrand::Rng::new()`; tests seed it for a repeatable pick.
+pub fn choose_option<'a>(options: &'a [String], rng: &mut fastrand::Rng) -> &'a str {
+    if options.is_empty() {
+        return "";
+    }
+    &options[rng.usize(..options.len())]
+}
+
+/// Split a `{OneOf}` argument on `|` into its options.
+///
+/// Inside `{OneOf}` a backslash escapes `|`, `{`, `}` and itself; any other
+/// backslash is kept as typed. Options aren't trimmed, so spaces around `|`
+/// are typed.
+fn parse_one_of(arg: &str) -> Vec<String> {
+    let mut options = vec![String::new()];
+    let mut chars = arg.chars();
+    while let Some(c) = chars.next() {
+        let option = options.last_mut().expect("never empty");
+        match c {
+            '|' => options.push(String::new()),
+            '\\' => match chars.next() {
+                Some(escaped @ ('|' | '{' | '}' | '\\')) => option.push(escaped),
+                Some(other) => {
+                    option.push('\\');
+                    option.push(other);
+                }
+                None => option.push('\\'),
+            },
+            c => option.push(c),
+        }
+    }
+    options
+}
+
+/// A `{OneOf}` option as written in the DSL, inverse of `parse_one_of`.
+fn escape_option(option: &str) -> String {
+    let mut escaped = String::with_capacity(option.len());
+    for c in option.chars() {
+        if matches!(c, '|' | '{' | '}' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Whether the command being read is a `{OneOf}`, whose backslash escapes
+/// keep an escaped `}` from closing it.
+fn is_one_of_command(key_name: &str) -> bool {
+    key_name.get(..6).is_some_and(|prefix| prefix.eq_ignore_ascii_case("oneof "))
+}
+
+/// The current local date/time in `format` (checked by `parse_command`).
+pub fn format_datetime(format: &str) -> String {
+    chrono::Local::now().format(format).to_string()
+}
+
+/// Whether chrono understands every specifier in `format`.
+fn is_valid_datetime_format(format: &str) -> bool {
+    !format.is_empty() && StrftimeItems::new(format).all(|item| !matches!(item, Item::Error))
+}
+
+/// Most times a `{Repeat N}` block may run; a larger N is typed literally.
+const MAX_REPEAT_COUNT: u32 = 10_000;
+
+/// Most keystrokes a macro may unroll to by the end of a `{Repeat}` block.
+/// Blocks are unrolled in memory before anything is typed, so one that
+/// goes over (e.g. two nested `{Repeat 10000}`) is typed literally.
+const MAX_UNROLLED_LEN: usize = 100_000;
+
+/// Keystrokes `segments` unroll to: one per typed character or command,
+/// with `{Repeat}` bodies multiplied out.
+fn unrolled_len(segments: &[MacroSegment]) -> usize {
+    segments.iter().fold(0, |total: usize, segment| {
+        let len = match segment {
+            MacroSegment::Text(text) => text.chars().count(),
+            MacroSegment::Repeat { count, body } => unrolled_len(body).saturating_mul(*count as usize),
+            _ => 1,
+        };
+        total.saturating_add(len)
+    })
+}
+
+/// Unroll `{Repeat}` blocks, including nested ones, into plain segments.
+///
+/// The parser keeps blocks within `MAX_REPEAT_COUNT` and `MAX_UNROLLED_LEN`,
+/// so the result stays small.
+pub fn flatten_repeats(segments: Vec<MacroSegment>) -> Vec<MacroSegment> {
+    let mut flat = Vec::with_capacity(segments.len());
+    for segment in segments {
+        match segment {
+            MacroSegment::Repeat { count, body } => {
+                let body = flatten_repeats(body);
+                for _ in 0..count {
+                    flat.extend(body.iter().cloned());
+                }
+            }
+            other => flat.push(other),
+        }
+    }
+    flat
+}
+
+/// A `--from` label that doesn't appear in the macro.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelNotFound {
+    pub label: String,
+    /// Labels the macro does have, in order.
+    pub available: Vec<String>,
+}
+
+impl std::fmt::Display for LabelNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "No {{Label {}}} in macro", self.label)?;
+        if self.available.is_empty() {
+            write!(f, " (it has no labels)")
+        } else {
+            write!(f, " (labels: {})", self.available.join(", "))
+        }
+    }
+}
+
+impl std::error::Error for LabelNotFound {}
+
+/// Index of the first segment after `{Label label}`.
+///
+/// Labels match exactly (case-sensitive); if a label appears more than once
+/// the first one wins. A label at the very end yields `segments.len()`.
+pub fn label_start(segments: &[MacroSegment], label: &str) -> Result<usize, LabelNotFound> {
+    let label = label.trim();
+    segments
+        .iter()
+        .position(|s| matches!(s, MacroSegment::Label(name) if name == label))
+        .map(|i| i + 1)
+        .ok_or_else(|| LabelNotFound {
+            label: label.to_string(),
+            available: segments
+                .iter()
+                .filter_map(|s| match s {
+                    MacroSegment::Label(name) => Some(name.clone()),
+                    _ => None,
+                })
+                .collect(),
+        })
+}
+
+/// The segments to run when starting from `{Label label}`.
+pub fn segments_after_label<'a>(
+    segments: &'a [MacroSegment],
+    label: &str,
+) -> Result<&'a [MacroSegment], LabelNotFound> {
+    label_start(segments, label).map(|start| &segments[start..])
+}
+
+/// Map a `{Click}` button name to an enigo Button.
+///
+/// Returns `None` for unknown names.
+fn button_from_name(name: &str) -> Option<Button> {
+    match name.to_lowercase().as_str() {
+        "left" => Some(Button::Left),
+        "right" => Some(Button::Right),
+        "middle" => Some(Button::Middle),
+        _ => None,
+    }
+}
+
+impl From<ClickButton> for Button {
+    fn from(button: ClickButton) -> Self {
+        match button {
+            ClickButton::Left => Button::Left,
+            ClickButton::Right => Button::Right,
+            ClickButton::Middle => Button::Middle,
+        }
+    }
+}
+
+/// The `{Click}` name of an enigo Button, inverse of `button_from_name`.
+fn button_name(button: Button) -> &'static str {
+    match button {
+        Button::Right => "right",
+        Button::Middle => "middle",
+        _ => "left",
+    }
+}
+
+/// What `{DoubleClick}` runs: a click, a `gap_ms` pause, and a second click.
+pub fn double_click_segments(button: Option<Button>, gap_ms: u64) -> Vec<MacroSegment> {
+    vec![
+        MacroSegment::Click(button),
+        MacroSegment::Delay(gap_ms),
+        MacroSegment::Click(button),
+    ]
+}
+
+/// Replace each `{DoubleClick}` with `double_click_segments`, so the gap is
+/// an ordinary (cancellable) `Delay`.
+///
+/// Used by both `execute_sequence` and the async execution worker.
+pub fn expand_double_clicks(segments: Vec<MacroSegment>, gap_ms: u64) -> Vec<MacroSegment> {
+    segments
+        .into_iter()
+        .flat_map(|segment| match segment {
+            MacroSegment::DoubleClick(button) => double_click_segments(button, gap_ms),
+            segment => vec![segment],
+        })
+        .collect()
+}
+
+/// Type `text` with the given method.
+///
+/// `Text` hands the whole string to the platform. `Unicode` clicks one
+/// `Key::Unicode` per character so the active keyboard layout can't turn
+/// characters into dead-key or AltGr combinations; newlines and tabs become
+/// Enter and Tab presses and carriage returns are dropped.
+pub fn type_text<K: Keyboard>(keyboard: &mut K, text: &str, method: InjectionMethod) -> Result<(), InputError> {
+    match method {
+        InjectionMethod::Text => keyboard.text(text),
+        InjectionMethod::Unicode => {
+            for c in text.chars() {
+                match c {
+                    '\r' => {}
+                    '\n' => keyboard.key(Key::Return, Direction::Click)?,
+                    '\t' => keyboard.key(Key::Tab, Direction::Click)?,
+                    c => keyboard.key(Key::Unicode(c), Direction::Click)?,
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Text access to a clipboard, so `ClipboardRestore` works (and is tested)
+/// without the system clipboard.
+pub trait ClipboardText {
+    /// Current text; None if the clipboard is empty or holds something else.
+    fn text(&mut self) -> Option<String>;
+    /// Replace the contents with `text`, or clear them for None.
+    fn set(&mut self, text: Option<&str>) -> Result<(), String>;
+}
+
+impl ClipboardText for Clipboard {
+    fn text(&mut self) -> Option<String> {
+        self.get_text().ok()
+    }
+
+    fn set(&mut self, text: Option<&str>) -> Result<(), String> {
+        match text {
+            Some(text) => self.set_text(text),
+            None => self.clear(),
+        }
+        .map_err(|e| e.to_string())
+    }
+}
+
+/// Clipboard contents saved before something first writes the clipboard
+/// while a macro runs, to put back when it ends (`settings.restore_clipboard`).
+///
+/// Everything that writes the clipboard while typing saves through here, so
+/// one restore covers them all.
+#[derive(Debug, Default)]
+pub struct ClipboardRestore {
+    /// Some once saved; the inner None means there was no text to save.
+    saved: Option<Option<String>>,
+}
+
+impl ClipboardRestore {
+    /// Save the current contents, unless already saved since the last `restore`.
+    pub fn save(&mut self, clipboard: &mut impl ClipboardText) {
+        if self.saved.is_none() {
+            self.saved = Some(clipboard.text());
+        }
+    }
+
+    pub fn is_saved(&self) -> bool {
+        self.saved.is_some()
+    }
+
+    /// Put the saved contents back (clearing the clipboard if it held no
+    /// text), then forget them. Does nothing if nothing was saved.
+    pub fn restore(&mut self, clipboard: &mut impl ClipboardText) {
+        if let Some(saved) = self.saved.take() {
+            if let Err(e) = clipboard.set(saved.as_deref()) {
+                tracing::warn!("Could not restore clipboard: {}", e);
+            }
+        }
+    }
+}
+
+/// Read a `{Type path}` file for typing.
+///
+/// Relative paths are resolved against the config directory. A missing or
+/// unreadable file is skipped with a warning rather than failing the macro.
+pub fn read_type_file(path: &str) -> Option<String> {
+    let resolved = config::resolve_config_relative(path, &config::config_dir());
+    match std::fs::read_to_string(&resolved) {
+        Ok(text) => Some(text),
+        Err(e) => {
+            tracing::warn!("Could not read {} for {{Type}}: {}", resolved.display(), e);
+            None
+        }
+    }
+}
+
+/// `delay_ms` moved randomly by up to `jitter_ms` either way, so typing
+/// doesn't tick at a robotic constant rate. Never below 0; exactly `delay_ms`
+/// when `jitter_ms` is 0.
+pub fn jittered_delay(delay_ms: u64, jitter_ms: u64) -> u64 {
+    if jitter_ms == 0 {
+        return delay_ms;
+    }
+    fastrand::u64(delay_ms.saturating_sub(jitter_ms)..=delay_ms.saturating_add(jitter_ms))
+}
+
+/// Expand segments for per-character typing when a delay is set.
+///
+/// With `delay_ms > 0`, Text segments are split into one segment per character
+/// and Paste, TypeFile, DateTime and OneOf segments are replaced by the
+/// clipboard or file contents, the formatted time or the picked option, also
+/// split per character, so the delay applies between every typed character. A
+/// Paste after a SetClip uses the text that SetClip put on the clipboard. With
+/// `delay_ms == 0` the segments are returned unchanged.
+///
+/// Used by both `execute_sequence` and the async execution worker.
+pub fn expand_segments(segments: Vec<MacroSegment>, delay_ms: u64) -> Vec<MacroSegment> {
+    expand_segments_with(segments, delay_ms, read_clipboard_text)
+}
+
+/// The clipboard's text, as `{Paste}` expansion reads it.
+pub fn read_clipboard_text() -> Option<String> {
+    match Clipboard::new().and_then(|mut cb| cb.get_text()) {
+        Ok(text) => Some(text),
+        Err(e) => {
+            tracing::warn!("Could not read clipboard for delayed paste: {}", e);
+            None
+        }
+    }
+}
+
+/// `expand_segments` with a caller-supplied clipboard reader.
+///
+/// A reader returning `None` (clipboard empty or unavailable) skips the paste.
+pub fn expand_segments_with<F>(
+    segments: Vec<MacroSegment>,
+    delay_ms: u64,
+    mut read_clipboard: F,
+) -> Vec<MacroSegment>
+where
+    F: FnMut() -> Option<String>,
+{
+    let segments = flatten_repeats(segments);
+    if delay_ms == 0 {
+        return segments;
+    }
+    let mut rng = fastrand::Rng::new();
+
+    let per_char = |text: &str| {
+        text.chars()
+            .map(|c| MacroSegment::Text(c.to_string()))
+            .collect::<Vec<_>>()
+    };
+
+    // Expansion runs before anything is typed, so a {Paste} after a
+    // {SetClip} would otherwise read the clipboard as it was before the macro
+    let mut set_clip: Option<String> = None;
+
+    segments
+        .into_iter()
+        .flat_map(|seg| match seg {
+            MacroSegment::Text(text) => per_char(&text),
+            MacroSegment::SetClip(text) => {
+                set_clip = Some(text.clone());
+                vec![MacroSegment::SetClip(text)]
+            }
+            MacroSegment::Paste => set_clip
+                .clone()
+                .or_else(&mut read_clipboard)
+                .map(|text| per_char(&text))
+                .unwrap_or_default(),
+            MacroSegment::TypeFile(path) => read_type_file(&path).map(|text| per_char(&text)).unwrap_or_default(),
+            MacroSegment::DateTime(format) => per_char(&format_datetime(&format)),
+            MacroSegment::OneOf(options) => per_char(choose_option(&options, &mut rng)),
+            other => vec![other],
+        })
+        .collect()
 }
 
 /// Parse a macro string with escape sequences into segments.
@@ -302,6 +988,15 @@ pub enum MacroSegment {
 /// - `{KeyDown key}` - Press and hold a modifier key
 /// - `{KeyUp key}` - Release a modifier key
 /// - `{Paste}` - Paste clipboard contents
+/// - `{Type path}` - Type a file's contents (read each time the macro runs)
+/// - `{Click}` / `{Click button}` - Click the default (or left/right/middle) mouse button
+/// - `{DoubleClick}` / `{DoubleClick button}` - Double-click
+/// - `{Prompt label}` - Ask for text when the macro runs and type it
+/// - `{Label name}` - Checkpoint for `--run NAME --from name`; types nothing
+/// - `{Repeat N}...{EndRepeat}` - Run the enclosed part N times (may be nested,
+///   at most `MAX_REPEAT_COUNT` times and `MAX_UNROLLED_LEN` keystrokes in all)
+/// - `{Date format}` / `{Time format}` - Type the current date/time (strftime format)
+/// - `{OneOf a|b|c}` - Type one of the options, picked at random each run
 ///
 /// ## Escape Sequences
 /// - `{{` - Literal `{` character
@@ -320,7 +1015,67 @@ pub enum MacroSegment {
 /// // Returns: [Text("{braces}")]
 /// ```
 pub fn parse_macro_sequence(input: &str) -> Vec<MacroSegment> {
+    parse_with_issues(input).0
+}
+
+/// Something lenient parsing silently types as literal text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DslIssue {
+    /// `{...}` that isn't a known key or command (e.g. `{Entr}`, `{Delay}`).
+    UnknownCommand(String),
+    /// `{` with no matching `}`.
+    UnclosedBrace,
+    /// `}` that isn't part of a command or a `}}` escape.
+    LoneCloseBrace,
+    /// `{Repeat N}` (as written) with no `{EndRepeat}` after it.
+    UnclosedRepeat(String),
+    /// `{EndRepeat}` with no `{Repeat N}` before it.
+    UnmatchedEndRepeat,
+    /// `{Repeat N}` (as written) with N over `MAX_REPEAT_COUNT`.
+    RepeatCountTooLarge(String),
+    /// `{Repeat N}` (as written) whose block takes the macro over
+    /// `MAX_UNROLLED_LEN` keystrokes.
+    RepeatTooLong(String),
+}
+
+impl std::fmt::Display for DslIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DslIssue::UnknownCommand(command) => write!(f, "unknown command {{{}}}", command),
+            DslIssue::UnclosedBrace => write!(f, "unclosed '{{' (use {{{{ for a literal brace)"),
+            DslIssue::LoneCloseBrace => write!(f, "unmatched '}}' (use }}}} for a literal brace)"),
+            DslIssue::UnclosedRepeat(open) => write!(f, "{} without {{EndRepeat}}", open),
+            DslIssue::UnmatchedEndRepeat => write!(f, "{{EndRepeat}} without {{Repeat N}}"),
+            DslIssue::RepeatCountTooLarge(open) => {
+                write!(f, "{} repeats more than {} times", open, MAX_REPEAT_COUNT)
+            }
+            DslIssue::RepeatTooLong(open) => {
+                write!(f, "{} unrolls to more than {} keystrokes", open, MAX_UNROLLED_LEN)
+            }
+        }
+    }
+}
+
+/// Parse like `parse_macro_sequence`, but reject input it would type literally
+/// by accident (unknown commands, unescaped braces).
+///
+/// Use for macros with strict parsing enabled; lenient parsing suits
+/// brace-heavy text like JSON templates.
+pub fn parse_macro_sequence_strict(input: &str) -> Result<Vec<MacroSegment>, Vec<DslIssue>> {
+    let (segments, issues) = parse_with_issues(input);
+    if issues.is_empty() {
+        Ok(segments)
+    } else {
+        Err(issues)
+    }
+}
+
+/// Shared parser: segments plus whatever was treated as literal by fallback.
+fn parse_with_issues(input: &str) -> (Vec<MacroSegment>, Vec<DslIssue>) {
     let mut segments = Vec::new();
+    let mut issues = Vec::new();
+    // `{Repeat N}` blocks not closed yet, innermost last
+    let mut open_repeats: Vec<OpenRepeat> = Vec::new();
     let mut current_text = String::new();
     let mut chars = input.chars().peekable();
 
@@ -344,21 +1099,67 @@ pub fn parse_macro_sequence(input: &str) -> Vec<MacroSegment> {
                     break;
                 }
                 key_name.push(chars.next().unwrap());
+                // `\}` inside {OneOf} is part of an option (see `parse_one_of`)
+                if next == '\\' && is_one_of_command(&key_name) {
+                    key_name.extend(chars.next());
+                }
             }
 
             if found_close {
-                // Try to parse as command
-                if let Some(segment) = parse_command(&key_name) {
+                if let Some(count) = parse_repeat_count(&key_name) {
+                    // Collect the body in a fresh list until {EndRepeat}
+                    flush_text(&mut current_text, &mut segments);
+                    open_repeats.push(OpenRepeat {
+                        count,
+                        raw: format!("{{{}}}", key_name),
+                        before: std::mem::take(&mut segments),
+                    });
+                } else if key_name.eq_ignore_ascii_case("endrepeat") {
+                    match open_repeats.pop() {
+                        Some(open) => {
+                            flush_text(&mut current_text, &mut segments);
+                            let body = std::mem::replace(&mut segments, open.before);
+                            // Everything before the block counts toward the limit
+                            let total = open_repeats
+                                .iter()
+                                .map(|outer| unrolled_len(&outer.before))
+                                .fold(unrolled_len(&segments), usize::saturating_add)
+                                .saturating_add(unrolled_len(&body).saturating_mul(open.count as usize));
+                            let too_large = if open.count > MAX_REPEAT_COUNT {
+                                Some(DslIssue::RepeatCountTooLarge(open.raw.clone()))
+                            } else if total > MAX_UNROLLED_LEN {
+                                Some(DslIssue::RepeatTooLong(open.raw.clone()))
+                            } else {
+                                None
+                            };
+                            match too_large {
+                                // Typed literally, like an unclosed block
+                                Some(issue) => {
+                                    issues.push(issue);
+                                    push_literal_block(&mut segments, &open.raw, body);
+                                    push_text(&mut segments, "{EndRepeat}");
+                                }
+                                None => segments.push(MacroSegment::Repeat { count: open.count, body }),
+                            }
+                        }
+                        None => {
+                            issues.push(DslIssue::UnmatchedEndRepeat);
+                            current_text.push_str("{EndRepeat}");
+                        }
+                    }
+                } else if let Some(segment) = parse_command(&key_name) {
                     flush_text(&mut current_text, &mut segments);
                     segments.push(segment);
                 } else {
                     // Unknown command - treat as literal
+                    issues.push(DslIssue::UnknownCommand(key_name.clone()));
                     current_text.push('{');
                     current_text.push_str(&key_name);
                     current_text.push('}');
                 }
             } else {
                 // Unclosed brace - treat as literal
+                issues.push(DslIssue::UnclosedBrace);
                 current_text.push('{');
                 current_text.push_str(&key_name);
             }
@@ -370,6 +1171,7 @@ pub fn parse_macro_sequence(input: &str) -> Vec<MacroSegment> {
                 continue;
             }
             // Lone '}' - treat as literal
+            issues.push(DslIssue::LoneCloseBrace);
             current_text.push(c);
         } else {
             current_text.push(c);
@@ -379,7 +1181,51 @@ pub fn parse_macro_sequence(input: &str) -> Vec<MacroSegment> {
     // Flush any remaining text
     flush_text(&mut current_text, &mut segments);
 
-    segments
+    // A {Repeat N} never closed is literal text, like an unknown command
+    while let Some(open) = open_repeats.pop() {
+        issues.push(DslIssue::UnclosedRepeat(open.raw.clone()));
+        let body = std::mem::replace(&mut segments, open.before);
+        push_literal_block(&mut segments, &open.raw, body);
+    }
+
+    (segments, issues)
+}
+
+/// Append a `{Repeat N}` opener as text, followed by its body.
+fn push_literal_block(segments: &mut Vec<MacroSegment>, raw: &str, body: Vec<MacroSegment>) {
+    push_text(segments, raw);
+    for segment in body {
+        match segment {
+            MacroSegment::Text(text) => push_text(segments, &text),
+            other => segments.push(other),
+        }
+    }
+}
+
+/// A `{Repeat N}` whose `{EndRepeat}` hasn't been reached yet.
+struct OpenRepeat {
+    count: u32,
+    /// The command as written, for typing it literally if it's never closed.
+    raw: String,
+    /// Segments parsed before the `{Repeat}`.
+    before: Vec<MacroSegment>,
+}
+
+/// The count of a `{Repeat N}` command; None unless N is a positive number.
+fn parse_repeat_count(key_name: &str) -> Option<u32> {
+    let (command, arg) = key_name.split_once(' ')?;
+    if !command.eq_ignore_ascii_case("repeat") {
+        return None;
+    }
+    arg.trim().parse::<u32>().ok().filter(|&count| count > 0)
+}
+
+/// Append text, merging it into a trailing Text segment.
+fn push_text(segments: &mut Vec<MacroSegment>, text: &str) {
+    match segments.last_mut() {
+        Some(MacroSegment::Text(last)) => last.push_str(text),
+        _ => segments.push(MacroSegment::Text(text.to_string())),
+    }
 }
 
 /// Flush accumulated text to the segments vector.
@@ -416,17 +1262,92 @@ fn parse_command(key_name: &str) -> Option<MacroSegment> {
                 .map(MacroSegment::KeyUp)
         }
         "paste" => {
-            // {Paste} - no argument needed
-            Some(MacroSegment::Paste)
+            // {Paste} types the clipboard; {Paste N} types a history slot
+            match arg.and_then(|s| s.parse::<usize>().ok()) {
+                Some(slot) => Some(MacroSegment::PasteSlot(slot)),
+                None => Some(MacroSegment::Paste),
+            }
         }
-        _ => {
-            // Try as a special key (Enter, Tab, etc.)
-            special_key_from_name(key_name).map(MacroSegment::SpecialKey)
+        "click" => {
+            // {Click}, {Click button}, {Click x y} or {Click button x y}; an
+            // unknown button name or bad coordinates make it literal text
+            let words: Vec<&str> = arg.map_or_else(Vec::new, |s| s.split_whitespace().collect());
+            match words[..] {
+                [] => Some(MacroSegment::Click(None)),
+                [name] => Some(MacroSegment::Click(Some(button_from_name(name)?))),
+                [x, y] => Some(MacroSegment::ClickAt { button: None, x: x.parse().ok()?, y: y.parse().ok()? }),
+                [name, x, y] => Some(MacroSegment::ClickAt {
+                    button: Some(button_from_name(name)?),
+                    x: x.parse().ok()?,
+                    y: y.parse().ok()?,
+                }),
+                _ => None,
+            }
         }
-    }
-}
-
-/// Map a key name to an enigo Key variant.
+        "mousemove" => {
+            // {MouseMove x y} / {MouseMove x y rel}; anything else is literal text
+            let words: Vec<&str> = arg.map_or_else(Vec::new, |s| s.split_whitespace().collect());
+            let (x, y, relative) = match words[..] {
+                [x, y] => (x, y, false),
+                [x, y, keyword] if keyword.eq_ignore_ascii_case("rel") => (x, y, true),
+                _ => return None,
+            };
+            Some(MacroSegment::MouseMove { x: x.parse().ok()?, y: y.parse().ok()?, relative })
+        }
+        "scroll" => {
+            // {Scroll dx dy} - requires two whole numbers of notches
+            let words: Vec<&str> = arg.map_or_else(Vec::new, |s| s.split_whitespace().collect());
+            let [dx, dy] = words[..] else {
+                return None;
+            };
+            Some(MacroSegment::Scroll { dx: dx.parse().ok()?, dy: dy.parse().ok()? })
+        }
+        "doubleclick" => {
+            // {DoubleClick} / {DoubleClick button}; an unknown button name is literal text
+            let button = match arg {
+                Some(name) => Some(button_from_name(name)?),
+                None => None,
+            };
+            Some(MacroSegment::DoubleClick(button))
+        }
+        "type" => {
+            // {Type path} - requires a path
+            arg.filter(|s| !s.is_empty())
+                .map(|s| MacroSegment::TypeFile(s.to_string()))
+        }
+        "prompt" => {
+            // {Prompt label} - requires a label
+            arg.filter(|s| !s.is_empty())
+                .map(|s| MacroSegment::Prompt(s.to_string()))
+        }
+        "label" => {
+            // {Label name} - requires a name
+            arg.filter(|s| !s.is_empty())
+                .map(|s| MacroSegment::Label(s.to_string()))
+        }
+        "date" | "time" => {
+            // {Date format} / {Time format} - requires a valid strftime format
+            arg.filter(|s| is_valid_datetime_format(s))
+                .map(|s| MacroSegment::DateTime(s.to_string()))
+        }
+        "oneof" => {
+            // {OneOf a|b|c} - requires at least one character of options
+            arg.filter(|s| !s.is_empty())
+                .map(|s| MacroSegment::OneOf(parse_one_of(s)))
+        }
+        "copy" | "setclip" => {
+            // {Copy text} / {SetClip text} - requires text
+            arg.filter(|s| !s.is_empty())
+                .map(|s| MacroSegment::SetClip(s.to_string()))
+        }
+        _ => {
+            // Try as a special key (Enter, Tab, etc.)
+            special_key_from_name(key_name).map(MacroSegment::SpecialKey)
+        }
+    }
+}
+
+/// Map a key name to an enigo Key variant.
 ///
 /// Returns `None` for unknown key names.
 fn special_key_from_name(name: &str) -> Option<Key> {
@@ -462,6 +1383,54 @@ fn special_key_from_name(name: &str) -> Option<Key> {
     }
 }
 
+/// The `{Name}` of a special key, inverse of `special_key_from_name`.
+fn special_key_name(key: Key) -> Option<&'static str> {
+    let name = match key {
+        Key::Return => "Enter",
+        Key::Tab => "Tab",
+        Key::Escape => "Escape",
+        Key::Backspace => "Backspace",
+        Key::Delete => "Delete",
+        Key::UpArrow => "Up",
+        Key::DownArrow => "Down",
+        Key::LeftArrow => "Left",
+        Key::RightArrow => "Right",
+        Key::Home => "Home",
+        Key::End => "End",
+        Key::PageUp => "PageUp",
+        Key::PageDown => "PageDown",
+        Key::Space => "Space",
+        Key::F1 => "F1",
+        Key::F2 => "F2",
+        Key::F3 => "F3",
+        Key::F4 => "F4",
+        Key::F5 => "F5",
+        Key::F6 => "F6",
+        Key::F7 => "F7",
+        Key::F8 => "F8",
+        Key::F9 => "F9",
+        Key::F10 => "F10",
+        Key::F11 => "F11",
+        Key::F12 => "F12",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// The `{KeyDown}` name of a modifier key, inverse of `modifier_key_from_name`.
+fn modifier_key_name(key: Key) -> &'static str {
+    match key {
+        Key::Shift => "Shift",
+        Key::Alt => "Alt",
+        Key::Meta => "Meta",
+        Key::LControl => "LCtrl",
+        Key::RControl => "RCtrl",
+        Key::LShift => "LShift",
+        Key::RShift => "RShift",
+        _ => "Ctrl",
+    }
+}
+
 /// Map a modifier key name to an enigo Key variant.
 ///
 /// Returns `None` for unknown modifier key names.
@@ -819,4 +1788,813 @@ mod tests {
             ]
         );
     }
+
+    // === Segment expansion ===
+
+    #[test]
+    fn test_expand_segments_no_delay_unchanged() {
+        let segments = vec![MacroSegment::Text("ab".to_string()), MacroSegment::Paste];
+        let expanded = expand_segments_with(segments.clone(), 0, || Some("xy".to_string()));
+        assert_eq!(expanded, segments);
+    }
+
+    #[test]
+    fn test_expand_segments_paste_per_char_with_delay() {
+        let segments = parse_macro_sequence("a{Paste}{Enter}");
+        let expanded = expand_segments_with(segments, 10, || Some("xy".to_string()));
+        assert_eq!(
+            expanded,
+            vec![
+                MacroSegment::Text("a".to_string()),
+                MacroSegment::Text("x".to_string()),
+                MacroSegment::Text("y".to_string()),
+                MacroSegment::SpecialKey(Key::Return),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_type_file() {
+        assert_eq!(
+            parse_macro_sequence("a{Type notes/today.txt}b"),
+            vec![
+                MacroSegment::Text("a".to_string()),
+                MacroSegment::TypeFile("notes/today.txt".to_string()),
+                MacroSegment::Text("b".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse_macro_sequence("{type My Notes.txt}"),
+            vec![MacroSegment::TypeFile("My Notes.txt".to_string())]
+        );
+        // A path is required
+        assert_eq!(
+            parse_macro_sequence("{Type}"),
+            vec![MacroSegment::Text("{Type}".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_expand_type_file_reads_current_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("snippet.txt");
+        let text = format!("{{Type {}}}", path.display());
+
+        std::fs::write(&path, "hi").unwrap();
+        let expanded = expand_segments_with(parse_macro_sequence(&text), 10, || None);
+        assert_eq!(
+            expanded,
+            vec![MacroSegment::Text("h".to_string()), MacroSegment::Text("i".to_string())]
+        );
+
+        // Read again on the next run, not cached from parsing
+        std::fs::write(&path, "!").unwrap();
+        let expanded = expand_segments_with(parse_macro_sequence(&text), 10, || None);
+        assert_eq!(expanded, vec![MacroSegment::Text("!".to_string())]);
+
+        assert_eq!(read_type_file(&path.display().to_string()).as_deref(), Some("!"));
+    }
+
+    #[test]
+    fn test_expand_missing_type_file_skips() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing.txt");
+        let segments = vec![
+            MacroSegment::Text("a".to_string()),
+            MacroSegment::TypeFile(missing.display().to_string()),
+        ];
+        let expanded = expand_segments_with(segments, 10, || None);
+        assert_eq!(expanded, vec![MacroSegment::Text("a".to_string())]);
+    }
+
+    #[test]
+    fn test_expand_segments_paste_after_set_clip_uses_set_text() {
+        let segments = parse_macro_sequence("{SetClip x}{Paste}");
+        let expanded = expand_segments_with(segments, 5, || Some("stale".to_string()));
+        assert_eq!(
+            expanded,
+            vec![
+                MacroSegment::SetClip("x".to_string()),
+                MacroSegment::Text("x".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_expand_segments_unreadable_clipboard_skips_paste() {
+        let segments = parse_macro_sequence("a{Paste}b");
+        let expanded = expand_segments_with(segments, 10, || None);
+        assert_eq!(
+            expanded,
+            vec![
+                MacroSegment::Text("a".to_string()),
+                MacroSegment::Text("b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stale_keys_timeout() {
+        let start = Instant::now();
+        let held = vec![
+            (Key::Shift, start),
+            (Key::Control, start + Duration::from_millis(800)),
+        ];
+        let timeout = Duration::from_millis(1000);
+
+        // Nothing has been held long enough yet
+        assert!(stale_keys(&held, start + Duration::from_millis(500), timeout).is_empty());
+
+        // Shift crossed the timeout, Control hasn't
+        assert_eq!(
+            stale_keys(&held, start + Duration::from_millis(1000), timeout),
+            vec![Key::Shift]
+        );
+
+        assert_eq!(
+            stale_keys(&held, start + Duration::from_millis(2000), timeout),
+            vec![Key::Shift, Key::Control]
+        );
+    }
+
+    #[test]
+    fn test_injection_error_from_new_con() {
+        let err = InjectionError::from(NewConError::NoPermission);
+        assert!(matches!(err, InjectionError::PermissionLikely));
+        assert_eq!(err.to_string(), "Failed to create Enigo: NoPermission");
+
+        let err = InjectionError::from(NewConError::Reply);
+        assert!(matches!(err, InjectionError::NewCon(NewConError::Reply)));
+        assert_eq!(err.to_string(), "Failed to create Enigo: Reply");
+    }
+
+    #[test]
+    fn test_injection_error_from_input() {
+        let err = InjectionError::from(InputError::NoEmptyKeycodes);
+        assert!(matches!(err, InjectionError::Input(InputError::NoEmptyKeycodes)));
+        assert_eq!(err.to_string(), "Input error: NoEmptyKeycodes");
+    }
+
+    #[test]
+    fn test_injection_error_clipboard_display() {
+        let err = InjectionError::Clipboard("unavailable".to_string());
+        assert_eq!(err.to_string(), "Clipboard error: unavailable");
+    }
+
+    #[test]
+    fn test_parse_paste_slot() {
+        assert_eq!(parse_macro_sequence("{Paste 0}"), vec![MacroSegment::PasteSlot(0)]);
+        assert_eq!(parse_macro_sequence("{paste 3}"), vec![MacroSegment::PasteSlot(3)]);
+    }
+
+    #[test]
+    fn test_parse_copy_and_setclip() {
+        assert_eq!(
+            parse_macro_sequence("{Copy hello world}{SetClip x}"),
+            vec![
+                MacroSegment::SetClip("hello world".to_string()),
+                MacroSegment::SetClip("x".to_string()),
+            ]
+        );
+        // Text is required
+        assert_eq!(
+            parse_macro_sequence("{Copy}"),
+            vec![MacroSegment::Text("{Copy}".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_strict_parse_rejects_literal_fallbacks() {
+        assert_eq!(
+            parse_macro_sequence_strict("Hi{Enter}{{ok}}"),
+            Ok(parse_macro_sequence("Hi{Enter}{{ok}}"))
+        );
+
+        assert_eq!(
+            parse_macro_sequence_strict("{Entr}"),
+            Err(vec![DslIssue::UnknownCommand("Entr".to_string())])
+        );
+        assert_eq!(
+            parse_macro_sequence_strict("{\"a\": 1}"),
+            Err(vec![DslIssue::UnknownCommand("\"a\": 1".to_string())])
+        );
+        assert_eq!(parse_macro_sequence_strict("a{b"), Err(vec![DslIssue::UnclosedBrace]));
+        assert_eq!(parse_macro_sequence_strict("a}b"), Err(vec![DslIssue::LoneCloseBrace]));
+    }
+
+    /// Keyboard double that records what it was asked to type.
+    #[derive(Default)]
+    struct RecordingKeyboard {
+        texts: Vec<String>,
+        keys: Vec<(Key, Direction)>,
+    }
+
+    impl Keyboard for RecordingKeyboard {
+        fn fast_text(&mut self, text: &str) -> enigo::InputResult<Option<()>> {
+            self.texts.push(text.to_string());
+            Ok(Some(()))
+        }
+
+        fn key(&mut self, key: Key, direction: Direction) -> enigo::InputResult<()> {
+            self.keys.push((key, direction));
+            Ok(())
+        }
+
+        fn raw(&mut self, _keycode: u16, _direction: Direction) -> enigo::InputResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_type_text_method() {
+        let mut keyboard = RecordingKeyboard::default();
+        type_text(&mut keyboard, "é€", InjectionMethod::Text).unwrap();
+        assert_eq!(keyboard.texts, vec!["é€"]);
+        assert!(keyboard.keys.is_empty());
+    }
+
+    #[test]
+    fn test_type_unicode_per_character() {
+        let mut keyboard = RecordingKeyboard::default();
+        type_text(&mut keyboard, "é€\r\n", InjectionMethod::Unicode).unwrap();
+        assert!(keyboard.texts.is_empty());
+        assert_eq!(
+            keyboard.keys,
+            vec![
+                (Key::Unicode('é'), Direction::Click),
+                (Key::Unicode('€'), Direction::Click),
+                (Key::Return, Direction::Click),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_button_from_name() {
+        assert_eq!(button_from_name("left"), Some(Button::Left));
+        assert_eq!(button_from_name("Right"), Some(Button::Right));
+        assert_eq!(button_from_name("MIDDLE"), Some(Button::Middle));
+        assert_eq!(button_from_name("back"), None);
+    }
+
+    #[test]
+    fn test_parse_click() {
+        assert_eq!(
+            parse_macro_sequence("{Click}{click right}{DoubleClick}{doubleclick middle}"),
+            vec![
+                MacroSegment::Click(None),
+                MacroSegment::Click(Some(Button::Right)),
+                MacroSegment::DoubleClick(None),
+                MacroSegment::DoubleClick(Some(Button::Middle)),
+            ]
+        );
+        // Unknown button names stay literal
+        assert_eq!(
+            parse_macro_sequence("{Click thumb}"),
+            vec![MacroSegment::Text("{Click thumb}".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_parse_click_at() {
+        assert_eq!(
+            parse_macro_sequence("{Click left 100 200}{click 5 -30}{Click  Right  0 0 }"),
+            vec![
+                MacroSegment::ClickAt { button: Some(Button::Left), x: 100, y: 200 },
+                // Negative coordinates reach monitors left of or above the main one
+                MacroSegment::ClickAt { button: None, x: 5, y: -30 },
+                MacroSegment::ClickAt { button: Some(Button::Right), x: 0, y: 0 },
+            ]
+        );
+        // Bad coordinates, a missing one, extra words or an unknown button stay literal
+        for literal in [
+            "{Click left 100}",
+            "{Click left 100 2x}",
+            "{Click left 1.5 2}",
+            "{Click left 1 2 3}",
+            "{Click thumb 100 200}",
+            "{Click 99999999999 0}",
+            "{DoubleClick left 100 200}",
+        ] {
+            assert_eq!(
+                parse_macro_sequence(literal),
+                vec![MacroSegment::Text(literal.to_string())],
+                "{}",
+                literal
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_mouse_move_and_scroll() {
+        assert_eq!(
+            parse_macro_sequence("{MouseMove 640 480}{mousemove 50 0 rel}{MouseMove -20 -5 REL}"),
+            vec![
+                MacroSegment::MouseMove { x: 640, y: 480, relative: false },
+                MacroSegment::MouseMove { x: 50, y: 0, relative: true },
+                MacroSegment::MouseMove { x: -20, y: -5, relative: true },
+            ]
+        );
+        assert_eq!(
+            parse_macro_sequence("{Scroll 0 -3}{scroll 2 0}"),
+            vec![
+                MacroSegment::Scroll { dx: 0, dy: -3 },
+                MacroSegment::Scroll { dx: 2, dy: 0 },
+            ]
+        );
+        // Non-numeric or missing values and unknown keywords stay literal
+        for literal in [
+            "{MouseMove}",
+            "{MouseMove 50}",
+            "{MouseMove 50 x}",
+            "{MouseMove 50 0 abs}",
+            "{MouseMove 50 0 rel more}",
+            "{MouseMove --5 0}",
+            "{Scroll}",
+            "{Scroll -3}",
+            "{Scroll 0 down}",
+            "{Scroll 0 1.5}",
+            "{Scroll 0 -3 0}",
+        ] {
+            assert_eq!(
+                parse_macro_sequence(literal),
+                vec![MacroSegment::Text(literal.to_string())],
+                "{}",
+                literal
+            );
+        }
+    }
+
+    #[test]
+    fn test_segments_to_dsl_round_trip() {
+        let dsl = "Hi {{there}}{Enter}{Tab}{Delay 250}{KeyDown Ctrl}c{KeyUp Ctrl}{KeyDown RShift}{KeyUp RShift}\
+                   {Paste}{Paste 2}{SetClip ticket-42}{Type notes.txt}{Click}{Click right}{Click 5 -30}\
+                   {Click middle 10 20}{MouseMove 50 0 rel}{MouseMove 640 480}{Scroll 0 -3}{DoubleClick}\
+                   {DoubleClick right}{Prompt Ticket}{Label step 2}{Repeat 3}x{F5}{EndRepeat}{Date %Y-%m-%d}";
+        let segments = parse_macro_sequence(dsl);
+        assert!(!segments.iter().any(|s| matches!(s, MacroSegment::Text(t) if t.contains("Delay"))));
+        let written = segments_to_dsl(&segments);
+        assert_eq!(parse_macro_sequence(&written), segments);
+        assert!(written.starts_with("Hi {{there}}{Enter}{Tab}{Delay 250}{KeyDown Ctrl}c{KeyUp Ctrl}"), "{}", written);
+
+        // Aliases come back in one spelling
+        assert_eq!(segments_to_dsl(&parse_macro_sequence("{return}{esc}{pgdn}{Time %H:%M}")), "{Enter}{Escape}{PageDown}{Date %H:%M}");
+        assert_eq!(MacroSegment::SpecialKey(Key::Unicode('{')).to_string(), "{{");
+    }
+
+    #[test]
+    fn test_double_click_segments() {
+        assert_eq!(
+            double_click_segments(Some(Button::Right), 80),
+            vec![
+                MacroSegment::Click(Some(Button::Right)),
+                MacroSegment::Delay(80),
+                MacroSegment::Click(Some(Button::Right)),
+            ]
+        );
+        assert_eq!(
+            expand_double_clicks(parse_macro_sequence("a{DoubleClick}b"), 30),
+            vec![
+                MacroSegment::Text("a".to_string()),
+                MacroSegment::Click(None),
+                MacroSegment::Delay(30),
+                MacroSegment::Click(None),
+                MacroSegment::Text("b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_prompt() {
+        assert_eq!(
+            parse_macro_sequence("Dear {Prompt Customer name},{Enter}"),
+            vec![
+                MacroSegment::Text("Dear ".to_string()),
+                MacroSegment::Prompt("Customer name".to_string()),
+                MacroSegment::Text(",".to_string()),
+                MacroSegment::SpecialKey(Key::Return),
+            ]
+        );
+        // A label is required
+        assert_eq!(
+            parse_macro_sequence("{Prompt}"),
+            vec![MacroSegment::Text("{Prompt}".to_string())]
+        );
+        // Prompts are not expanded per character
+        assert_eq!(
+            expand_segments_with(vec![MacroSegment::Prompt("x".to_string())], 10, || None),
+            vec![MacroSegment::Prompt("x".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_parse_label() {
+        assert_eq!(
+            parse_macro_sequence("Intro{Label step 2}Body{Enter}"),
+            vec![
+                MacroSegment::Text("Intro".to_string()),
+                MacroSegment::Label("step 2".to_string()),
+                MacroSegment::Text("Body".to_string()),
+                MacroSegment::SpecialKey(Key::Return),
+            ]
+        );
+        // A label needs a name
+        assert_eq!(
+            parse_macro_sequence("{Label}"),
+            vec![MacroSegment::Text("{Label}".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_label_start() {
+        let segments = parse_macro_sequence("a{Label one}b{Label two}c{Label one}d{Label end}");
+
+        assert_eq!(label_start(&segments, "one"), Ok(2));
+        assert_eq!(label_start(&segments, " two "), Ok(4));
+        assert_eq!(label_start(&segments, "end"), Ok(segments.len()));
+        assert_eq!(
+            segments_after_label(&segments, "two").unwrap().first(),
+            Some(&MacroSegment::Text("c".to_string()))
+        );
+        assert!(segments_after_label(&segments, "end").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_label_start_missing() {
+        let segments = parse_macro_sequence("a{Label one}b{Label two}c");
+        let err = label_start(&segments, "One").unwrap_err();
+        assert_eq!(
+            err,
+            LabelNotFound {
+                label: "One".to_string(),
+                available: vec!["one".to_string(), "two".to_string()],
+            }
+        );
+        assert_eq!(err.to_string(), "No {Label One} in macro (labels: one, two)");
+
+        let err = label_start(&parse_macro_sequence("plain"), "x").unwrap_err();
+        assert_eq!(err.to_string(), "No {Label x} in macro (it has no labels)");
+    }
+
+    #[test]
+    fn test_parse_repeat() {
+        assert_eq!(
+            parse_macro_sequence("Rows:{Repeat 3}{Tab}x{EndRepeat}{Enter}"),
+            vec![
+                MacroSegment::Text("Rows:".to_string()),
+                MacroSegment::Repeat {
+                    count: 3,
+                    body: vec![
+                        MacroSegment::SpecialKey(Key::Tab),
+                        MacroSegment::Text("x".to_string()),
+                    ],
+                },
+                MacroSegment::SpecialKey(Key::Return),
+            ]
+        );
+        // Case-insensitive, like other commands
+        assert_eq!(
+            parse_macro_sequence("{repeat 2}a{endrepeat}"),
+            vec![MacroSegment::Repeat { count: 2, body: vec![MacroSegment::Text("a".to_string())] }]
+        );
+    }
+
+    #[test]
+    fn test_parse_nested_repeat() {
+        let segments = parse_macro_sequence("{Repeat 2}a{Repeat 3}b{EndRepeat}c{EndRepeat}");
+        assert_eq!(
+            segments,
+            vec![MacroSegment::Repeat {
+                count: 2,
+                body: vec![
+                    MacroSegment::Text("a".to_string()),
+                    MacroSegment::Repeat { count: 3, body: vec![MacroSegment::Text("b".to_string())] },
+                    MacroSegment::Text("c".to_string()),
+                ],
+            }]
+        );
+
+        let typed: String = flatten_repeats(segments)
+            .into_iter()
+            .map(|s| match s {
+                MacroSegment::Text(text) => text,
+                other => panic!("unexpected segment {:?}", other),
+            })
+            .collect();
+        assert_eq!(typed, "abbbcabbbc");
+    }
+
+    #[test]
+    fn test_parse_unbalanced_repeat_is_literal() {
+        // Missing {EndRepeat}
+        assert_eq!(
+            parse_macro_sequence("a{Repeat 2}b{Tab}c"),
+            vec![
+                MacroSegment::Text("a{Repeat 2}b".to_string()),
+                MacroSegment::SpecialKey(Key::Tab),
+                MacroSegment::Text("c".to_string()),
+            ]
+        );
+        // Only the unclosed outer block is literal
+        assert_eq!(
+            parse_macro_sequence("{Repeat 2}{Repeat 3}b{EndRepeat}"),
+            vec![
+                MacroSegment::Text("{Repeat 2}".to_string()),
+                MacroSegment::Repeat { count: 3, body: vec![MacroSegment::Text("b".to_string())] },
+            ]
+        );
+        // {EndRepeat} without {Repeat}
+        assert_eq!(
+            parse_macro_sequence("x{EndRepeat}y"),
+            vec![MacroSegment::Text("x{EndRepeat}y".to_string())]
+        );
+
+        let issues = parse_macro_sequence_strict("{Repeat 2}a").unwrap_err();
+        assert_eq!(issues, vec![DslIssue::UnclosedRepeat("{Repeat 2}".to_string())]);
+        assert_eq!(issues[0].to_string(), "{Repeat 2} without {EndRepeat}");
+        assert_eq!(parse_macro_sequence_strict("{EndRepeat}").unwrap_err(), vec![DslIssue::UnmatchedEndRepeat]);
+    }
+
+    #[test]
+    fn test_parse_repeat_bad_count_is_literal() {
+        for input in ["{Repeat 0}a{EndRepeat}", "{Repeat x}a{EndRepeat}", "{Repeat}a{EndRepeat}"] {
+            let (segments, issues) = parse_with_issues(input);
+            assert!(
+                segments.iter().all(|s| matches!(s, MacroSegment::Text(_))),
+                "{} should be literal: {:?}",
+                input,
+                segments
+            );
+            // The opener is unknown, so the {EndRepeat} is unmatched
+            assert_eq!(issues.len(), 2);
+            assert_eq!(issues[1], DslIssue::UnmatchedEndRepeat);
+        }
+    }
+
+    #[test]
+    fn test_parse_repeat_over_limit_is_literal() {
+        // Count over MAX_REPEAT_COUNT
+        let (segments, issues) = parse_with_issues("{Repeat 4000000000}x{EndRepeat}");
+        assert_eq!(segments, vec![MacroSegment::Text("{Repeat 4000000000}x{EndRepeat}".to_string())]);
+        assert_eq!(issues, vec![DslIssue::RepeatCountTooLarge("{Repeat 4000000000}".to_string())]);
+        assert_eq!(issues[0].to_string(), "{Repeat 4000000000} repeats more than 10000 times");
+
+        // Each count is allowed, but together they unroll to 10^8 keystrokes;
+        // the inner block stays a valid repeat inside the literal outer one
+        let (segments, issues) = parse_with_issues("{Repeat 10000}{Repeat 10000}x{EndRepeat}{EndRepeat}");
+        assert_eq!(
+            segments,
+            vec![
+                MacroSegment::Text("{Repeat 10000}".to_string()),
+                MacroSegment::Repeat { count: 10_000, body: vec![MacroSegment::Text("x".to_string())] },
+                MacroSegment::Text("{EndRepeat}".to_string()),
+            ]
+        );
+        assert_eq!(issues, vec![DslIssue::RepeatTooLong("{Repeat 10000}".to_string())]);
+        assert!(unrolled_len(&segments) <= MAX_UNROLLED_LEN + 100);
+
+        // Blocks that are fine alone still count toward one total
+        let block = "{Repeat 10000}abcdefghij{EndRepeat}";
+        let (_, issues) = parse_with_issues(&block.repeat(2));
+        assert_eq!(issues, vec![DslIssue::RepeatTooLong("{Repeat 10000}".to_string())]);
+
+        // Right at the limit is allowed
+        assert!(parse_macro_sequence_strict(block).is_ok());
+        assert_eq!(unrolled_len(&parse_macro_sequence(block)), MAX_UNROLLED_LEN);
+    }
+
+    #[test]
+    fn test_expand_segments_unrolls_repeat() {
+        let segments = parse_macro_sequence("{Repeat 2}ab{Enter}{EndRepeat}");
+        assert_eq!(
+            expand_segments_with(segments.clone(), 0, || None),
+            vec![
+                MacroSegment::Text("ab".to_string()),
+                MacroSegment::SpecialKey(Key::Return),
+                MacroSegment::Text("ab".to_string()),
+                MacroSegment::SpecialKey(Key::Return),
+            ]
+        );
+        assert_eq!(expand_segments_with(segments, 5, || None).len(), 6);
+    }
+
+    #[test]
+    fn test_parse_datetime() {
+        assert_eq!(
+            parse_macro_sequence("Log {Date %Y-%m-%d} {Time %H:%M}:"),
+            vec![
+                MacroSegment::Text("Log ".to_string()),
+                MacroSegment::DateTime("%Y-%m-%d".to_string()),
+                MacroSegment::Text(" ".to_string()),
+                MacroSegment::DateTime("%H:%M".to_string()),
+                MacroSegment::Text(":".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse_macro_sequence("{date %d.%m.%Y}"),
+            vec![MacroSegment::DateTime("%d.%m.%Y".to_string())]
+        );
+        // No format, or one chrono doesn't understand, is typed literally
+        for text in ["{Date}", "{Time }", "{Date %Q}", "{Time %H:%}"] {
+            assert_eq!(parse_macro_sequence(text), vec![MacroSegment::Text(text.to_string())]);
+        }
+    }
+
+    #[test]
+    fn test_datetime_formatted_at_execution() {
+        let format = "%Y-%m-%d";
+        assert_eq!(format_datetime(format), chrono::Local::now().format(format).to_string());
+
+        // Expanded per character like other typed text
+        let expanded = expand_segments_with(parse_macro_sequence("{Date %Y-%m-%d}"), 5, || None);
+        let typed: String = expanded
+            .iter()
+            .map(|s| match s {
+                MacroSegment::Text(t) => t.as_str(),
+                other => panic!("unexpected segment {:?}", other),
+            })
+            .collect();
+        assert_eq!(typed, chrono::Local::now().format(format).to_string());
+    }
+
+    /// In-memory stand-in for the system clipboard.
+    #[derive(Default)]
+    struct FakeClipboard(Option<String>);
+
+    impl ClipboardText for FakeClipboard {
+        fn text(&mut self) -> Option<String> {
+            self.0.clone()
+        }
+
+        fn set(&mut self, text: Option<&str>) -> Result<(), String> {
+            self.0 = text.map(str::to_string);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_clipboard_restore_keeps_first_snapshot() {
+        let mut clipboard = FakeClipboard(Some("before".to_string()));
+        let mut restore = ClipboardRestore::default();
+
+        restore.save(&mut clipboard);
+        clipboard.0 = Some("first copy".to_string());
+        // A second write in the same macro doesn't replace the snapshot
+        restore.save(&mut clipboard);
+        clipboard.0 = Some("second copy".to_string());
+
+        restore.restore(&mut clipboard);
+        assert_eq!(clipboard.0.as_deref(), Some("before"));
+        assert!(!restore.is_saved());
+
+        // Nothing saved since: restoring leaves the clipboard alone
+        clipboard.0 = Some("later".to_string());
+        restore.restore(&mut clipboard);
+        assert_eq!(clipboard.0.as_deref(), Some("later"));
+    }
+
+    #[test]
+    fn test_clipboard_restore_clears_when_no_text() {
+        let mut clipboard = FakeClipboard(None);
+        let mut restore = ClipboardRestore::default();
+        restore.save(&mut clipboard);
+        clipboard.0 = Some("copied".to_string());
+        restore.restore(&mut clipboard);
+        assert_eq!(clipboard.0, None);
+    }
+
+    #[test]
+    fn test_jittered_delay_stays_in_range() {
+        assert!((0..1000).all(|_| jittered_delay(50, 0) == 50));
+
+        let samples: Vec<u64> = (0..1000).map(|_| jittered_delay(50, 20)).collect();
+        assert!(samples.iter().all(|d| (30..=70).contains(d)));
+        // Actually varies (1000 draws from 41 values)
+        assert!(samples.iter().any(|&d| d != samples[0]));
+
+        // Lower bound clamped at zero
+        let samples: Vec<u64> = (0..1000).map(|_| jittered_delay(10, 50)).collect();
+        assert!(samples.iter().all(|d| *d <= 60));
+        assert!(jittered_delay(0, 0) == 0);
+    }
+
+    fn one_of(options: &[&str]) -> MacroSegment {
+        MacroSegment::OneOf(options.iter().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn test_parse_one_of() {
+        assert_eq!(
+            parse_macro_sequence("{OneOf Thanks!|Cheers|Best}{Enter}"),
+            vec![one_of(&["Thanks!", "Cheers", "Best"]), MacroSegment::SpecialKey(Key::Return)]
+        );
+        // Case-insensitive; spaces around `|` and empty options are kept
+        assert_eq!(parse_macro_sequence("{oneof a | b||c}"), vec![one_of(&["a ", " b", "", "c"])]);
+        assert_eq!(parse_macro_sequence("{OneOf only}"), vec![one_of(&["only"])]);
+        // No options is literal text
+        assert_eq!(parse_macro_sequence("{OneOf}"), vec![MacroSegment::Text("{OneOf}".to_string())]);
+        assert_eq!(
+            parse_macro_sequence_strict("{OneOf }"),
+            Err(vec![DslIssue::UnknownCommand("OneOf ".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_parse_one_of_escapes() {
+        assert_eq!(
+            parse_macro_sequence(r"{OneOf a\|b|\{x\}|back\\slash|C:\temp}!"),
+            vec![one_of(&["a|b", "{x}", r"back\slash", r"C:\temp"]), MacroSegment::Text("!".to_string())]
+        );
+        // A trailing backslash is kept
+        assert_eq!(parse_one_of(r"x\"), vec![r"x\".to_string()]);
+        // Backslashes only escape inside {OneOf}
+        assert_eq!(
+            parse_macro_sequence(r"{Type C:\notes\}x"),
+            vec![MacroSegment::TypeFile(r"C:\notes\".to_string()), MacroSegment::Text("x".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_one_of_round_trip() {
+        let segment = one_of(&["a|b", "{x}", r"back\slash", "plain text"]);
+        let written = segment.to_string();
+        assert_eq!(written, r"{OneOf a\|b|\{x\}|back\\slash|plain text}");
+        assert_eq!(parse_macro_sequence(&written), vec![segment]);
+    }
+
+    #[test]
+    fn test_choose_option_with_seed() {
+        let options: Vec<String> = ["Thanks!", "Cheers", "Best"].iter().map(|s| s.to_string()).collect();
+
+        // Same seed, same picks
+        let mut first = fastrand::Rng::with_seed(42);
+        let mut second = fastrand::Rng::with_seed(42);
+        let picks: Vec<&str> = (0..20).map(|_| choose_option(&options, &mut first)).collect();
+        let again: Vec<&str> = (0..20).map(|_| choose_option(&options, &mut second)).collect();
+        assert_eq!(picks, again);
+
+        // Every option gets picked eventually, and nothing else
+        let mut rng = fastrand::Rng::with_seed(7);
+        let mut seen: Vec<&str> = (0..200).map(|_| choose_option(&options, &mut rng)).collect();
+        seen.sort_unstable();
+        seen.dedup();
+        assert_eq!(seen, vec!["Best", "Cheers", "Thanks!"]);
+
+        assert_eq!(choose_option(&options[..1], &mut rng), "Thanks!");
+        assert_eq!(choose_option(&[], &mut rng), "");
+    }
+
+    #[test]
+    fn test_expand_one_of_per_char() {
+        let expanded = expand_segments_with(vec![one_of(&["ab", "cd"])], 10, || None);
+        assert!(
+            expanded == vec![MacroSegment::Text("a".to_string()), MacroSegment::Text("b".to_string())]
+                || expanded == vec![MacroSegment::Text("c".to_string()), MacroSegment::Text("d".to_string())],
+            "{:?}",
+            expanded
+        );
+        // Without a delay it is picked when injected
+        assert_eq!(expand_segments_with(vec![one_of(&["ab", "cd"])], 0, || None), vec![one_of(&["ab", "cd"])]);
+    }
+
+    /// Golden-file parser cases in `tests/cases/*.txt`.
+    ///
+    /// Each file holds a macro string, a line with just `---`, then the
+    /// expected segments one per line: each segment's `Display`, quoted and
+    /// escaped like a Rust string (`"Hi "`, `"{Enter}"`). Add a file to add
+    /// a case. The crate has no library target, so this lives here rather
+    /// than in `tests/`.
+    #[test]
+    fn test_golden_cases() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("cases");
+        let mut paths: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+            .collect();
+        paths.sort();
+        assert!(!paths.is_empty(), "no cases in {}", dir.display());
+
+        let mut failures = Vec::new();
+        for path in &paths {
+            let content = std::fs::read_to_string(path).unwrap().replace("\r\n", "\n");
+            let Some((input, expected)) = content.split_once("\n---\n") else {
+                failures.push(format!("{}: missing --- line", path.display()));
+                continue;
+            };
+            let expected: Vec<&str> = expected.lines().filter(|line| !line.is_empty()).collect();
+            let actual: Vec<String> = parse_macro_sequence(input)
+                .iter()
+                .map(|segment| format!("{:?}", segment.to_string()))
+                .collect();
+            if actual != expected {
+                failures.push(format!(
+                    "{}:\n  expected: {:?}\n  actual:   {:?}",
+                    path.display(),
+                    expected,
+                    actual
+                ));
+            }
+        }
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
 }
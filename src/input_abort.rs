@@ -0,0 +1,299 @@
+//! Stop a running macro when the user takes over the keyboard or mouse.
+//!
+//! With `settings.abort_on_user_input` on, a macro running in the background
+//! (delays, long text) is stopped as soon as the user presses a key, clicks,
+//! scrolls or moves the mouse, like `stop_on_focus_loss`. Input comes from the
+//! shared global input hook (see `input_hook`), with the same caveats as
+//! abbreviations:
+//! - macOS: requires Input Monitoring permission
+//! - Linux: X11 only (no Wayland support)
+//! - Windows: input in elevated apps isn't seen
+//!
+//! The hook also sees KeyBlast's own keystrokes and clicks, and rdev doesn't
+//! say which events were injected. The main thread reports when it injects a
+//! segment, and events arriving meanwhile or within `INJECTION_GRACE` after
+//! are taken as KeyBlast's own (see `is_injected`). So user input during a
+//! stretch of instant typing only counts once the typing pauses.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rdev::{EventType, Key};
+
+use crate::input_hook;
+
+/// How long after injecting a segment its events may still arrive at the hook.
+pub const INJECTION_GRACE: Duration = Duration::from_millis(150);
+
+/// Mouse movement (in pixels) small enough to be a bumped desk, not the user.
+pub const MOUSE_MOVE_TOLERANCE: f64 = 5.0;
+
+/// Whether an event seen at `now` is KeyBlast's own input: a segment is
+/// being injected, or the last one finished less than `grace` ago.
+pub fn is_injected(now: Instant, injecting: bool, last_injection: Option<Instant>, grace: Duration) -> bool {
+    injecting || last_injection.is_some_and(|at| now.saturating_duration_since(at) < grace)
+}
+
+/// Decides from input events whether the user interrupted a macro.
+///
+/// Tracks held keys and the pointer position between executions too, so
+/// keys still held from the triggering hotkey (and their auto-repeat) and
+/// the pointer's resting place don't count as new input.
+#[derive(Debug, Default)]
+pub struct AbortState {
+    armed: bool,
+    /// Keys currently down, real or injected.
+    held: HashSet<Key>,
+    /// Last known pointer position.
+    pointer: Option<(f64, f64)>,
+    /// Where the pointer is expected to be: at arming or after KeyBlast moved it.
+    anchor: Option<(f64, f64)>,
+}
+
+impl AbortState {
+    /// Start watching for user input (a macro started running).
+    pub fn arm(&mut self) {
+        self.armed = true;
+        self.anchor = self.pointer;
+    }
+
+    /// Stop watching (the macro finished or was stopped).
+    pub fn disarm(&mut self) {
+        self.armed = false;
+    }
+
+    #[cfg(test)]
+    pub fn is_armed(&self) -> bool {
+        self.armed
+    }
+
+    /// Feed an input event and whether it was injected by KeyBlast.
+    ///
+    /// Returns true if it is user input that should stop the macro; after
+    /// that the state is disarmed, so one interruption stops it once.
+    pub fn handle(&mut self, event: &EventType, injected: bool) -> bool {
+        let user_input = match *event {
+            EventType::KeyPress(key) => {
+                // Auto-repeat of a held key isn't a new press
+                let new_press = self.held.insert(key);
+                new_press && !injected
+            }
+            EventType::KeyRelease(key) => {
+                self.held.remove(&key);
+                false
+            }
+            EventType::MouseMove { x, y } => {
+                self.pointer = Some((x, y));
+                if injected || !self.armed {
+                    self.anchor = Some((x, y));
+                    false
+                } else {
+                    let anchor = *self.anchor.get_or_insert((x, y));
+                    (x - anchor.0).hypot(y - anchor.1) > MOUSE_MOVE_TOLERANCE
+                }
+            }
+            EventType::ButtonPress(_) | EventType::Wheel { .. } => !injected,
+            EventType::ButtonRelease(_) => false,
+        };
+
+        if user_input && self.armed {
+            self.armed = false;
+            return true;
+        }
+        false
+    }
+}
+
+/// State shared between the hook thread and the main thread.
+#[derive(Debug, Default)]
+struct Shared {
+    state: AbortState,
+    /// A segment is being injected right now.
+    injecting: bool,
+    /// When the last injected segment finished.
+    last_injection: Option<Instant>,
+}
+
+/// Global input listener that reports user input while armed.
+///
+/// The listener runs for the rest of the process lifetime (see `input_hook`);
+/// with the setting off it is simply never armed. Clones share the listener.
+#[derive(Clone)]
+pub struct AbortListener {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl AbortListener {
+    /// Start tracking input, unarmed.
+    ///
+    /// `on_abort` is called from the listener thread when user input is seen
+    /// while armed; forward it to the main thread (e.g. via the event loop proxy).
+    pub fn start<F>(on_abort: F) -> Self
+    where
+        F: Fn() + Send + 'static,
+    {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let thread_shared = Arc::clone(&shared);
+
+        input_hook::subscribe(move |event| {
+            let Ok(mut shared) = thread_shared.lock() else {
+                return;
+            };
+            let injected = is_injected(Instant::now(), shared.injecting, shared.last_injection, INJECTION_GRACE);
+            if shared.state.handle(&event.event_type, injected) {
+                tracing::debug!("User input during macro: {:?}", event.event_type);
+                on_abort();
+            }
+        });
+
+        Self { shared }
+    }
+
+    /// Report user input from now on (a macro started running in the background).
+    pub fn arm(&self) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.state.arm();
+        }
+    }
+
+    /// Stop reporting user input.
+    pub fn disarm(&self) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.state.disarm();
+        }
+    }
+
+    /// KeyBlast is about to inject a segment; its events aren't user input.
+    pub fn begin_injection(&self) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.injecting = true;
+        }
+    }
+
+    /// The segment was injected; its events may still arrive for `INJECTION_GRACE`.
+    pub fn end_injection(&self) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.injecting = false;
+            shared.last_injection = Some(Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn move_to(x: f64, y: f64) -> EventType {
+        EventType::MouseMove { x, y }
+    }
+
+    #[test]
+    fn test_is_injected() {
+        let start = Instant::now();
+        let grace = Duration::from_millis(150);
+
+        assert!(!is_injected(start, false, None, grace));
+        assert!(is_injected(start, true, None, grace));
+        // Events trailing the last injection
+        assert!(is_injected(start + Duration::from_millis(149), false, Some(start), grace));
+        assert!(!is_injected(start + Duration::from_millis(150), false, Some(start), grace));
+        // Clock reads taken just before the injection ended
+        assert!(is_injected(start, false, Some(start + Duration::from_millis(5)), grace));
+    }
+
+    #[test]
+    fn test_real_input_aborts_injected_does_not() {
+        for event in [
+            EventType::KeyPress(Key::KeyA),
+            EventType::ButtonPress(rdev::Button::Left),
+            EventType::Wheel { delta_x: 0, delta_y: -1 },
+        ] {
+            let mut state = AbortState::default();
+            state.arm();
+            assert!(!state.handle(&event, true), "injected {:?}", event);
+            assert!(state.is_armed());
+
+            let mut state = AbortState::default();
+            state.arm();
+            assert!(state.handle(&event, false), "real {:?}", event);
+            // Reported once, then disarmed
+            assert!(!state.is_armed());
+            assert!(!state.handle(&EventType::KeyPress(Key::KeyZ), false));
+        }
+    }
+
+    #[test]
+    fn test_nothing_aborts_when_unarmed() {
+        let mut state = AbortState::default();
+        assert!(!state.handle(&EventType::KeyPress(Key::KeyA), false));
+        assert!(!state.handle(&EventType::ButtonPress(rdev::Button::Left), false));
+        assert!(!state.handle(&move_to(500.0, 500.0), false));
+
+        state.arm();
+        state.disarm();
+        assert!(!state.handle(&EventType::KeyPress(Key::KeyB), false));
+    }
+
+    #[test]
+    fn test_held_hotkey_and_releases_do_not_abort() {
+        let mut state = AbortState::default();
+        // The hotkey that started the macro
+        state.handle(&EventType::KeyPress(Key::ControlLeft), false);
+        state.handle(&EventType::KeyPress(Key::KeyK), false);
+        state.arm();
+
+        // Auto-repeat while still held, then letting go
+        assert!(!state.handle(&EventType::KeyPress(Key::KeyK), false));
+        assert!(!state.handle(&EventType::KeyRelease(Key::KeyK), false));
+        assert!(!state.handle(&EventType::KeyRelease(Key::ControlLeft), false));
+        assert!(!state.handle(&EventType::ButtonRelease(rdev::Button::Left), false));
+        assert!(state.is_armed());
+
+        // Pressing it again is new input
+        assert!(state.handle(&EventType::KeyPress(Key::KeyK), false));
+    }
+
+    #[test]
+    fn test_injected_key_release_is_tracked() {
+        let mut state = AbortState::default();
+        state.arm();
+        // KeyBlast types 'a'; a later real 'a' still counts
+        assert!(!state.handle(&EventType::KeyPress(Key::KeyA), true));
+        assert!(!state.handle(&EventType::KeyRelease(Key::KeyA), true));
+        assert!(state.handle(&EventType::KeyPress(Key::KeyA), false));
+    }
+
+    #[test]
+    fn test_mouse_movement_beyond_tolerance() {
+        let mut state = AbortState::default();
+        state.handle(&move_to(100.0, 100.0), false);
+        state.arm();
+
+        // Jitter within the tolerance, in small steps that add up to less
+        assert!(!state.handle(&move_to(103.0, 100.0), false));
+        assert!(!state.handle(&move_to(103.0, 104.0), false));
+        // Measured from where the pointer was at arming, not the last step
+        assert!(state.handle(&move_to(106.0, 100.0), false));
+    }
+
+    #[test]
+    fn test_injected_mouse_move_moves_anchor() {
+        let mut state = AbortState::default();
+        state.handle(&move_to(0.0, 0.0), false);
+        state.arm();
+
+        // {MouseMove 400 300} from the macro
+        assert!(!state.handle(&move_to(400.0, 300.0), true));
+        assert!(!state.handle(&move_to(402.0, 301.0), false));
+        assert!(state.handle(&move_to(380.0, 300.0), false));
+    }
+
+    #[test]
+    fn test_unknown_pointer_anchors_on_first_move() {
+        let mut state = AbortState::default();
+        state.arm();
+        assert!(!state.handle(&move_to(50.0, 50.0), false));
+        assert!(state.handle(&move_to(80.0, 50.0), false));
+    }
+}
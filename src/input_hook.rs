@@ -0,0 +1,44 @@
+//! One process-wide global input hook shared by KeyBlast's listeners.
+//!
+//! On macOS and Windows rdev keeps a single global callback, so a second
+//! `rdev::listen` would silently replace the first listener's callback.
+//! Listeners (abbreviations, mouse triggers, pass-through, recording,
+//! abort on user input) subscribe here instead: the hook thread starts with the first subscriber
+//! and hands every event to all of them. Like `rdev::listen` itself, it runs
+//! for the rest of the process lifetime.
+
+use std::sync::{Mutex, Once, PoisonError};
+use std::thread;
+
+use rdev::{listen, Event};
+
+type Subscriber = Box<dyn FnMut(&Event) + Send>;
+
+static SUBSCRIBERS: Mutex<Vec<Subscriber>> = Mutex::new(Vec::new());
+static START: Once = Once::new();
+
+/// Call `callback` (on the hook thread) with every global input event.
+///
+/// Starts the hook on first use. Subscriptions can't be removed; have the
+/// callback ignore events when its feature is turned off.
+pub fn subscribe(callback: impl FnMut(&Event) + Send + 'static) {
+    SUBSCRIBERS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(Box::new(callback));
+
+    START.call_once(|| {
+        thread::spawn(|| {
+            let result = listen(|event| {
+                let mut subscribers = SUBSCRIBERS.lock().unwrap_or_else(PoisonError::into_inner);
+                for subscriber in subscribers.iter_mut() {
+                    subscriber(&event);
+                }
+            });
+
+            if let Err(e) = result {
+                tracing::error!("Global input listener failed: {:?}", e);
+            }
+        });
+    });
+}
@@ -0,0 +1,69 @@
+//! Single-instance guard for KeyBlast.
+//!
+//! A second KeyBlast process would register every hotkey again and fight the
+//! first one over injection. `InstanceLock::acquire` takes an exclusive lock
+//! on a file in the config directory (flock on Unix, LockFileEx on Windows);
+//! the OS releases it when the file is closed or the process exits, so a
+//! crash never leaves a stale lock behind.
+
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Lock file name in the config directory.
+const LOCK_FILE: &str = "keyblast.lock";
+
+/// Held for as long as this process is the running instance.
+///
+/// Dropping it releases the lock.
+#[derive(Debug)]
+pub struct InstanceLock {
+    _file: File,
+    path: PathBuf,
+}
+
+impl InstanceLock {
+    /// Try to become the running instance.
+    ///
+    /// Returns `Ok(None)` if another process already holds the lock.
+    pub fn acquire(dir: &Path) -> io::Result<Option<Self>> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(LOCK_FILE);
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)?;
+
+        match file.try_lock() {
+            Ok(()) => Ok(Some(Self { _file: file, path })),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(e)) => Err(e),
+        }
+    }
+
+    /// Path of the lock file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_second_acquire_fails_until_released() {
+        let dir = tempdir().unwrap();
+
+        let first = InstanceLock::acquire(dir.path()).unwrap();
+        assert!(first.is_some());
+        assert!(InstanceLock::acquire(dir.path()).unwrap().is_none());
+
+        drop(first);
+        let again = InstanceLock::acquire(dir.path()).unwrap();
+        assert!(again.is_some());
+        assert_eq!(again.unwrap().path(), dir.path().join(LOCK_FILE));
+    }
+}
@@ -0,0 +1,58 @@
+//! Keep-awake ticks for KeyBlast.
+//!
+//! When `settings.keep_awake_interval_s` is non-zero, the main loop taps a
+//! harmless key on that interval so the machine doesn't idle into sleep or
+//! lock. Ticks are skipped while a macro runs or macros are disabled.
+
+use std::time::{Duration, Instant};
+
+use enigo::Key;
+
+/// Key tapped on each tick.
+///
+/// - Windows/Linux: F15, which no common application binds
+/// - macOS: Shift, since F15 may adjust display brightness
+#[cfg(not(target_os = "macos"))]
+pub const KEEP_AWAKE_KEY: Key = Key::F15;
+
+#[cfg(target_os = "macos")]
+pub const KEEP_AWAKE_KEY: Key = Key::Shift;
+
+/// Whether a keep-awake tick is due.
+///
+/// An interval of 0 disables ticking. With no previous tick, one is due now.
+pub fn should_tick(last_tick: Option<Instant>, now: Instant, interval_s: u64) -> bool {
+    if interval_s == 0 {
+        return false;
+    }
+
+    match last_tick {
+        Some(last) => now.saturating_duration_since(last) >= Duration::from_secs(interval_s),
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_tick_interval() {
+        let start = Instant::now();
+
+        assert!(!should_tick(Some(start), start + Duration::from_secs(59), 60));
+        assert!(should_tick(Some(start), start + Duration::from_secs(60), 60));
+    }
+
+    #[test]
+    fn test_should_tick_first_time() {
+        assert!(should_tick(None, Instant::now(), 60));
+    }
+
+    #[test]
+    fn test_should_tick_disabled() {
+        let start = Instant::now();
+        assert!(!should_tick(None, start, 0));
+        assert!(!should_tick(Some(start), start + Duration::from_secs(3600), 0));
+    }
+}
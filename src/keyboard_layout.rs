@@ -0,0 +1,104 @@
+//! Active keyboard layout lookups for `settings.hotkey_layout = "logical"`.
+//!
+//! Hotkey codes are physical key positions (US QWERTY names), so on AZERTY
+//! "ctrl+a" would bind the key labelled Q. With the logical layout, letters
+//! and digits are looked up in the active layout instead:
+//! - Windows: `VkKeyScanW` / `MapVirtualKeyW`
+//! - macOS and Linux: not supported; callers fall back to physical positions
+//!
+//! The layout is looked up whenever a hotkey string is parsed; switch layouts
+//! before starting KeyBlast, as already registered hotkeys keep their keys.
+
+use global_hotkey::hotkey::Code;
+
+/// Whether this platform can look up the active layout.
+pub const LOGICAL_SUPPORTED: bool = cfg!(target_os = "windows");
+
+/// The key that types `c` (unshifted or shifted) in the active layout.
+///
+/// None if no key types it, or the platform can't tell.
+pub fn code_for_char(c: char) -> Option<Code> {
+    platform::code_for_char(c)
+}
+
+/// The lowercase letter or digit the key at `code` types in the active layout.
+///
+/// None for keys that type anything else, or if the platform can't tell.
+pub fn char_for_code(code: Code) -> Option<char> {
+    platform::char_for_code(code)
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use global_hotkey::hotkey::Code;
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+        MapVirtualKeyW, VkKeyScanW, MAPVK_VK_TO_CHAR, MAPVK_VK_TO_VSC, MAPVK_VSC_TO_VK,
+    };
+
+    /// Set 1 scan codes of the keys a letter or digit can live on.
+    const SCAN_CODES: &[(u32, Code)] = &[
+        (0x02, Code::Digit1), (0x03, Code::Digit2), (0x04, Code::Digit3),
+        (0x05, Code::Digit4), (0x06, Code::Digit5), (0x07, Code::Digit6),
+        (0x08, Code::Digit7), (0x09, Code::Digit8), (0x0A, Code::Digit9),
+        (0x0B, Code::Digit0), (0x0C, Code::Minus), (0x0D, Code::Equal),
+        (0x10, Code::KeyQ), (0x11, Code::KeyW), (0x12, Code::KeyE),
+        (0x13, Code::KeyR), (0x14, Code::KeyT), (0x15, Code::KeyY),
+        (0x16, Code::KeyU), (0x17, Code::KeyI), (0x18, Code::KeyO),
+        (0x19, Code::KeyP), (0x1A, Code::BracketLeft), (0x1B, Code::BracketRight),
+        (0x1E, Code::KeyA), (0x1F, Code::KeyS), (0x20, Code::KeyD),
+        (0x21, Code::KeyF), (0x22, Code::KeyG), (0x23, Code::KeyH),
+        (0x24, Code::KeyJ), (0x25, Code::KeyK), (0x26, Code::KeyL),
+        (0x27, Code::Semicolon), (0x28, Code::Quote), (0x29, Code::Backquote),
+        (0x2B, Code::Backslash), (0x2C, Code::KeyZ), (0x2D, Code::KeyX),
+        (0x2E, Code::KeyC), (0x2F, Code::KeyV), (0x30, Code::KeyB),
+        (0x31, Code::KeyN), (0x32, Code::KeyM), (0x33, Code::Comma),
+        (0x34, Code::Period), (0x35, Code::Slash), (0x56, Code::IntlBackslash),
+    ];
+
+    pub fn code_for_char(c: char) -> Option<Code> {
+        if c.len_utf16() != 1 {
+            return None;
+        }
+        let mut unit = [0u16; 1];
+        c.encode_utf16(&mut unit);
+
+        // Low byte is the virtual key (high byte the shift state); -1 if no key types it
+        // SAFETY: VkKeyScanW takes a UTF-16 unit by value and has no preconditions.
+        let packed = unsafe { VkKeyScanW(unit[0]) };
+        if packed == -1 {
+            return None;
+        }
+        let vk = u32::from(packed as u16 & 0xFF);
+        // SAFETY: MapVirtualKeyW only reads its integer arguments; an unmapped key returns 0.
+        let scan_code = unsafe { MapVirtualKeyW(vk, MAPVK_VK_TO_VSC) };
+        SCAN_CODES.iter().find(|(sc, _)| *sc == scan_code).map(|(_, code)| *code)
+    }
+
+    pub fn char_for_code(code: Code) -> Option<char> {
+        let (scan_code, _) = SCAN_CODES.iter().find(|(_, c)| *c == code)?;
+        // SAFETY: MapVirtualKeyW only reads its integer arguments; an unmapped key returns 0.
+        let vk = unsafe { MapVirtualKeyW(*scan_code, MAPVK_VSC_TO_VK) };
+        if vk == 0 {
+            return None;
+        }
+        // Unshifted character, uppercase for letters; the top bit marks dead keys
+        // SAFETY: as above, integer arguments only.
+        let ch = unsafe { MapVirtualKeyW(vk, MAPVK_VK_TO_CHAR) } & 0x7FFF_FFFF;
+        char::from_u32(ch)
+            .filter(char::is_ascii_alphanumeric)
+            .map(|c| c.to_ascii_lowercase())
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use global_hotkey::hotkey::Code;
+
+    pub fn code_for_char(_c: char) -> Option<Code> {
+        None
+    }
+
+    pub fn char_for_code(_code: Code) -> Option<char> {
+        None
+    }
+}
@@ -1,11 +1,77 @@
-/// File logging setup for KeyBlast.
-///
-/// Uses tracing + tracing-appender for rolling log files with daily rotation.
+//! File logging setup for KeyBlast.
+//!
+//! Uses tracing + tracing-appender for rolling log files with daily rotation.
+//! Optionally mirrors log output to stdout for development (`settings.log_to_stdout`
+//! or `KEYBLAST_LOG_STDOUT=1`).
+//!
+//! Only info and above is recorded unless `KEYBLAST_LOG_LEVEL` asks for more,
+//! or "Verbose Logging (5 min)" in the tray raises it to trace for a while.
+//! Macro text is logged at debug level only (see `log_trigger`), so it stays
+//! out of the logs by default.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use std::path::PathBuf;
+use crate::config::{self, MacroDefinition};
 use tracing_appender::non_blocking::WorkerGuard;
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::LevelFilter;
 use tracing_subscriber::fmt;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{reload, Registry};
+
+/// Environment variable that turns stdout logging on ("1"/"true") or off ("0"/"false"),
+/// overriding `settings.log_to_stdout`.
+pub const LOG_STDOUT_ENV: &str = "KEYBLAST_LOG_STDOUT";
+
+/// Environment variable setting the most verbose level logged
+/// ("error", "warn", "info", "debug" or "trace").
+pub const LOG_LEVEL_ENV: &str = "KEYBLAST_LOG_LEVEL";
+
+/// How long "Verbose Logging (5 min)" logs at trace level.
+pub const VERBOSE_LOGGING_DURATION: Duration = Duration::from_secs(5 * 60);
+
+/// Changes the most verbose level logged after `init_file_logging`.
+#[derive(Clone)]
+pub struct LevelHandle {
+    handle: reload::Handle<LevelFilter, Registry>,
+    /// The level chosen at startup, which `restore` goes back to.
+    base: LevelFilter,
+}
+
+impl LevelHandle {
+    /// A reloadable filter starting at `level`, and its handle.
+    fn new(level: LevelFilter) -> (reload::Layer<LevelFilter, Registry>, Self) {
+        let (layer, handle) = reload::Layer::new(level);
+        (layer, Self { handle, base: level })
+    }
+
+    /// Log everything more severe than `level` from now on.
+    pub fn set(&self, level: LevelFilter) {
+        if let Err(e) = self.handle.reload(level) {
+            eprintln!("Warning: Could not change log level: {}", e);
+        }
+    }
+
+    /// Go back to the level chosen at startup.
+    pub fn restore(&self) {
+        self.set(self.base);
+    }
+
+    /// The level currently logged, if the subscriber is still around.
+    #[cfg(test)]
+    pub fn current(&self) -> Option<LevelFilter> {
+        self.handle.clone_current()
+    }
+}
+
+/// Whether verbose logging that lasts until `verbose_until` should end at `now`.
+pub fn verbose_logging_expired(verbose_until: Option<Instant>, now: Instant) -> bool {
+    verbose_until.is_some_and(|until| now >= until)
+}
 
 /// Returns the path to the log directory.
 ///
@@ -20,11 +86,61 @@ pub fn log_directory() -> PathBuf {
         .join("logs")
 }
 
+/// Decide whether to add a stdout layer next to the file appender.
+///
+/// The env value wins over the setting when it's a recognized boolean. Never
+/// enabled without a console: Windows GUI-subsystem builds have no stdout.
+pub fn stdout_logging_enabled(setting: bool, env: Option<&str>, console_available: bool) -> bool {
+    let requested = match env.map(|v| v.trim().to_ascii_lowercase()).as_deref() {
+        Some("1" | "true" | "yes" | "on") => true,
+        Some("0" | "false" | "no" | "off") => false,
+        _ => setting,
+    };
+    requested && console_available
+}
+
+/// Most verbose level to log: the env value if it names a level, else info.
+pub fn log_level(env: Option<&str>) -> LevelFilter {
+    env.and_then(|v| v.trim().parse().ok()).unwrap_or(LevelFilter::INFO)
+}
+
+/// Log that a macro is being injected.
+///
+/// The info line names the macro only; its text (redacted for sensitive
+/// macros, see `config::loggable_text`) goes to debug.
+pub fn log_trigger(macro_def: &MacroDefinition, mode: &str) {
+    tracing::info!("Injecting macro '{}' ({})", macro_def.name, mode);
+    tracing::debug!("Macro '{}' text: {}", macro_def.name, config::loggable_text(macro_def));
+}
+
+/// Whether stdout goes anywhere.
+///
+/// With `#![windows_subsystem = "windows"]` there is no console unless the
+/// output was redirected, in which case the std handle is set.
+#[cfg(target_os = "windows")]
+pub fn console_available() -> bool {
+    use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+    use windows_sys::Win32::System::Console::{GetStdHandle, STD_OUTPUT_HANDLE};
+
+    // SAFETY: GetStdHandle has no preconditions.
+    let handle = unsafe { GetStdHandle(STD_OUTPUT_HANDLE) };
+    !handle.is_null() && handle != INVALID_HANDLE_VALUE
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn console_available() -> bool {
+    true
+}
+
 /// Initialize file logging with daily rotation and 7-day retention.
 ///
+/// With `log_to_stdout`, events are also written to stdout (see
+/// `stdout_logging_enabled` for the decision). Events more verbose than
+/// `level` are dropped; the returned `LevelHandle` changes that later.
+///
 /// Returns the WorkerGuard that must be kept alive for the duration of the program.
 /// If logging setup fails, returns None and the application continues without file logging.
-pub fn init_file_logging() -> Option<WorkerGuard> {
+pub fn init_file_logging(log_to_stdout: bool, level: LevelFilter) -> Option<(WorkerGuard, LevelHandle)> {
     let log_dir = log_directory();
 
     // Ensure log directory exists
@@ -51,17 +167,56 @@ pub fn init_file_logging() -> Option<WorkerGuard> {
     // Wrap in non-blocking writer for performance
     let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
 
-    // Initialize the subscriber (use try_init to avoid panic on double-init)
-    if fmt::Subscriber::builder()
+    let file_layer = fmt::layer()
         .with_writer(non_blocking)
-        .with_ansi(false) // No ANSI colors in log files
+        .with_ansi(false); // No ANSI colors in log files
+    let stdout_layer = log_to_stdout.then(|| fmt::layer().with_writer(std::io::stdout));
+    let (level_layer, level_handle) = LevelHandle::new(level);
+
+    // Initialize the subscriber (use try_init to avoid panic on double-init)
+    if tracing_subscriber::registry()
+        .with(level_layer)
+        .with(file_layer)
+        .with(stdout_layer)
         .try_init()
         .is_err()
     {
         eprintln!("Warning: Logging already initialized");
     }
 
-    Some(guard)
+    Some((guard, level_handle))
+}
+
+/// The most recently modified KeyBlast log file in `dir`, if any.
+pub fn latest_log_file(dir: &Path) -> Option<PathBuf> {
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name.starts_with("keyblast") && name.ends_with(".log")
+        })
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
+/// The last `lines` lines of a log file (all of it if shorter).
+pub fn tail_log(path: &Path, lines: usize) -> io::Result<String> {
+    let content = fs::read(path)?;
+    let content = String::from_utf8_lossy(&content);
+
+    let mut tail = VecDeque::with_capacity(lines);
+    for line in content.lines() {
+        if tail.len() == lines {
+            tail.pop_front();
+        }
+        if lines > 0 {
+            tail.push_back(line);
+        }
+    }
+    Ok(tail.into_iter().collect::<Vec<_>>().join("\n"))
 }
 
 /// Open the log directory in the system file browser.
@@ -79,3 +234,146 @@ pub fn open_logs_directory() {
         eprintln!("Failed to open logs directory: {}", e);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stdout_logging_enabled() {
+        // Setting alone
+        assert!(stdout_logging_enabled(true, None, true));
+        assert!(!stdout_logging_enabled(false, None, true));
+
+        // Env overrides the setting either way
+        assert!(stdout_logging_enabled(false, Some("1"), true));
+        assert!(stdout_logging_enabled(false, Some("TRUE"), true));
+        assert!(!stdout_logging_enabled(true, Some("0"), true));
+        // Unrecognized env values fall back to the setting
+        assert!(stdout_logging_enabled(true, Some("maybe"), true));
+
+        // Never without a console
+        assert!(!stdout_logging_enabled(true, Some("1"), false));
+    }
+
+    #[test]
+    fn test_tail_log() {
+        use tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("keyblast.2026-01-01.log");
+        let content: String = (1..=5).map(|i| format!("line {}\n", i)).collect();
+        fs::write(&path, content).unwrap();
+
+        // Fewer lines than the file has
+        assert_eq!(tail_log(&path, 2).unwrap(), "line 4\nline 5");
+        // More lines than the file has
+        assert_eq!(tail_log(&path, 50).unwrap().lines().count(), 5);
+        assert_eq!(tail_log(&path, 0).unwrap(), "");
+
+        assert!(tail_log(&dir.path().join("missing.log"), 5).is_err());
+    }
+
+    #[test]
+    fn test_latest_log_file() {
+        use tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        assert_eq!(latest_log_file(dir.path()), None);
+
+        let older = dir.path().join("keyblast.2026-01-01.log");
+        let newer = dir.path().join("keyblast.2026-01-02.log");
+        fs::write(&older, "old").unwrap();
+        fs::write(&newer, "new").unwrap();
+        fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+        let earlier = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+        fs::File::options()
+            .write(true)
+            .open(&older)
+            .unwrap()
+            .set_modified(earlier)
+            .unwrap();
+
+        assert_eq!(latest_log_file(dir.path()), Some(newer));
+    }
+
+    #[test]
+    fn test_log_level() {
+        assert_eq!(log_level(None), LevelFilter::INFO);
+        assert_eq!(log_level(Some("debug")), LevelFilter::DEBUG);
+        assert_eq!(log_level(Some(" TRACE ")), LevelFilter::TRACE);
+        assert_eq!(log_level(Some("loud")), LevelFilter::INFO);
+    }
+
+    /// Run `f` with a subscriber at `level` and return what it logged.
+    fn capture_logs(level: LevelFilter, f: impl FnOnce()) -> String {
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Default)]
+        struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+        impl io::Write for Buffer {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::registry()
+            .with(fmt::layer().with_writer(move || writer.clone()).with_ansi(false))
+            .with(level);
+        tracing::subscriber::with_default(subscriber, f);
+        let logged = buffer.0.lock().unwrap().clone();
+        String::from_utf8(logged).unwrap()
+    }
+
+    #[test]
+    fn test_log_trigger_keeps_text_out_of_info() {
+        let macro_def = MacroDefinition {
+            name: "Greeting".to_string(),
+            text: "hello there".to_string(),
+            ..MacroDefinition::default()
+        };
+
+        let info = capture_logs(LevelFilter::INFO, || log_trigger(&macro_def, "instant"));
+        assert!(info.contains("Injecting macro 'Greeting' (instant)"));
+        assert!(!info.contains("hello there"));
+
+        let debug = capture_logs(LevelFilter::DEBUG, || log_trigger(&macro_def, "instant"));
+        assert!(debug.contains("hello there"));
+
+        let secret = MacroDefinition {
+            sensitive: true,
+            ..macro_def
+        };
+        let debug = capture_logs(LevelFilter::DEBUG, || log_trigger(&secret, "instant"));
+        assert!(!debug.contains("hello there"));
+        assert!(debug.contains(config::REDACTED));
+    }
+
+    #[test]
+    fn test_verbose_logging_expired() {
+        let now = Instant::now();
+        assert!(!verbose_logging_expired(None, now));
+        assert!(!verbose_logging_expired(Some(now + VERBOSE_LOGGING_DURATION), now));
+        assert!(verbose_logging_expired(Some(now), now));
+        assert!(verbose_logging_expired(Some(now), now + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_level_handle_set_and_restore() {
+        let (layer, handle) = LevelHandle::new(LevelFilter::INFO);
+        let _subscriber = tracing_subscriber::registry().with(layer);
+
+        handle.set(LevelFilter::TRACE);
+        assert_eq!(handle.current(), Some(LevelFilter::TRACE));
+        handle.restore();
+        assert_eq!(handle.current(), Some(LevelFilter::INFO));
+    }
+}
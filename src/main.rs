@@ -1,38 +1,74 @@
 #![windows_subsystem = "windows"]
 
-/// KeyBlast - A lightweight macro playback application.
-///
-/// Sits in the system tray and provides hotkey-triggered keystroke injection.
+//! KeyBlast - A lightweight macro playback application.
+//!
+//! Sits in the system tray and provides hotkey-triggered keystroke injection.
 
 mod app;
+mod audit;
 mod autostart;
+mod bundle;
+mod cli;
+mod clipboard_ring;
 mod config;
+mod diff;
+mod doctor;
 mod execution;
+mod focus;
 mod hotkey;
+mod hotstring;
+mod http;
 mod injection;
+mod input_abort;
+mod input_hook;
+mod instance;
+mod keep_awake;
+mod keyboard_layout;
 mod logging;
+mod mouse_trigger;
 mod notification;
 mod permission;
+mod prompt;
+mod recorder;
+mod suspend;
+mod transaction;
 mod tray;
+mod wizard;
 
 use std::collections::HashMap;
 use std::sync::mpsc;
 use winit::application::ApplicationHandler;
 use winit::event::WindowEvent;
-use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
+use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy};
 use winit::window::WindowId;
 use muda::MenuEvent;
-use tray_icon::TrayIcon;
+use tray_icon::{MouseButton, MouseButtonState, TrayIcon, TrayIconEvent};
 use global_hotkey::{GlobalHotKeyEvent, HotKeyState};
 use notify::{RecommendedWatcher, RecursiveMode, Watcher, Event, EventKind};
 use rfd::FileDialog;
-use crossbeam_channel;
-use tracing::{info, debug, error};
+use tracing::{info, debug, error, warn};
+
+/// Least time between two debounced config saves (see `mark_config_dirty`).
+const CONFIG_SAVE_INTERVAL: std::time::Duration = std::time::Duration::from_millis(2000);
+
+/// How often to re-check a missing accessibility permission.
+const PERMISSION_RECHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Most free hotkeys offered per macro under Suggested Free Hotkeys.
+const HOTKEY_SUGGESTION_COUNT: usize = 5;
 
 /// Custom events for the winit event loop.
 #[derive(Debug)]
 enum AppEvent {
     HotKey(GlobalHotKeyEvent),
+    /// A macro abbreviation was typed (see hotstring module)
+    Hotstring(hotstring::HotstringMatch),
+    /// `POST /run/{name}` on the HTTP endpoint (see http module)
+    RunByName(String),
+    /// A mouse button bound to this macro was pressed (see mouse_trigger module)
+    MouseButton(uuid::Uuid),
+    /// The user typed or used the mouse while a macro was running (see input_abort module)
+    UserInput,
 }
 
 /// Application wrapper for winit event loop integration.
@@ -47,6 +83,8 @@ struct KeyBlastApp {
     config: Option<config::Config>,
     /// Map hotkey_id -> macro definition for quick lookup
     macros: HashMap<u32, config::MacroDefinition>,
+    /// Chord hotkeys ("ctrl+k ctrl+s") and the chord in progress
+    chords: hotkey::ChordTracker,
     /// File watcher for config hot-reload
     config_watcher: Option<RecommendedWatcher>,
     /// Receiver for config file change events
@@ -61,16 +99,57 @@ struct KeyBlastApp {
     flash_state: bool,
     /// Instant of last flash toggle for timing
     last_flash_toggle: Option<std::time::Instant>,
-    /// Active execution handle (if macro running)
-    active_execution: Option<execution::ExecutionHandle>,
-    /// Receiver for execution commands from worker thread
-    execution_rx: Option<crossbeam_channel::Receiver<execution::ExecutionCommand>>,
+    /// Active execution (if macro running)
+    active_execution: Option<execution::Execution>,
+    /// Macro waiting out its `countdown_secs` before it runs
+    countdown: Option<app::Countdown>,
     /// Whether we've prepared the injector for this execution run
     execution_prepared: bool,
     /// ID of the stop macro hotkey (Ctrl+Escape)
     stop_hotkey_id: Option<u32>,
     /// Validation warnings from config load
     config_warnings: Vec<config::ValidationWarning>,
+    /// `settings.blocklist_apps`, compiled when the config loads
+    blocklist: focus::Blocklist,
+    /// Free hotkeys for unregistered macros, probed by `rebuild_menu`
+    hotkey_suggestions: Vec<tray::HotkeySuggestions>,
+    /// Macros run this session (Run Macro > Recent, usage sort)
+    run_history: app::RunHistory,
+    /// Last polled accessibility status (None where no permission applies)
+    accessibility_status: Option<bool>,
+    /// When accessibility permission was last polled
+    last_permission_check: Option<std::time::Instant>,
+    /// When the last keep-awake key was tapped
+    last_keep_awake: Option<std::time::Instant>,
+    /// Last macro trigger or execution event (for auto_quit_idle_minutes)
+    last_activity: std::time::Instant,
+    /// The in-memory config has changes not yet written (see `mark_config_dirty`)
+    config_dirty: bool,
+    /// When the config was last written
+    last_save: std::time::Instant,
+    /// Detects focus changes during execution (for stop_on_focus_loss)
+    focus_watcher: Box<dyn focus::FocusWatcher>,
+    /// Proxy for sending events from background threads (set before run_app)
+    event_proxy: Option<EventLoopProxy<AppEvent>>,
+    /// Global keyboard listener for abbreviations (started when hotstrings are enabled)
+    hotstring_listener: Option<hotstring::HotstringListener>,
+    /// Global mouse listener for `mouse_button` (started when mouse triggers are enabled)
+    mouse_listener: Option<mouse_trigger::MouseListener>,
+    /// Global key listener for `settings.suspend_while_held` (started when it is set)
+    suspend_listener: Option<suspend::SuspendListener>,
+    /// Global input listener for `settings.abort_on_user_input` (started when it is on)
+    abort_listener: Option<input_abort::AbortListener>,
+    /// Global key listener for "Record Keystrokes" (started by the first recording)
+    recorder: Option<recorder::Recorder>,
+    http_server: Option<http::HttpServer>,
+    /// Raises the log level for "Verbose Logging (5 min)"
+    log_level_handle: Option<logging::LevelHandle>,
+    /// When verbose logging goes back to the startup level
+    verbose_logging_until: Option<std::time::Instant>,
+    /// Started with --safe-mode: disabled, no macro hotkeys, no config watcher
+    safe_mode: bool,
+    /// Ask the first-run questions if there is no config (off with --no-wizard)
+    first_run_wizard: bool,
     /// Flag to signal clean shutdown
     should_exit: bool,
 }
@@ -84,19 +163,32 @@ impl KeyBlastApp {
                 toggle: muda::MenuId::new(""),
                 edit_config: muda::MenuId::new(""),
                 export_macros: muda::MenuId::new(""),
-                import_macros: muda::MenuId::new(""),
+                export_shareable: muda::MenuId::new(""),
+                reimport_last: muda::MenuId::new(""),
+                export_config: muda::MenuId::new(""),
+                import_config: muda::MenuId::new(""),
+                search_macros: muda::MenuId::new(""),
+                record_keystrokes: muda::MenuId::new(""),
                 open_logs: muda::MenuId::new(""),
+                copy_logs: muda::MenuId::new(""),
+                verbose_logging: muda::MenuId::new(""),
+                reregister_hotkeys: muda::MenuId::new(""),
                 auto_start: muda::MenuId::new(""),
                 stop_macro: muda::MenuId::new(""),
                 quit: muda::MenuId::new(""),
+                toggle_macro_ids: std::collections::HashMap::new(),
+                edit_macro_ids: std::collections::HashMap::new(),
                 delete_macro_ids: std::collections::HashMap::new(),
                 run_macro_ids: std::collections::HashMap::new(),
+                suggested_hotkey_ids: std::collections::HashMap::new(),
+                import_macro_ids: std::collections::HashMap::new(),
             },
             _tray_icon: None,
             hotkey_manager: None,
             injector: None,
             config: None,
             macros: HashMap::new(),
+            chords: hotkey::ChordTracker::default(),
             config_watcher: None,
             config_change_rx: None,
             flash_remaining: 0,
@@ -105,10 +197,31 @@ impl KeyBlastApp {
             flash_state: false,
             last_flash_toggle: None,
             active_execution: None,
-            execution_rx: None,
             execution_prepared: false,
             stop_hotkey_id: None,
+            countdown: None,
             config_warnings: Vec::new(),
+            hotkey_suggestions: Vec::new(),
+            blocklist: focus::Blocklist::default(),
+            run_history: app::RunHistory::default(),
+            accessibility_status: None,
+            last_permission_check: None,
+            last_keep_awake: Some(std::time::Instant::now()),
+            last_activity: std::time::Instant::now(),
+            config_dirty: false,
+            last_save: std::time::Instant::now(),
+            focus_watcher: Box::new(focus::PollingFocusWatcher::new()),
+            event_proxy: None,
+            hotstring_listener: None,
+            mouse_listener: None,
+            suspend_listener: None,
+            abort_listener: None,
+            recorder: None,
+            http_server: None,
+            log_level_handle: None,
+            verbose_logging_until: None,
+            safe_mode: false,
+            first_run_wizard: false,
             should_exit: false,
         }
     }
@@ -116,20 +229,785 @@ impl KeyBlastApp {
     /// Rebuild the tray menu with current macros.
     /// Call after config changes (import, delete).
     fn rebuild_menu(&mut self) {
+        self.hotkey_suggestions = self.hotkey_suggestions();
+        self.refresh_menu();
+    }
+
+    /// Rebuild the tray menu without probing hotkeys again, for changes
+    /// that don't touch the config or registrations (e.g. Recent after a run).
+    fn refresh_menu(&mut self) {
         if let Some(ref config) = self.config {
-            let (menu, menu_ids) = tray::build_menu(
-                self.state.enabled,
-                &config.macros,
-                &self.config_warnings,
-            );
+            let (menu, menu_ids) = tray::build_menu(&tray::MenuInputs {
+                enabled: self.state.enabled,
+                macros: &config.macros,
+                warnings: &self.config_warnings,
+                hotkey_suggestions: &self.hotkey_suggestions,
+                history: &self.run_history,
+                accessibility: self.accessibility_status,
+                settings: &config.settings,
+                safe_mode: self.safe_mode,
+            });
 
             // Update the tray icon's menu
             if let Some(ref tray_icon) = self._tray_icon {
                 tray_icon.set_menu(Some(Box::new(menu.clone())));
+                tray_icon.set_show_menu_on_left_click(
+                    config.settings.tray_click_action == config::TrayClickAction::Menu,
+                );
             }
 
             self.menu = menu;
             self.menu_ids = menu_ids;
+            self.update_verbose_logging_item();
+            self.update_record_item();
+        }
+    }
+
+    /// Free hotkeys for each macro whose hotkey the OS or another app has.
+    ///
+    /// Availability is probed by briefly registering each candidate, so this
+    /// reflects the moment the menu is built.
+    fn hotkey_suggestions(&mut self) -> Vec<tray::HotkeySuggestions> {
+        let (Some(cfg), Some(manager)) = (self.config.as_ref(), self.hotkey_manager.as_mut()) else {
+            return Vec::new();
+        };
+        if self.safe_mode {
+            return Vec::new();
+        }
+        let in_config: Vec<_> = cfg.macros.iter()
+            .filter_map(|m| config::parse_hotkey_string(&m.hotkey))
+            .collect();
+
+        hotkey::unregistered_macros(&cfg.macros, &self.macros)
+            .into_iter()
+            .filter_map(|macro_def| {
+                let hotkey = config::parse_hotkey_string(&macro_def.hotkey)?;
+                let hotkeys = hotkey::suggest_available_detailed(hotkey, HOTKEY_SUGGESTION_COUNT, |candidate| {
+                    if in_config.contains(candidate) {
+                        hotkey::CandidateStatus::InternalConflict
+                    } else {
+                        hotkey::CandidateStatus::from_register_result(&manager.check_available(*candidate))
+                    }
+                });
+                Some(tray::HotkeySuggestions {
+                    macro_id: macro_def.id,
+                    macro_name: macro_def.name.clone(),
+                    hotkeys,
+                })
+            })
+            .collect()
+    }
+
+    /// Give a macro whose hotkey couldn't be registered one of the suggested
+    /// free hotkeys, register it and save the config.
+    fn apply_suggested_hotkey(&mut self, macro_id: uuid::Uuid, hotkey: global_hotkey::hotkey::HotKey) {
+        let hotkey_str = config::hotkey_to_string(&hotkey);
+        let result = self.with_config_transaction(|tx| {
+            let macro_def = tx.config().macros.iter_mut().find(|m| m.id == macro_id)?;
+            macro_def.hotkey = hotkey_str.clone();
+            macro_def.modified = Some(config::timestamp_now());
+            let updated = macro_def.clone();
+            Some(tx.register_macro(&updated).map(|()| updated.name))
+        });
+
+        match result {
+            Some(Ok(Some(Ok(name)))) => {
+                info!("Macro '{}' now uses {}", name, hotkey_str);
+                notification::show_info(
+                    "Hotkey Changed",
+                    &format!("{} now runs with {}", name, config::pretty_hotkey(&hotkey)),
+                );
+            }
+            Some(Ok(Some(Err(e)))) => {
+                // Saved with the new hotkey; it will be retried on reload
+                warn!("Failed to register {}: {}", hotkey_str, e);
+                notification::show_error(
+                    "Hotkey Changed",
+                    &format!("{} was saved but couldn't be registered: {}", hotkey_str, e),
+                    notification::NotificationSeverity::ActionFailed,
+                );
+            }
+            Some(Ok(None)) => {}
+            Some(Err(e)) => {
+                error!("Failed to save config after changing hotkey (change undone): {}", e);
+                notification::show_config_save_error(&e);
+            }
+            None => {}
+        }
+
+        if let Some(ref cfg) = self.config {
+            self.config_warnings = config::validate_config(cfg);
+        }
+        self.rebuild_menu();
+    }
+
+    /// Switch a macro on or off from its Enabled checkbox: register or
+    /// unregister its hotkey right away and save the config.
+    fn toggle_macro_enabled(&mut self, macro_id: uuid::Uuid) {
+        let result = self.with_config_transaction(|tx| {
+            let macro_def = tx.config().macros.iter_mut().find(|m| m.id == macro_id)?;
+            macro_def.enabled = !macro_def.enabled;
+            let updated = macro_def.clone();
+            if updated.enabled {
+                Some((updated.name.clone(), true, tx.register_macro(&updated)))
+            } else {
+                tx.unregister_macro(macro_id);
+                Some((updated.name, false, Ok(())))
+            }
+        });
+
+        match result {
+            Some(Ok(Some((name, enabled, registered)))) => {
+                info!("Macro '{}' {}, config saved", name, if enabled { "enabled" } else { "disabled" });
+                if let Err(e) = registered {
+                    warn!("Failed to register '{}': {}", name, e);
+                }
+            }
+            Some(Ok(None)) => {}
+            Some(Err(e)) => {
+                error!("Failed to save config after toggling macro (change undone): {}", e);
+                notification::show_config_save_error(&e);
+            }
+            None => {}
+        }
+
+        self.sync_hotstrings();
+        self.sync_mouse_triggers();
+        if let Some(ref cfg) = self.config {
+            self.config_warnings = config::validate_config(cfg);
+        }
+        self.rebuild_menu();
+    }
+
+    /// Ask for a macro's new name, hotkey and text, then save it.
+    ///
+    /// Input dialogs are single-line, so text with line breaks or from a
+    /// `text_file` is left alone (edit the config file for those). Cancelling
+    /// any dialog keeps the macro as it was.
+    fn edit_macro(&mut self, macro_id: uuid::Uuid) {
+        let Some(current) = self.config.as_ref()
+            .and_then(|cfg| cfg.macros.iter().find(|m| m.id == macro_id))
+            .cloned()
+        else {
+            return;
+        };
+        let title = format!("Edit Macro \u{2014} {}", current.name);
+
+        let Some(name) = prompt::prompt_edit(&title, "Name:", &current.name) else {
+            return;
+        };
+        let Some(hotkey) = prompt::prompt_edit(&title, "Hotkey (e.g. ctrl+shift+k):", &current.hotkey) else {
+            return;
+        };
+        let text = if current.text_file.is_none() && !current.text.contains('\n') {
+            let Some(text) = prompt::prompt_edit(&title, "Text:", &current.text) else {
+                return;
+            };
+            Some(text)
+        } else {
+            None
+        };
+
+        let macros = self.config.as_ref().map_or(&[][..], |cfg| &cfg.macros[..]);
+        let updated = match config::apply_macro_edit(macros, macro_id, config::MacroEdit { name, hotkey, text }) {
+            Ok(updated) => updated,
+            Err(e) => {
+                notification::show_error("Edit Macro", &e, notification::NotificationSeverity::ActionFailed);
+                return;
+            }
+        };
+        // Don't save a macro that can't run (bad DSL, missing text_file, ...)
+        let issues = self.config.as_ref()
+            .map_or_else(Vec::new, |cfg| updated.validate(&cfg.settings));
+        let blocking: Vec<String> = issues
+            .iter()
+            .filter(|issue| issue.is_blocking())
+            .map(ToString::to_string)
+            .collect();
+        if !blocking.is_empty() {
+            notification::show_error(
+                "Edit Macro",
+                &format!("{} wasn't saved: {}", updated.name, blocking.join("; ")),
+                notification::NotificationSeverity::ActionFailed,
+            );
+            return;
+        }
+
+        // Swap the macro and its hotkey registration, saving atomically
+        let result = self.with_config_transaction(|tx| {
+            tx.unregister_macro(macro_id);
+            if let Some(slot) = tx.config().macros.iter_mut().find(|m| m.id == macro_id) {
+                *slot = updated.clone();
+            }
+            tx.register_macro(&updated)
+        });
+        match result {
+            Some(Ok(Ok(()))) => {
+                info!("Macro '{}' edited and config saved", updated.name);
+            }
+            Some(Ok(Err(e))) => {
+                // Saved; the hotkey shows under Suggested Free Hotkeys if it's taken
+                warn!("Macro '{}' saved but its hotkey couldn't be registered: {}", updated.name, e);
+                notification::show_error(
+                    "Edit Macro",
+                    &format!("{} was saved but its hotkey couldn't be registered: {}", updated.name, e),
+                    notification::NotificationSeverity::ActionFailed,
+                );
+            }
+            Some(Err(e)) => {
+                error!("Failed to save config after edit (edit undone): {}", e);
+                notification::show_config_save_error(&e);
+            }
+            None => {}
+        }
+
+        // Re-validate and rebuild, like after a delete
+        if let Some(ref cfg) = self.config {
+            self.config_warnings = config::validate_config(cfg);
+        }
+        self.rebuild_menu();
+    }
+
+    /// Log at trace level for `VERBOSE_LOGGING_DURATION`, or go back to the
+    /// startup level early.
+    fn set_verbose_logging(&mut self, verbose: bool) {
+        let Some(ref handle) = self.log_level_handle else {
+            warn!("Logging isn't initialized; can't change the log level");
+            return;
+        };
+        if verbose {
+            handle.set(tracing_subscriber::filter::LevelFilter::TRACE);
+            self.verbose_logging_until = Some(std::time::Instant::now() + logging::VERBOSE_LOGGING_DURATION);
+            info!("Verbose logging on for {} minutes", logging::VERBOSE_LOGGING_DURATION.as_secs() / 60);
+        } else {
+            // Logged before lowering the level, so it isn't filtered out
+            info!("Verbose logging off");
+            handle.restore();
+            self.verbose_logging_until = None;
+        }
+        self.update_verbose_logging_item();
+    }
+
+    /// Check "Verbose Logging (5 min)" while it is on.
+    fn update_verbose_logging_item(&self) {
+        for item in self.menu.items() {
+            if let muda::MenuItemKind::Check(check_item) = item {
+                if check_item.id() == &self.menu_ids.verbose_logging {
+                    check_item.set_checked(self.verbose_logging_until.is_some());
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Run a macro triggered by a hotkey or the Run Macro menu.
+    ///
+    /// A macro with `countdown_secs` runs once its countdown is over (see
+    /// `advance_countdown`); others run right away (see `run_macro_now`).
+    fn trigger_macro(&mut self, macro_def: &config::MacroDefinition, source: &str) {
+        self.trigger_macro_erasing(macro_def, source, 0);
+    }
+
+    /// Like `trigger_macro`, first backspacing over `erase_len` typed
+    /// characters (an abbreviation) when the macro runs.
+    fn trigger_macro_erasing(&mut self, macro_def: &config::MacroDefinition, source: &str, erase_len: usize) {
+        // Any trigger counts as activity, even one that ends up not running
+        self.last_activity = std::time::Instant::now();
+
+        // Check if macros are enabled
+        if !self.state.enabled {
+            info!("Macros disabled, ignoring {} trigger", source);
+            self.notify_if_disabled();
+            return;
+        }
+        if !macro_def.enabled {
+            info!("Macro '{}' is disabled, ignoring {} trigger", macro_def.name, source);
+            return;
+        }
+        if !config::macro_applies(macro_def) {
+            info!("Macro '{}' isn't for this machine (only_on), ignoring {} trigger", macro_def.name, source);
+            return;
+        }
+        if self.suspend_listener.as_ref().is_some_and(|listener| listener.is_suspended()) {
+            info!("Pass-through key held, ignoring {} trigger", source);
+            return;
+        }
+
+        // Check if already executing (or counting down to it)
+        if self.active_execution.is_some() || self.countdown.is_some() {
+            info!("Macro already running, ignoring new trigger");
+            return;
+        }
+
+        if macro_def.countdown_secs > 0 {
+            let mut countdown = app::Countdown::new(macro_def.clone(), std::time::Instant::now());
+            countdown.erase_len = erase_len;
+            self.countdown = Some(countdown);
+            self.advance_countdown(std::time::Instant::now());
+            return;
+        }
+        self.run_macro_now(macro_def, erase_len);
+    }
+
+    /// Report due countdown ticks (log and flash the tray icon) and run the
+    /// macro once its countdown is over.
+    fn advance_countdown(&mut self, now: std::time::Instant) {
+        loop {
+            let Some(countdown) = self.countdown.as_mut() else {
+                return;
+            };
+            match countdown.poll(now) {
+                app::CountdownStep::Waiting => return,
+                app::CountdownStep::Tick(left) => {
+                    info!("Running '{}' in {}...", countdown.macro_def.name, left);
+                    self.start_flash(2);
+                }
+                app::CountdownStep::Finished => {
+                    let Some(countdown) = self.countdown.take() else {
+                        return;
+                    };
+                    // Disabled during the countdown
+                    if !self.state.enabled {
+                        info!("Macros disabled, not running '{}'", countdown.macro_def.name);
+                        return;
+                    }
+                    self.run_macro_now(&countdown.macro_def, countdown.erase_len);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Flash the tray icon, switching between the flash and normal icon
+    /// `toggles` times 100 ms apart.
+    fn start_flash(&mut self, toggles: u8) {
+        self.flash_remaining = toggles;
+        self.flash_state = false;
+        self.last_flash_toggle = Some(std::time::Instant::now());
+    }
+
+    /// Inject a macro that passed `trigger_macro`'s checks.
+    ///
+    /// Short macros with no delay are injected synchronously; long or delayed
+    /// macros run on the async execution worker. `erase_len` backspaces are
+    /// typed first (see `trigger_macro_erasing`).
+    fn run_macro_now(&mut self, macro_def: &config::MacroDefinition, erase_len: usize) {
+        // Global guard: nothing runs while a blocklisted app is focused
+        if !self.blocklist.is_empty() {
+            if let Some(app) = focus::foreground_app() {
+                if let Some(pattern) = self.blocklist.find(&app) {
+                    info!(
+                        "Not running '{}': {} is focused (blocklist_apps entry '{}')",
+                        macro_def.name, app, pattern
+                    );
+                    return;
+                }
+            }
+        }
+
+        let Some(ref mut injector) = self.injector else {
+            error!("No injector available");
+            return;
+        };
+
+        let recent_cap = self.config.as_ref()
+            .map_or(0, |cfg| cfg.settings.recent_macros_count);
+        let delay_after_special_ms = self.config.as_ref()
+            .and_then(|cfg| cfg.settings.delay_after_special_ms);
+        let burst = self.config.as_ref()
+            .and_then(|cfg| execution::Burst::from_settings(cfg.settings.burst_size, cfg.settings.burst_pause_ms));
+        match self.config {
+            Some(ref cfg) => injector.configure(&cfg.settings, macro_def),
+            None => injector.configure(&config::AppSettings::default(), macro_def),
+        }
+
+        let text = match config::load_macro_text(macro_def, &config::config_dir()) {
+            Ok(text) => text,
+            Err(e) => {
+                error!("Failed to read text for macro '{}': {}", macro_def.name, e);
+                notification::show_error(
+                    "KeyBlast",
+                    &format!("Macro '{}' could not read its text file", macro_def.name),
+                    notification::NotificationSeverity::InjectionFailed,
+                );
+                return;
+            }
+        };
+
+        let strict = self.config.as_ref()
+            .is_some_and(|cfg| macro_def.is_strict(&cfg.settings));
+        let segments = if strict {
+            match injection::parse_macro_sequence_strict(&text) {
+                Ok(segments) => segments,
+                Err(issues) => {
+                    let issues: Vec<_> = issues.iter().map(ToString::to_string).collect();
+                    warn!("Not running macro '{}': {}", macro_def.name, issues.join(", "));
+                    notification::show_error(
+                        "KeyBlast",
+                        &format!("Macro '{}' has DSL errors: {}", macro_def.name, issues.join(", ")),
+                        notification::NotificationSeverity::InjectionFailed,
+                    );
+                    return;
+                }
+            }
+        } else {
+            injection::parse_macro_sequence(&text)
+        };
+        // Erase a typed abbreviation, whether the text is inline or from text_file
+        let segments: Vec<_> = std::iter::repeat_n(injection::MacroSegment::SpecialKey(enigo::Key::Backspace), erase_len)
+            .chain(segments)
+            .collect();
+        // Unroll {Repeat} so the checks below see what will actually run
+        let segments = injection::flatten_repeats(segments);
+        let delay_ms = self.config.as_ref()
+            .map_or(macro_def.delay_ms, |cfg| macro_def.effective_delay_ms(&cfg.settings));
+        let mode_name = if delay_ms == 0 {
+            "instant"
+        } else {
+            "slow"
+        };
+        logging::log_trigger(macro_def, mode_name);
+
+        // {DoubleClick} pauses between its clicks, so it counts as a delay
+        let has_delay = segments.iter().any(|s| {
+            matches!(s, injection::MacroSegment::Delay(_) | injection::MacroSegment::DoubleClick(_))
+                || (delay_after_special_ms.is_some() && matches!(s, injection::MacroSegment::SpecialKey(_)))
+        });
+        // File contents can be any length, so {Type} always runs async;
+        // {Prompt} is only answered on the async path
+        let needs_async = segments.iter().any(|s| {
+            matches!(s, injection::MacroSegment::TypeFile(_) | injection::MacroSegment::Prompt(_))
+        });
+        if delay_ms == 0 && segments.len() <= 10 && !has_delay && !needs_async && burst.is_none() {
+            // Fast path: short macros with no delay run synchronously
+            // This avoids overhead for simple text expansion
+            if let Some(ref listener) = self.hotstring_listener {
+                listener.begin_injection();
+            }
+            let result = injector.execute_sequence(&segments, 0, 0);
+            if let Some(ref listener) = self.hotstring_listener {
+                listener.end_injection();
+            }
+            match result {
+                Ok(()) => {
+                    info!("Injection complete");
+                    self.start_flash(4);
+                    self.record_run(macro_def, recent_cap);
+                }
+                Err(e) => {
+                    error!("Injection failed: {}", e);
+                    notification::show_injection_error(&e);
+                }
+            }
+        } else {
+            // Async path: spawn worker thread for long or delayed macros
+            let mut execution = execution::start_execution(segments, execution::ExecutionOptions {
+                delay_ms,
+                jitter_ms: macro_def.jitter_ms,
+                delay_after_special_ms,
+                burst,
+                revert_partial: self.config.as_ref().is_some_and(|cfg| cfg.settings.cancel_backspaces_partial),
+                inject_via_clipboard: macro_def.inject_via_clipboard,
+                double_click_gap_ms: self.config.as_ref()
+                    .map_or_else(|| config::AppSettings::default().double_click_gap_ms, |cfg| cfg.settings.double_click_gap_ms),
+            });
+            execution.set_macro_name(&macro_def.name);
+            let name = macro_def.name.clone();
+            let sensitive = macro_def.sensitive;
+            // An unanswered prompt is closed after the timeout and cancels the macro
+            let prompt_timeout = self.config.as_ref()
+                .map(|cfg| cfg.settings.interactive_timeout_ms)
+                .filter(|&ms| ms > 0)
+                .map(std::time::Duration::from_millis);
+            // Watch for the user taking over, except while they answer a prompt
+            let abort_listener = self.abort_listener.clone()
+                .filter(|_| self.config.as_ref().is_some_and(|cfg| cfg.settings.abort_on_user_input));
+            let prompt_abort_listener = abort_listener.clone();
+            // Shown on a helper thread, so the tray, stop hotkey and watchers
+            // keep working while it is open; stopping the macro closes it
+            execution.on_prompt(move |label, stop| {
+                if let Some(ref listener) = prompt_abort_listener {
+                    listener.disarm();
+                }
+                let answer = prompt::prompt_text_within(label, &format!("{}:", label), "", prompt_timeout, Some(stop));
+                // A stopped macro's listener stays disarmed
+                if let Some(ref listener) = prompt_abort_listener {
+                    if !stop.load(std::sync::atomic::Ordering::Relaxed) {
+                        listener.arm();
+                    }
+                }
+                answer
+            });
+            execution.on_event(move |event| match event {
+                execution::ExecutionEvent::SegmentDone(_) if sensitive => {
+                    debug!("Macro '{}': SegmentDone({})", name, config::REDACTED)
+                }
+                event => debug!("Macro '{}': {:?}", name, event),
+            });
+            self.active_execution = Some(execution);
+            self.execution_prepared = false;
+            self.focus_watcher.reset();
+            if let Some(listener) = abort_listener {
+                listener.arm();
+            }
+            // Flash happens when Complete command received
+            self.record_run(macro_def, recent_cap);
+        }
+    }
+
+    /// Move a macro that ran (or started running) to the top of Run Macro's
+    /// recent section.
+    fn record_run(&mut self, macro_def: &config::MacroDefinition, recent_cap: usize) {
+        self.run_history.record(macro_def.id, recent_cap);
+        self.refresh_menu();
+    }
+
+    /// Ask for part of a macro name and run the macro if exactly one matches.
+    fn search_and_run(&mut self) {
+        let Some(query) = prompt::prompt_text("KeyBlast \u{2014} Search & Run", "Macro name contains:") else {
+            return;
+        };
+        let query = query.trim();
+        if query.is_empty() {
+            return;
+        }
+
+        let matches: Vec<config::MacroDefinition> = match self.config {
+            Some(ref cfg) => tray::filter_macros(query, &cfg.macros).into_iter().cloned().collect(),
+            None => return,
+        };
+        match matches.as_slice() {
+            [] => notification::show_info("KeyBlast", &format!("No macro matches '{}'", query)),
+            [macro_def] => self.trigger_macro(macro_def, "search"),
+            several => {
+                let names: Vec<_> = several.iter().map(|m| format!("\u{2022} {}", m.name)).collect();
+                rfd::MessageDialog::new()
+                    .set_level(rfd::MessageLevel::Info)
+                    .set_title("Search & Run")
+                    .set_description(format!(
+                        "{} macros match '{}':\n\n{}\n\nSearch again with a more specific name.",
+                        several.len(),
+                        query,
+                        names.join("\n")
+                    ))
+                    .set_buttons(rfd::MessageButtons::Ok)
+                    .show();
+            }
+        }
+    }
+
+    /// Start recording keystrokes, or stop and save the recording as a new macro.
+    ///
+    /// The macro is menu-only (no hotkey) and replays the pauses between keys
+    /// with `{Delay}`; give it a hotkey with Edit... afterwards.
+    fn toggle_recording(&mut self) {
+        let recorder = self.recorder.get_or_insert_with(recorder::Recorder::new);
+        if !recorder.is_recording() {
+            recorder.begin();
+            info!("Recording keystrokes");
+            self.update_record_item();
+            return;
+        }
+
+        let events = recorder.finish();
+        self.update_record_item();
+        let segments = recorder::segments_from_recording(
+            &events,
+            recorder::MIN_RECORDED_DELAY,
+            recorder::MAX_RECORDED_DELAY,
+        );
+        info!("Recording stopped: {} key events, {} segments", events.len(), segments.len());
+        if segments.is_empty() {
+            notification::show_info("KeyBlast", "Nothing was recorded");
+            return;
+        }
+
+        let Some(name) = prompt::prompt_text("KeyBlast \u{2014} Save Recording", "Macro name:") else {
+            return;
+        };
+        let name = name.trim();
+        let name = if name.is_empty() { "Recorded Macro" } else { name };
+        let mut recorded = config::MacroDefinition {
+            name: name.to_string(),
+            text: injection::segments_to_dsl(&segments),
+            hotkey_enabled: false,
+            modified: Some(config::timestamp_now()),
+            ..config::MacroDefinition::default()
+        };
+
+        // Like Rename Duplicates on import, a taken name gets " (2)", " (3)", ...
+        let result = self.with_config_transaction(|tx| {
+            let taken: std::collections::HashSet<String> =
+                tx.config().macros.iter().map(|m| m.name.clone()).collect();
+            if taken.contains(&recorded.name) {
+                recorded.name = config::unique_name(&recorded.name, &taken);
+            }
+            tx.config().macros.push(recorded.clone());
+            recorded
+        });
+        match result {
+            Some(Ok(recorded)) => {
+                info!("Recorded macro '{}' added and config saved", recorded.name);
+                notification::show_info(
+                    "KeyBlast",
+                    &format!("Saved '{}'; find it under Run Macro", recorded.name),
+                );
+            }
+            Some(Err(e)) => {
+                error!("Failed to save config after recording (macro not added): {}", e);
+                notification::show_config_save_error(&e);
+            }
+            None => {}
+        }
+
+        if let Some(ref cfg) = self.config {
+            self.config_warnings = config::validate_config(cfg);
+        }
+        self.rebuild_menu();
+    }
+
+    /// Check "Record Keystrokes" while a recording is in progress. It stays
+    /// enabled then even if the config got locked, so the recording can stop.
+    fn update_record_item(&self) {
+        let recording = self.recorder.as_ref().is_some_and(recorder::Recorder::is_recording);
+        let writable = self.config.as_ref().is_some_and(|cfg| !cfg.settings.locked);
+        for item in self.menu.items() {
+            if let muda::MenuItemKind::Check(check_item) = item {
+                if check_item.id() == &self.menu_ids.record_keystrokes {
+                    check_item.set_checked(recording);
+                    check_item.set_enabled(recording || writable);
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Copy the tail of the newest log file to the clipboard for support requests.
+    fn copy_logs_to_clipboard(&self) {
+        let lines = self.config.as_ref()
+            .map_or(200, |cfg| cfg.settings.log_copy_lines);
+
+        let Some(path) = logging::latest_log_file(&logging::log_directory()) else {
+            notification::show_info("KeyBlast", "No log file found yet");
+            return;
+        };
+        let result = logging::tail_log(&path, lines)
+            .map_err(|e| e.to_string())
+            .and_then(|tail| {
+                let copied = tail.lines().count();
+                arboard::Clipboard::new()
+                    .and_then(|mut clipboard| clipboard.set_text(tail))
+                    .map(|()| copied)
+                    .map_err(|e| e.to_string())
+            });
+        match result {
+            Ok(copied) => {
+                notification::show_info("KeyBlast", &format!("Copied the last {} log lines to the clipboard", copied));
+            }
+            Err(e) => {
+                error!("Failed to copy logs from {}: {}", path.display(), e);
+                notification::show_error(
+                    "KeyBlast",
+                    "Could not copy logs to the clipboard",
+                    notification::NotificationSeverity::ActionFailed,
+                );
+            }
+        }
+    }
+
+    /// Stop the running macro, or apply `settings.stop_hotkey_idle_action` if none is running.
+    fn handle_stop_hotkey(&mut self) {
+        let idle_action = self.config.as_ref()
+            .map_or(config::StopIdleAction::None, |cfg| cfg.settings.stop_hotkey_idle_action);
+        let running = self.active_execution.is_some() || self.countdown.is_some();
+        match self.state.stop_hotkey_action(running, idle_action) {
+            app::StopHotkeyAction::StopMacro => {
+                if let Some(countdown) = self.countdown.take() {
+                    info!("Stop hotkey pressed - '{}' won't run", countdown.macro_def.name);
+                }
+                if let Some(ref execution) = self.active_execution {
+                    execution.stop();
+                    info!("Stop hotkey pressed - macro will stop");
+                }
+            }
+            app::StopHotkeyAction::Nothing => {}
+            app::StopHotkeyAction::ReleaseModifiers => {
+                if let Some(ref mut injector) = self.injector {
+                    match injector.release_modifiers() {
+                        Ok(()) => info!("Stop hotkey pressed - released modifiers"),
+                        Err(e) => error!("Failed to release modifiers: {}", e),
+                    }
+                }
+            }
+            app::StopHotkeyAction::ClearQueue => {
+                // Macros don't queue yet: a trigger while one runs is dropped
+                info!("Stop hotkey pressed - no queued macros to clear");
+            }
+            app::StopHotkeyAction::Disable => {
+                info!("Stop hotkey pressed - disabling KeyBlast");
+                self.set_enabled(false);
+            }
+        }
+    }
+
+    /// Enable or disable macros, save the setting and update the tray checkbox.
+    ///
+    /// With a locked config the change only lasts until restart.
+    fn set_enabled(&mut self, enabled: bool) {
+        self.state.enabled = enabled;
+        info!("KeyBlast {}", if enabled { "enabled" } else { "disabled" });
+
+        // Save enabled state to config (debounced, so rapid toggling writes once)
+        if let Some(ref mut cfg) = self.config {
+            cfg.settings.enabled = enabled;
+            if cfg.settings.locked {
+                info!("Config is locked; enabled state not saved");
+            } else {
+                self.mark_config_dirty();
+            }
+        }
+
+        // Update the checkbox state
+        for item in self.menu.items() {
+            if let muda::MenuItemKind::Check(check_item) = item {
+                if check_item.id() == &self.menu_ids.toggle {
+                    check_item.set_checked(enabled);
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Act on a left click on the tray icon per `settings.tray_click_action`.
+    fn handle_tray_click(&mut self) {
+        let Some(ref cfg) = self.config else {
+            return;
+        };
+        match app::tray_click_response(cfg.settings.tray_click_action, &cfg.macros) {
+            app::TrayClickResponse::Nothing => {
+                if cfg.settings.tray_click_action == config::TrayClickAction::RunPrimary {
+                    warn!("Tray clicked, but no macro has primary = true");
+                }
+            }
+            app::TrayClickResponse::ToggleEnabled => self.set_enabled(!self.state.enabled),
+            app::TrayClickResponse::RunMacro(id) => {
+                let macro_def = cfg.macros.iter().find(|m| m.id == id).cloned();
+                if let Some(macro_def) = macro_def {
+                    info!("Running primary macro '{}' from tray click", macro_def.name);
+                    self.trigger_macro(&macro_def, "tray");
+                }
+            }
+            // Opened by the tray icon itself
+            app::TrayClickResponse::ShowMenu => {}
+        }
+    }
+
+    /// Remind the user that KeyBlast is disabled, if they opted in.
+    fn notify_if_disabled(&self) {
+        let notify_when_disabled = self.config.as_ref()
+            .is_some_and(|cfg| cfg.settings.notify_when_disabled);
+        if self.state.should_notify_disabled(notify_when_disabled) {
+            notification::show_info("KeyBlast", "KeyBlast is disabled — enable from the tray");
         }
     }
 
@@ -153,18 +1031,18 @@ impl KeyBlastApp {
                 // Watch parent directory to catch rename/create events
                 if let Some(parent) = config_path.parent() {
                     if let Err(e) = watcher.watch(parent, RecursiveMode::NonRecursive) {
-                        eprintln!("Failed to watch config directory: {}", e);
+                        error!("Failed to watch config directory: {}", e);
                     } else {
-                        println!("Watching config directory for changes: {}", parent.display());
+                        info!("Watching config directory for changes: {}", parent.display());
                         self.config_watcher = Some(watcher);
                         self.config_change_rx = Some(rx);
                     }
                 } else {
-                    eprintln!("Could not determine config file parent directory");
+                    error!("Could not determine config file parent directory");
                 }
             }
             Err(e) => {
-                eprintln!("Failed to create config watcher: {}", e);
+                error!("Failed to create config watcher: {}", e);
             }
         }
     }
@@ -184,78 +1062,398 @@ impl KeyBlastApp {
                         continue;
                     }
                     // Reload on modify, create, or rename events (editors use atomic save)
-                    // Reset to defaults on file deletion
+                    // Deletion is handled per on_config_delete below
                     match event.kind {
-                        EventKind::Modify(_) | EventKind::Create(_) => {
+                        EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_) => {
                             should_reload = true;
                         }
-                        EventKind::Remove(_) => {
-                            should_reload = true; // Will trigger reload which handles missing file
-                        }
                         _ => {}
                     }
                 }
             }
         }
 
-        if should_reload {
-            println!("Config file changed, reloading...");
-            self.reload_config();
+        if !should_reload {
+            return;
+        }
+        let (on_delete, locked) = self.config.as_ref()
+            .map_or((Default::default(), false), |cfg| (cfg.settings.on_config_delete, cfg.settings.locked));
+        match app::config_change_action(config_path.exists(), on_delete, locked) {
+            app::ConfigChangeAction::Reload => {
+                info!("Config file changed, reloading...");
+                self.reload_config();
+            }
+            app::ConfigChangeAction::KeepInMemory => {
+                warn!("Config file was deleted; keeping the config in memory");
+            }
+            app::ConfigChangeAction::Recreate => {
+                // The write shows up as a Create event and reloads the same config
+                if let Some(ref cfg) = self.config {
+                    match config::save_config(cfg) {
+                        Ok(()) => info!("Config file was deleted; recreated it from memory"),
+                        Err(e) => {
+                            error!("Failed to recreate deleted config: {}", e);
+                            notification::show_config_save_error(&e);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Start the localhost HTTP endpoint if `http_port` and `http_token` are set.
+    ///
+    /// Only read at startup; changing the port or token needs a restart.
+    fn start_http_server(&mut self) {
+        let Some(settings) = self.config.as_ref().map(|cfg| &cfg.settings) else {
+            return;
+        };
+        let Some(port) = settings.http_port else {
+            return;
+        };
+        let Some(token) = settings.http_token.clone().filter(|t| !t.trim().is_empty()) else {
+            warn!("http_port is set but http_token is empty; HTTP endpoint not started");
+            return;
+        };
+        let allowed_origin = settings.http_allowed_origin.clone();
+        let Some(proxy) = self.event_proxy.clone() else {
+            return;
+        };
+
+        match http::HttpServer::start(port, token, allowed_origin, move |name| {
+            let _ = proxy.send_event(AppEvent::RunByName(name));
+        }) {
+            Ok(server) => {
+                info!("HTTP endpoint listening on 127.0.0.1:{}", port);
+                self.http_server = Some(server);
+            }
+            Err(e) => error!("Failed to start HTTP endpoint on port {}: {}", port, e),
+        }
+    }
+
+    /// Start the hotstring listener or refresh its abbreviations from config.
+    ///
+    /// The listener can't be stopped once started, so disabling hotstrings
+    /// just clears its abbreviation list.
+    fn sync_hotstrings(&mut self) {
+        let (enabled, abbreviations) = match self.config {
+            Some(ref cfg) if cfg.settings.hotstrings_enabled && !self.safe_mode => {
+                (true, hotstring::abbreviations_from(&cfg.macros))
+            }
+            _ => (false, Vec::new()),
+        };
+
+        if let Some(ref listener) = self.hotstring_listener {
+            listener.set_abbreviations(abbreviations);
+        } else if enabled {
+            if let Some(proxy) = self.event_proxy.clone() {
+                self.hotstring_listener = Some(hotstring::HotstringListener::start(
+                    abbreviations,
+                    move |m| {
+                        let _ = proxy.send_event(AppEvent::Hotstring(m));
+                    },
+                ));
+                info!("Hotstring listener started");
+            }
+        }
+    }
+
+    /// Register the config's chord hotkeys, replacing the previous ones.
+    ///
+    /// Runs after the plain hotkeys are registered, so a first step one of
+    /// them already has is refused. None are registered in safe mode.
+    fn sync_chords(&mut self) {
+        let Some(ref mut manager) = self.hotkey_manager else {
+            return;
+        };
+        let (macros, timeout_ms) = match self.config {
+            Some(ref cfg) => (cfg.macros.as_slice(), cfg.settings.chord_timeout_ms),
+            None => (&[][..], 0),
+        };
+        let macros = hotkey::registrable_macros(macros, self.safe_mode);
+        let timeout = Some(timeout_ms)
+            .filter(|&ms| ms > 0)
+            .map(std::time::Duration::from_millis);
+        self.chords.set_chords(manager, &self.macros, macros, timeout);
+    }
+
+    /// Start the mouse listener or refresh its button bindings from config.
+    ///
+    /// Like the hotstring listener it can't be stopped, so disabling mouse
+    /// triggers just clears its bindings.
+    fn sync_mouse_triggers(&mut self) {
+        let (enabled, bindings) = match self.config {
+            Some(ref cfg) if cfg.settings.mouse_triggers_enabled && !self.safe_mode => {
+                (true, mouse_trigger::bindings_from(&cfg.macros))
+            }
+            _ => (false, Vec::new()),
+        };
+
+        if let Some(ref listener) = self.mouse_listener {
+            listener.set_bindings(bindings);
+        } else if enabled {
+            if let Some(proxy) = self.event_proxy.clone() {
+                self.mouse_listener = Some(mouse_trigger::MouseListener::start(
+                    bindings,
+                    move |macro_id| {
+                        let _ = proxy.send_event(AppEvent::MouseButton(macro_id));
+                    },
+                ));
+                info!("Mouse trigger listener started");
+            }
+        }
+    }
+
+    /// Start the pass-through key listener or update its key from config.
+    ///
+    /// Like the hotstring listener it can't be stopped, so unsetting
+    /// `suspend_while_held` just clears its key.
+    fn sync_suspend_key(&mut self) {
+        let name = self.config.as_ref().and_then(|cfg| cfg.settings.suspend_while_held.clone());
+        let key = name.as_deref().and_then(|name| {
+            let key = suspend::parse_hold_key(name);
+            if key.is_none() {
+                warn!("Unknown suspend_while_held key '{}'; pass-through is off", name);
+            }
+            key
+        });
+
+        if let Some(ref listener) = self.suspend_listener {
+            listener.set_key(key);
+        } else if key.is_some() {
+            self.suspend_listener = Some(suspend::SuspendListener::start(key));
+            info!("Pass-through listener started for '{}'", name.unwrap_or_default());
+        }
+    }
+
+    /// Stop the running macro right away and release held modifiers.
+    ///
+    /// Dropping the execution discards queued segments, so nothing more is
+    /// typed (e.g. into a newly focused window).
+    fn stop_and_release(&mut self) {
+        if let Some(execution) = self.active_execution.take() {
+            execution.stop();
+        }
+        self.execution_prepared = false;
+        if let Some(ref listener) = self.abort_listener {
+            listener.disarm();
+        }
+        if let Some(ref mut injector) = self.injector {
+            if let Err(e) = injector.release_modifiers() {
+                error!("Failed to release modifiers: {}", e);
+            }
+            injector.restore_saved_clipboard();
+        }
+    }
+
+    /// Start the user-input listener for `abort_on_user_input` once it is turned on.
+    ///
+    /// Like the other input listeners it can't be stopped; with the setting
+    /// off it just isn't armed when a macro starts.
+    fn sync_abort_on_input(&mut self) {
+        let enabled = self.config.as_ref().is_some_and(|cfg| cfg.settings.abort_on_user_input);
+        if !enabled || self.abort_listener.is_some() {
+            return;
+        }
+        if let Some(proxy) = self.event_proxy.clone() {
+            self.abort_listener = Some(input_abort::AbortListener::start(move || {
+                let _ = proxy.send_event(AppEvent::UserInput);
+            }));
+            info!("User input listener started");
+        }
+    }
+
+    /// Apply a multi-step config edit and save it, all or nothing.
+    ///
+    /// On save failure the config, hotkey map and hotkey registrations are
+    /// rolled back (see `transaction::run_config_transaction`). Returns None
+    /// if no config is loaded.
+    fn with_config_transaction<T, F>(&mut self, edit: F) -> Option<Result<T, config::ConfigError>>
+    where
+        F: FnOnce(&mut transaction::ConfigTransaction) -> T,
+    {
+        let cfg = self.config.as_mut()?;
+        let registrar = self
+            .hotkey_manager
+            .as_mut()
+            .map(|manager| manager as &mut dyn transaction::HotkeyRegistrar);
+        let result = transaction::run_config_transaction(
+            cfg,
+            &mut self.macros,
+            registrar,
+            config::save_config,
+            edit,
+        );
+        // The whole config was written, including any pending debounced change
+        if result.is_ok() {
+            self.config_dirty = false;
+            self.last_save = std::time::Instant::now();
+        }
+        self.sync_chords();
+        Some(result)
+    }
+
+    /// Note a config change to save later instead of writing it right away.
+    ///
+    /// For frequent small changes that don't need a transaction's rollback:
+    /// `about_to_wait` writes them at most every `CONFIG_SAVE_INTERVAL`, and
+    /// always before quitting.
+    fn mark_config_dirty(&mut self) {
+        self.config_dirty = true;
+    }
+
+    /// Write the config now if it has unsaved changes.
+    ///
+    /// A failed save is reported once and not retried until the next change.
+    fn flush_config(&mut self) {
+        if !self.config_dirty {
+            return;
+        }
+        self.config_dirty = false;
+        self.last_save = std::time::Instant::now();
+        let Some(ref cfg) = self.config else {
+            return;
+        };
+        match config::save_config(cfg) {
+            Ok(()) => debug!("Saved pending config changes"),
+            Err(e) => {
+                error!("Failed to save config: {}", e);
+                notification::show_config_save_error(&e);
+            }
+        }
+    }
+
+    /// Import macros from a TOML file or bundle and merge them into the config.
+    ///
+    /// Macros whose name already exists are handled per `strategy`. The path
+    /// and strategy are remembered as `settings.last_import_path` and
+    /// `last_import_strategy` (saved with the merged macros) for Re-import
+    /// Last File.
+    fn import_macros_from(&mut self, path: &std::path::Path, strategy: config::MergeStrategy) {
+        let (imported_macros, files) = match bundle::import_file(path, &config::config_dir()) {
+            Ok(imported) => imported,
+            Err(e) => {
+                error!("Failed to import macros: {}", e);
+                notification::show_error(
+                    "Import Macros",
+                    &format!("Couldn't import {}: {}", path.display(), e),
+                    notification::NotificationSeverity::ActionFailed,
+                );
+                return;
+            }
+        };
+        info!("Imported {} macros from: {}", imported_macros.len(), path.display());
+
+        // Merge imported macros (add new ones, handle existing names per
+        // strategy, resolve hotkey conflicts per import_conflict_policy).
+        // Runs as a transaction so a failed save doesn't leave
+        // hotkeys registered for macros that aren't on disk.
+        let policy = self.config.as_ref()
+            .map_or_else(Default::default, |cfg| cfg.settings.import_conflict_policy);
+        let result = self.with_config_transaction(|tx| {
+            tx.config().settings.last_import_path = Some(path.display().to_string());
+            tx.config().settings.last_import_strategy = Some(strategy);
+            transaction::import_macros(tx, imported_macros, strategy, policy)
+        });
+
+        match result {
+            Some(Ok(summary)) => {
+                files.keep();
+                for (name, e) in &summary.failed {
+                    error!("Failed to register imported macro '{}': {}", name, e);
+                }
+                info!("{}, config saved", summary.message());
+                notification::show_info("Import Macros", &summary.message());
+            }
+            // Dropping `files` removes any extracted text files
+            Some(Err(e)) => {
+                error!("Failed to save config after import (import undone): {}", e);
+                notification::show_config_save_error(&e);
+            }
+            None => {}
+        }
+
+        if let Some(ref cfg) = self.config {
+            // Refresh validation warnings after import
+            let warnings = config::validate_config(cfg);
+            for warning in &warnings {
+                warn!("Config warning: {}", warning);
+            }
+            self.config_warnings = warnings;
+
+            // Rebuild menu to show new macros, updated warnings and Re-import Last File
+            self.rebuild_menu();
+        }
+    }
+
+    /// Replace all macro hotkey registrations, chords included, with the
+    /// current config's.
+    fn reregister_macros(&mut self) {
+        let Some(ref mut manager) = self.hotkey_manager else {
+            return;
+        };
+        let (macros, timeout_ms) = match self.config {
+            Some(ref cfg) => (cfg.macros.as_slice(), cfg.settings.chord_timeout_ms),
+            None => (&[][..], 0),
+        };
+        let timeout = Some(timeout_ms)
+            .filter(|&ms| ms > 0)
+            .map(std::time::Duration::from_millis);
+        hotkey::reregister_macros(manager, &mut self.macros, &mut self.chords, macros, self.safe_mode, timeout);
+    }
+
+    /// Unregister and re-register every hotkey, including the stop hotkey.
+    ///
+    /// Registrations can silently stop firing after an OS session change
+    /// (screen lock, user switch) on Windows. Doesn't re-read the config file.
+    fn reregister_all(&mut self) {
+        self.reregister_macros();
+
+        if let Some(ref mut manager) = self.hotkey_manager {
+            let stop_hotkey = hotkey::stop_hotkey();
+            let _ = manager.unregister(&stop_hotkey);
+            match manager.register_raw(stop_hotkey) {
+                Ok(()) => self.stop_hotkey_id = Some(stop_hotkey.id()),
+                Err(e) => error!("Failed to register stop hotkey: {}", e),
+            }
         }
+        info!("Re-registered {} hotkeys", self.macros.len());
     }
 
     /// Reload config from disk and re-register hotkeys.
     fn reload_config(&mut self) {
         match config::load_config() {
             Ok(new_config) => {
-                // Unregister all old hotkeys
-                if let Some(ref mut manager) = self.hotkey_manager {
-                    for (_, macro_def) in self.macros.drain() {
-                        if let Some(hotkey) = config::parse_hotkey_string(&macro_def.hotkey) {
-                            let _ = manager.unregister(&hotkey);
-                        }
-                    }
-                }
-
-                // Register new hotkeys
-                for macro_def in &new_config.macros {
-                    if let Some(ref mut manager) = self.hotkey_manager {
-                        if let Some(hotkey) = config::parse_hotkey_string(&macro_def.hotkey) {
-                            match manager.register(hotkey, macro_def.name.clone()) {
-                                Ok(()) => {
-                                    let hotkey_id = hotkey.id();
-                                    self.macros.insert(hotkey_id, macro_def.clone());
-                                    println!("Registered: {} -> {}", macro_def.hotkey, macro_def.name);
-                                }
-                                Err(e) => {
-                                    eprintln!("Failed to register '{}': {}", macro_def.name, e);
-                                }
-                            }
-                        } else {
-                            eprintln!(
-                                "Invalid hotkey '{}' for macro '{}' (skipped during reload)",
-                                macro_def.hotkey, macro_def.name
-                            );
-                        }
-                    }
-                }
+                // The file changed since our last write, so it wins over an
+                // unsaved debounced change
+                self.config_dirty = false;
 
                 // Validate and store warnings
                 let warnings = config::validate_config(&new_config);
                 for warning in &warnings {
-                    eprintln!("Config warning: {}", warning);
+                    warn!("Config warning: {}", warning);
                 }
                 self.config_warnings = warnings;
 
                 // Apply settings from config file (sync enabled state)
-                self.state.enabled = new_config.settings.enabled;
+                // Safe mode stays disabled until the user enables it from the tray
+                if !self.safe_mode {
+                    self.state.enabled = new_config.settings.enabled;
+                }
 
+                self.blocklist = focus::Blocklist::new(&new_config.settings.blocklist_apps);
                 self.config = Some(new_config);
+                // Replace old hotkey registrations with the new config's
+                self.reregister_macros();
+                self.sync_hotstrings();
+                self.sync_mouse_triggers();
+                self.sync_suspend_key();
+                self.sync_abort_on_input();
                 self.rebuild_menu();
-                println!("Config reloaded successfully");
+                info!("Config reloaded successfully");
             }
             Err(e) => {
-                eprintln!("Failed to reload config: {}", e);
+                error!("Failed to reload config: {}", e);
             }
         }
     }
@@ -269,8 +1467,11 @@ impl ApplicationHandler<AppEvent> for KeyBlastApp {
             info!("KeyBlast initializing...");
 
             // Check accessibility permission (macOS)
-            // Detailed guidance is printed by the permission module if not granted
+            // Detailed guidance is printed by the permission module if not granted.
+            // This is the only prompting check; later re-checks use the silent API.
             let _ = permission::check_accessibility_permission();
+            self.accessibility_status = permission::accessibility_status();
+            self.last_permission_check = Some(std::time::Instant::now());
 
             // Initialize keystroke injector
             match injection::KeystrokeInjector::new() {
@@ -308,9 +1509,43 @@ impl ApplicationHandler<AppEvent> for KeyBlastApp {
 
             // Only seed example macros on first run (config file didn't exist)
             // This allows users to intentionally keep an empty macro list
-            let final_config = if is_first_run && loaded_config.macros.is_empty() {
-                let mut cfg = loaded_config;
-                cfg.macros = config::default_example_macros();
+            let config_dir = config::config_dir();
+            let final_config = if is_first_run
+                && loaded_config.macros.is_empty()
+                && !config::is_dir_writable(&config_dir)
+            {
+                // Seeding macros that can never be saved would leave an in-memory
+                // config that silently disappears on restart
+                warn!("Config directory is not writable, skipping example macros: {}", config_dir.display());
+                notification::show_error(
+                    "KeyBlast",
+                    &notification::not_writable_message(&config_dir),
+                    notification::NotificationSeverity::Permission,
+                );
+                loaded_config
+            } else if is_first_run && loaded_config.macros.is_empty() {
+                let template = config::default_macro_template_path();
+                let examples = config::first_run_macros(template.as_deref());
+                let cfg = if self.first_run_wizard {
+                    // A throwaway manager, as the real one isn't created yet
+                    let mut checker = hotkey::HotkeyManager::new().ok();
+                    let answers = wizard::ask(&examples, &config_dir, |hotkey| {
+                        checker.as_mut().is_some_and(|manager| {
+                            matches!(manager.check_available(*hotkey), hotkey::RegisterResult::Success)
+                        })
+                    });
+                    drop(checker);
+                    if answers.auto_start {
+                        if let Err(e) = autostart::set_auto_start(true) {
+                            error!("Failed to enable auto-start: {}", e);
+                        }
+                    }
+                    wizard::first_run_config(loaded_config, examples, &answers)
+                } else {
+                    let mut cfg = loaded_config;
+                    cfg.macros = examples;
+                    cfg
+                };
 
                 // Save the default config so user has a template
                 match config::save_config(&cfg) {
@@ -318,7 +1553,8 @@ impl ApplicationHandler<AppEvent> for KeyBlastApp {
                         info!("Created default config with example macros at: {}", config_path.display());
                     }
                     Err(e) => {
-                        eprintln!("Warning: Failed to save default config: {}", e);
+                        warn!("Failed to save default config: {}", e);
+                        notification::show_config_save_error(&e);
                     }
                 }
                 cfg
@@ -329,21 +1565,35 @@ impl ApplicationHandler<AppEvent> for KeyBlastApp {
             // Validate config and store warnings
             let warnings = config::validate_config(&final_config);
             for warning in &warnings {
-                eprintln!("Config warning: {}", warning);
+                warn!("Config warning: {}", warning);
             }
             self.config_warnings = warnings;
+            self.blocklist = focus::Blocklist::new(&final_config.settings.blocklist_apps);
             self.config = Some(final_config.clone());
 
             // Load enabled state from config (before build_menu so menu shows correct state)
-            self.state.enabled = final_config.settings.enabled;
+            self.state.enabled = final_config.settings.enabled && !self.safe_mode;
+            if self.safe_mode {
+                warn!("Safe mode: macros disabled, macro hotkeys and config auto-reload off");
+            }
 
             // Build menu with macros and create tray icon
-            let (menu, menu_ids) = tray::build_menu(
-                self.state.enabled,
-                &final_config.macros,
-                &self.config_warnings,
+            let (menu, menu_ids) = tray::build_menu(&tray::MenuInputs {
+                enabled: self.state.enabled,
+                macros: &final_config.macros,
+                warnings: &self.config_warnings,
+                // Hotkeys aren't registered yet; rebuilt below if any fail
+                hotkey_suggestions: &[],
+                history: &self.run_history,
+                accessibility: self.accessibility_status,
+                settings: &final_config.settings,
+                safe_mode: self.safe_mode,
+            });
+            let tray_icon = tray::create_tray(
+                &menu,
+                self.safe_mode,
+                final_config.settings.tray_click_action == config::TrayClickAction::Menu,
             );
-            let tray_icon = tray::create_tray(&menu);
 
             self.menu = menu;
             self.menu_ids = menu_ids;
@@ -357,59 +1607,50 @@ impl ApplicationHandler<AppEvent> for KeyBlastApp {
             match hotkey::HotkeyManager::new() {
                 Ok(mut manager) => {
                     // Register each macro from config
-                    for macro_def in &final_config.macros {
-                        match config::parse_hotkey_string(&macro_def.hotkey) {
-                            Some(hotkey) => {
-                                match manager.register(hotkey, macro_def.name.clone()) {
-                                    Ok(()) => {
-                                        let hotkey_id = hotkey.id();
-                                        self.macros.insert(hotkey_id, macro_def.clone());
-                                        debug!(
-                                            "Registered macro: {} ({})",
-                                            macro_def.name, macro_def.hotkey
-                                        );
-                                    }
-                                    Err(e) => {
-                                        error!(
-                                            "Failed to register macro '{}': {}",
-                                            macro_def.name, e
-                                        );
-                                    }
-                                }
-                            }
-                            None => {
-                                eprintln!(
-                                    "Invalid hotkey '{}' for macro '{}'",
-                                    macro_def.hotkey, macro_def.name
-                                );
-                            }
-                        }
-                    }
+                    let macros = hotkey::registrable_macros(&final_config.macros, self.safe_mode);
+                    hotkey::register_macros(&mut manager, &mut self.macros, macros);
 
                     // Register stop hotkey (Ctrl+Escape on all platforms)
-                    use global_hotkey::hotkey::{HotKey, Code, Modifiers};
-                    let stop_hotkey = HotKey::new(Some(Modifiers::CONTROL), Code::Escape);
+                    let stop_hotkey = hotkey::stop_hotkey();
                     match manager.register_raw(stop_hotkey) {
                         Ok(()) => {
                             self.stop_hotkey_id = Some(stop_hotkey.id());
-                            println!("Stop hotkey registered: Ctrl+Escape");
+                            info!("Stop hotkey registered: Ctrl+Escape");
                         }
                         Err(e) => {
-                            eprintln!("Failed to register stop hotkey: {}", e);
+                            error!("Failed to register stop hotkey: {}", e);
                         }
                     }
 
                     self.hotkey_manager = Some(manager);
+                    if !hotkey::unregistered_macros(macros, &self.macros).is_empty() {
+                        self.rebuild_menu();
+                    }
                 }
                 Err(e) => {
-                    eprintln!("Failed to create hotkey manager: {}", e);
+                    error!("Failed to create hotkey manager: {}", e);
                 }
             }
 
-            // Set up file watcher for hot-reload
-            self.setup_config_watcher();
+            self.sync_chords();
+
+            // Start listening for abbreviations if enabled
+            self.sync_hotstrings();
+            self.sync_mouse_triggers();
+            self.sync_suspend_key();
+            self.sync_abort_on_input();
+
+            if !self.safe_mode {
+                self.start_http_server();
+            }
+
+            // Set up file watcher for hot-reload (not in safe mode, so a broken
+            // config can be fixed via Edit Config File without it being applied)
+            if !self.safe_mode {
+                self.setup_config_watcher();
+            }
 
-            println!("KeyBlast running. Right-click tray icon for menu.");
+            info!("KeyBlast running. Right-click tray icon for menu.");
         }
     }
 
@@ -423,156 +1664,328 @@ impl ApplicationHandler<AppEvent> for KeyBlastApp {
                 if hotkey_event.state == HotKeyState::Pressed {
                     // Check for stop hotkey
                     if Some(hotkey_event.id) == self.stop_hotkey_id {
-                        if let Some(ref handle) = self.active_execution {
-                            handle.stop();
-                            println!("Stop hotkey pressed - macro will stop");
-                        }
+                        self.handle_stop_hotkey();
                         return;
                     }
 
+                    // A pending chord's second step wins over a plain hotkey
+                    // on the same keys. No chord starts while macros can't run.
+                    let suspended = self.suspend_listener.as_ref().is_some_and(|listener| listener.is_suspended());
+                    if let Some(manager) = self.hotkey_manager.as_mut().filter(|_| self.state.enabled && !suspended) {
+                        let now = std::time::Instant::now();
+                        match self.chords.press(manager, &self.macros, hotkey_event.id, now) {
+                            hotkey::ChordPress::Started => {
+                                debug!("Chord started, waiting for its second key");
+                                return;
+                            }
+                            hotkey::ChordPress::Completed(macro_def) => {
+                                info!("Chord triggered: {}", macro_def.name);
+                                self.trigger_macro(&macro_def, "hotkey");
+                                return;
+                            }
+                            hotkey::ChordPress::NotChord => {}
+                        }
+                    }
+
                     // Look up macro by hotkey_id
-                    if let Some(macro_def) = self.macros.get(&hotkey_event.id) {
-                        println!("Hotkey triggered: {}", macro_def.name);
+                    if let Some(macro_def) = self.macros.get(&hotkey_event.id).cloned() {
+                        info!("Hotkey triggered: {}", macro_def.name);
+                        self.trigger_macro(&macro_def, "hotkey");
+                    }
+                }
+            }
+            AppEvent::Hotstring(m) => {
+                let macro_def = self
+                    .config
+                    .as_ref()
+                    .filter(|cfg| cfg.settings.hotstrings_enabled)
+                    .and_then(|cfg| cfg.macros.iter().find(|m2| m2.id == m.macro_id))
+                    .cloned();
 
-                        // Check if macros are enabled
-                        if !self.state.enabled {
-                            println!("Macros disabled, ignoring hotkey");
-                            return;
-                        }
+                if let Some(macro_def) = macro_def {
+                    info!("Abbreviation triggered: {}", macro_def.name);
+                    // Erase the typed abbreviation before expanding
+                    self.trigger_macro_erasing(&macro_def, "hotstring", m.typed_len);
+                }
+            }
+            AppEvent::MouseButton(macro_id) => {
+                let macro_def = self
+                    .config
+                    .as_ref()
+                    .filter(|cfg| cfg.settings.mouse_triggers_enabled)
+                    .and_then(|cfg| cfg.macros.iter().find(|m| m.id == macro_id))
+                    .cloned();
 
-                        // Check if already executing
-                        if self.active_execution.is_some() {
-                            println!("Macro already running, ignoring new trigger");
-                            return;
-                        }
+                if let Some(macro_def) = macro_def {
+                    info!("Mouse button triggered: {}", macro_def.name);
+                    self.trigger_macro(&macro_def, "mouse");
+                }
+            }
+            AppEvent::UserInput => {
+                // Stale if the macro already ended or the setting was turned off
+                let abort = self.config.as_ref().is_some_and(|cfg| cfg.settings.abort_on_user_input);
+                if abort && self.active_execution.is_some() {
+                    info!("User input detected - stopping macro");
+                    self.stop_and_release();
+                    if self.config.as_ref().is_some_and(|cfg| cfg.settings.notify_on_cancel) {
+                        notification::show_info("KeyBlast", "Macro stopped");
+                    }
+                }
+            }
+            AppEvent::RunByName(name) => {
+                let macro_def = self
+                    .config
+                    .as_ref()
+                    .and_then(|cfg| cfg.macros.iter().find(|m| m.name == name))
+                    .cloned();
 
-                        // Inject the macro text using async execution
-                        if let Some(ref mut injector) = self.injector {
-                            let segments = injection::parse_macro_sequence(&macro_def.text);
-                            let mode_name = if macro_def.delay_ms == 0 {
-                                "instant"
-                            } else {
-                                "slow"
-                            };
-                            println!(
-                                "Injecting macro '{}' ({}): {}",
-                                macro_def.name, mode_name, macro_def.text
-                            );
-
-                            let has_delay = segments.iter().any(|s| matches!(s, injection::MacroSegment::Delay(_)));
-                            if macro_def.delay_ms == 0 && segments.len() <= 10 && !has_delay {
-                                // Fast path: short macros with no delay run synchronously
-                                // This avoids overhead for simple text expansion
-                                match injector.execute_sequence(&segments, 0) {
-                                    Ok(()) => {
-                                        println!("Injection complete");
-                                        self.flash_remaining = 4;
-                                        self.flash_state = false;
-                                        self.last_flash_toggle = Some(std::time::Instant::now());
-                                    }
-                                    Err(e) => {
-                                        eprintln!("Injection failed: {}", e);
-                                        notification::show_error(
-                                            "KeyBlast",
-                                            "Macro injection failed",
-                                            notification::NotificationSeverity::InjectionFailed,
-                                        );
-                                    }
-                                }
-                            } else {
-                                // Async path: spawn worker thread for long or delayed macros
-                                let (rx, handle) = execution::start_execution(segments, macro_def.delay_ms);
-                                self.execution_rx = Some(rx);
-                                self.active_execution = Some(handle);
-                                self.execution_prepared = false;
-                                // Flash happens when Complete command received
-                            }
-                        } else {
-                            eprintln!("No injector available");
-                        }
+                match macro_def {
+                    Some(macro_def) => {
+                        info!("HTTP triggered: {}", macro_def.name);
+                        self.trigger_macro(&macro_def, "http");
                     }
+                    None => warn!("HTTP run request for unknown macro: {}", name),
                 }
             }
         }
     }
 
     fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        // Auto-quit when idle (never while a macro is running)
+        let auto_quit_idle_minutes = self.config.as_ref()
+            .map_or(0, |cfg| cfg.settings.auto_quit_idle_minutes);
+        if self.active_execution.is_none()
+            && app::should_auto_quit(self.last_activity, std::time::Instant::now(), auto_quit_idle_minutes)
+        {
+            info!("No macro activity for {} minutes, quitting", auto_quit_idle_minutes);
+            // Don't leave {KeyDown} keys or modifiers stuck down
+            if let Some(ref mut injector) = self.injector {
+                let released = injector.release_stale_keys(std::time::Duration::ZERO)
+                    .and_then(|_| injector.release_modifiers());
+                if let Err(e) = released {
+                    warn!("Failed to release keys before quitting: {}", e);
+                }
+            }
+            // Logs are flushed when main's log guard drops after the loop exits
+            self.should_exit = true;
+        }
+
         // Check for clean shutdown request
         if self.should_exit {
+            // Don't lose a debounced change that hasn't been written yet
+            self.flush_config();
             event_loop.exit();
             return;
         }
 
         // Process async execution commands (non-blocking)
-        // Collect commands first to avoid borrow issues when clearing state
-        let commands: Vec<_> = self.execution_rx.as_ref()
-            .map(|rx| rx.try_iter().collect())
-            .unwrap_or_default();
-
-        let mut injection_failed = false;
-        for cmd in commands {
-            match cmd {
-                execution::ExecutionCommand::Inject(segment) => {
-                    if let Some(ref mut injector) = self.injector {
-                        // Prepare injector once at start of execution
-                        if !self.execution_prepared {
-                            if let Err(e) = injector.prepare_for_injection() {
-                                eprintln!("Failed to prepare injection: {}", e);
-                                notification::show_error(
-                                    "KeyBlast",
-                                    &format!("Failed to prepare injection: {}", e),
-                                    notification::NotificationSeverity::InjectionFailed,
-                                );
-                                injection_failed = true;
-                                break;
-                            }
-                            self.execution_prepared = true;
-                        }
-                        // Execute segment on main thread (safe for macOS TIS/TSM)
-                        if let Err(e) = injector.execute_single_segment(&segment) {
-                            eprintln!("Injection error: {}", e);
-                            notification::show_error(
-                                "KeyBlast",
-                                "Macro injection failed",
-                                notification::NotificationSeverity::InjectionFailed,
-                            );
-                            injection_failed = true;
-                            break;
-                        }
+        let injector = &mut self.injector;
+        let execution_prepared = &mut self.execution_prepared;
+        let abort_listener = &self.abort_listener;
+        let hotstring_listener = &self.hotstring_listener;
+        let events = match self.active_execution.as_mut() {
+            Some(execution) => execution.pump(|segment| {
+                let Some(injector) = injector.as_mut() else {
+                    return Ok(());
+                };
+                // Prepare injector once at start of execution
+                if !*execution_prepared {
+                    if let Err(e) = injector.prepare_for_injection() {
+                        error!("Failed to prepare injection: {}", e);
+                        notification::show_injection_error(&e);
+                        return Err(e);
                     }
+                    *execution_prepared = true;
                 }
-                execution::ExecutionCommand::Complete => {
-                    println!("Macro execution complete");
-                    self.active_execution = None;
-                    self.execution_rx = None;
-                    self.execution_prepared = false;
+                // Execute segment on main thread (safe for macOS TIS/TSM);
+                // the input listeners ignore what it types
+                if let Some(listener) = abort_listener {
+                    listener.begin_injection();
+                }
+                if let Some(listener) = hotstring_listener {
+                    listener.begin_injection();
+                }
+                let result = injector.execute_single_segment(segment);
+                if let Some(listener) = abort_listener {
+                    listener.end_injection();
+                }
+                if let Some(listener) = hotstring_listener {
+                    listener.end_injection();
+                }
+                result.inspect_err(|e| {
+                    error!("Injection error: {}", e);
+                    notification::show_injection_error(e);
+                })
+            }),
+            None => Vec::new(),
+        };
+
+        let notify_on_cancel = self.config.as_ref().is_some_and(|cfg| cfg.settings.notify_on_cancel);
+        let notify_on_complete = self.config.as_ref().is_some_and(|cfg| cfg.settings.notify_on_complete);
+        for event in events {
+            self.last_activity = std::time::Instant::now();
+            if app::should_notify_cancel(&event, notify_on_cancel) {
+                notification::show_info("KeyBlast", "Macro stopped");
+            }
+            let macro_name = self.active_execution.as_ref().and_then(execution::Execution::macro_name);
+            if let Some(message) = app::completion_notice(&event, notify_on_complete, macro_name) {
+                notification::show_info("KeyBlast", &message);
+            }
+            match event {
+                execution::ExecutionEvent::Completed => {
+                    info!("Macro execution complete");
                     // Trigger icon flash AFTER completion
-                    self.flash_remaining = 4;
-                    self.flash_state = false;
-                    self.last_flash_toggle = Some(std::time::Instant::now());
-                }
-                execution::ExecutionCommand::Cancelled => {
-                    println!("Macro execution cancelled");
-                    self.active_execution = None;
-                    self.execution_rx = None;
-                    self.execution_prepared = false;
+                    self.start_flash(4);
+                }
+                execution::ExecutionEvent::Cancelled => {
+                    info!("Macro execution cancelled");
                     // No flash on cancel - user knows they cancelled
                 }
+                _ => {}
             }
         }
 
-        // Handle injection failure: stop execution and clean up
-        if injection_failed {
-            if let Some(ref handle) = self.active_execution {
-                handle.stop();
-            }
+        // Clear finished execution (completed, cancelled, or stopped after a failure)
+        if self.active_execution.as_ref().is_some_and(|execution| execution.is_done()) {
             self.active_execution = None;
-            self.execution_rx = None;
             self.execution_prepared = false;
+            if let Some(ref listener) = self.abort_listener {
+                listener.disarm();
+            }
+            if let Some(ref mut injector) = self.injector {
+                injector.restore_saved_clipboard();
+            }
+        }
+
+        // Stop if focus moved to another window mid-macro. A {Prompt} dialog
+        // takes focus on purpose, so don't look while one is open.
+        let stop_on_focus_loss = self.config.as_ref()
+            .is_some_and(|cfg| cfg.settings.stop_on_focus_loss);
+        let action = focus::focus_action(
+            self.focus_watcher.as_mut(),
+            stop_on_focus_loss,
+            self.active_execution.as_ref().is_some_and(|execution| !execution.is_awaiting_prompt()),
+        );
+        if action == focus::FocusAction::StopAndRelease {
+            info!("Focus changed - stopping macro");
+            self.stop_and_release();
+        }
+
+        // Keydown watchdog: release keys whose {KeyUp} never came
+        let keydown_safety_ms = self.config.as_ref()
+            .map_or(0, |cfg| cfg.settings.keydown_safety_ms);
+        let mut holding_keys = false;
+        if keydown_safety_ms > 0 {
+            if let Some(ref mut injector) = self.injector {
+                match injector.release_stale_keys(std::time::Duration::from_millis(keydown_safety_ms)) {
+                    Ok(released) => {
+                        for key in released {
+                            warn!("Auto-released {:?}: held for over {}ms without KeyUp", key, keydown_safety_ms);
+                        }
+                    }
+                    Err(e) => {
+                        error!("Failed to release held keys: {}", e);
+                    }
+                }
+                holding_keys = injector.has_held_keys();
+            }
+        }
+        // Re-check a missing accessibility permission without prompting
+        let awaiting_permission = self.accessibility_status == Some(false);
+        if awaiting_permission {
+            let due = self.last_permission_check
+                .is_none_or(|t| t.elapsed() >= PERMISSION_RECHECK_INTERVAL);
+            if due {
+                self.last_permission_check = Some(std::time::Instant::now());
+                let status = permission::accessibility_status();
+                if status != self.accessibility_status {
+                    self.accessibility_status = status;
+                    if status == Some(true) {
+                        info!("Accessibility permission granted");
+                        notification::show_info(
+                            "KeyBlast",
+                            "Accessibility permission granted. If macros still don't type, restart KeyBlast.",
+                        );
+                    }
+                    self.refresh_menu();
+                }
+            }
+        }
+
+        // Keep the machine awake by tapping a harmless key while idle
+        let keep_awake_interval_s = self.config.as_ref()
+            .map_or(0, |cfg| cfg.settings.keep_awake_interval_s);
+        let now = std::time::Instant::now();
+        if self.state.enabled
+            && self.active_execution.is_none()
+            && keep_awake::should_tick(self.last_keep_awake, now, keep_awake_interval_s)
+        {
+            self.last_keep_awake = Some(now);
+            if let Some(ref mut injector) = self.injector {
+                let tick = injection::MacroSegment::SpecialKey(keep_awake::KEEP_AWAKE_KEY);
+                if let Err(e) = injector.execute_single_segment(&tick) {
+                    error!("Keep-awake tick failed: {}", e);
+                }
+            }
+        }
+
+        // Write debounced config changes
+        if app::should_flush_config(self.config_dirty, self.last_save, now, CONFIG_SAVE_INTERVAL) {
+            self.flush_config();
+        }
+
+        // Give up on a chord whose second key didn't come in time
+        if let Some(ref mut manager) = self.hotkey_manager {
+            self.chords.expire(manager, now);
+        }
+
+        // Verbose logging ran its course
+        if logging::verbose_logging_expired(self.verbose_logging_until, now) {
+            self.set_verbose_logging(false);
+        }
+
+        // Tick a macro's countdown_secs, then run it
+        self.advance_countdown(now);
+
+        // Wake up periodically while keys are held so the watchdog can fire,
+        // while a {Prompt} dialog is open to pick up its answer,
+        // while waiting for the user to grant accessibility permission,
+        // for the next keep-awake tick, to auto-quit when idle, to end
+        // verbose logging, to time out a pending chord, to save the config,
+        // and for the next countdown tick and its flash
+        let mut wake_times = Vec::new();
+        wake_times.extend(app::config_flush_deadline(self.config_dirty, self.last_save, CONFIG_SAVE_INTERVAL));
+        wake_times.extend(self.verbose_logging_until);
+        wake_times.extend(self.chords.deadline());
+        wake_times.extend(self.countdown.as_ref().map(app::Countdown::next_due));
+        if self.flash_remaining > 0 {
+            wake_times.push(now + std::time::Duration::from_millis(100));
+        }
+        if self.active_execution.is_none() {
+            if let Some(deadline) = app::auto_quit_deadline(self.last_activity, auto_quit_idle_minutes) {
+                wake_times.push(deadline);
+            }
+        }
+        if holding_keys {
+            wake_times.push(now + std::time::Duration::from_millis(100));
+        }
+        if self.active_execution.as_ref().is_some_and(execution::Execution::is_awaiting_prompt) {
+            wake_times.push(now + std::time::Duration::from_millis(50));
+        }
+        if awaiting_permission {
+            wake_times.push(now + PERMISSION_RECHECK_INTERVAL);
+        }
+        if keep_awake_interval_s > 0 {
+            let last = self.last_keep_awake.unwrap_or(now);
+            wake_times.push(last + std::time::Duration::from_secs(keep_awake_interval_s));
+        }
+        match wake_times.into_iter().min() {
+            Some(wake_at) => event_loop.set_control_flow(ControlFlow::WaitUntil(wake_at)),
+            None => event_loop.set_control_flow(ControlFlow::Wait),
         }
 
         // Update Stop Macro menu item enabled state
-        let is_running = self.active_execution.is_some();
+        let is_running = self.active_execution.is_some() || self.countdown.is_some();
         for item in self.menu.items() {
             if let muda::MenuItemKind::MenuItem(normal_item) = item {
                 if normal_item.id() == &self.menu_ids.stop_macro {
@@ -609,6 +2022,13 @@ impl ApplicationHandler<AppEvent> for KeyBlastApp {
         // Check for config file changes (hot-reload)
         self.check_config_changes();
 
+        // Process tray icon clicks (left button, on release)
+        while let Ok(event) = TrayIconEvent::receiver().try_recv() {
+            if let TrayIconEvent::Click { button: MouseButton::Left, button_state: MouseButtonState::Up, .. } = event {
+                self.handle_tray_click();
+            }
+        }
+
         // Process any pending menu events
         while let Ok(event) = MenuEvent::receiver().try_recv() {
             // Check if this is a run macro action (check before delete and static IDs)
@@ -621,135 +2041,82 @@ impl ApplicationHandler<AppEvent> for KeyBlastApp {
                     .cloned();
 
                 if let Some(macro_def) = macro_def {
-                    // Check if macros are enabled
-                    if !self.state.enabled {
-                        println!("Macros disabled, ignoring run request");
-                        continue;
-                    }
+                    info!("Running macro '{}' from menu", macro_def.name);
+                    self.trigger_macro(&macro_def, "menu");
+                }
+                continue;
+            }
 
-                    // Check if already executing
-                    if self.active_execution.is_some() {
-                        println!("Macro already running, ignoring new trigger");
-                        continue;
-                    }
+            if let Some(&(macro_id, hotkey)) = self.menu_ids.suggested_hotkey_ids.get(&event.id) {
+                self.apply_suggested_hotkey(macro_id, hotkey);
+                continue;
+            }
 
-                    // Trigger execution (same logic as hotkey trigger)
-                    if let Some(ref mut injector) = self.injector {
-                        let segments = injection::parse_macro_sequence(&macro_def.text);
-                        println!("Running macro '{}' from menu", macro_def.name);
-
-                        let has_delay = segments.iter().any(|s| matches!(s, injection::MacroSegment::Delay(_)));
-                        if macro_def.delay_ms == 0 && segments.len() <= 10 && !has_delay {
-                            // Fast path: short macros with no delay
-                            match injector.execute_sequence(&segments, 0) {
-                                Ok(()) => {
-                                    println!("Injection complete");
-                                    self.flash_remaining = 4;
-                                    self.flash_state = false;
-                                    self.last_flash_toggle = Some(std::time::Instant::now());
-                                }
-                                Err(e) => {
-                                    eprintln!("Injection failed: {}", e);
-                                    notification::show_error(
-                                        "KeyBlast",
-                                        "Macro injection failed",
-                                        notification::NotificationSeverity::InjectionFailed,
-                                    );
-                                }
-                            }
-                        } else {
-                            // Async path
-                            let (rx, handle) = execution::start_execution(segments, macro_def.delay_ms);
-                            self.execution_rx = Some(rx);
-                            self.active_execution = Some(handle);
-                            self.execution_prepared = false;
-                        }
-                    }
+            if let Some(&strategy) = self.menu_ids.import_macro_ids.get(&event.id) {
+                // Show open file dialog
+                if let Some(path) = FileDialog::new()
+                    .add_filter("TOML", &["toml"])
+                    .add_filter("JSON", &["json"])
+                    .add_filter("Bundle with text files", &["zip"])
+                    .add_filter("Tab-separated list", &["tsv", "txt"])
+                    .pick_file()
+                {
+                    self.import_macros_from(&path, strategy);
                 }
                 continue;
             }
 
+            if let Some(&macro_id) = self.menu_ids.toggle_macro_ids.get(&event.id) {
+                self.toggle_macro_enabled(macro_id);
+                continue;
+            }
+
+            if let Some(&macro_id) = self.menu_ids.edit_macro_ids.get(&event.id) {
+                self.edit_macro(macro_id);
+                continue;
+            }
+
             // Check if this is a delete macro action (check before static IDs)
             if let Some(macro_id) = self.menu_ids.delete_macro_ids.get(&event.id) {
                 let macro_id = *macro_id; // Copy the UUID
-                println!("Deleting macro with ID: {}", macro_id);
-
-                if let Some(ref mut cfg) = self.config {
-                    // Find and remove the macro by UUID
-                    let original_len = cfg.macros.len();
-                    cfg.macros.retain(|m| m.id != macro_id);
-
-                    if cfg.macros.len() < original_len {
-                        // Find and unregister the hotkey
-                        if let Some(ref mut manager) = self.hotkey_manager {
-                            let mut id_to_remove = None;
-                            for (&hotkey_id, binding) in self.macros.iter() {
-                                if binding.id == macro_id {
-                                    if let Some(hotkey) = config::parse_hotkey_string(&binding.hotkey) {
-                                        let _ = manager.unregister(&hotkey);
-                                    }
-                                    id_to_remove = Some(hotkey_id);
-                                    break;
-                                }
-                            }
-                            if let Some(id) = id_to_remove {
-                                self.macros.remove(&id);
-                            }
+                info!("Deleting macro with ID: {}", macro_id);
+
+                let exists = self.config.as_ref()
+                    .is_some_and(|cfg| cfg.macros.iter().any(|m| m.id == macro_id));
+                if exists {
+                    // Remove the macro and unregister its hotkey, saving atomically
+                    let result = self.with_config_transaction(|tx| {
+                        tx.unregister_macro(macro_id);
+                        tx.config().macros.retain(|m| m.id != macro_id);
+                    });
+                    match result {
+                        Some(Ok(())) => {
+                            info!("Macro deleted and config saved");
                         }
-
-                        // Re-validate after deletion
-                        self.config_warnings = config::validate_config(cfg);
-
-                        // Save updated config
-                        match config::save_config(cfg) {
-                            Ok(()) => {
-                                println!("Macro deleted and config saved");
-                            }
-                            Err(e) => {
-                                eprintln!("Failed to save config after delete: {}", e);
-                            }
+                        Some(Err(e)) => {
+                            error!("Failed to save config after delete (delete undone): {}", e);
+                            notification::show_config_save_error(&e);
                         }
+                        None => {}
+                    }
 
-                        // Rebuild menu to reflect changes
-                        self.rebuild_menu();
+                    // Re-validate after deletion
+                    if let Some(ref cfg) = self.config {
+                        self.config_warnings = config::validate_config(cfg);
                     }
+
+                    // Rebuild menu to reflect changes
+                    self.rebuild_menu();
                 }
                 continue; // Skip further processing for this event
             }
 
             if event.id == self.menu_ids.toggle {
-                // Toggle enabled state
-                self.state.toggle();
-                println!(
-                    "KeyBlast {}",
-                    if self.state.enabled {
-                        "enabled"
-                    } else {
-                        "disabled"
-                    }
-                );
-
-                // Save enabled state to config immediately
-                if let Some(ref mut cfg) = self.config {
-                    cfg.settings.enabled = self.state.enabled;
-                    if let Err(e) = config::save_config(cfg) {
-                        eprintln!("Failed to save enabled state: {}", e);
-                    }
-                }
-
-                // Update the checkbox state
-                for item in self.menu.items() {
-                    if let muda::MenuItemKind::Check(check_item) = item {
-                        if check_item.id() == &self.menu_ids.toggle {
-                            check_item.set_checked(self.state.enabled);
-                            break;
-                        }
-                    }
-                }
+                self.set_enabled(!self.state.enabled);
             } else if event.id == self.menu_ids.edit_config {
                 // Open config file in default editor
                 let config_path = config::config_path();
-                println!("Opening config file: {}", config_path.display());
+                info!("Opening config file: {}", config_path.display());
 
                 #[cfg(target_os = "macos")]
                 {
@@ -772,108 +2139,152 @@ impl ApplicationHandler<AppEvent> for KeyBlastApp {
                         .spawn();
                 }
 
-                println!("Changes will be applied automatically when you save the file.");
+                info!("Changes will be applied automatically when you save the file.");
             } else if event.id == self.menu_ids.export_macros {
                 // Show save file dialog
                 if let Some(path) = FileDialog::new()
                     .add_filter("TOML", &["toml"])
+                    .add_filter("JSON", &["json"])
+                    .add_filter("Bundle with text files", &["zip"])
                     .set_file_name("keyblast-macros.toml")
                     .save_file()
                 {
                     if let Some(ref cfg) = self.config {
-                        match config::export_macros(&cfg.macros, &path) {
+                        // A .zip also carries the files referenced by `text_file`
+                        let macros = config::macros_for_export(cfg);
+                        let result = if bundle::is_bundle_path(&path) {
+                            bundle::export_bundle(&macros, &config::config_dir(), &path)
+                        } else {
+                            config::export_macros(&macros, &path)
+                        };
+                        match result {
+                            Ok(()) => {
+                                info!("Macros exported to: {}", path.display());
+                            }
+                            Err(e) => {
+                                error!("Failed to export macros: {}", e);
+                            }
+                        }
+                    }
+                }
+            } else if event.id == self.menu_ids.export_shareable {
+                // Show save file dialog
+                if let Some(path) = FileDialog::new()
+                    .add_filter("TOML", &["toml"])
+                    .add_filter("JSON", &["json"])
+                    .set_file_name("keyblast-macros-shared.toml")
+                    .save_file()
+                {
+                    if let Some(ref cfg) = self.config {
+                        let macros = config::macros_for_export(cfg);
+                        match config::export_macros_shareable(&macros, &config::config_dir(), &path) {
                             Ok(()) => {
-                                println!("Macros exported to: {}", path.display());
+                                info!("Macros exported for sharing to: {}", path.display());
                             }
                             Err(e) => {
-                                eprintln!("Failed to export macros: {}", e);
+                                error!("Failed to export macros for sharing: {}", e);
                             }
                         }
                     }
                 }
-            } else if event.id == self.menu_ids.import_macros {
-                // Show open file dialog
+            } else if event.id == self.menu_ids.export_config {
+                // Show save file dialog
                 if let Some(path) = FileDialog::new()
                     .add_filter("TOML", &["toml"])
+                    .add_filter("JSON", &["json"])
+                    .set_file_name("keyblast-config.toml")
+                    .save_file()
+                {
+                    if let Some(ref cfg) = self.config {
+                        match config::export_config(cfg, &path) {
+                            Ok(()) => {
+                                info!("Config exported to: {}", path.display());
+                            }
+                            Err(e) => {
+                                error!("Failed to export config: {}", e);
+                            }
+                        }
+                    }
+                }
+            } else if event.id == self.menu_ids.import_config {
+                // Greyed out when locked; replace_config would bypass the transaction check
+                if self.config.as_ref().is_some_and(|cfg| cfg.settings.locked) {
+                    error!("Failed to import config: {}", config::ConfigError::Locked);
+                } else if let Some(path) = FileDialog::new()
+                    .add_filter("TOML", &["toml"])
+                    .add_filter("JSON", &["json"])
                     .pick_file()
                 {
-                    match config::import_macros(&path) {
-                        Ok(imported_macros) => {
-                            println!("Imported {} macros from: {}", imported_macros.len(), path.display());
-
-                            if let Some(ref mut cfg) = self.config {
-                                // Merge imported macros (add new ones, skip duplicates by name)
-                                let mut existing_names: std::collections::HashSet<_> =
-                                    cfg.macros.iter().map(|m| m.name.clone()).collect();
-                                // Collect existing IDs to detect collisions
-                                let existing_ids: std::collections::HashSet<_> =
-                                    cfg.macros.iter().map(|m| m.id).collect();
-
-                                let mut added = 0;
-                                for mut macro_def in imported_macros {
-                                    if !existing_names.contains(&macro_def.name) {
-                                        // Regenerate ID if it collides with existing macros
-                                        if existing_ids.contains(&macro_def.id) {
-                                            macro_def.id = uuid::Uuid::new_v4();
+                    match config::import_config(&path) {
+                        Ok(imported) => {
+                            let confirmed = rfd::MessageDialog::new()
+                                .set_level(rfd::MessageLevel::Warning)
+                                .set_title("Import Full Config")
+                                .set_description(format!(
+                                    "Replace your current settings and macros with {} macros from {}?\n\nYour current config will be backed up to {}.bak.",
+                                    imported.macros.len(),
+                                    path.display(),
+                                    config::config_path().file_name().unwrap_or_default().to_string_lossy()
+                                ))
+                                .set_buttons(rfd::MessageButtons::OkCancel)
+                                .show();
+
+                            if confirmed == rfd::MessageDialogResult::Ok {
+                                match config::replace_config(&imported) {
+                                    Ok(backup) => {
+                                        if let Some(backup) = backup {
+                                            info!("Previous config backed up to: {}", backup.display());
                                         }
-                                        // Register the hotkey for the new macro
-                                        if let Some(ref mut manager) = self.hotkey_manager {
-                                            if let Some(hotkey) = config::parse_hotkey_string(&macro_def.hotkey) {
-                                                match manager.register(hotkey, macro_def.name.clone()) {
-                                                    Ok(()) => {
-                                                        let hotkey_id = hotkey.id();
-                                                        // Track this name to prevent duplicates within import
-                                                        existing_names.insert(macro_def.name.clone());
-                                                        self.macros.insert(hotkey_id, macro_def.clone());
-                                                        cfg.macros.push(macro_def);
-                                                        added += 1;
-                                                    }
-                                                    Err(e) => {
-                                                        eprintln!("Failed to register imported macro '{}': {}", macro_def.name, e);
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    } else {
-                                        println!("Skipping duplicate macro: {}", macro_def.name);
-                                    }
-                                }
-
-                                // Save updated config
-                                match config::save_config(cfg) {
-                                    Ok(()) => {
-                                        println!("Added {} new macros, config saved", added);
+                                        info!("Config imported from: {}", path.display());
+                                        // Re-register hotkeys and apply imported settings
+                                        self.reload_config();
                                     }
                                     Err(e) => {
-                                        eprintln!("Failed to save config after import: {}", e);
+                                        error!("Failed to replace config: {}", e);
                                     }
                                 }
-
-                                // Refresh validation warnings after import
-                                let warnings = config::validate_config(cfg);
-                                for warning in &warnings {
-                                    eprintln!("Config warning: {}", warning);
-                                }
-                                self.config_warnings = warnings;
-
-                                // Rebuild menu to show new macros and updated warnings
-                                self.rebuild_menu();
                             }
                         }
                         Err(e) => {
-                            eprintln!("Failed to import macros: {}", e);
+                            error!("Failed to import config: {}", e);
                         }
                     }
                 }
+            } else if event.id == self.menu_ids.reimport_last {
+                let (last, strategy) = self.config.as_ref().map_or((None, None), |cfg| {
+                    (cfg.settings.last_import_path.clone(), cfg.settings.last_import_strategy)
+                });
+                match last.map(std::path::PathBuf::from) {
+                    Some(path) if path.exists() => self.import_macros_from(&path, strategy.unwrap_or_default()),
+                    Some(path) => {
+                        warn!("Last imported file no longer exists: {}", path.display());
+                        notification::show_error(
+                            "KeyBlast",
+                            &format!("Can't re-import: {} no longer exists", path.display()),
+                            notification::NotificationSeverity::ActionFailed,
+                        );
+                    }
+                    None => {}
+                }
+            } else if event.id == self.menu_ids.search_macros {
+                self.search_and_run();
+            } else if event.id == self.menu_ids.record_keystrokes {
+                self.toggle_recording();
+            } else if event.id == self.menu_ids.reregister_hotkeys {
+                self.reregister_all();
             } else if event.id == self.menu_ids.open_logs {
                 // Open logs directory in system file browser
                 logging::open_logs_directory();
+            } else if event.id == self.menu_ids.copy_logs {
+                self.copy_logs_to_clipboard();
+            } else if event.id == self.menu_ids.verbose_logging {
+                self.set_verbose_logging(self.verbose_logging_until.is_none());
             } else if event.id == self.menu_ids.auto_start {
                 // Toggle auto-start at login
                 let currently_enabled = autostart::is_auto_start_enabled();
                 match autostart::set_auto_start(!currently_enabled) {
                     Ok(()) => {
-                        println!(
+                        info!(
                             "Auto-start {}",
                             if !currently_enabled { "enabled" } else { "disabled" }
                         );
@@ -888,21 +2299,44 @@ impl ApplicationHandler<AppEvent> for KeyBlastApp {
                         }
                     }
                     Err(e) => {
-                        eprintln!("Failed to toggle auto-start: {}", e);
+                        error!("Failed to toggle auto-start: {}", e);
                     }
                 }
             } else if event.id == self.menu_ids.stop_macro {
-                if let Some(ref handle) = self.active_execution {
-                    handle.stop();
-                    println!("Stop menu clicked - macro will stop");
+                if let Some(countdown) = self.countdown.take() {
+                    info!("Stop menu clicked - '{}' won't run", countdown.macro_def.name);
+                }
+                if let Some(ref execution) = self.active_execution {
+                    execution.stop();
+                    info!("Stop menu clicked - macro will stop");
                 }
             } else if event.id == self.menu_ids.quit {
+                let confirm = app::should_confirm_quit(
+                    self.active_execution.is_some(),
+                    self.config.as_ref().is_some_and(|cfg| cfg.settings.confirm_quit_if_running),
+                );
+                if confirm {
+                    let confirmed = rfd::MessageDialog::new()
+                        .set_level(rfd::MessageLevel::Warning)
+                        .set_title("Quit KeyBlast")
+                        .set_description("A macro is still running. Stop it and quit?")
+                        .set_buttons(rfd::MessageButtons::OkCancel)
+                        .show();
+                    if confirmed != rfd::MessageDialogResult::Ok {
+                        info!("Quit cancelled");
+                        continue;
+                    }
+                }
+
                 // Clean up active execution if running
-                if let Some(handle) = self.active_execution.take() {
-                    handle.stop();
-                    handle.join();
+                if let Some(execution) = self.active_execution.take() {
+                    execution.stop();
+                    execution.join();
                 }
-                println!("KeyBlast shutting down.");
+                if let Some(ref mut injector) = self.injector {
+                    injector.restore_saved_clipboard();
+                }
+                info!("KeyBlast shutting down.");
                 // Set flag for clean exit (allows destructors to run for log flushing)
                 self.should_exit = true;
             }
@@ -913,11 +2347,126 @@ impl ApplicationHandler<AppEvent> for KeyBlastApp {
 fn main() {
     // Initialize file logging BEFORE event loop creation
     // Keep guard alive for program lifetime
-    let _log_guard = logging::init_file_logging();
+    let log_to_stdout = logging::stdout_logging_enabled(
+        config::load_config().is_ok_and(|cfg| cfg.settings.log_to_stdout),
+        std::env::var(logging::LOG_STDOUT_ENV).ok().as_deref(),
+        logging::console_available(),
+    );
+    let log_level = logging::log_level(std::env::var(logging::LOG_LEVEL_ENV).ok().as_deref());
+    let (_log_guard, log_level_handle) = logging::init_file_logging(log_to_stdout, log_level).unzip();
+
+    // Hotkey strings resolve against the layout chosen at startup
+    let hotkey_layout = config::load_config()
+        .map_or_else(|_| Default::default(), |cfg| cfg.settings.hotkey_layout);
+    config::set_hotkey_layout(hotkey_layout);
+    if hotkey_layout == config::HotkeyLayout::Logical && !keyboard_layout::LOGICAL_SUPPORTED {
+        warn!("hotkey_layout = \"logical\" is not supported on this platform, using physical key positions");
+    }
+
+    // Handle one-shot CLI commands before starting the tray
+    let command = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("Error: {}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    match command {
+        cli::CliCommand::RunTray { .. } | cli::CliCommand::SafeMode => {}
+        cli::CliCommand::Inject { text, delay_ms, wait_ms } => {
+            if let Err(e) = cli::run_inject(&text, delay_ms, wait_ms) {
+                error!("{}", e);
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        cli::CliCommand::Run { name, from, wait_ms } => {
+            if let Err(e) = cli::run_macro(&name, from.as_deref(), wait_ms) {
+                error!("{}", e);
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        cli::CliCommand::Diff { old, new, delay_ms } => {
+            // Exit status follows diff(1): 0 identical, 1 different
+            let identical = cli::run_diff(&old, &new, delay_ms);
+            std::process::exit(if identical { 0 } else { 1 });
+        }
+        cli::CliCommand::CheckHotkey(hotkey) => {
+            let (message, code) = cli::run_check_hotkey(&hotkey);
+            println!("{}", message);
+            std::process::exit(code);
+        }
+        cli::CliCommand::Doctor => {
+            print!("{}", doctor::format_report(&doctor::DoctorInputs::gather()));
+            return;
+        }
+        cli::CliCommand::Audit => {
+            // Exit status: 0 nothing flagged, 1 flagged, 2 config unreadable
+            match cli::run_audit() {
+                Ok((report, flagged)) => {
+                    print!("{}", report);
+                    std::process::exit(if flagged { 1 } else { 0 });
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(2);
+                }
+            }
+        }
+        cli::CliCommand::Import { path, strategy } => {
+            match cli::run_import(std::path::Path::new(&path), strategy) {
+                Ok(summary) => print!("{}", summary),
+                Err(e) => {
+                    error!("{}", e);
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        cli::CliCommand::Version => {
+            println!("{}", cli::build_version());
+            return;
+        }
+        cli::CliCommand::Bench { chars, wait_ms, dry_run } => {
+            match cli::run_bench(chars, wait_ms, dry_run) {
+                Ok(summary) => println!("{}", summary),
+                Err(e) => {
+                    error!("{}", e);
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+    }
 
     // Initialize notification system (sets bundle identifier on macOS)
     notification::init();
 
+    // Only one instance may own the hotkeys; the lock is released when
+    // `instance_lock` is dropped at the end of main (or by the OS on exit)
+    let instance_lock = match instance::InstanceLock::acquire(&config::config_dir()) {
+        Ok(Some(lock)) => {
+            debug!("Holding single-instance lock: {}", lock.path().display());
+            Some(lock)
+        }
+        Ok(None) => {
+            info!("Another KeyBlast instance is already running, exiting");
+            notification::show_info("KeyBlast", "KeyBlast is already running \u{2014} look for it in the system tray");
+            return;
+        }
+        Err(e) => {
+            // Don't refuse to start just because the lock file can't be created
+            warn!("Could not acquire single-instance lock: {}", e);
+            None
+        }
+    };
+
     // Create the event loop with custom event type for hotkey integration
     let event_loop = EventLoop::<AppEvent>::with_user_event()
         .build()
@@ -925,6 +2474,7 @@ fn main() {
 
     // Set up global hotkey event forwarding to the winit event loop
     let proxy = event_loop.create_proxy();
+    let app_proxy = proxy.clone();
     GlobalHotKeyEvent::set_event_handler(Some(move |event| {
         let _ = proxy.send_event(AppEvent::HotKey(event));
     }));
@@ -934,7 +2484,13 @@ fn main() {
 
     // Create and run the application
     let mut app = KeyBlastApp::new();
+    app.safe_mode = command == cli::CliCommand::SafeMode;
+    app.first_run_wizard = command == cli::CliCommand::RunTray { wizard: true };
+    app.event_proxy = Some(app_proxy);
+    app.log_level_handle = log_level_handle;
     event_loop
         .run_app(&mut app)
         .expect("Failed to run event loop");
+
+    drop(instance_lock);
 }
@@ -0,0 +1,188 @@
+//! Mouse-button triggers for KeyBlast.
+//!
+//! Lets a macro run when an extra mouse button is pressed: set its
+//! `mouse_button` to "middle", "x1" (back) or "x2" (forward). Button presses
+//! come from the shared global input hook (see `input_hook`):
+//! - Windows: middle, X1 and X2
+//! - Linux: X11 only; middle, and buttons 8 and 9 as X1 and X2
+//! - macOS: not supported (rdev doesn't report buttons other than left and right)
+//!
+//! The listener is only started when `settings.mouse_triggers_enabled` is on.
+//! The button still does its usual thing too (e.g. Back in a browser), as
+//! the hook only observes input.
+
+use std::sync::{Arc, Mutex};
+
+use rdev::{Button, EventType};
+use uuid::Uuid;
+
+use crate::config::{self, MacroDefinition};
+use crate::input_hook;
+
+/// A mouse button that can trigger a macro.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Middle,
+    /// First extra button, usually "back".
+    X1,
+    /// Second extra button, usually "forward".
+    X2,
+}
+
+/// Parse a `mouse_button` value, ignoring case and surrounding whitespace.
+///
+/// Accepts "middle", "x1" (or "back") and "x2" (or "forward"). Left and
+/// right are refused, as binding them would take over normal clicking.
+pub fn parse_mouse_button(name: &str) -> Option<MouseButton> {
+    match name.trim().to_lowercase().as_str() {
+        "middle" => Some(MouseButton::Middle),
+        "x1" | "back" => Some(MouseButton::X1),
+        "x2" | "forward" => Some(MouseButton::X2),
+        _ => None,
+    }
+}
+
+/// The trigger button an rdev button event stands for, if any.
+fn mouse_button_from(button: Button) -> Option<MouseButton> {
+    match button {
+        Button::Middle => Some(MouseButton::Middle),
+        // Windows XBUTTON1/XBUTTON2
+        Button::Unknown(1) if cfg!(target_os = "windows") => Some(MouseButton::X1),
+        Button::Unknown(2) if cfg!(target_os = "windows") => Some(MouseButton::X2),
+        // X11 buttons 8 and 9 (4 to 7 are the wheel)
+        Button::Unknown(8) if !cfg!(target_os = "windows") => Some(MouseButton::X1),
+        Button::Unknown(9) if !cfg!(target_os = "windows") => Some(MouseButton::X2),
+        _ => None,
+    }
+}
+
+/// Collect the (button, macro id) pairs from a macro list.
+///
+/// Disabled macros, macros for other machines (`only_on`) and macros
+/// without a `mouse_button`, or with one that doesn't parse, are skipped
+/// (validation reports the latter).
+pub fn bindings_from(macros: &[MacroDefinition]) -> Vec<(MouseButton, Uuid)> {
+    macros
+        .iter()
+        .filter(|m| m.enabled && config::macro_applies(m))
+        .filter_map(|m| {
+            let button = parse_mouse_button(m.mouse_button.as_deref()?)?;
+            Some((button, m.id))
+        })
+        .collect()
+}
+
+/// The macro to run for a press of `button`: the first one bound to it.
+pub fn macro_for_button(bindings: &[(MouseButton, Uuid)], button: MouseButton) -> Option<Uuid> {
+    bindings.iter().find(|(b, _)| *b == button).map(|(_, id)| *id)
+}
+
+/// Global mouse listener running the macro bound to a pressed button.
+///
+/// The listener runs for the rest of the process lifetime (see `input_hook`);
+/// disable mouse triggers by clearing the bindings.
+pub struct MouseListener {
+    bindings: Arc<Mutex<Vec<(MouseButton, Uuid)>>>,
+}
+
+impl MouseListener {
+    /// Start listening for bound button presses.
+    ///
+    /// `on_trigger` is called from the listener thread with the ID of the
+    /// macro to run; forward it to the main thread (e.g. via the event loop proxy).
+    pub fn start<F>(bindings: Vec<(MouseButton, Uuid)>, on_trigger: F) -> Self
+    where
+        F: Fn(Uuid) + Send + 'static,
+    {
+        let bindings = Arc::new(Mutex::new(bindings));
+        let thread_bindings = Arc::clone(&bindings);
+
+        input_hook::subscribe(move |event| {
+            let EventType::ButtonPress(button) = event.event_type else {
+                return;
+            };
+            let Some(button) = mouse_button_from(button) else {
+                return;
+            };
+            let Ok(bindings) = thread_bindings.lock() else {
+                return;
+            };
+            if let Some(macro_id) = macro_for_button(&bindings, button) {
+                on_trigger(macro_id);
+            }
+        });
+
+        Self { bindings }
+    }
+
+    /// Replace the button bindings being listened for.
+    pub fn set_bindings(&self, bindings: Vec<(MouseButton, Uuid)>) {
+        if let Ok(mut current) = self.bindings.lock() {
+            *current = bindings;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_macro;
+
+    fn macro_on(name: &str, button: Option<&str>) -> MacroDefinition {
+        MacroDefinition {
+            mouse_button: button.map(str::to_string),
+            ..test_macro(name, "")
+        }
+    }
+
+    #[test]
+    fn test_parse_mouse_button() {
+        assert_eq!(parse_mouse_button("middle"), Some(MouseButton::Middle));
+        assert_eq!(parse_mouse_button(" X1 "), Some(MouseButton::X1));
+        assert_eq!(parse_mouse_button("back"), Some(MouseButton::X1));
+        assert_eq!(parse_mouse_button("x2"), Some(MouseButton::X2));
+        assert_eq!(parse_mouse_button("Forward"), Some(MouseButton::X2));
+        assert_eq!(parse_mouse_button("left"), None);
+        assert_eq!(parse_mouse_button("right"), None);
+        assert_eq!(parse_mouse_button("x3"), None);
+        assert_eq!(parse_mouse_button(""), None);
+    }
+
+    #[test]
+    fn test_mouse_button_from_rdev() {
+        assert_eq!(mouse_button_from(Button::Middle), Some(MouseButton::Middle));
+        assert_eq!(mouse_button_from(Button::Left), None);
+        assert_eq!(mouse_button_from(Button::Right), None);
+        if cfg!(target_os = "windows") {
+            assert_eq!(mouse_button_from(Button::Unknown(1)), Some(MouseButton::X1));
+            assert_eq!(mouse_button_from(Button::Unknown(2)), Some(MouseButton::X2));
+        } else {
+            assert_eq!(mouse_button_from(Button::Unknown(8)), Some(MouseButton::X1));
+            assert_eq!(mouse_button_from(Button::Unknown(9)), Some(MouseButton::X2));
+            assert_eq!(mouse_button_from(Button::Unknown(1)), None);
+        }
+    }
+
+    #[test]
+    fn test_bindings_and_dispatch() {
+        let first = macro_on("First", Some("x1"));
+        let second = macro_on("Second", Some("back"));
+        let middle = macro_on("Middle", Some("middle"));
+        let macros = vec![
+            first.clone(),
+            second,
+            middle.clone(),
+            macro_on("None", None),
+            macro_on("Bogus", Some("wheel")),
+            MacroDefinition { enabled: false, ..macro_on("Off", Some("x2")) },
+        ];
+
+        let bindings = bindings_from(&macros);
+        assert_eq!(bindings.len(), 3);
+        // First macro bound to a button wins
+        assert_eq!(macro_for_button(&bindings, MouseButton::X1), Some(first.id));
+        assert_eq!(macro_for_button(&bindings, MouseButton::Middle), Some(middle.id));
+        assert_eq!(macro_for_button(&bindings, MouseButton::X2), None);
+        assert_eq!(macro_for_button(&[], MouseButton::X1), None);
+    }
+}
@@ -7,6 +7,11 @@ use notify_rust::{Notification, Timeout};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use std::path::Path;
+
+use crate::config::{self, ConfigError};
+use crate::injection::InjectionError;
+
 /// Initialize the notification system.
 ///
 /// On macOS, this sets the application bundle identifier so notifications
@@ -34,6 +39,10 @@ pub enum NotificationSeverity {
     Permission,
     /// Injection failed - transient notification, informational
     InjectionFailed,
+    /// A user action (saving config, copying logs) failed - transient notification
+    ActionFailed,
+    /// Status reminders (not errors) - transient notification
+    Info,
 }
 
 impl NotificationSeverity {
@@ -42,6 +51,8 @@ impl NotificationSeverity {
             // Note: macOS ignores timeout - system controls duration
             NotificationSeverity::Permission => Timeout::Never,
             NotificationSeverity::InjectionFailed => Timeout::Milliseconds(5000),
+            NotificationSeverity::ActionFailed => Timeout::Milliseconds(5000),
+            NotificationSeverity::Info => Timeout::Milliseconds(5000),
         }
     }
 }
@@ -57,6 +68,17 @@ impl NotificationSeverity {
 /// * `message` - Error message to display
 /// * `severity` - Determines notification timeout behavior
 pub fn show_error(title: &str, message: &str, severity: NotificationSeverity) {
+    show(title, message, severity);
+}
+
+/// Show an informational notification to the user.
+///
+/// Shares the debounce window with error notifications.
+pub fn show_info(title: &str, message: &str) {
+    show(title, message, NotificationSeverity::Info);
+}
+
+fn show(title: &str, message: &str, severity: NotificationSeverity) {
     // Permission errors always show (critical)
     // Other errors are debounced
     if !matches!(severity, NotificationSeverity::Permission) {
@@ -86,6 +108,63 @@ pub fn show_error(title: &str, message: &str, severity: NotificationSeverity) {
     }
 }
 
+/// Choose the message and severity to show for an injection failure.
+///
+/// Likely permission problems get the persistent permission guidance;
+/// everything else is a transient failure notice.
+pub fn injection_failure_notice(error: &InjectionError) -> (String, NotificationSeverity) {
+    match error {
+        InjectionError::PermissionLikely => (
+            permission_error_message().to_string(),
+            NotificationSeverity::Permission,
+        ),
+        InjectionError::Clipboard(_) => (
+            "Macro injection failed: could not read the clipboard".to_string(),
+            NotificationSeverity::InjectionFailed,
+        ),
+        InjectionError::NewCon(_) => (
+            "Macro injection failed: could not connect to the input system".to_string(),
+            NotificationSeverity::InjectionFailed,
+        ),
+        InjectionError::Input(_) => (
+            "Macro injection failed".to_string(),
+            NotificationSeverity::InjectionFailed,
+        ),
+    }
+}
+
+/// Show the notification for an injection failure (see `injection_failure_notice`).
+pub fn show_injection_error(error: &InjectionError) {
+    let (message, severity) = injection_failure_notice(error);
+    show_error("KeyBlast", &message, severity);
+}
+
+/// Choose the message and severity to show when saving the config fails.
+///
+/// An unwritable config directory gets a persistent notice naming the
+/// directory, since every later change will fail the same way.
+pub fn config_save_failure_notice(error: &ConfigError, config_dir: &Path) -> (String, NotificationSeverity) {
+    if config::is_not_writable_error(error) {
+        (not_writable_message(config_dir), NotificationSeverity::Permission)
+    } else {
+        (
+            format!("Failed to save config: {}", error),
+            NotificationSeverity::ActionFailed,
+        )
+    }
+}
+
+/// Message for a config directory that can't be written to.
+pub fn not_writable_message(config_dir: &Path) -> String {
+    format!("Cannot save config \u{2014} directory not writable: {}", config_dir.display())
+}
+
+/// Show the notification for a config save failure (see `config_save_failure_notice`).
+pub fn show_config_save_error(error: &ConfigError) {
+    let (message, severity) = config_save_failure_notice(error, &config::config_dir());
+    show_error("KeyBlast", &message, severity);
+}
+
 /// Get platform-specific permission error message.
 pub fn permission_error_message() -> &'static str {
     #[cfg(target_os = "macos")]
@@ -101,3 +180,42 @@ pub fn permission_error_message() -> &'static str {
         "Permission denied for keystroke injection."
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enigo::InputError;
+
+    #[test]
+    fn test_injection_failure_notice_by_variant() {
+        let (message, severity) = injection_failure_notice(&InjectionError::PermissionLikely);
+        assert_eq!(message, permission_error_message());
+        assert!(matches!(severity, NotificationSeverity::Permission));
+
+        let (message, severity) =
+            injection_failure_notice(&InjectionError::Clipboard("busy".to_string()));
+        assert!(message.contains("clipboard"));
+        assert!(matches!(severity, NotificationSeverity::InjectionFailed));
+
+        let (message, severity) =
+            injection_failure_notice(&InjectionError::Input(InputError::NoEmptyKeycodes));
+        assert_eq!(message, "Macro injection failed");
+        assert!(matches!(severity, NotificationSeverity::InjectionFailed));
+    }
+
+    #[test]
+    fn test_config_save_failure_notice() {
+        use std::io;
+
+        let dir = Path::new("/etc/keyblast");
+        let denied = ConfigError::Io(io::Error::from(io::ErrorKind::PermissionDenied));
+        let (message, severity) = config_save_failure_notice(&denied, dir);
+        assert_eq!(message, "Cannot save config \u{2014} directory not writable: /etc/keyblast");
+        assert!(matches!(severity, NotificationSeverity::Permission));
+
+        let other = ConfigError::Io(io::Error::other("disk full"));
+        let (message, severity) = config_save_failure_notice(&other, dir);
+        assert!(message.starts_with("Failed to save config"));
+        assert!(matches!(severity, NotificationSeverity::ActionFailed));
+    }
+}
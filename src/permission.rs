@@ -1,26 +1,28 @@
-/// Accessibility permission checking for KeyBlast.
-///
-/// On macOS, keystroke injection requires Accessibility permissions.
-/// This module provides a cross-platform abstraction for checking these permissions.
-///
-/// # macOS Requirements
-///
-/// macOS requires applications to be granted Accessibility permissions in
-/// System Preferences > Privacy & Security > Accessibility before they can
-/// simulate keyboard input. This function will prompt the user to grant
-/// permission if it has not been granted yet.
-///
-/// # Other Platforms
-///
-/// Windows and Linux do not require special permissions for input simulation,
-/// so this function returns `true` on those platforms.
+//! Accessibility permission checking for KeyBlast.
+//!
+//! On macOS, keystroke injection requires Accessibility permissions.
+//! This module provides a cross-platform abstraction for checking these permissions.
+//!
+//! # macOS Requirements
+//!
+//! macOS requires applications to be granted Accessibility permissions in
+//! System Preferences > Privacy & Security > Accessibility before they can
+//! simulate keyboard input. This function will prompt the user to grant
+//! permission if it has not been granted yet.
+//!
+//! # Other Platforms
+//!
+//! Windows and Linux do not require special permissions for input simulation,
+//! so this function returns `true` on those platforms.
 
+#[cfg(target_os = "macos")]
 use crate::notification;
 
 /// Check if the application has accessibility permission to inject keystrokes.
 ///
 /// On macOS, this will prompt the user to grant permission if not already granted,
-/// and print detailed guidance if permission is denied.
+/// and print detailed guidance if permission is denied. Only use this for the
+/// startup check; poll with `is_accessibility_trusted` to avoid repeated dialogs.
 /// On other platforms, this always returns `true`.
 ///
 /// # Returns
@@ -45,6 +47,73 @@ pub fn check_accessibility_permission() -> bool {
     trusted
 }
 
+/// Check accessibility permission without prompting the user.
+///
+/// Safe to call repeatedly (e.g. for menu status). Returns `true` on
+/// platforms that don't need the permission.
+#[cfg(target_os = "macos")]
+pub fn is_accessibility_trusted() -> bool {
+    macos_accessibility_client::accessibility::application_is_trusted()
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn is_accessibility_trusted() -> bool {
+    true
+}
+
+/// Permission status to show in the tray menu.
+///
+/// `None` on platforms without an accessibility permission, so the menu
+/// item is hidden there.
+pub fn accessibility_status() -> Option<bool> {
+    if cfg!(target_os = "macos") {
+        Some(is_accessibility_trusted())
+    } else {
+        None
+    }
+}
+
+/// Whether KeyBlast is running elevated (as Administrator).
+///
+/// Matters on Windows, where a non-elevated process can't type into elevated
+/// apps. `None` on other platforms or if the token can't be queried.
+#[cfg(target_os = "windows")]
+pub fn is_elevated() -> Option<bool> {
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::Security::{GetTokenInformation, TokenElevation, TOKEN_ELEVATION, TOKEN_QUERY};
+    use windows_sys::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+    let mut token: HANDLE = std::ptr::null_mut();
+    // SAFETY: GetCurrentProcess returns a pseudo-handle that needs no cleanup;
+    // `token` is a valid out-pointer.
+    if unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) } == 0 {
+        return None;
+    }
+
+    let mut elevation = TOKEN_ELEVATION { TokenIsElevated: 0 };
+    let mut returned = 0u32;
+    // SAFETY: `token` was opened with TOKEN_QUERY above, and the buffer and
+    // length describe `elevation`.
+    let ok = unsafe {
+        GetTokenInformation(
+            token,
+            TokenElevation,
+            &mut elevation as *mut TOKEN_ELEVATION as *mut _,
+            std::mem::size_of::<TOKEN_ELEVATION>() as u32,
+            &mut returned,
+        )
+    };
+    // SAFETY: `token` is an open handle owned by this function.
+    unsafe { CloseHandle(token) };
+
+    (ok != 0).then_some(elevation.TokenIsElevated != 0)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn is_elevated() -> Option<bool> {
+    None
+}
+
 #[cfg(target_os = "macos")]
 fn print_accessibility_guidance() {
     eprintln!();
@@ -78,3 +147,15 @@ pub fn check_accessibility_permission() -> bool {
     // Windows and Linux don't need special permissions for input simulation
     true
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn test_non_prompting_check_off_macos() {
+        assert!(is_accessibility_trusted());
+        assert!(check_accessibility_permission());
+    }
+}
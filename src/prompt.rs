@@ -0,0 +1,170 @@
+//! Text input dialogs for KeyBlast.
+//!
+//! KeyBlast has no windows of its own and `rfd` only offers file and message
+//! dialogs, so text input uses each platform's scripting tool:
+//! - macOS: `osascript` (`display dialog ... default answer`)
+//! - Windows: PowerShell (`Microsoft.VisualBasic.Interaction.InputBox`)
+//! - Linux: `zenity --entry`
+//!
+//! The call blocks until the dialog is closed, like `rfd::MessageDialog`,
+//! or until an optional timeout or stop flag closes it for the user.
+
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// How often a dialog with a timeout is checked for having closed.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Ask the user for a line of text.
+///
+/// Returns None if the dialog was cancelled or couldn't be shown. On Windows
+/// an empty answer is indistinguishable from Cancel and also returns None.
+pub fn prompt_text(title: &str, message: &str) -> Option<String> {
+    prompt_text_within(title, message, "", None, None)
+}
+
+/// Like `prompt_text`, with the input pre-filled with `current` for editing.
+pub fn prompt_edit(title: &str, message: &str, current: &str) -> Option<String> {
+    prompt_text_within(title, message, current, None, None)
+}
+
+/// Like `prompt_text`, with the input pre-filled with `default`, but closes
+/// the dialog and returns None once `timeout` has passed without an answer
+/// (see `timed_out`) or `stop` is set.
+pub fn prompt_text_within(
+    title: &str,
+    message: &str,
+    default: &str,
+    timeout: Option<Duration>,
+    stop: Option<&AtomicBool>,
+) -> Option<String> {
+    let mut child = match prompt_command(title, message, default)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            tracing::error!("Failed to show input dialog: {}", e);
+            return None;
+        }
+    };
+
+    let started = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) if stop.is_some_and(|stop| stop.load(Ordering::Relaxed)) => {
+                tracing::info!("Input dialog '{}' closed: macro stopped", title);
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+            Ok(None) if timed_out(started.elapsed(), timeout) => {
+                tracing::warn!(
+                    "Input dialog '{}' not answered within {} ms; treating it as cancelled",
+                    title,
+                    timeout.map_or(0, |t| t.as_millis())
+                );
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+            Ok(None) => std::thread::sleep(POLL_INTERVAL),
+            Err(e) => {
+                tracing::error!("Failed to wait for input dialog: {}", e);
+                let _ = child.kill();
+                return None;
+            }
+        }
+    }
+
+    let output = match child.wait_with_output() {
+        Ok(output) => output,
+        Err(e) => {
+            tracing::error!("Failed to read input dialog answer: {}", e);
+            return None;
+        }
+    };
+    if !output.status.success() {
+        // Cancel exits non-zero on macOS and Linux
+        return None;
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    let text = text.trim_end_matches(['\r', '\n']);
+    if cfg!(target_os = "windows") && text.is_empty() {
+        return None;
+    }
+    Some(text.to_string())
+}
+
+/// Whether a dialog open for `elapsed` has outlived `timeout` (None: never).
+///
+/// Reaching the limit counts: a dialog still open at exactly `timeout` is
+/// cancelled.
+pub fn timed_out(elapsed: Duration, timeout: Option<Duration>) -> bool {
+    timeout.is_some_and(|limit| elapsed >= limit)
+}
+
+/// Title and message are passed as arguments or environment variables,
+/// never spliced into the script, so quotes in them can't break out.
+#[cfg(target_os = "macos")]
+fn prompt_command(title: &str, message: &str, default: &str) -> Command {
+    let mut command = Command::new("osascript");
+    command
+        .args(["-e", "on run argv"])
+        .args([
+            "-e",
+            "text returned of (display dialog (item 2 of argv) with title (item 1 of argv) default answer (item 3 of argv))",
+        ])
+        .args(["-e", "end run"])
+        .args([title, message, default]);
+    command
+}
+
+#[cfg(target_os = "windows")]
+fn prompt_command(title: &str, message: &str, default: &str) -> Command {
+    use std::os::windows::process::CommandExt;
+    use windows_sys::Win32::System::Threading::CREATE_NO_WINDOW;
+
+    let mut command = Command::new("powershell");
+    // No console window: it would flash up and take focus from the target app
+    command.creation_flags(CREATE_NO_WINDOW);
+    command
+        .args([
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            "Add-Type -AssemblyName Microsoft.VisualBasic; \
+             [Microsoft.VisualBasic.Interaction]::InputBox($env:KEYBLAST_PROMPT_MESSAGE, $env:KEYBLAST_PROMPT_TITLE, $env:KEYBLAST_PROMPT_DEFAULT)",
+        ])
+        .env("KEYBLAST_PROMPT_TITLE", title)
+        .env("KEYBLAST_PROMPT_MESSAGE", message)
+        .env("KEYBLAST_PROMPT_DEFAULT", default);
+    command
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn prompt_command(title: &str, message: &str, default: &str) -> Command {
+    let mut command = Command::new("zenity");
+    command.args(["--entry", "--title", title, "--text", message, "--entry-text", default]);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timed_out() {
+        let limit = Some(Duration::from_secs(30));
+        assert!(!timed_out(Duration::from_secs(29), limit));
+        assert!(timed_out(Duration::from_secs(30), limit));
+        assert!(timed_out(Duration::from_secs(31), limit));
+        // No limit: wait forever
+        assert!(!timed_out(Duration::from_secs(86_400), None));
+    }
+}
@@ -0,0 +1,384 @@
+//! Keystroke recording for KeyBlast.
+//!
+//! **Record Keystrokes** in the tray starts capturing key presses with their
+//! timing; unchecking it turns them into a new macro that replays them with
+//! `{Delay}` segments for the pauses (see `segments_from_recording`). Keys
+//! come from the shared global input hook (see `input_hook`), which is only
+//! started the first time a recording begins, with the same caveats as
+//! abbreviations:
+//! - macOS: requires Input Monitoring permission
+//! - Linux: X11 only (no Wayland support)
+//! - Windows: key presses in elevated apps aren't seen
+//!
+//! Shift isn't recorded on its own; the characters it produces are.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use enigo::Key;
+use rdev::{EventType, Key as RdevKey};
+
+use crate::injection::MacroSegment;
+use crate::input_hook;
+
+/// Pauses shorter than this are left out, so fast typing stays one text segment.
+pub const MIN_RECORDED_DELAY: Duration = Duration::from_millis(20);
+
+/// Longer pauses (e.g. reaching for the mouse to stop recording) are shortened to this.
+pub const MAX_RECORDED_DELAY: Duration = Duration::from_millis(5000);
+
+/// One key action seen while recording.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordedInput {
+    /// Characters typed by a key press.
+    Text(String),
+    /// A key typed as a `{Name}` (Enter, Tab, arrows, function keys, ...).
+    Key(Key),
+    /// Ctrl, Alt or Meta pressed.
+    ModifierDown(Key),
+    /// Ctrl, Alt or Meta released.
+    ModifierUp(Key),
+}
+
+/// A recorded input and when it happened, relative to the start of recording.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedEvent {
+    pub at: Duration,
+    pub input: RecordedInput,
+}
+
+/// Turn recorded key actions into macro segments that replay them with the
+/// original timing.
+///
+/// Each pause between two actions of at least `min_delay` becomes a
+/// `{Delay}` (capped at `max_delay`); characters typed with shorter pauses
+/// are merged into one text segment. Repeated presses of a held modifier
+/// (auto-repeat) and releases of modifiers that weren't pressed during the
+/// recording are dropped, and modifiers still held at the end are released.
+pub fn segments_from_recording(events: &[RecordedEvent], min_delay: Duration, max_delay: Duration) -> Vec<MacroSegment> {
+    let mut segments = Vec::new();
+    let mut held: Vec<Key> = Vec::new();
+    let mut last_at: Option<Duration> = None;
+
+    for event in events {
+        let segment = match &event.input {
+            RecordedInput::Text(text) if text.is_empty() => continue,
+            RecordedInput::Text(text) => MacroSegment::Text(text.clone()),
+            RecordedInput::Key(key) => MacroSegment::SpecialKey(*key),
+            RecordedInput::ModifierDown(key) => {
+                if held.contains(key) {
+                    continue;
+                }
+                held.push(*key);
+                MacroSegment::KeyDown(*key)
+            }
+            RecordedInput::ModifierUp(key) => {
+                let Some(index) = held.iter().position(|k| k == key) else {
+                    continue;
+                };
+                held.remove(index);
+                MacroSegment::KeyUp(*key)
+            }
+        };
+
+        if let Some(last_at) = last_at {
+            let pause = event.at.saturating_sub(last_at);
+            if pause >= min_delay {
+                segments.push(MacroSegment::Delay(pause.min(max_delay).as_millis() as u64));
+            }
+        }
+        last_at = Some(event.at);
+
+        match (segments.last_mut(), segment) {
+            (Some(MacroSegment::Text(last)), MacroSegment::Text(text)) => last.push_str(&text),
+            (_, segment) => segments.push(segment),
+        }
+    }
+
+    segments.extend(held.into_iter().rev().map(MacroSegment::KeyUp));
+    segments
+}
+
+/// The recorded action for a key event, if it's one that gets recorded.
+///
+/// `name` is the text rdev says the press typed.
+fn recorded_input(event_type: &EventType, name: Option<&str>) -> Option<RecordedInput> {
+    match *event_type {
+        EventType::KeyPress(key) => {
+            if let Some(modifier) = modifier_key(key) {
+                return Some(RecordedInput::ModifierDown(modifier));
+            }
+            if let Some(special) = special_key(key) {
+                return Some(RecordedInput::Key(special));
+            }
+            // With Ctrl held rdev reports control characters; use the key instead
+            match name.filter(|text| !text.is_empty() && !text.chars().any(char::is_control)) {
+                Some(text) => Some(RecordedInput::Text(text.to_string())),
+                None => key_char(key).map(|c| RecordedInput::Text(c.to_string())),
+            }
+        }
+        EventType::KeyRelease(key) => modifier_key(key).map(RecordedInput::ModifierUp),
+        _ => None,
+    }
+}
+
+fn modifier_key(key: RdevKey) -> Option<Key> {
+    match key {
+        RdevKey::ControlLeft | RdevKey::ControlRight => Some(Key::Control),
+        RdevKey::Alt | RdevKey::AltGr => Some(Key::Alt),
+        RdevKey::MetaLeft | RdevKey::MetaRight => Some(Key::Meta),
+        _ => None,
+    }
+}
+
+fn special_key(key: RdevKey) -> Option<Key> {
+    let key = match key {
+        RdevKey::Return | RdevKey::KpReturn => Key::Return,
+        RdevKey::Tab => Key::Tab,
+        RdevKey::Escape => Key::Escape,
+        RdevKey::Backspace => Key::Backspace,
+        RdevKey::Delete => Key::Delete,
+        RdevKey::UpArrow => Key::UpArrow,
+        RdevKey::DownArrow => Key::DownArrow,
+        RdevKey::LeftArrow => Key::LeftArrow,
+        RdevKey::RightArrow => Key::RightArrow,
+        RdevKey::Home => Key::Home,
+        RdevKey::End => Key::End,
+        RdevKey::PageUp => Key::PageUp,
+        RdevKey::PageDown => Key::PageDown,
+        RdevKey::F1 => Key::F1,
+        RdevKey::F2 => Key::F2,
+        RdevKey::F3 => Key::F3,
+        RdevKey::F4 => Key::F4,
+        RdevKey::F5 => Key::F5,
+        RdevKey::F6 => Key::F6,
+        RdevKey::F7 => Key::F7,
+        RdevKey::F8 => Key::F8,
+        RdevKey::F9 => Key::F9,
+        RdevKey::F10 => Key::F10,
+        RdevKey::F11 => Key::F11,
+        RdevKey::F12 => Key::F12,
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// The unshifted character of a letter or digit key.
+fn key_char(key: RdevKey) -> Option<char> {
+    const KEYS: [(RdevKey, char); 36] = [
+        (RdevKey::KeyA, 'a'), (RdevKey::KeyB, 'b'), (RdevKey::KeyC, 'c'), (RdevKey::KeyD, 'd'),
+        (RdevKey::KeyE, 'e'), (RdevKey::KeyF, 'f'), (RdevKey::KeyG, 'g'), (RdevKey::KeyH, 'h'),
+        (RdevKey::KeyI, 'i'), (RdevKey::KeyJ, 'j'), (RdevKey::KeyK, 'k'), (RdevKey::KeyL, 'l'),
+        (RdevKey::KeyM, 'm'), (RdevKey::KeyN, 'n'), (RdevKey::KeyO, 'o'), (RdevKey::KeyP, 'p'),
+        (RdevKey::KeyQ, 'q'), (RdevKey::KeyR, 'r'), (RdevKey::KeyS, 's'), (RdevKey::KeyT, 't'),
+        (RdevKey::KeyU, 'u'), (RdevKey::KeyV, 'v'), (RdevKey::KeyW, 'w'), (RdevKey::KeyX, 'x'),
+        (RdevKey::KeyY, 'y'), (RdevKey::KeyZ, 'z'), (RdevKey::Num0, '0'), (RdevKey::Num1, '1'),
+        (RdevKey::Num2, '2'), (RdevKey::Num3, '3'), (RdevKey::Num4, '4'), (RdevKey::Num5, '5'),
+        (RdevKey::Num6, '6'), (RdevKey::Num7, '7'), (RdevKey::Num8, '8'), (RdevKey::Num9, '9'),
+    ];
+    KEYS.iter().find(|(k, _)| *k == key).map(|(_, c)| *c)
+}
+
+/// A recording in progress.
+struct Recording {
+    started: Instant,
+    events: Vec<RecordedEvent>,
+}
+
+/// Global key listener that records while a recording is in progress.
+///
+/// The listener runs for the rest of the process lifetime (see `input_hook`)
+/// and ignores keys between recordings.
+pub struct Recorder {
+    recording: Arc<Mutex<Option<Recording>>>,
+}
+
+impl Recorder {
+    /// Start listening, without recording yet.
+    pub fn new() -> Self {
+        let recording: Arc<Mutex<Option<Recording>>> = Arc::new(Mutex::new(None));
+        let thread_recording = Arc::clone(&recording);
+
+        input_hook::subscribe(move |event| {
+            let Ok(mut recording) = thread_recording.lock() else {
+                return;
+            };
+            let Some(recording) = recording.as_mut() else {
+                return;
+            };
+            if let Some(input) = recorded_input(&event.event_type, event.name.as_deref()) {
+                recording.events.push(RecordedEvent {
+                    at: recording.started.elapsed(),
+                    input,
+                });
+            }
+        });
+
+        Self { recording }
+    }
+
+    /// Start a new recording, discarding one in progress.
+    pub fn begin(&self) {
+        if let Ok(mut recording) = self.recording.lock() {
+            *recording = Some(Recording {
+                started: Instant::now(),
+                events: Vec::new(),
+            });
+        }
+    }
+
+    /// End the recording in progress and return what it captured.
+    pub fn finish(&self) -> Vec<RecordedEvent> {
+        self.recording
+            .lock()
+            .ok()
+            .and_then(|mut recording| recording.take())
+            .map_or_else(Vec::new, |recording| recording.events)
+    }
+
+    /// Whether a recording is in progress.
+    pub fn is_recording(&self) -> bool {
+        self.recording.lock().is_ok_and(|recording| recording.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::injection::{parse_macro_sequence, segments_to_dsl};
+
+    fn at(ms: u64, input: RecordedInput) -> RecordedEvent {
+        RecordedEvent { at: Duration::from_millis(ms), input }
+    }
+
+    fn text(s: &str) -> RecordedInput {
+        RecordedInput::Text(s.to_string())
+    }
+
+    fn convert(events: &[RecordedEvent]) -> Vec<MacroSegment> {
+        segments_from_recording(events, MIN_RECORDED_DELAY, MAX_RECORDED_DELAY)
+    }
+
+    #[test]
+    fn test_pauses_become_delays() {
+        let events = [
+            at(0, text("H")),
+            at(120, text("i")),
+            at(300, RecordedInput::Key(Key::Return)),
+        ];
+        assert_eq!(
+            convert(&events),
+            vec![
+                MacroSegment::Text("H".to_string()),
+                MacroSegment::Delay(120),
+                MacroSegment::Text("i".to_string()),
+                MacroSegment::Delay(180),
+                MacroSegment::SpecialKey(Key::Return),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fast_typing_merges_and_start_has_no_delay() {
+        // The time before the first key is not part of the macro
+        let events = [at(900, text("a")), at(905, text("b")), at(919, text("c")), at(960, text("d"))];
+        assert_eq!(
+            convert(&events),
+            vec![
+                MacroSegment::Text("abc".to_string()),
+                MacroSegment::Delay(41),
+                MacroSegment::Text("d".to_string()),
+            ]
+        );
+        // The threshold itself counts as a pause
+        let events = [at(0, text("a")), at(20, text("b"))];
+        assert_eq!(convert(&events)[1], MacroSegment::Delay(20));
+    }
+
+    #[test]
+    fn test_long_pauses_are_capped() {
+        let events = [at(0, text("a")), at(60_000, text("b"))];
+        assert_eq!(convert(&events)[1], MacroSegment::Delay(5000));
+        let custom = segments_from_recording(&events, Duration::from_millis(1), Duration::from_millis(250));
+        assert_eq!(custom[1], MacroSegment::Delay(250));
+    }
+
+    #[test]
+    fn test_modifiers_deduped_and_released() {
+        let events = [
+            at(0, RecordedInput::ModifierDown(Key::Control)),
+            // Auto-repeat while held
+            at(30, RecordedInput::ModifierDown(Key::Control)),
+            at(50, text("c")),
+            at(80, RecordedInput::ModifierUp(Key::Control)),
+            // Released without being pressed during the recording
+            at(90, RecordedInput::ModifierUp(Key::Alt)),
+            at(100, RecordedInput::ModifierDown(Key::Alt)),
+            at(100, RecordedInput::ModifierDown(Key::Meta)),
+        ];
+        assert_eq!(
+            convert(&events),
+            vec![
+                MacroSegment::KeyDown(Key::Control),
+                MacroSegment::Delay(50),
+                MacroSegment::Text("c".to_string()),
+                MacroSegment::Delay(30),
+                MacroSegment::KeyUp(Key::Control),
+                MacroSegment::Delay(20),
+                MacroSegment::KeyDown(Key::Alt),
+                MacroSegment::KeyDown(Key::Meta),
+                // Still held at the end: released, most recent first
+                MacroSegment::KeyUp(Key::Meta),
+                MacroSegment::KeyUp(Key::Alt),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_and_out_of_order_input() {
+        assert!(convert(&[]).is_empty());
+        assert!(convert(&[at(10, text("")), at(20, RecordedInput::ModifierUp(Key::Shift))]).is_empty());
+        // Timestamps going backwards don't panic or add a delay
+        let events = [at(100, text("a")), at(50, text("b"))];
+        assert_eq!(convert(&events), vec![MacroSegment::Text("ab".to_string())]);
+    }
+
+    #[test]
+    fn test_recording_serializes_to_dsl() {
+        let events = [
+            at(0, text("{")),
+            at(5, text("x")),
+            at(200, RecordedInput::Key(Key::Tab)),
+            at(400, RecordedInput::ModifierDown(Key::Control)),
+            at(450, text("s")),
+            at(500, RecordedInput::ModifierUp(Key::Control)),
+        ];
+        let segments = convert(&events);
+        let dsl = segments_to_dsl(&segments);
+        assert_eq!(dsl, "{{x{Delay 195}{Tab}{Delay 200}{KeyDown Ctrl}{Delay 50}s{Delay 50}{KeyUp Ctrl}");
+        assert_eq!(parse_macro_sequence(&dsl), segments);
+    }
+
+    #[test]
+    fn test_recorded_input_from_key_events() {
+        assert_eq!(recorded_input(&EventType::KeyPress(RdevKey::KeyA), Some("A")), Some(text("A")));
+        assert_eq!(
+            recorded_input(&EventType::KeyPress(RdevKey::Return), Some("\r")),
+            Some(RecordedInput::Key(Key::Return))
+        );
+        assert_eq!(
+            recorded_input(&EventType::KeyPress(RdevKey::ControlLeft), None),
+            Some(RecordedInput::ModifierDown(Key::Control))
+        );
+        assert_eq!(
+            recorded_input(&EventType::KeyRelease(RdevKey::AltGr), None),
+            Some(RecordedInput::ModifierUp(Key::Alt))
+        );
+        // Ctrl+C reports a control character; the key's letter is used
+        assert_eq!(recorded_input(&EventType::KeyPress(RdevKey::KeyC), Some("\u{3}")), Some(text("c")));
+        // Shift, releases of other keys and mouse events aren't recorded
+        assert_eq!(recorded_input(&EventType::KeyPress(RdevKey::ShiftLeft), None), None);
+        assert_eq!(recorded_input(&EventType::KeyRelease(RdevKey::KeyA), None), None);
+        assert_eq!(recorded_input(&EventType::ButtonPress(rdev::Button::Left), None), None);
+    }
+}
@@ -0,0 +1,205 @@
+//! Pass-through mode: suspend KeyBlast while a key is held.
+//!
+//! With `settings.suspend_while_held` naming a key (e.g. "right_alt"), no
+//! macro runs from any trigger while that key is down, so games and other
+//! apps get every hotkey. Key state comes from the shared global input hook
+//! (see `input_hook`), with the same caveats as abbreviations:
+//! - macOS: requires Input Monitoring permission
+//! - Linux: X11 only (no Wayland support)
+//! - Windows: key presses in elevated apps aren't seen
+//!
+//! The global hotkeys stay registered, so the held key's combinations still
+//! don't reach other apps; only the macros are skipped.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use rdev::{EventType, Key};
+
+use crate::input_hook;
+
+/// Parse a `suspend_while_held` key name, ignoring case and surrounding
+/// whitespace ("right_alt", "caps_lock", "f8", "q", ...).
+pub fn parse_hold_key(name: &str) -> Option<Key> {
+    let name = name.trim().to_lowercase().replace(['-', ' '], "_");
+    let key = match name.as_str() {
+        "alt" | "left_alt" | "option" => Key::Alt,
+        "right_alt" | "altgr" | "alt_gr" => Key::AltGr,
+        "ctrl" | "control" | "left_ctrl" | "left_control" => Key::ControlLeft,
+        "right_ctrl" | "right_control" => Key::ControlRight,
+        "shift" | "left_shift" => Key::ShiftLeft,
+        "right_shift" => Key::ShiftRight,
+        "meta" | "win" | "super" | "cmd" | "left_meta" => Key::MetaLeft,
+        "right_meta" | "right_win" | "right_cmd" => Key::MetaRight,
+        "caps_lock" | "capslock" => Key::CapsLock,
+        "tab" => Key::Tab,
+        "space" => Key::Space,
+        "scroll_lock" => Key::ScrollLock,
+        "pause" => Key::Pause,
+        "insert" => Key::Insert,
+        "backquote" | "`" => Key::BackQuote,
+        "f1" => Key::F1,
+        "f2" => Key::F2,
+        "f3" => Key::F3,
+        "f4" => Key::F4,
+        "f5" => Key::F5,
+        "f6" => Key::F6,
+        "f7" => Key::F7,
+        "f8" => Key::F8,
+        "f9" => Key::F9,
+        "f10" => Key::F10,
+        "f11" => Key::F11,
+        "f12" => Key::F12,
+        single if single.chars().count() == 1 => letter_key(single.chars().next()?)?,
+        _ => return None,
+    };
+    Some(key)
+}
+
+fn letter_key(c: char) -> Option<Key> {
+    const LETTERS: [Key; 26] = [
+        Key::KeyA, Key::KeyB, Key::KeyC, Key::KeyD, Key::KeyE, Key::KeyF, Key::KeyG,
+        Key::KeyH, Key::KeyI, Key::KeyJ, Key::KeyK, Key::KeyL, Key::KeyM, Key::KeyN,
+        Key::KeyO, Key::KeyP, Key::KeyQ, Key::KeyR, Key::KeyS, Key::KeyT, Key::KeyU,
+        Key::KeyV, Key::KeyW, Key::KeyX, Key::KeyY, Key::KeyZ,
+    ];
+    c.is_ascii_lowercase().then(|| LETTERS[(c as u8 - b'a') as usize])
+}
+
+/// Whether KeyBlast is suspended, tracked from press and release events.
+#[derive(Debug, Default)]
+pub struct SuspendState {
+    /// Key that suspends while held (None: never suspended).
+    key: Option<Key>,
+    held: bool,
+}
+
+impl SuspendState {
+    pub fn new(key: Option<Key>) -> Self {
+        Self { key, held: false }
+    }
+
+    /// Change the hold key (e.g. after a config reload); resumes if suspended.
+    pub fn set_key(&mut self, key: Option<Key>) {
+        self.key = key;
+        self.held = false;
+    }
+
+    /// Feed an input event. Returns the new state if it changed.
+    ///
+    /// Auto-repeated presses of a held key don't count as changes.
+    pub fn handle(&mut self, event: &EventType) -> Option<bool> {
+        let held = match *event {
+            EventType::KeyPress(key) if Some(key) == self.key => true,
+            EventType::KeyRelease(key) if Some(key) == self.key => false,
+            _ => return None,
+        };
+        if held == self.held {
+            return None;
+        }
+        self.held = held;
+        Some(held)
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.held
+    }
+}
+
+/// Global key listener maintaining a `SuspendState`.
+///
+/// The listener runs for the rest of the process lifetime (see `input_hook`);
+/// turn pass-through off by setting the key to None.
+pub struct SuspendListener {
+    state: Arc<Mutex<SuspendState>>,
+    /// Mirrors `state.is_suspended()` for lock-free reads from the trigger path.
+    suspended: Arc<AtomicBool>,
+}
+
+impl SuspendListener {
+    /// Start tracking whether `key` is held.
+    pub fn start(key: Option<Key>) -> Self {
+        let state = Arc::new(Mutex::new(SuspendState::new(key)));
+        let suspended = Arc::new(AtomicBool::new(false));
+        let thread_state = Arc::clone(&state);
+        let thread_suspended = Arc::clone(&suspended);
+
+        input_hook::subscribe(move |event| {
+            let Ok(mut state) = thread_state.lock() else {
+                return;
+            };
+            if let Some(held) = state.handle(&event.event_type) {
+                thread_suspended.store(held, Ordering::Relaxed);
+                tracing::debug!("Pass-through {}", if held { "on" } else { "off" });
+            }
+        });
+
+        Self { state, suspended }
+    }
+
+    /// Replace the hold key; resumes if currently suspended.
+    pub fn set_key(&self, key: Option<Key>) {
+        if let Ok(mut state) = self.state.lock() {
+            state.set_key(key);
+            self.suspended.store(state.is_suspended(), Ordering::Relaxed);
+        }
+    }
+
+    /// Whether the hold key is down right now.
+    pub fn is_suspended(&self) -> bool {
+        self.suspended.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hold_key() {
+        assert_eq!(parse_hold_key("right_alt"), Some(Key::AltGr));
+        assert_eq!(parse_hold_key(" Right-Alt "), Some(Key::AltGr));
+        assert_eq!(parse_hold_key("alt"), Some(Key::Alt));
+        assert_eq!(parse_hold_key("Caps Lock"), Some(Key::CapsLock));
+        assert_eq!(parse_hold_key("F8"), Some(Key::F8));
+        assert_eq!(parse_hold_key("q"), Some(Key::KeyQ));
+        assert_eq!(parse_hold_key("Z"), Some(Key::KeyZ));
+        assert_eq!(parse_hold_key("é"), None);
+        assert_eq!(parse_hold_key("hyper"), None);
+        assert_eq!(parse_hold_key(""), None);
+    }
+
+    #[test]
+    fn test_press_and_release_toggle_suspend() {
+        let mut state = SuspendState::new(Some(Key::AltGr));
+        assert!(!state.is_suspended());
+
+        assert_eq!(state.handle(&EventType::KeyPress(Key::AltGr)), Some(true));
+        assert!(state.is_suspended());
+        // Auto-repeat while held
+        assert_eq!(state.handle(&EventType::KeyPress(Key::AltGr)), None);
+        // Other keys while held don't resume
+        assert_eq!(state.handle(&EventType::KeyPress(Key::KeyW)), None);
+        assert_eq!(state.handle(&EventType::KeyRelease(Key::KeyW)), None);
+        assert!(state.is_suspended());
+
+        assert_eq!(state.handle(&EventType::KeyRelease(Key::AltGr)), Some(false));
+        assert!(!state.is_suspended());
+        assert_eq!(state.handle(&EventType::KeyRelease(Key::AltGr)), None);
+    }
+
+    #[test]
+    fn test_no_key_or_changed_key() {
+        let mut state = SuspendState::new(None);
+        assert_eq!(state.handle(&EventType::KeyPress(Key::AltGr)), None);
+        assert!(!state.is_suspended());
+
+        state.set_key(Some(Key::CapsLock));
+        state.handle(&EventType::KeyPress(Key::CapsLock));
+        assert!(state.is_suspended());
+        // Changing the key while held resumes
+        state.set_key(Some(Key::F8));
+        assert!(!state.is_suspended());
+        assert_eq!(state.handle(&EventType::KeyRelease(Key::CapsLock)), None);
+    }
+}
@@ -0,0 +1,696 @@
+//! All-or-nothing config edits for KeyBlast.
+//!
+//! Operations like import change the macro list and register hotkeys in
+//! several steps before saving. If the save fails, the in-memory config,
+//! the hotkey -> macro map, and the OS registrations would no longer match
+//! the file on disk. `run_config_transaction` snapshots the state, applies
+//! the edit, and rolls everything back if saving fails.
+
+use std::collections::HashMap;
+
+use global_hotkey::hotkey::HotKey;
+
+use crate::config::{self, Config, ConfigError, ImportConflictPolicy, MacroDefinition, MergeAction, MergeStrategy};
+use crate::hotkey::{self, HotkeyManager};
+
+/// Something hotkeys can be registered with (the OS via `HotkeyManager`, or a test stub).
+pub trait HotkeyRegistrar {
+    fn register(&mut self, hotkey: HotKey, name: String) -> Result<(), String>;
+    fn unregister(&mut self, hotkey: &HotKey) -> Result<(), String>;
+}
+
+impl HotkeyRegistrar for HotkeyManager {
+    fn register(&mut self, hotkey: HotKey, name: String) -> Result<(), String> {
+        HotkeyManager::register(self, hotkey, name)
+    }
+
+    fn unregister(&mut self, hotkey: &HotKey) -> Result<(), String> {
+        HotkeyManager::unregister(self, hotkey)
+    }
+}
+
+/// In-progress config edit handed to the transaction closure.
+///
+/// Register and unregister hotkeys through this (not directly) so they can
+/// be undone on rollback.
+pub struct ConfigTransaction<'a> {
+    config: &'a mut Config,
+    macros: &'a mut HashMap<u32, MacroDefinition>,
+    registrar: Option<&'a mut dyn HotkeyRegistrar>,
+    /// Hotkeys registered during the transaction
+    registered: Vec<HotKey>,
+    /// Hotkeys unregistered during the transaction, with their macro names
+    unregistered: Vec<(HotKey, String)>,
+}
+
+impl ConfigTransaction<'_> {
+    /// The config being edited.
+    pub fn config(&mut self) -> &mut Config {
+        self.config
+    }
+
+    /// Register a macro's hotkey and add it to the hotkey -> macro map.
+    ///
+    /// Does not add the macro to the config; push it to `config().macros` on success.
+    /// Menu-only and disabled macros succeed without registering, as do
+    /// chords (the caller re-syncs `hotkey::ChordTracker` afterwards).
+    pub fn register_macro(&mut self, macro_def: &MacroDefinition) -> Result<(), String> {
+        if !macro_def.registers_hotkey() || config::parse_hotkey_chord(&macro_def.hotkey).is_some() {
+            return Ok(());
+        }
+        let hotkey = config::parse_hotkey_string(&macro_def.hotkey)
+            .ok_or_else(|| format!("Invalid hotkey '{}'", macro_def.hotkey))?;
+        let registrar = self
+            .registrar
+            .as_deref_mut()
+            .ok_or("Hotkey manager is not available")?;
+
+        hotkey::bind_macro(registrar, self.macros, hotkey, macro_def)?;
+        self.registered.push(hotkey);
+        Ok(())
+    }
+
+    /// The macro currently bound to `hotkey`, if any.
+    pub fn bound_macro(&self, hotkey: &HotKey) -> Option<&MacroDefinition> {
+        self.macros.get(&hotkey.id())
+    }
+
+    /// Unregister whichever hotkey is bound to the macro with this ID, if any.
+    pub fn unregister_macro(&mut self, macro_id: uuid::Uuid) {
+        let Some((&hotkey_id, binding)) = self.macros.iter().find(|(_, m)| m.id == macro_id) else {
+            return;
+        };
+        let name = binding.name.clone();
+        let hotkey = config::parse_hotkey_string(&binding.hotkey);
+        self.macros.remove(&hotkey_id);
+
+        if let (Some(hotkey), Some(registrar)) = (hotkey, self.registrar.as_deref_mut()) {
+            if registrar.unregister(&hotkey).is_ok() {
+                self.unregistered.push((hotkey, name));
+            }
+        }
+    }
+}
+
+/// What `import_macros` did with each imported macro.
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportSummary {
+    /// Added with their own hotkey.
+    pub added: usize,
+    /// Left out because a macro with the same name exists (`MergeStrategy::Skip`).
+    pub duplicates: Vec<String>,
+    /// Put in place of the existing macro with the same name (`MergeStrategy::Overwrite`).
+    pub overwritten: Vec<String>,
+    /// Given a free name because theirs was taken: (name in the file, new name).
+    /// They are also counted as added, skipped, ... like any other macro.
+    pub renamed: Vec<(String, String)>,
+    /// Left out because their hotkey is taken (`skip`, nothing free to reassign
+    /// to, or an overwrite whose new hotkey another macro holds).
+    pub skipped: Vec<String>,
+    /// Added under a different hotkey: (name, new hotkey).
+    pub reassigned: Vec<(String, String)>,
+    /// Added with their hotkey, which the existing macro gave up: (name, existing name).
+    pub replaced: Vec<(String, String)>,
+    /// Not added for another reason (e.g. invalid hotkey): (name, error).
+    pub failed: Vec<(String, String)>,
+}
+
+impl ImportSummary {
+    /// Number of macros added to the config.
+    pub fn total_added(&self) -> usize {
+        self.added + self.reassigned.len() + self.replaced.len()
+    }
+
+    /// One-paragraph summary for the import notification.
+    pub fn message(&self) -> String {
+        let mut parts = vec![format!("Added {} macros", self.total_added())];
+        if !self.overwritten.is_empty() {
+            parts.push(format!("overwrote {}", self.overwritten.join(", ")));
+        }
+        if !self.renamed.is_empty() {
+            let list: Vec<_> = self
+                .renamed
+                .iter()
+                .map(|(original, name)| format!("{} \u{2192} {}", original, name))
+                .collect();
+            parts.push(format!("renamed {}", list.join(", ")));
+        }
+        if !self.reassigned.is_empty() {
+            let list: Vec<_> = self
+                .reassigned
+                .iter()
+                .map(|(name, hotkey)| format!("{} \u{2192} {}", name, config::display_hotkey(hotkey)))
+                .collect();
+            parts.push(format!("reassigned {}", list.join(", ")));
+        }
+        if !self.replaced.is_empty() {
+            let list: Vec<_> = self
+                .replaced
+                .iter()
+                .map(|(name, old)| format!("{} (took hotkey from {})", name, old))
+                .collect();
+            parts.push(format!("replaced {}", list.join(", ")));
+        }
+        if !self.skipped.is_empty() {
+            parts.push(format!("skipped (hotkey in use) {}", self.skipped.join(", ")));
+        }
+        if !self.duplicates.is_empty() {
+            parts.push(format!("{} already present", self.duplicates.len()));
+        }
+        if !self.failed.is_empty() {
+            let names: Vec<_> = self.failed.iter().map(|(name, _)| name.as_str()).collect();
+            parts.push(format!("failed {}", names.join(", ")));
+        }
+        parts.join("; ")
+    }
+}
+
+/// Merge imported macros into the config being edited.
+///
+/// Macros whose name already exists are handled per `strategy` (see
+/// `config::merge_imported`); an ID that collides with an existing macro is
+/// regenerated. Macros whose hotkey is already bound are collected and
+/// resolved afterwards per `policy` (see `reconcile_conflicts`).
+pub fn import_macros(
+    tx: &mut ConfigTransaction,
+    imported: Vec<MacroDefinition>,
+    strategy: MergeStrategy,
+    policy: ImportConflictPolicy,
+) -> ImportSummary {
+    let mut summary = ImportSummary::default();
+    let actions = config::merge_imported(&tx.config().macros, imported, strategy);
+
+    let mut conflicts = Vec::new();
+    for action in actions {
+        let mut macro_def = match action {
+            MergeAction::Add(macro_def) => macro_def,
+            MergeAction::Skip(name) => {
+                summary.duplicates.push(name);
+                continue;
+            }
+            MergeAction::Overwrite(mut macro_def) => {
+                macro_def.modified = Some(config::timestamp_now());
+                overwrite_macro(tx, macro_def, &mut summary);
+                continue;
+            }
+            MergeAction::Rename { original, macro_def } => {
+                summary.renamed.push((original, macro_def.name.clone()));
+                macro_def
+            }
+        };
+        macro_def.modified = Some(config::timestamp_now());
+
+        if hotkey_taken(tx, &macro_def) {
+            conflicts.push(macro_def);
+            continue;
+        }
+        match tx.register_macro(&macro_def) {
+            Ok(()) => {
+                tx.config().macros.push(macro_def);
+                summary.added += 1;
+            }
+            Err(e) => summary.failed.push((macro_def.name, e)),
+        }
+    }
+
+    reconcile_conflicts(tx, conflicts, policy, &mut summary);
+    summary
+}
+
+/// Whether `macro_def` would register a hotkey another macro is bound to.
+fn hotkey_taken(tx: &ConfigTransaction, macro_def: &MacroDefinition) -> bool {
+    macro_def.registers_hotkey()
+        && config::parse_hotkey_string(&macro_def.hotkey).is_some_and(|hk| tx.bound_macro(&hk).is_some())
+}
+
+/// Put `macro_def` in place of the existing macro with its ID and move the
+/// hotkey registration over.
+///
+/// If its hotkey is bound to a different macro, or can't be registered, the
+/// existing macro is kept as it was.
+fn overwrite_macro(tx: &mut ConfigTransaction, macro_def: MacroDefinition, summary: &mut ImportSummary) {
+    let Some(index) = tx.config().macros.iter().position(|m| m.id == macro_def.id) else {
+        return;
+    };
+    let existing = tx.config().macros[index].clone();
+    tx.unregister_macro(existing.id);
+
+    if hotkey_taken(tx, &macro_def) {
+        let _ = tx.register_macro(&existing);
+        summary.skipped.push(macro_def.name);
+        return;
+    }
+    match tx.register_macro(&macro_def) {
+        Ok(()) => {
+            summary.overwritten.push(macro_def.name.clone());
+            tx.config().macros[index] = macro_def;
+        }
+        Err(e) => {
+            let _ = tx.register_macro(&existing);
+            summary.failed.push((macro_def.name, e));
+        }
+    }
+}
+
+/// Resolve imported macros whose hotkey is bound to another macro.
+///
+/// - `Skip`: leave them out.
+/// - `Reassign`: add them under the first of `hotkey::suggest_available_many`'s
+///   suggestions that registers (one the OS or another app has is passed over).
+/// - `KeepNew`: unregister the existing macro, make it menu-only so the
+///   clash doesn't return on reload, and register the imported one.
+pub fn reconcile_conflicts(
+    tx: &mut ConfigTransaction,
+    conflicts: Vec<MacroDefinition>,
+    policy: ImportConflictPolicy,
+    summary: &mut ImportSummary,
+) {
+    for mut macro_def in conflicts {
+        let Some(hotkey) = config::parse_hotkey_string(&macro_def.hotkey) else {
+            summary.failed.push((macro_def.name, "Invalid hotkey".to_string()));
+            continue;
+        };
+        match policy {
+            ImportConflictPolicy::Skip => summary.skipped.push(macro_def.name),
+            ImportConflictPolicy::Reassign => {
+                let candidates =
+                    hotkey::suggest_available_many(hotkey, usize::MAX, |candidate| tx.bound_macro(candidate).is_none());
+                // None if there was nothing to try, else the last attempt
+                let mut result = None;
+                for free in candidates {
+                    macro_def.hotkey = config::hotkey_to_string(&free);
+                    let registered = tx.register_macro(&macro_def);
+                    let done = registered.is_ok();
+                    result = Some(registered);
+                    if done {
+                        break;
+                    }
+                }
+                match result {
+                    Some(Ok(())) => {
+                        summary.reassigned.push((macro_def.name.clone(), macro_def.hotkey.clone()));
+                        tx.config().macros.push(macro_def);
+                    }
+                    Some(Err(e)) => summary.failed.push((macro_def.name, e)),
+                    None => summary.skipped.push(macro_def.name),
+                }
+            }
+            ImportConflictPolicy::KeepNew => {
+                let Some(existing) = tx.bound_macro(&hotkey).cloned() else {
+                    continue;
+                };
+                tx.unregister_macro(existing.id);
+                match tx.register_macro(&macro_def) {
+                    Ok(()) => {
+                        if let Some(old) = tx.config().macros.iter_mut().find(|m| m.id == existing.id) {
+                            old.hotkey_enabled = false;
+                        }
+                        summary.replaced.push((macro_def.name.clone(), existing.name));
+                        tx.config().macros.push(macro_def);
+                    }
+                    Err(e) => {
+                        // Give the hotkey back to the existing macro
+                        let _ = tx.register_macro(&existing);
+                        summary.failed.push((macro_def.name, e));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Apply `edit` to the config, then save with `save`.
+///
+/// If saving fails, the config and hotkey map are restored to their prior
+/// state, hotkeys registered during the edit are unregistered, and hotkeys
+/// unregistered during the edit are registered again.
+///
+/// A locked config (`settings.locked`) is left untouched: `edit` and `save`
+/// are never called and `ConfigError::Locked` is returned.
+pub fn run_config_transaction<'a, T, F, S>(
+    config: &'a mut Config,
+    macros: &'a mut HashMap<u32, MacroDefinition>,
+    registrar: Option<&'a mut dyn HotkeyRegistrar>,
+    save: S,
+    edit: F,
+) -> Result<T, ConfigError>
+where
+    F: FnOnce(&mut ConfigTransaction<'a>) -> T,
+    S: FnOnce(&Config) -> Result<(), ConfigError>,
+{
+    if config.settings.locked {
+        return Err(ConfigError::Locked);
+    }
+    let config_snapshot = config.clone();
+    let macros_snapshot = macros.clone();
+
+    let mut tx = ConfigTransaction {
+        config,
+        macros,
+        registrar,
+        registered: Vec::new(),
+        unregistered: Vec::new(),
+    };
+    let output = edit(&mut tx);
+
+    match save(tx.config) {
+        Ok(()) => Ok(output),
+        Err(e) => {
+            if let Some(registrar) = tx.registrar.as_deref_mut() {
+                for hotkey in tx.registered.iter().rev() {
+                    let _ = registrar.unregister(hotkey);
+                }
+                for (hotkey, name) in tx.unregistered.drain(..).rev() {
+                    let _ = registrar.register(hotkey, name);
+                }
+            }
+            *tx.config = config_snapshot;
+            *tx.macros = macros_snapshot;
+            Err(e)
+        }
+    }
+}
+
+/// Registrar that tracks registered hotkey IDs in memory, for tests.
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct StubRegistrar {
+    pub(crate) registered: std::collections::HashSet<u32>,
+}
+
+#[cfg(test)]
+impl HotkeyRegistrar for StubRegistrar {
+    fn register(&mut self, hotkey: HotKey, _name: String) -> Result<(), String> {
+        if self.registered.insert(hotkey.id()) {
+            Ok(())
+        } else {
+            Err("already registered".to_string())
+        }
+    }
+
+    fn unregister(&mut self, hotkey: &HotKey) -> Result<(), String> {
+        self.registered.remove(&hotkey.id());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_macro;
+    use std::io;
+
+    fn failing_save(_: &Config) -> Result<(), ConfigError> {
+        Err(ConfigError::Io(io::Error::other("disk full")))
+    }
+
+    /// Config, hotkey map and registrar with one macro already registered.
+    fn setup() -> (Config, HashMap<u32, MacroDefinition>, StubRegistrar) {
+        let existing = test_macro("Existing", "ctrl+1");
+        let hotkey = config::parse_hotkey_string(&existing.hotkey).unwrap();
+
+        let mut registrar = StubRegistrar::default();
+        registrar.register(hotkey, existing.name.clone()).unwrap();
+        let macros = HashMap::from([(hotkey.id(), existing.clone())]);
+        let config = Config {
+            macros: vec![existing],
+            ..Config::default()
+        };
+        (config, macros, registrar)
+    }
+
+    #[test]
+    fn test_failed_save_rolls_back_additions() {
+        let (mut config, mut macros, mut registrar) = setup();
+        let (config_before, macros_before) = (config.clone(), macros.clone());
+        let registered_before = registrar.registered.clone();
+
+        let result = run_config_transaction(
+            &mut config,
+            &mut macros,
+            Some(&mut registrar),
+            failing_save,
+            |tx| {
+                for def in [test_macro("New 1", "ctrl+2"), test_macro("New 2", "ctrl+3")] {
+                    tx.register_macro(&def).unwrap();
+                    tx.config().macros.push(def);
+                }
+            },
+        );
+
+        assert!(result.is_err());
+        assert_eq!(config, config_before);
+        assert_eq!(macros, macros_before);
+        assert_eq!(registrar.registered, registered_before);
+    }
+
+    #[test]
+    fn test_failed_save_restores_unregistered() {
+        let (mut config, mut macros, mut registrar) = setup();
+        let existing_id = config.macros[0].id;
+        let registered_before = registrar.registered.clone();
+
+        let result = run_config_transaction(
+            &mut config,
+            &mut macros,
+            Some(&mut registrar),
+            failing_save,
+            |tx| {
+                tx.unregister_macro(existing_id);
+                tx.config().macros.retain(|m| m.id != existing_id);
+            },
+        );
+
+        assert!(result.is_err());
+        assert_eq!(config.macros.len(), 1);
+        assert_eq!(macros.len(), 1);
+        assert_eq!(registrar.registered, registered_before);
+    }
+
+    #[test]
+    fn test_successful_save_keeps_changes() {
+        let (mut config, mut macros, mut registrar) = setup();
+
+        let added = run_config_transaction(
+            &mut config,
+            &mut macros,
+            Some(&mut registrar),
+            |_| Ok(()),
+            |tx| {
+                let def = test_macro("New", "ctrl+2");
+                tx.register_macro(&def).unwrap();
+                tx.config().macros.push(def);
+                1
+            },
+        );
+
+        assert_eq!(added.unwrap(), 1);
+        assert_eq!(config.macros.len(), 2);
+        assert_eq!(macros.len(), 2);
+        assert_eq!(registrar.registered.len(), 2);
+    }
+
+    #[test]
+    fn test_register_invalid_hotkey_fails() {
+        let (mut config, mut macros, mut registrar) = setup();
+
+        let result = run_config_transaction(
+            &mut config,
+            &mut macros,
+            Some(&mut registrar),
+            |_| Ok(()),
+            |tx| tx.register_macro(&test_macro("Bad", "ctrl+shift")),
+        );
+
+        assert!(result.unwrap().is_err());
+        assert_eq!(macros.len(), 1);
+    }
+
+    #[test]
+    fn test_locked_config_skips_edit_and_save() {
+        let (mut config, mut macros, mut registrar) = setup();
+        config.settings.locked = true;
+        let (config_before, macros_before) = (config.clone(), macros.clone());
+        let registered_before = registrar.registered.clone();
+
+        let result = run_config_transaction(
+            &mut config,
+            &mut macros,
+            Some(&mut registrar),
+            |_: &Config| -> Result<(), ConfigError> { panic!("locked config must not be saved") },
+            |tx| {
+                let def = test_macro("New", "ctrl+2");
+                tx.register_macro(&def).unwrap();
+                tx.config().macros.push(def);
+            },
+        );
+
+        assert!(matches!(result, Err(ConfigError::Locked)));
+        assert_eq!(config, config_before);
+        assert_eq!(macros, macros_before);
+        assert_eq!(registrar.registered, registered_before);
+    }
+
+    /// An import where "Clash" wants the hotkey "Existing" already holds.
+    fn conflicting_import(policy: ImportConflictPolicy) -> (Config, HashMap<u32, MacroDefinition>, StubRegistrar, ImportSummary) {
+        let (mut config, mut macros, mut registrar) = setup();
+        let imported = vec![
+            test_macro("Fresh", "ctrl+2"),
+            test_macro("Clash", "ctrl+1"),
+            test_macro("Existing", "ctrl+3"),
+        ];
+        let summary = run_config_transaction(
+            &mut config,
+            &mut macros,
+            Some(&mut registrar),
+            |_: &Config| Ok(()),
+            |tx| import_macros(tx, imported, MergeStrategy::Skip, policy),
+        )
+        .unwrap();
+        (config, macros, registrar, summary)
+    }
+
+    fn names(config: &Config) -> Vec<&str> {
+        config.macros.iter().map(|m| m.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_import_conflict_skip() {
+        let (config, macros, registrar, summary) = conflicting_import(ImportConflictPolicy::Skip);
+
+        assert_eq!(names(&config), vec!["Existing", "Fresh"]);
+        assert_eq!(summary.added, 1);
+        assert_eq!(summary.skipped, vec!["Clash".to_string()]);
+        assert_eq!(summary.duplicates, vec!["Existing".to_string()]);
+        assert_eq!(macros.len(), 2);
+        assert_eq!(registrar.registered.len(), 2);
+    }
+
+    #[test]
+    fn test_import_conflict_reassign() {
+        let (config, macros, registrar, summary) = conflicting_import(ImportConflictPolicy::Reassign);
+
+        assert_eq!(names(&config), vec!["Existing", "Fresh", "Clash"]);
+        assert_eq!(summary.reassigned, vec![("Clash".to_string(), "ctrl+alt+1".to_string())]);
+        assert_eq!(summary.total_added(), 2);
+        assert_eq!(config.macros[2].hotkey, "ctrl+alt+1");
+        let reassigned = config::parse_hotkey_string("ctrl+alt+1").unwrap();
+        assert_eq!(macros[&reassigned.id()].name, "Clash");
+        assert!(registrar.registered.contains(&reassigned.id()));
+        // The existing macro keeps its hotkey
+        let original = config::parse_hotkey_string("ctrl+1").unwrap();
+        assert_eq!(macros[&original.id()].name, "Existing");
+    }
+
+    #[test]
+    fn test_import_conflict_reassign_skips_os_taken_hotkey() {
+        let (mut config, mut macros, mut registrar) = setup();
+        // Held by the OS or another app: registering it fails
+        let os_taken = config::parse_hotkey_string("ctrl+alt+1").unwrap();
+        registrar.registered.insert(os_taken.id());
+
+        let summary = run_config_transaction(
+            &mut config,
+            &mut macros,
+            Some(&mut registrar),
+            |_: &Config| Ok(()),
+            |tx| {
+                import_macros(tx, vec![test_macro("Clash", "ctrl+1")], MergeStrategy::Skip, ImportConflictPolicy::Reassign)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(summary.reassigned, vec![("Clash".to_string(), "ctrl+shift+1".to_string())]);
+        assert!(summary.failed.is_empty());
+        let reassigned = config::parse_hotkey_string("ctrl+shift+1").unwrap();
+        assert_eq!(macros[&reassigned.id()].name, "Clash");
+        assert_eq!(config.macros[1].hotkey, "ctrl+shift+1");
+    }
+
+    #[test]
+    fn test_import_conflict_keep_new() {
+        let (config, macros, registrar, summary) = conflicting_import(ImportConflictPolicy::KeepNew);
+
+        assert_eq!(names(&config), vec!["Existing", "Fresh", "Clash"]);
+        assert_eq!(summary.replaced, vec![("Clash".to_string(), "Existing".to_string())]);
+        let hotkey = config::parse_hotkey_string("ctrl+1").unwrap();
+        assert_eq!(macros[&hotkey.id()].name, "Clash");
+        assert!(registrar.registered.contains(&hotkey.id()));
+        // The existing macro stays, menu-only
+        assert!(!config.macros[0].hotkey_enabled);
+        assert_eq!(macros.len(), 2);
+    }
+
+    /// Import "Existing" again, with a new hotkey and text, per `strategy`.
+    fn reimport(strategy: MergeStrategy) -> (Config, HashMap<u32, MacroDefinition>, StubRegistrar, ImportSummary) {
+        let (mut config, mut macros, mut registrar) = setup();
+        let updated = MacroDefinition { text: "updated".to_string(), ..test_macro("Existing", "ctrl+5") };
+        let summary = run_config_transaction(
+            &mut config,
+            &mut macros,
+            Some(&mut registrar),
+            |_: &Config| Ok(()),
+            |tx| import_macros(tx, vec![updated], strategy, ImportConflictPolicy::Skip),
+        )
+        .unwrap();
+        (config, macros, registrar, summary)
+    }
+
+    #[test]
+    fn test_import_overwrite_reregisters_hotkey() {
+        let (config, macros, registrar, summary) = reimport(MergeStrategy::Overwrite);
+
+        assert_eq!(names(&config), vec!["Existing"]);
+        assert_eq!(config.macros[0].text, "updated");
+        assert_eq!(summary.overwritten, vec!["Existing".to_string()]);
+        assert_eq!(summary.total_added(), 0);
+
+        let old = config::parse_hotkey_string("ctrl+1").unwrap();
+        let new = config::parse_hotkey_string("ctrl+5").unwrap();
+        assert!(!registrar.registered.contains(&old.id()));
+        assert!(registrar.registered.contains(&new.id()));
+        assert_eq!(macros.len(), 1);
+        assert_eq!(macros[&new.id()].text, "updated");
+        // The replacement keeps the existing macro's ID (see `config::merge_imported`)
+        assert_eq!(macros[&new.id()].id, config.macros[0].id);
+    }
+
+    #[test]
+    fn test_import_overwrite_keeps_existing_when_hotkey_taken() {
+        let (mut config, mut macros, mut registrar) = setup();
+        let other = test_macro("Other", "ctrl+2");
+        let imported = vec![test_macro("Existing", "ctrl+2")];
+        let summary = run_config_transaction(
+            &mut config,
+            &mut macros,
+            Some(&mut registrar),
+            |_: &Config| Ok(()),
+            |tx| {
+                tx.register_macro(&other).unwrap();
+                tx.config().macros.push(other);
+                import_macros(tx, imported, MergeStrategy::Overwrite, ImportConflictPolicy::KeepNew)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(summary.skipped, vec!["Existing".to_string()]);
+        assert!(summary.overwritten.is_empty());
+        assert_eq!(config.macros[0].hotkey, "ctrl+1");
+        let original = config::parse_hotkey_string("ctrl+1").unwrap();
+        assert_eq!(macros[&original.id()].name, "Existing");
+        assert!(registrar.registered.contains(&original.id()));
+    }
+
+    #[test]
+    fn test_import_rename_on_conflict() {
+        let (config, macros, registrar, summary) = reimport(MergeStrategy::RenameOnConflict);
+
+        assert_eq!(names(&config), vec!["Existing", "Existing (2)"]);
+        assert_eq!(summary.renamed, vec![("Existing".to_string(), "Existing (2)".to_string())]);
+        assert_eq!(summary.added, 1);
+        assert_ne!(config.macros[0].id, config.macros[1].id);
+        assert_eq!(macros.len(), 2);
+        assert_eq!(registrar.registered.len(), 2);
+        assert!(summary.message().contains("renamed Existing \u{2192} Existing (2)"));
+    }
+}
@@ -1,30 +1,59 @@
-/// System tray and menu setup for KeyBlast.
-///
-/// Uses tray-icon and muda crates for cross-platform tray functionality.
+//! System tray and menu setup for KeyBlast.
+//!
+//! Uses tray-icon and muda crates for cross-platform tray functionality.
 
 use std::collections::HashMap;
 use muda::{Menu, MenuItem, PredefinedMenuItem, CheckMenuItem, Submenu};
 use muda::accelerator::Accelerator;
+use global_hotkey::hotkey::{Code, HotKey};
 use tray_icon::{Icon, TrayIcon, TrayIconBuilder};
+use unicode_segmentation::UnicodeSegmentation;
 use uuid::Uuid;
 
+use crate::app::RunHistory;
 use crate::config;
-use crate::config::ValidationWarning;
+use crate::config::{MergeStrategy, RunMenuSort, ValidationWarning};
+use crate::hotkey::{CandidateStatus, HotkeyCandidate};
 
 /// Menu item identifiers for event handling.
 pub struct MenuIds {
     pub toggle: muda::MenuId,
     pub edit_config: muda::MenuId,
     pub export_macros: muda::MenuId,
-    pub import_macros: muda::MenuId,
+    pub export_shareable: muda::MenuId,
+    pub reimport_last: muda::MenuId,
+    pub export_config: muda::MenuId,
+    pub import_config: muda::MenuId,
+    pub search_macros: muda::MenuId,
+    pub record_keystrokes: muda::MenuId,
     pub open_logs: muda::MenuId,
+    pub copy_logs: muda::MenuId,
+    pub verbose_logging: muda::MenuId,
+    pub reregister_hotkeys: muda::MenuId,
     pub auto_start: muda::MenuId,
     pub stop_macro: muda::MenuId,
     pub quit: muda::MenuId,
+    /// Map from menu item ID to macro UUID for the per-macro Enabled checkboxes
+    pub toggle_macro_ids: HashMap<muda::MenuId, Uuid>,
+    /// Map from menu item ID to macro UUID for edit actions
+    pub edit_macro_ids: HashMap<muda::MenuId, Uuid>,
     /// Map from menu item ID to macro UUID for delete actions
     pub delete_macro_ids: HashMap<muda::MenuId, Uuid>,
     /// Map from menu item ID to macro UUID for run actions
     pub run_macro_ids: HashMap<muda::MenuId, Uuid>,
+    /// Map from menu item ID to the macro and the free hotkey to give it
+    pub suggested_hotkey_ids: HashMap<muda::MenuId, (Uuid, HotKey)>,
+    /// Map from Import Macros submenu item ID to what it does with existing names
+    pub import_macro_ids: HashMap<muda::MenuId, MergeStrategy>,
+}
+
+/// Hotkeys to offer a macro whose own hotkey couldn't be registered: the
+/// free ones, and the taken ones tried before them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HotkeySuggestions {
+    pub macro_id: Uuid,
+    pub macro_name: String,
+    pub hotkeys: Vec<HotkeyCandidate>,
 }
 
 /// Load the normal application icon.
@@ -47,35 +76,248 @@ fn load_icon_from_bytes(bytes: &[u8]) -> Icon {
     Icon::from_rgba(rgba, width, height).expect("Failed to create icon")
 }
 
+/// Label for the accessibility status menu item.
+pub fn accessibility_label(trusted: bool) -> &'static str {
+    if trusted {
+        "Accessibility: granted"
+    } else {
+        "Accessibility: NOT granted"
+    }
+}
+
+/// An entry in the Run Macro submenu (after the Recent section).
+#[derive(Debug, PartialEq)]
+pub enum RunMenuEntry<'a> {
+    /// A directly clickable macro.
+    Macro(&'a config::MacroDefinition),
+    /// A group submenu with its macros.
+    Group(String, Vec<&'a config::MacroDefinition>),
+}
+
+/// Group macros by their `group` field (None -> "Ungrouped").
+///
+/// Groups are sorted by name with "Ungrouped" last; macros keep config order.
+pub fn group_macros(macros: &[config::MacroDefinition]) -> Vec<(String, Vec<&config::MacroDefinition>)> {
+    let mut groups: HashMap<String, Vec<&config::MacroDefinition>> = HashMap::new();
+    for macro_def in macros {
+        let group_name = macro_def.group.clone().unwrap_or_else(|| "Ungrouped".to_string());
+        groups.entry(group_name).or_default().push(macro_def);
+    }
+
+    // Sort group names for consistent ordering, but keep "Ungrouped" at the end
+    let mut grouped: Vec<_> = groups.into_iter().collect();
+    grouped.sort_by(|(a, _), (b, _)| {
+        if a == "Ungrouped" {
+            std::cmp::Ordering::Greater
+        } else if b == "Ungrouped" {
+            std::cmp::Ordering::Less
+        } else {
+            a.cmp(b)
+        }
+    });
+    grouped
+}
+
+/// Macros whose name contains `query`, ignoring case, in config order.
+/// Macros for other machines (`only_on`) never match.
+pub fn filter_macros<'a>(query: &str, macros: &'a [config::MacroDefinition]) -> Vec<&'a config::MacroDefinition> {
+    let query = query.to_lowercase();
+    macros
+        .iter()
+        .filter(|m| config::macro_applies(m) && m.name.to_lowercase().contains(&query))
+        .collect()
+}
+
+/// Sort Run Macro entries by `settings.run_menu_sort`.
+///
+/// Ties in "recent" and "usage" fall back to name order; "order" keeps the
+/// config order (the sort is stable).
+pub fn sort_run_macros(macros: &mut [&config::MacroDefinition], sort: RunMenuSort, run_counts: &HashMap<Uuid, u32>) {
+    match sort {
+        RunMenuSort::Name => macros.sort_by_key(|m| m.name.to_lowercase()),
+        RunMenuSort::Recent => macros.sort_by(|a, b| {
+            // Timestamps sort as strings; None (never edited) goes last
+            b.modified
+                .cmp(&a.modified)
+                .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+        }),
+        RunMenuSort::Usage => macros.sort_by(|a, b| {
+            let count = |m: &config::MacroDefinition| run_counts.get(&m.id).copied().unwrap_or(0);
+            count(b)
+                .cmp(&count(a))
+                .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+        }),
+        RunMenuSort::Order => {}
+    }
+}
+
+/// Lay out the Run Macro list.
+///
+/// Flat: every macro in one list. Grouped: one submenu per group, ordered
+/// like the Macros submenu. Macros are sorted with `sort_run_macros`.
+pub fn run_menu_layout<'a>(
+    macros: &'a [config::MacroDefinition],
+    flat: bool,
+    sort: RunMenuSort,
+    run_counts: &HashMap<Uuid, u32>,
+) -> Vec<RunMenuEntry<'a>> {
+    if flat {
+        let mut sorted_macros: Vec<_> = macros.iter().collect();
+        sort_run_macros(&mut sorted_macros, sort, run_counts);
+        return sorted_macros.into_iter().map(RunMenuEntry::Macro).collect();
+    }
+
+    group_macros(macros)
+        .into_iter()
+        .map(|(name, mut group)| {
+            sort_run_macros(&mut group, sort, run_counts);
+            RunMenuEntry::Group(name, group)
+        })
+        .collect()
+}
+
+/// Convert a hotkey to a menu accelerator, for display next to a menu item.
+///
+/// Returns None if the key has no accelerator equivalent.
+pub fn to_accelerator(hotkey: &HotKey) -> Option<Accelerator> {
+    if hotkey.key == Code::Unidentified {
+        return None;
+    }
+    let mods = (!hotkey.mods.is_empty()).then_some(hotkey.mods);
+    Some(Accelerator::new(mods, hotkey.key))
+}
+
+/// Run Macro item for a macro.
+///
+/// With `accelerators` on, the hotkey is shown as a native accelerator and the
+/// label is just the name; otherwise (or if the hotkey doesn't convert) it's
+/// "Name (hotkey)". Tray menus aren't attached to a window, so the accelerator
+/// is display-only and never registered alongside the global hotkey.
+/// Menu-only macros are labelled "Name (menu only)"; disabled macros are
+/// greyed out and labelled "Name (disabled)".
+fn run_item(macro_def: &config::MacroDefinition, settings: &config::AppSettings) -> MenuItem {
+    let max_chars = settings.menu_label_max_chars;
+    if !macro_def.registers_hotkey() {
+        return MenuItem::new(macro_label(macro_def, max_chars), macro_def.enabled, None::<Accelerator>);
+    }
+    let accelerator = settings
+        .show_hotkey_accelerators
+        .then(|| config::parse_hotkey_string(&macro_def.hotkey))
+        .flatten()
+        .and_then(|hotkey| to_accelerator(&hotkey));
+    match accelerator {
+        Some(accelerator) => MenuItem::new(menu_name(&macro_def.name, max_chars), true, Some(accelerator)),
+        None => MenuItem::new(macro_label(macro_def, max_chars), true, None::<Accelerator>),
+    }
+}
+
+/// "Name (hotkey)", or "Name (menu only)" / "Name (disabled)" when the
+/// hotkey isn't registered.
+///
+/// Only the name counts towards `max_chars`; the suffix is always shown whole.
+fn macro_label(macro_def: &config::MacroDefinition, max_chars: Option<usize>) -> String {
+    let name = menu_name(&macro_def.name, max_chars);
+    if !macro_def.enabled {
+        format!("{} (disabled)", name)
+    } else if macro_def.hotkey_enabled {
+        format!("{} ({})", name, config::display_hotkey(&macro_def.hotkey))
+    } else {
+        format!("{} (menu only)", name)
+    }
+}
+
+/// A macro name shortened to `settings.menu_label_max_chars`, if set.
+fn menu_name(name: &str, max_chars: Option<usize>) -> String {
+    match max_chars {
+        Some(max) => truncate_label(name, max),
+        None => name.to_string(),
+    }
+}
+
+/// Shorten `name` to at most `max` characters, ending in "…" if cut.
+///
+/// Counts and cuts by grapheme cluster, so an accented letter or an emoji
+/// made of several code points is never split. The ellipsis counts as one
+/// character; `max` below 1 is treated as 1.
+pub fn truncate_label(name: &str, max: usize) -> String {
+    let max = max.max(1);
+    if name.graphemes(true).nth(max).is_none() {
+        return name.to_string();
+    }
+    let kept: String = name.graphemes(true).take(max - 1).collect();
+    format!("{}\u{2026}", kept.trim_end())
+}
+
+/// What `build_menu` shows.
+pub struct MenuInputs<'a> {
+    /// State of the Enable toggle.
+    pub enabled: bool,
+    pub macros: &'a [config::MacroDefinition],
+    /// Listed under Warnings.
+    pub warnings: &'a [ValidationWarning],
+    /// Listed under Suggested Free Hotkeys.
+    pub hotkey_suggestions: &'a [HotkeySuggestions],
+    /// Recently and most run macros, for Run Macro.
+    pub history: &'a RunHistory,
+    /// Accessibility permission (None: no item, off macOS).
+    pub accessibility: Option<bool>,
+    pub settings: &'a config::AppSettings,
+    /// Started with --safe-mode.
+    pub safe_mode: bool,
+}
+
 /// Build the tray menu with macros organized by group.
 ///
 /// Menu structure:
 /// - [x] Enable
+/// - Stop Macro
+/// - Safe Mode (only when started with --safe-mode, informational)
+/// - Accessibility: granted/NOT granted (macOS only, informational)
 /// - ---
+/// - Run Macro > (recently run macros, separator, then all macros sorted by
+///   `run_menu_sort`, or group submenus when `flat_run_menu` is off)
+/// - Search & Run...
+/// - [x] Record Keystrokes
 /// - Macros > (submenu showing grouped macros)
 ///   - [Group Name] > (submenu if group exists)
-///     - Macro Name (Ctrl+Shift+K) > Delete
+///     - Macro Name (Ctrl+Shift+K) > [x] Enabled, Edit..., Delete
 ///   - [Ungrouped] > (for macros without group)
-///     - Macro Name (hotkey) > Delete
+///     - Macro Name (hotkey) > [x] Enabled, Edit..., Delete
 /// - Warnings (N) > (submenu if there are validation warnings)
 ///   - Warning 1
 ///   - Warning 2
+/// - Suggested Free Hotkeys > (submenu if a macro's hotkey is taken by the OS
+///   or another app)
+///   - Macro Name > Ctrl+Alt+K, Ctrl+Shift+K, ...
 /// - ---
 /// - Edit Config File...
 /// - Export Macros...
-/// - Import Macros...
+/// - Import Macros > Skip Existing Names..., Overwrite Existing...,
+///   Rename Duplicates...
 /// - ---
 /// - Quit
 ///
 /// Returns the menu and the menu item IDs for event handling.
-pub fn build_menu(
-    enabled: bool,
-    macros: &[config::MacroDefinition],
-    warnings: &[ValidationWarning],
-) -> (Menu, MenuIds) {
+pub fn build_menu(inputs: &MenuInputs) -> (Menu, MenuIds) {
+    let MenuInputs {
+        enabled,
+        macros,
+        warnings,
+        hotkey_suggestions,
+        history,
+        accessibility,
+        settings,
+        safe_mode,
+    } = *inputs;
+    // Macros for other machines (`only_on`) aren't listed anywhere
+    let macros: Vec<config::MacroDefinition> = macros.iter().filter(|m| config::macro_applies(m)).cloned().collect();
+    let macros = macros.as_slice();
     let menu = Menu::new();
+    let mut toggle_macro_ids: HashMap<muda::MenuId, Uuid> = HashMap::new();
+    let mut edit_macro_ids: HashMap<muda::MenuId, Uuid> = HashMap::new();
     let mut delete_macro_ids: HashMap<muda::MenuId, Uuid> = HashMap::new();
     let mut run_macro_ids: HashMap<muda::MenuId, Uuid> = HashMap::new();
+    let mut suggested_hotkey_ids: HashMap<muda::MenuId, (Uuid, HotKey)> = HashMap::new();
 
     // Create the toggle item as a CheckMenuItem (no keyboard accelerator)
     let toggle_item = CheckMenuItem::new("Enable", true, enabled, None::<Accelerator>);
@@ -88,62 +330,99 @@ pub fn build_menu(
     let stop_id = stop_item.id().clone();
     menu.append(&stop_item).expect("Failed to add stop item");
 
+    if safe_mode {
+        let safe_mode_item = MenuItem::new("Safe Mode \u{2014} macro hotkeys off", false, None::<Accelerator>);
+        menu.append(&safe_mode_item).expect("Failed to add safe mode item");
+    }
+
+    // Locked config: actions that write it are greyed out below
+    let writable = !settings.locked;
+    if settings.locked {
+        let locked_item = MenuItem::new("Locked \u{2014} config is read-only", false, None::<Accelerator>);
+        menu.append(&locked_item).expect("Failed to add locked item");
+    }
+
+    // Permission status (disabled info item; None hides it off macOS)
+    if let Some(trusted) = accessibility {
+        let status_item = MenuItem::new(accessibility_label(trusted), false, None::<Accelerator>);
+        menu.append(&status_item).expect("Failed to add accessibility item");
+    }
+
     menu.append(&PredefinedMenuItem::separator()).expect("Failed to add separator");
 
     // Build Run Macro submenu (flat alphabetized list for quick access)
     let run_submenu = Submenu::new("Run Macro", true);
-    let mut sorted_macros: Vec<_> = macros.iter().collect();
-    sorted_macros.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
 
-    for macro_def in &sorted_macros {
-        let label = format!("{} ({})", macro_def.name, macro_def.hotkey);
-        let item = MenuItem::new(&label, true, None::<Accelerator>);
-        let item_id = item.id().clone();
-        run_macro_ids.insert(item_id, macro_def.id);
-        run_submenu.append(&item).expect("Failed to add run item");
+    // Recently run macros first, most recent at the top
+    let recent_defs: Vec<_> = history
+        .recent
+        .iter()
+        .filter_map(|id| macros.iter().find(|m| m.id == *id))
+        .collect();
+    for macro_def in &recent_defs {
+        let item = run_item(macro_def, settings);
+        run_macro_ids.insert(item.id().clone(), macro_def.id);
+        run_submenu.append(&item).expect("Failed to add recent run item");
+    }
+    if !recent_defs.is_empty() {
+        run_submenu.append(&PredefinedMenuItem::separator()).expect("Failed to add separator");
+    }
+    for entry in run_menu_layout(macros, settings.flat_run_menu, settings.run_menu_sort, &history.counts) {
+        match entry {
+            RunMenuEntry::Macro(macro_def) => {
+                let item = run_item(macro_def, settings);
+                run_macro_ids.insert(item.id().clone(), macro_def.id);
+                run_submenu.append(&item).expect("Failed to add run item");
+            }
+            RunMenuEntry::Group(group_name, group_macros) => {
+                let group_submenu = Submenu::new(&group_name, true);
+                for macro_def in group_macros {
+                    let item = run_item(macro_def, settings);
+                    run_macro_ids.insert(item.id().clone(), macro_def.id);
+                    group_submenu.append(&item).expect("Failed to add run item");
+                }
+                run_submenu.append(&group_submenu).expect("Failed to add run group submenu");
+            }
+        }
     }
 
     menu.append(&run_submenu).expect("Failed to add run submenu");
 
-    // Build the Macros submenu with grouped macros
-    let macros_submenu = Submenu::new("Macros", true);
-
-    // Group macros by their `group` field (None -> "Ungrouped")
-    let mut groups: HashMap<String, Vec<&config::MacroDefinition>> = HashMap::new();
-    for macro_def in macros {
-        let group_name = macro_def.group.clone().unwrap_or_else(|| "Ungrouped".to_string());
-        groups.entry(group_name).or_default().push(macro_def);
-    }
+    let search_item = MenuItem::new("Search & Run...", true, None::<Accelerator>);
+    let search_id = search_item.id().clone();
+    menu.append(&search_item).expect("Failed to add search item");
 
-    // Sort group names for consistent ordering, but keep "Ungrouped" at the end
-    let mut group_names: Vec<&String> = groups.keys().collect();
-    group_names.sort_by(|a, b| {
-        if *a == "Ungrouped" {
-            std::cmp::Ordering::Greater
-        } else if *b == "Ungrouped" {
-            std::cmp::Ordering::Less
-        } else {
-            a.cmp(b)
-        }
-    });
+    // Checked by the app while a recording is in progress (see `update_record_item`);
+    // a locked config can't take the recorded macro
+    let record_item = CheckMenuItem::new("Record Keystrokes", writable, false, None::<Accelerator>);
+    let record_id = record_item.id().clone();
+    menu.append(&record_item).expect("Failed to add record item");
 
-    for group_name in group_names {
-        let group_macros = groups.get(group_name).unwrap();
+    // Build the Macros submenu with grouped macros
+    let macros_submenu = Submenu::new("Macros", true);
 
+    for (group_name, group_macros) in group_macros(macros) {
         // Create a submenu for this group
-        let group_submenu = Submenu::new(group_name, true);
+        let group_submenu = Submenu::new(&group_name, true);
 
         for macro_def in group_macros {
-            // Format: "macro_name (hotkey)"
-            let label = format!("{} ({})", macro_def.name, macro_def.hotkey);
+            let label = macro_label(macro_def, settings.menu_label_max_chars);
 
-            // Each macro gets a submenu with just "Delete" action
+            // Each macro gets a submenu with an "Enabled" checkbox and "Edit..." and "Delete" actions
             let macro_submenu = Submenu::new(&label, true);
 
-            let delete_item = MenuItem::new("Delete", true, None::<Accelerator>);
+            let enabled_item = CheckMenuItem::new("Enabled", writable, macro_def.enabled, None::<Accelerator>);
+            toggle_macro_ids.insert(enabled_item.id().clone(), macro_def.id);
+
+            let edit_item = MenuItem::new("Edit...", writable, None::<Accelerator>);
+            edit_macro_ids.insert(edit_item.id().clone(), macro_def.id);
+
+            let delete_item = MenuItem::new("Delete", writable, None::<Accelerator>);
             let delete_id = delete_item.id().clone();
             delete_macro_ids.insert(delete_id, macro_def.id);
 
+            macro_submenu.append(&enabled_item).expect("Failed to add macro enabled item");
+            macro_submenu.append(&edit_item).expect("Failed to add edit item");
             macro_submenu.append(&delete_item).expect("Failed to add delete item");
             group_submenu.append(&macro_submenu).expect("Failed to add macro submenu");
         }
@@ -166,25 +445,100 @@ pub fn build_menu(
         menu.append(&warnings_submenu).expect("Failed to add warnings submenu");
     }
 
+    // Picking a suggestion rewrites the macro's hotkey and saves the config.
+    // Taken candidates are listed greyed out to say why they weren't offered.
+    let suggestions: Vec<_> = hotkey_suggestions.iter().filter(|s| !s.hotkeys.is_empty()).collect();
+    if !suggestions.is_empty() {
+        let suggestions_submenu = Submenu::new("Suggested Free Hotkeys", true);
+
+        for suggestion in suggestions {
+            let macro_submenu = Submenu::new(menu_name(&suggestion.macro_name, settings.menu_label_max_chars), true);
+            for candidate in &suggestion.hotkeys {
+                let hotkey = config::pretty_hotkey(&candidate.hotkey);
+                let item = match candidate.status {
+                    CandidateStatus::Free => {
+                        let item = MenuItem::new(hotkey, writable, None::<Accelerator>);
+                        suggested_hotkey_ids.insert(item.id().clone(), (suggestion.macro_id, candidate.hotkey));
+                        item
+                    }
+                    CandidateStatus::InternalConflict => {
+                        MenuItem::new(format!("{} (used by another macro)", hotkey), false, None::<Accelerator>)
+                    }
+                    CandidateStatus::ExternalConflict => {
+                        MenuItem::new(format!("{} (taken by OS or another app)", hotkey), false, None::<Accelerator>)
+                    }
+                };
+                macro_submenu.append(&item).expect("Failed to add suggested hotkey item");
+            }
+            suggestions_submenu.append(&macro_submenu).expect("Failed to add suggestion submenu");
+        }
+
+        menu.append(&suggestions_submenu).expect("Failed to add suggested hotkeys submenu");
+    }
+
     menu.append(&PredefinedMenuItem::separator()).expect("Failed to add separator");
 
     // Management actions
-    let edit_config_item = MenuItem::new("Edit Config File...", true, None::<Accelerator>);
+    let edit_config_item = MenuItem::new("Edit Config File...", writable, None::<Accelerator>);
     let edit_config_id = edit_config_item.id().clone();
 
     let export_item = MenuItem::new("Export Macros...", true, None::<Accelerator>);
     let export_id = export_item.id().clone();
 
-    let import_item = MenuItem::new("Import Macros...", true, None::<Accelerator>);
-    let import_id = import_item.id().clone();
+    let export_shareable_item = MenuItem::new("Export for Sharing...", true, None::<Accelerator>);
+    let export_shareable_id = export_shareable_item.id().clone();
+
+    // One entry per way of handling names that already exist
+    let import_submenu = Submenu::new("Import Macros", writable);
+    let mut import_macro_ids: HashMap<muda::MenuId, MergeStrategy> = HashMap::new();
+    for (label, strategy) in [
+        ("Skip Existing Names...", MergeStrategy::Skip),
+        ("Overwrite Existing...", MergeStrategy::Overwrite),
+        ("Rename Duplicates...", MergeStrategy::RenameOnConflict),
+    ] {
+        let item = MenuItem::new(label, writable, None::<Accelerator>);
+        import_macro_ids.insert(item.id().clone(), strategy);
+        import_submenu.append(&item).expect("Failed to add import item");
+    }
+
+    // Greyed out until something has been imported
+    let reimport_item = MenuItem::new(
+        "Re-import Last File",
+        writable && settings.last_import_path.is_some(),
+        None::<Accelerator>,
+    );
+    let reimport_id = reimport_item.id().clone();
+
+    let export_config_item = MenuItem::new("Export Full Config...", true, None::<Accelerator>);
+    let export_config_id = export_config_item.id().clone();
+
+    let import_config_item = MenuItem::new("Import Full Config...", writable, None::<Accelerator>);
+    let import_config_id = import_config_item.id().clone();
 
     let open_logs_item = MenuItem::new("Open Logs...", true, None::<Accelerator>);
     let open_logs_id = open_logs_item.id().clone();
 
+    let copy_logs_item = MenuItem::new("Copy Logs to Clipboard", true, None::<Accelerator>);
+    let copy_logs_id = copy_logs_item.id().clone();
+
+    // Checked by the app while verbose logging is on (see `set_verbose_logging`)
+    let verbose_logging_item = CheckMenuItem::new("Verbose Logging (5 min)", true, false, None::<Accelerator>);
+    let verbose_logging_id = verbose_logging_item.id().clone();
+
+    let reregister_item = MenuItem::new("Re-register Hotkeys", true, None::<Accelerator>);
+    let reregister_id = reregister_item.id().clone();
+
     menu.append(&edit_config_item).expect("Failed to add edit config item");
     menu.append(&export_item).expect("Failed to add export item");
-    menu.append(&import_item).expect("Failed to add import item");
+    menu.append(&export_shareable_item).expect("Failed to add export for sharing item");
+    menu.append(&import_submenu).expect("Failed to add import submenu");
+    menu.append(&reimport_item).expect("Failed to add re-import item");
+    menu.append(&export_config_item).expect("Failed to add export config item");
+    menu.append(&import_config_item).expect("Failed to add import config item");
     menu.append(&open_logs_item).expect("Failed to add open logs item");
+    menu.append(&copy_logs_item).expect("Failed to add copy logs item");
+    menu.append(&verbose_logging_item).expect("Failed to add verbose logging item");
+    menu.append(&reregister_item).expect("Failed to add re-register item");
     menu.append(&PredefinedMenuItem::separator()).expect("Failed to add separator");
 
     // Auto-start toggle
@@ -209,27 +563,259 @@ pub fn build_menu(
         toggle: toggle_id,
         edit_config: edit_config_id,
         export_macros: export_id,
-        import_macros: import_id,
+        export_shareable: export_shareable_id,
+        reimport_last: reimport_id,
+        export_config: export_config_id,
+        import_config: import_config_id,
+        search_macros: search_id,
+        record_keystrokes: record_id,
         open_logs: open_logs_id,
+        copy_logs: copy_logs_id,
+        verbose_logging: verbose_logging_id,
+        reregister_hotkeys: reregister_id,
         auto_start: auto_start_id,
         stop_macro: stop_id,
         quit: quit_id,
+        toggle_macro_ids,
+        edit_macro_ids,
         delete_macro_ids,
         run_macro_ids,
+        suggested_hotkey_ids,
+        import_macro_ids,
     };
 
     (menu, ids)
 }
 
 /// Create the tray icon with the given menu.
-pub fn create_tray(menu: &Menu) -> TrayIcon {
+pub fn create_tray(menu: &Menu, safe_mode: bool, menu_on_left_click: bool) -> TrayIcon {
     let icon = load_icon();
+    let tooltip = if safe_mode { "KeyBlast (Safe Mode)" } else { "KeyBlast" };
 
     TrayIconBuilder::new()
         .with_menu(Box::new(menu.clone()))
-        .with_tooltip("KeyBlast")
+        .with_tooltip(tooltip)
         .with_icon(icon)
+        .with_menu_on_left_click(menu_on_left_click)
         .build()
         .expect("Failed to create tray icon")
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_macro;
+
+    #[test]
+    fn test_accessibility_label() {
+        assert_eq!(accessibility_label(true), "Accessibility: granted");
+        assert_eq!(accessibility_label(false), "Accessibility: NOT granted");
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn test_accessibility_item_hidden_off_macos() {
+        assert_eq!(crate::permission::accessibility_status(), None);
+    }
+
+    fn macro_in(name: &str, group: Option<&str>) -> config::MacroDefinition {
+        config::MacroDefinition {
+            group: group.map(str::to_string),
+            ..test_macro(name, "")
+        }
+    }
+
+    #[test]
+    fn test_run_menu_layout_flat() {
+        let macros = vec![macro_in("beta", Some("Work")), macro_in("Alpha", None)];
+
+        let layout = run_menu_layout(&macros, true, RunMenuSort::Name, &HashMap::new());
+        assert_eq!(
+            layout,
+            vec![RunMenuEntry::Macro(&macros[1]), RunMenuEntry::Macro(&macros[0])]
+        );
+    }
+
+    #[test]
+    fn test_run_menu_layout_grouped() {
+        let macros = vec![
+            macro_in("Zed", Some("Work")),
+            macro_in("Loose", None),
+            macro_in("Able", Some("Work")),
+            macro_in("Hi", Some("Examples")),
+        ];
+
+        let layout = run_menu_layout(&macros, false, RunMenuSort::Name, &HashMap::new());
+        assert_eq!(
+            layout,
+            vec![
+                RunMenuEntry::Group("Examples".to_string(), vec![&macros[3]]),
+                RunMenuEntry::Group("Work".to_string(), vec![&macros[2], &macros[0]]),
+                RunMenuEntry::Group("Ungrouped".to_string(), vec![&macros[1]]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_accelerator() {
+        use std::str::FromStr;
+
+        let cases = [
+            ("ctrl+shift+k", "ctrl+shift+KeyK"),
+            ("alt+f5", "alt+F5"),
+            ("meta+1", "super+Digit1"),
+            ("ctrl+alt+shift+meta+z", "ctrl+alt+shift+super+KeyZ"),
+        ];
+        for (hotkey, expected) in cases {
+            let hotkey = config::parse_hotkey_string(hotkey).unwrap();
+            assert_eq!(
+                to_accelerator(&hotkey),
+                Some(Accelerator::from_str(expected).unwrap()),
+                "{}",
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_filter_macros() {
+        let macros = vec![
+            macro_in("Email Signature", None),
+            macro_in("Code Block", None),
+            macro_in("signoff", None),
+        ];
+        let names = |found: Vec<&config::MacroDefinition>| {
+            found.into_iter().map(|m| m.name.clone()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(filter_macros("SIG", &macros)), vec!["Email Signature", "signoff"]);
+        assert_eq!(names(filter_macros("block", &macros)), vec!["Code Block"]);
+        assert!(filter_macros("xyz", &macros).is_empty());
+        // Empty query matches everything
+        assert_eq!(filter_macros("", &macros).len(), 3);
+    }
+
+    #[test]
+    fn test_menu_only_macro_listed_in_run_menu() {
+        let menu_only = config::MacroDefinition {
+            hotkey: "ctrl+shift+m".to_string(),
+            hotkey_enabled: false,
+            ..macro_in("Menu Only", None)
+        };
+        let macros = vec![macro_in("Alpha", None), menu_only];
+
+        let layout = run_menu_layout(&macros, true, RunMenuSort::Name, &HashMap::new());
+        assert!(layout.contains(&RunMenuEntry::Macro(&macros[1])));
+        assert_eq!(macro_label(&macros[1], None), "Menu Only (menu only)");
+    }
+
+    #[test]
+    fn test_disabled_macro_label() {
+        let disabled = config::MacroDefinition {
+            enabled: false,
+            ..macro_in("Work", None)
+        };
+        assert_eq!(macro_label(&disabled, None), "Work (disabled)");
+        let menu_only = config::MacroDefinition { hotkey_enabled: false, ..disabled };
+        assert_eq!(macro_label(&menu_only, None), "Work (disabled)");
+    }
+
+    fn sorted_names(macros: &[config::MacroDefinition], sort: RunMenuSort, counts: &HashMap<Uuid, u32>) -> Vec<String> {
+        let mut list: Vec<_> = macros.iter().collect();
+        sort_run_macros(&mut list, sort, counts);
+        list.into_iter().map(|m| m.name.clone()).collect()
+    }
+
+    fn sort_sample() -> Vec<config::MacroDefinition> {
+        let edited = |name: &str, modified: Option<&str>| config::MacroDefinition {
+            modified: modified.map(str::to_string),
+            ..macro_in(name, None)
+        };
+        vec![
+            edited("charlie", Some("2026-01-05T10:00:00Z")),
+            edited("Alpha", None),
+            edited("bravo", Some("2026-03-01T08:00:00Z")),
+            edited("delta", None),
+        ]
+    }
+
+    #[test]
+    fn test_sort_by_name() {
+        let macros = sort_sample();
+        assert_eq!(
+            sorted_names(&macros, RunMenuSort::Name, &HashMap::new()),
+            vec!["Alpha", "bravo", "charlie", "delta"]
+        );
+    }
+
+    #[test]
+    fn test_sort_by_recently_edited() {
+        let macros = sort_sample();
+        // Newest edit first, never-edited last by name
+        assert_eq!(
+            sorted_names(&macros, RunMenuSort::Recent, &HashMap::new()),
+            vec!["bravo", "charlie", "Alpha", "delta"]
+        );
+    }
+
+    #[test]
+    fn test_sort_by_usage() {
+        let macros = sort_sample();
+        let counts = HashMap::from([(macros[3].id, 5), (macros[0].id, 2)]);
+        assert_eq!(
+            sorted_names(&macros, RunMenuSort::Usage, &counts),
+            vec!["delta", "charlie", "Alpha", "bravo"]
+        );
+    }
+
+    #[test]
+    fn test_sort_by_config_order() {
+        let macros = sort_sample();
+        assert_eq!(
+            sorted_names(&macros, RunMenuSort::Order, &HashMap::new()),
+            vec!["charlie", "Alpha", "bravo", "delta"]
+        );
+    }
+
+    #[test]
+    fn test_truncate_label_ascii() {
+        assert_eq!(truncate_label("Signature", 9), "Signature");
+        assert_eq!(truncate_label("Signature", 10), "Signature");
+        assert_eq!(truncate_label("Signature", 8), "Signatu\u{2026}");
+        // No space left dangling before the ellipsis
+        assert_eq!(truncate_label("Long name", 6), "Long\u{2026}");
+        assert_eq!(truncate_label("Signature", 0), "\u{2026}");
+    }
+
+    #[test]
+    fn test_truncate_label_multibyte() {
+        // "é" as one code point and as e + combining acute: one grapheme either way
+        assert_eq!(truncate_label("Caf\u{e9} cr\u{e8}me", 10), "Caf\u{e9} cr\u{e8}me");
+        assert_eq!(truncate_label("Cafe\u{301} noir", 5), "Cafe\u{301}\u{2026}");
+        assert_eq!(truncate_label("Cafe\u{301} noir", 4), "Caf\u{2026}");
+        assert_eq!(truncate_label("\u{65e5}\u{672c}\u{8a9e}\u{306e}\u{540d}\u{524d}", 4), "\u{65e5}\u{672c}\u{8a9e}\u{2026}");
+    }
+
+    #[test]
+    fn test_truncate_label_emoji() {
+        let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+        let name = format!("Hi {}{}{}", family, family, family);
+        assert_eq!(truncate_label(&name, 6), name);
+        assert_eq!(truncate_label(&name, 5), format!("Hi {}\u{2026}", family));
+        // Cut before the first emoji rather than inside it
+        assert_eq!(truncate_label(&name, 4), "Hi\u{2026}");
+        let flag = "\u{1f1ef}\u{1f1f5}";
+        assert_eq!(truncate_label(&format!("{}{}{}", flag, flag, flag), 2), format!("{}\u{2026}", flag));
+    }
+
+    #[test]
+    fn test_macro_label_truncates_name_only() {
+        let def = config::MacroDefinition {
+            hotkey: "ctrl+shift+k".to_string(),
+            ..macro_in("A very long macro name", None)
+        };
+        let hotkey = config::display_hotkey(&def.hotkey);
+        assert_eq!(macro_label(&def, Some(8)), format!("A very\u{2026} ({})", hotkey));
+        assert_eq!(macro_label(&def, None), format!("A very long macro name ({})", hotkey));
+    }
+}
@@ -0,0 +1,195 @@
+//! First-run setup wizard.
+//!
+//! When KeyBlast starts without a config file (and without `--no-wizard`),
+//! a few message dialogs ask, in order:
+//! - whether to start KeyBlast at login
+//! - whether to bind the first example macro to a hotkey that is free
+//! - whether to import macros from an existing file instead of the examples
+//!
+//! `ask` shows the dialogs; `first_run_config` turns the answers into the
+//! config that gets written, so the decisions can be tested without dialogs.
+
+use std::path::{Path, PathBuf};
+
+use global_hotkey::hotkey::HotKey;
+use rfd::{FileDialog, MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+
+use crate::bundle;
+use crate::config::{self, Config, MacroDefinition};
+use crate::hotkey;
+
+/// Macros read from the file picked in the import step.
+#[derive(Debug, Clone)]
+pub struct ImportedFile {
+    pub path: PathBuf,
+    pub macros: Vec<MacroDefinition>,
+}
+
+/// The user's answers to the wizard's questions.
+#[derive(Debug, Clone, Default)]
+pub struct WizardAnswers {
+    /// Start KeyBlast at login.
+    pub auto_start: bool,
+    /// Hotkey for the first example macro (None keeps its own).
+    pub hotkey: Option<HotKey>,
+    /// Macros to start with instead of the examples.
+    pub import: Option<ImportedFile>,
+}
+
+/// The hotkey to offer for the first example: its own if free, otherwise
+/// `hotkey::suggest_available`'s pick.
+pub fn suggest_default_hotkey(base: HotKey, mut is_free: impl FnMut(&HotKey) -> bool) -> Option<HotKey> {
+    if is_free(&base) {
+        return Some(base);
+    }
+    hotkey::suggest_available(base, is_free)
+}
+
+/// The config to write on first run.
+///
+/// Imported macros replace `examples` (and are remembered for Re-import Last
+/// File); otherwise the examples are kept, the first one under the chosen
+/// hotkey. Auto-start isn't part of the config and is applied by the caller.
+pub fn first_run_config(base: Config, examples: Vec<MacroDefinition>, answers: &WizardAnswers) -> Config {
+    let mut cfg = base;
+    match &answers.import {
+        Some(imported) => {
+            cfg.macros = imported.macros.clone();
+            cfg.settings.last_import_path = Some(imported.path.display().to_string());
+        }
+        None => {
+            cfg.macros = examples;
+            if let (Some(hotkey), Some(first)) = (answers.hotkey, cfg.macros.first_mut()) {
+                first.hotkey = config::hotkey_to_string(&hotkey);
+            }
+        }
+    }
+    cfg
+}
+
+/// Ask the wizard's questions with message and file dialogs.
+///
+/// `is_free` tells whether a hotkey can be registered. Blocks until the
+/// last dialog is closed; a file that can't be imported is reported and
+/// the examples are kept.
+pub fn ask(examples: &[MacroDefinition], config_dir: &Path, is_free: impl FnMut(&HotKey) -> bool) -> WizardAnswers {
+    let auto_start = confirm(
+        "Welcome to KeyBlast",
+        "KeyBlast types text and keystrokes when you press a hotkey.\n\nStart KeyBlast automatically when you log in?",
+    );
+
+    let hotkey = examples.first().and_then(|first| {
+        let base = config::parse_hotkey_string(&first.hotkey)?;
+        let suggested = suggest_default_hotkey(base, is_free)?;
+        let question = format!(
+            "Use {} to run the example macro '{}'?",
+            config::pretty_hotkey(&suggested),
+            first.name
+        );
+        confirm("KeyBlast Hotkey", &question).then_some(suggested)
+    });
+
+    let import = if confirm(
+        "Import Macros",
+        "Do you have macros from another KeyBlast installation?\n\nImport them instead of the examples?",
+    ) {
+        FileDialog::new()
+            .add_filter("TOML", &["toml"])
+            .add_filter("JSON", &["json"])
+            .add_filter("Bundle with text files", &["zip"])
+            .add_filter("Tab-separated list", &["tsv", "txt"])
+            .set_title("Import Macros")
+            .pick_file()
+            .and_then(|path| import_file(path, config_dir))
+    } else {
+        None
+    };
+
+    WizardAnswers { auto_start, hotkey, import }
+}
+
+/// Read macros from a config file, bundle or list, reporting failures.
+fn import_file(path: PathBuf, config_dir: &Path) -> Option<ImportedFile> {
+    match bundle::import_file(&path, config_dir) {
+        Ok((macros, files)) => {
+            // Nothing is merged on first run: the macros are written as they are
+            files.keep();
+            Some(ImportedFile { path, macros })
+        }
+        Err(e) => {
+            tracing::error!("Failed to import macros from {}: {}", path.display(), e);
+            MessageDialog::new()
+                .set_level(MessageLevel::Error)
+                .set_title("Import Macros")
+                .set_description(format!("Couldn't import {}: {}\n\nStarting with the example macros.", path.display(), e))
+                .set_buttons(MessageButtons::Ok)
+                .show();
+            None
+        }
+    }
+}
+
+fn confirm(title: &str, description: &str) -> bool {
+    MessageDialog::new()
+        .set_level(MessageLevel::Info)
+        .set_title(title)
+        .set_description(description)
+        .set_buttons(MessageButtons::YesNo)
+        .show()
+        == MessageDialogResult::Yes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_macro;
+    use global_hotkey::hotkey::{Code, Modifiers};
+
+    #[test]
+    fn test_suggest_default_hotkey() {
+        let base = HotKey::new(Some(Modifiers::CONTROL | Modifiers::SHIFT), Code::KeyH);
+        assert_eq!(suggest_default_hotkey(base, |_| true), Some(base));
+        let taken = suggest_default_hotkey(base, |hk| *hk != base).unwrap();
+        assert_eq!(taken.mods, Modifiers::CONTROL | Modifiers::SHIFT | Modifiers::ALT);
+        assert_eq!(suggest_default_hotkey(base, |_| false), None);
+    }
+
+    #[test]
+    fn test_first_run_config_keeps_examples() {
+        let examples = vec![test_macro("Hello", "ctrl+shift+h"), test_macro("Sig", "ctrl+shift+s")];
+
+        let cfg = first_run_config(Config::default(), examples.clone(), &WizardAnswers::default());
+        assert_eq!(cfg.macros, examples);
+        assert_eq!(cfg.settings.last_import_path, None);
+
+        let answers = WizardAnswers {
+            auto_start: true,
+            hotkey: Some(HotKey::new(Some(Modifiers::CONTROL | Modifiers::ALT), Code::KeyH)),
+            import: None,
+        };
+        let cfg = first_run_config(Config::default(), examples, &answers);
+        assert_eq!(cfg.macros[0].hotkey, "ctrl+alt+h");
+        assert_eq!(cfg.macros[1].hotkey, "ctrl+shift+s");
+    }
+
+    #[test]
+    fn test_first_run_config_import_replaces_examples() {
+        let imported = vec![test_macro("Mine", "ctrl+alt+m")];
+        let answers = WizardAnswers {
+            auto_start: false,
+            hotkey: Some(HotKey::new(Some(Modifiers::CONTROL | Modifiers::ALT), Code::KeyH)),
+            import: Some(ImportedFile {
+                path: PathBuf::from("old/macros.toml"),
+                macros: imported.clone(),
+            }),
+        };
+        let mut base = Config::default();
+        base.settings.strict_dsl = true;
+
+        let cfg = first_run_config(base, vec![test_macro("Hello", "ctrl+shift+h")], &answers);
+        // The hotkey was for an example that is no longer there
+        assert_eq!(cfg.macros, imported);
+        assert_eq!(cfg.settings.last_import_path.as_deref(), Some("old/macros.toml"));
+        assert!(cfg.settings.strict_dsl);
+    }
+}